@@ -0,0 +1,271 @@
+//! Run inference and per-image processing over many inputs in parallel.
+//!
+//! [`run`] spreads the inputs over a bounded number of worker threads, each with its own model
+//! session, so inference runs in parallel too. Each input is processed in isolation: an error or
+//! panic is recorded in that input's [`BatchItem`] and the remaining inputs keep going.
+//!
+//! # Example
+//! ```no_run
+//! use outline::Outline;
+//! use outline::batch::{self, BatchOptions};
+//!
+//! let outline = Outline::new("model.onnx");
+//! let inputs = ["a.jpg", "b.jpg", "c.jpg"];
+//! let options = BatchOptions::new().with_concurrency(Some(2)).with_retries(1);
+//!
+//! let items = batch::run(&outline, &inputs, &options, |input, session| {
+//!     let path = input.with_extension("mask.png");
+//!     session.matte().threshold().processed()?.save(&path)?;
+//!     Ok(path)
+//! })?;
+//! for item in &items {
+//!     if let Err(err) = &item.result {
+//!         eprintln!("{}: {err}", item.input.display());
+//!     }
+//! }
+//! # Ok::<_, outline::OutlineError>(())
+//! ```
+
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+use std::time::Duration;
+
+use crate::{InferencedMatte, Outline, OutlineError, OutlineResult};
+
+/// Concurrency, retry, and timeout settings for [`run`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchOptions {
+    concurrency: Option<usize>,
+    retries: u32,
+    timeout: Option<Duration>,
+}
+
+impl BatchOptions {
+    /// Create options that use one worker per available CPU and do not retry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximum number of inputs in flight at once.
+    pub fn concurrency(&self) -> Option<usize> {
+        self.concurrency
+    }
+
+    /// How many times a failed input is retried.
+    pub fn retries(&self) -> u32 {
+        self.retries
+    }
+
+    /// Time limit for each input's inference.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Limit how many inputs are decoded and processed at once.
+    ///
+    /// Each worker holds one image and one model session at a time, so this also bounds peak
+    /// memory. `None` uses the available parallelism of the machine; an explicit limit must be at
+    /// least 1.
+    pub fn with_concurrency(mut self, concurrency: Option<usize>) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Retry an input up to `retries` more times when inference or the closure returns an error.
+    ///
+    /// Panics are never retried.
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Give up on an input whose inference takes longer than `timeout` (`None` for no limit).
+    ///
+    /// The input fails with [`OutlineError::Timeout`] and `process` is not called for it, so
+    /// nothing is written from a late result. Inference cannot be interrupted, so the abandoned
+    /// run keeps its thread and model session until it finishes; the worker continues with a new
+    /// session. Timed-out inputs are retried like other errors.
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+/// Outcome of one input processed by [`run`].
+#[derive(Debug)]
+pub struct BatchItem<T> {
+    /// The input path as given to [`run`].
+    pub input: PathBuf,
+    /// Number of attempts made, including the first.
+    pub attempts: u32,
+    /// The closure's value, or the error from the last attempt.
+    ///
+    /// A panic is reported as [`OutlineError::Panicked`].
+    pub result: OutlineResult<T>,
+}
+
+/// Run inference on each input and pass the result to `process`, in parallel.
+///
+/// Every input is loaded with [`Outline::for_image`] and its [`InferencedMatte`] handed to
+/// `process` together with the input path. Errors and panics from either step are caught and
+/// recorded in that input's [`BatchItem`] without affecting the others. The returned items
+/// follow the order of `inputs`, whichever finishes first.
+///
+/// The first worker uses `outline` and its cached session; every other worker runs a clone,
+/// which loads its own session on first use. With a [timeout](BatchOptions::with_timeout), the
+/// first worker runs a clone too, since inference then runs on a thread of its own. A session only runs one image at a time, so this
+/// is what lets model runs overlap, at the cost of one loaded model per worker. With ORT, set
+/// [`Outline::with_intra_threads`] to split the cores between workers instead of giving each
+/// session all of them.
+///
+/// Returns [`OutlineError::InvalidThreadCount`] if the concurrency limit is zero.
+pub fn run<P, T, F>(
+    outline: &Outline,
+    inputs: &[P],
+    options: &BatchOptions,
+    process: F,
+) -> OutlineResult<Vec<BatchItem<T>>>
+where
+    P: AsRef<Path> + Sync,
+    T: Send,
+    F: Fn(&Path, InferencedMatte) -> OutlineResult<T> + Sync,
+{
+    let workers = match options.concurrency {
+        Some(0) => {
+            return Err(OutlineError::InvalidThreadCount {
+                setting: "concurrency",
+            });
+        }
+        Some(limit) => limit,
+        None => thread::available_parallelism().map_or(1, |threads| threads.get()),
+    }
+    .min(inputs.len());
+
+    // Workers are plain threads rather than rayon tasks: the RTen backend runs rayon jobs while
+    // holding the session lock, and a rayon worker waiting on those jobs could steal another
+    // item and block on the same lock. Each worker pulls the next index, so at most `workers`
+    // images are in flight.
+    let next = AtomicUsize::new(0);
+    let finished = Mutex::new(Vec::with_capacity(inputs.len()));
+    let process = &process;
+    thread::scope(|scope| {
+        for worker in 0..workers {
+            let (next, finished) = (&next, &finished);
+            scope.spawn(move || {
+                let mut outline = if worker == 0 && options.timeout.is_none() {
+                    WorkerOutline::Borrowed(outline)
+                } else {
+                    WorkerOutline::Owned(Arc::new(outline.clone()))
+                };
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(input) = inputs.get(index) else {
+                        break;
+                    };
+                    let item = run_item(&mut outline, input.as_ref(), options, process);
+                    finished
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())
+                        .push((index, item));
+                }
+            });
+        }
+    });
+
+    let mut finished = finished
+        .into_inner()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    finished.sort_unstable_by_key(|(index, _)| *index);
+    Ok(finished.into_iter().map(|(_, item)| item).collect())
+}
+
+/// The `Outline` a worker runs inference with.
+enum WorkerOutline<'a> {
+    Borrowed(&'a Outline),
+    /// Shared with the inference thread when a timeout is set.
+    Owned(Arc<Outline>),
+}
+
+impl WorkerOutline<'_> {
+    fn get(&self) -> &Outline {
+        match self {
+            Self::Borrowed(outline) => outline,
+            Self::Owned(outline) => outline,
+        }
+    }
+
+    /// Run inference for `input`, giving up after `timeout`.
+    fn infer(&mut self, input: &Path, timeout: Option<Duration>) -> OutlineResult<InferencedMatte> {
+        let (Some(limit), Self::Owned(outline)) = (timeout, &*self) else {
+            return self.get().for_image(input);
+        };
+        let outline = Arc::clone(outline);
+        let input = input.to_path_buf();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| outline.for_image(&input)))
+                .unwrap_or_else(|payload| Err(OutlineError::Panicked(panic_message(payload))));
+            // The receiver is gone once the timeout fires, so a failed send is expected then.
+            let _ = sender.send(result);
+        });
+
+        match receiver.recv_timeout(limit) {
+            Ok(result) => result,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                // The abandoned run still holds the session, so continue with a fresh one.
+                *self = Self::Owned(Arc::new(self.get().clone()));
+                Err(OutlineError::Timeout { limit })
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err(OutlineError::Panicked(
+                "inference thread exited without a result".to_string(),
+            )),
+        }
+    }
+}
+
+/// Process one input, retrying errors and catching panics.
+fn run_item<T, F>(
+    outline: &mut WorkerOutline<'_>,
+    input: &Path,
+    options: &BatchOptions,
+    process: &F,
+) -> BatchItem<T>
+where
+    F: Fn(&Path, InferencedMatte) -> OutlineResult<T>,
+{
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            outline
+                .infer(input, options.timeout)
+                .and_then(|session| process(input, session))
+        }))
+        .unwrap_or_else(|payload| Err(OutlineError::Panicked(panic_message(payload))));
+
+        let retry = attempts <= options.retries
+            && matches!(&result, Err(err) if !matches!(err, OutlineError::Panicked(_)));
+        if !retry {
+            return BatchItem {
+                input: input.to_path_buf(),
+                attempts,
+                result,
+            };
+        }
+    }
+}
+
+/// Extract the message from a panic payload.
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "panic with a non-string payload".to_string()
+    }
+}
@@ -0,0 +1,60 @@
+//! On-disk cache locations shared by the CLI commands.
+
+use std::path::PathBuf;
+
+const APP_DIR_NAME: &str = "outline-core";
+const OPTIMIZED_MODEL_DIR_NAME: &str = "optimized";
+
+/// Get the default cache directory path.
+///
+/// Returns `~/.cache/outline-core/` on Linux, `~/Library/Caches/outline-core/` on macOS,
+/// or falls back to current directory if home cannot be determined.
+pub fn default_cache_dir() -> PathBuf {
+    let env_override = std::env::var_os("OUTLINE_MODEL_CACHE_DIR").map(PathBuf::from);
+    resolve_cache_dir(env_override, dirs::cache_dir())
+}
+
+/// Get the directory used by `--cache-optimized` for graph-optimized models.
+pub fn optimized_model_cache_dir() -> PathBuf {
+    default_cache_dir().join(OPTIMIZED_MODEL_DIR_NAME)
+}
+
+fn resolve_cache_dir(env_override: Option<PathBuf>, system_cache_dir: Option<PathBuf>) -> PathBuf {
+    if let Some(path) = env_override
+        && !path.as_os_str().is_empty()
+    {
+        return path;
+    }
+
+    system_cache_dir
+        .map(|path| path.join(APP_DIR_NAME))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_cache_dir_uses_env_override() {
+        let custom_dir = tempfile::tempdir().expect("failed to create temp dir");
+
+        let resolved = resolve_cache_dir(Some(custom_dir.path().to_path_buf()), None);
+        assert_eq!(resolved, custom_dir.path().to_path_buf());
+    }
+
+    #[test]
+    fn resolve_cache_dir_empty_override_falls_back_to_system_cache() {
+        let system_cache = tempfile::tempdir().expect("failed to create temp dir");
+        let expected = system_cache.path().join(APP_DIR_NAME);
+
+        let resolved = resolve_cache_dir(Some(PathBuf::from("")), Some(system_cache.path().into()));
+        assert_eq!(resolved, expected);
+    }
+
+    #[test]
+    fn resolve_cache_dir_without_any_source_falls_back_to_current_dir() {
+        let resolved = resolve_cache_dir(None, None);
+        assert_eq!(resolved, PathBuf::from("."));
+    }
+}
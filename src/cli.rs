@@ -1,14 +1,21 @@
 use std::ffi::OsString;
-use std::path::PathBuf;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use clap::{
     ArgMatches, Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum, ValueHint,
-    error::ErrorKind,
+    error::ErrorKind, parser::ValueSource,
 };
-use image::imageops::FilterType;
+use image::ImageFormat;
 use outline::{
-    ErosionBorderMode, MaskPipeline, MaskProcessingDefaults, ModelInputSize, TraceOptions,
+    BackgroundFit, ColorMap, Connectivity, ErosionBorderMode, ExecutionProvider, Gradient,
+    MaskPipeline, MaskProcessingDefaults, MatteActivation, MatteCombine, ModelInputLayout,
+    ModelInputSize, OutlineResult, OutputSelector, PreprocessMode, ResampleFilter, TileOptions,
+    TraceOptions, TraceOutputStyle,
 };
+use serde::Deserialize;
 use visioncortex::PathSimplifyMode;
 use vtracer::{ColorMode, Hierarchical};
 
@@ -16,10 +23,17 @@ use vtracer::{ColorMode, Hierarchical};
 // Tests keep these synced with `MaskProcessingDefaults`.
 const DEFAULT_BLUR_SIGMA: &str = "6.0";
 const DEFAULT_MASK_THRESHOLD: &str = "120";
-const DEFAULT_MASK_THRESHOLD_VALUE: u8 = 120;
 const DEFAULT_DILATION_RADIUS: &str = "5.0";
 const DEFAULT_EROSION_RADIUS: &str = "5.0";
 
+/// Default `--max-pixels`: 100 megapixels, well above camera output but far below sizes that
+/// exhaust memory during compositing.
+const DEFAULT_MAX_PIXELS: u64 = 100_000_000;
+
+/// Default `--avif-quality`, matching the `image` crate's AVIF encoder default.
+#[cfg(feature = "avif")]
+pub(crate) const DEFAULT_AVIF_QUALITY: u8 = 80;
+
 /// Command line interface definition.
 #[derive(Parser, Debug)]
 #[command(author, version, about, propagate_version = true)]
@@ -44,28 +58,116 @@ impl Cli {
         I: IntoIterator<Item = T>,
         T: Into<OsString> + Clone,
     {
-        let matches = Self::command().try_get_matches_from(itr)?;
+        let args: Vec<OsString> = itr.into_iter().map(Into::into).collect();
+        let mut matches = Self::command().try_get_matches_from(&args)?;
+        let config = match matches.get_one::<PathBuf>("config") {
+            Some(path) => {
+                let config = load_config_file(path)
+                    .map_err(|err| clap::Error::raw(ErrorKind::Io, format!("{err}\n")))?;
+                // Parse again so bare options such as `--blur` pick up the file's values.
+                matches = Self::command_with_mask_defaults(&config.mask_processing)
+                    .try_get_matches_from(&args)?;
+                config
+            }
+            None => ConfigFile::default(),
+        };
         let mut cli = <Self as FromArgMatches>::from_arg_matches(&matches)?;
+        cli.resolve_model_dir_conflict(&matches)?;
+        cli.global.presets = config;
         cli.populate_ordered_mask_steps(&matches)?;
         Ok(cli)
     }
 
+    // Replace the built-in values of bare mask-processing options with `defaults`.
+    fn command_with_mask_defaults(defaults: &MaskProcessingDefaults) -> clap::Command {
+        let mut command = Self::command();
+        let names: Vec<String> = command
+            .get_subcommands()
+            .filter(|sub| sub.get_arguments().any(|arg| arg.get_id() == "blur"))
+            .map(|sub| sub.get_name().to_string())
+            .collect();
+        for name in names {
+            command = command.mut_subcommand(name, |sub| {
+                sub.mut_arg("blur", |arg| {
+                    arg.default_missing_value(defaults.blur_sigma.to_string())
+                })
+                .mut_arg("normalized_blur", |arg| {
+                    arg.default_missing_value(defaults.blur_sigma.to_string())
+                })
+                .mut_arg("threshold", |arg| {
+                    arg.default_missing_value(defaults.mask_threshold.to_string())
+                })
+                .mut_arg("fill_holes", |arg| {
+                    arg.default_missing_value(defaults.mask_threshold.to_string())
+                })
+                .mut_arg("solidify", |arg| {
+                    arg.default_missing_value(defaults.mask_threshold.to_string())
+                })
+                .mut_arg("dilate", |arg| {
+                    arg.default_missing_value(defaults.dilation_radius.to_string())
+                })
+                .mut_arg("erode", |arg| {
+                    arg.default_missing_value(defaults.erosion_radius.to_string())
+                })
+            });
+        }
+        command
+    }
+
+    // `--model-dir` conflicts with `--model`, but should still override `OUTLINE_MODEL_PATH`,
+    // which clap's `conflicts_with` would also reject.
+    fn resolve_model_dir_conflict(&mut self, matches: &ArgMatches) -> Result<(), clap::Error> {
+        if self.global.model_dir.is_none() || self.global.model.is_none() {
+            return Ok(());
+        }
+        if matches.value_source("model") == Some(ValueSource::EnvVariable) {
+            self.global.model = None;
+            return Ok(());
+        }
+        Err(clap::Error::raw(
+            ErrorKind::ArgumentConflict,
+            "`--model-dir` cannot be used with `--model`",
+        ))
+    }
+
     fn populate_ordered_mask_steps(&mut self, matches: &ArgMatches) -> Result<(), clap::Error> {
         let Some((_, command_matches)) = matches.subcommand() else {
             return Ok(());
         };
 
+        let defaults = &self.global.presets.mask_processing;
         match &mut self.command {
-            Commands::Mask(cmd) => cmd.mask_processing.populate_ordered_steps(command_matches),
-            Commands::Cut(cmd) => cmd.mask_processing.populate_ordered_steps(command_matches),
-            Commands::Trace(cmd) => cmd.mask_processing.populate_ordered_steps(command_matches),
+            Commands::Mask(cmd) => cmd
+                .mask_processing
+                .populate_ordered_steps(command_matches, defaults),
+            Commands::Cut(cmd) => cmd
+                .mask_processing
+                .populate_ordered_steps(command_matches, defaults),
+            Commands::Trace(cmd) => {
+                cmd.trace_options.populate_explicit(command_matches);
+                cmd.mask_processing
+                    .populate_ordered_steps(command_matches, defaults)
+            }
+            Commands::Reprocess(cmd) => cmd
+                .mask_processing
+                .populate_ordered_steps(command_matches, defaults),
+            Commands::Icon(cmd) => cmd
+                .mask_processing
+                .populate_ordered_steps(command_matches, defaults),
+            Commands::Batch(cmd) => cmd
+                .mask_processing
+                .populate_ordered_steps(command_matches, defaults),
+            Commands::Contours(cmd) => cmd
+                .mask_processing
+                .populate_ordered_steps(command_matches, defaults),
+            Commands::Trimap(_) => Ok(()),
             #[cfg(feature = "fetch-model")]
             Commands::FetchModel(_) => Ok(()),
         }
     }
 }
 
-#[derive(Args, Debug)]
+#[derive(Args, Debug, Clone)]
 pub struct GlobalOptions {
     /// ONNX model path
     #[arg(
@@ -76,23 +178,194 @@ pub struct GlobalOptions {
         value_hint = ValueHint::FilePath
     )]
     pub model: Option<PathBuf>,
+    /// Directory holding exactly one `.onnx` or `.ort` model to use
+    #[arg(
+        long = "model-dir",
+        value_name = "DIR",
+        global = true,
+        value_hint = ValueHint::DirPath
+    )]
+    pub model_dir: Option<PathBuf>,
+    /// Background model whose matte is combined with the main model output
+    #[arg(long = "bg-model", value_name = "PATH", global = true, value_hint = ValueHint::FilePath)]
+    pub bg_model: Option<PathBuf>,
+    /// How to combine the main matte with the `--bg-model` matte
+    #[arg(long = "matte-combine", value_enum, default_value_t = MatteCombineArg::Multiply, global = true)]
+    pub matte_combine: MatteCombineArg,
+    /// How raw model outputs become matte values; `sigmoid` for models that output logits
+    #[arg(long = "matte-activation", value_enum, default_value_t = MatteActivationArg::Clamp, global = true)]
+    pub matte_activation: MatteActivationArg,
+    /// Model output the matte is read from, by name or index (see `--list-outputs`; default 0)
+    #[arg(long = "model-output", value_name = "NAME|INDEX", value_parser = parse_output_selector, global = true)]
+    pub model_output: Option<OutputSelector>,
     /// Intra-op thread count for ORT (None to let ORT decide)
     #[arg(long, global = true)]
     pub intra_threads: Option<usize>,
-    /// Override model input size when it cannot be inferred
+    /// Inter-op thread count for ORT (None to let ORT decide)
+    #[arg(long, global = true)]
+    pub inter_threads: Option<usize>,
+    /// Device to run the model on with ORT: `cpu`, `cuda[:N]` (`cuda` feature), or `coreml` (`coreml` feature)
+    #[arg(long, value_name = "DEVICE", default_value = "cpu", value_parser = parse_device, global = true)]
+    pub device: ExecutionProvider,
+    /// Cache the ORT-optimized model and reuse it on later runs to skip graph optimization
+    #[arg(long = "cache-optimized", global = true)]
+    pub cache_optimized: bool,
+    /// Deprecated height-first spelling of `--input-size`
     #[arg(
         long = "model-input-size",
         value_name = "HEIGHTxWIDTH",
         value_parser = parse_model_input_size,
+        hide = true,
         global = true
     )]
     pub model_input_size: Option<ModelInputSize>,
+    /// Override model input size when it cannot be inferred (bypasses auto-detection)
+    #[arg(
+        long = "input-size",
+        value_name = "WIDTHxHEIGHT",
+        value_parser = parse_input_size,
+        conflicts_with = "model_input_size",
+        global = true
+    )]
+    pub input_size: Option<ModelInputSize>,
+    /// Override model input width; pair with `--input-height` (same as `--input-size`)
+    #[arg(
+        long = "input-width",
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..),
+        requires = "input_height",
+        conflicts_with_all = ["model_input_size", "input_size"],
+        global = true
+    )]
+    pub input_width: Option<u32>,
+    /// Override model input height; pair with `--input-width` (same as `--input-size`)
+    #[arg(
+        long = "input-height",
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..),
+        requires = "input_width",
+        conflicts_with_all = ["model_input_size", "input_size"],
+        global = true
+    )]
+    pub input_height: Option<u32>,
+    /// Override model input tensor layout (bypasses auto-detection)
+    #[arg(
+        long = "model-input-layout",
+        visible_alias = "input-layout",
+        value_enum,
+        global = true
+    )]
+    pub model_input_layout: Option<InputLayoutArg>,
+    /// How the input is fit to the model input size
+    #[arg(long, value_enum, default_value_t = PreprocessArg::Stretch, global = true)]
+    pub preprocess: PreprocessArg,
+    /// Infer large images in overlapping SIZE×SIZE tiles (overlap defaults to SIZE/8)
+    #[arg(long, value_name = "SIZE[,OVERLAP]", value_parser = parse_tile, global = true)]
+    pub tile: Option<TileOptions>,
+    /// Derive the matte from inverted luminance instead of running a model (for dark subjects on light backgrounds)
+    #[arg(long = "from-luma", global = true)]
+    pub from_luma: bool,
+    /// Fail when the matte is more than 99% fully opaque or fully transparent
+    #[arg(long = "sanity-check", global = true)]
+    pub sanity_check: bool,
+    /// Reject any decoded image (input, background, or matte) with more pixels than this (0 disables the limit)
+    #[arg(long = "max-pixels", value_name = "N", default_value_t = DEFAULT_MAX_PIXELS, global = true)]
+    pub max_pixels: u64,
+    /// Suppress warnings reported at the end of the run
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+    /// Treat warnings as errors, stopping at the first one
+    #[arg(long, global = true)]
+    pub strict: bool,
+    /// Pad the model input to a multiple of N (for fully-convolutional models)
+    #[arg(
+        long = "pad-to-multiple",
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..),
+        global = true
+    )]
+    pub pad_to_multiple: Option<u32>,
+    /// Load `[mask_processing]` and `[trace]` defaults from a TOML file; explicit flags override them
+    #[arg(long, value_name = "PATH", global = true, value_hint = ValueHint::FilePath)]
+    pub config: Option<PathBuf>,
+    /// Defaults loaded from `--config`, or the built-in defaults without it.
+    #[arg(skip)]
+    pub presets: ConfigFile,
+    /// Compute the matte from a copy scaled by this factor in (0, 1], keeping full-resolution outputs
+    #[arg(long = "infer-scale", value_name = "SCALE", value_parser = parse_infer_scale, global = true)]
+    pub infer_scale: Option<f32>,
+    /// Fail if reading the input and inferring its matte take longer than this many seconds (per image in `batch`)
+    #[arg(long, value_name = "SECONDS", value_parser = parse_timeout, global = true)]
+    pub timeout: Option<Duration>,
     /// Filter used when resizing the input before inference
     #[arg(long = "input-resample-filter", value_enum, default_value_t = ResampleFilter::Triangle, global = true)]
     pub input_resample_filter: ResampleFilter,
     /// Filter used when resizing the matte back to the original resolution
     #[arg(long = "output-resample-filter", value_enum, default_value_t = ResampleFilter::Lanczos3, global = true)]
     pub output_resample_filter: ResampleFilter,
+    /// How results are printed; `json` prints one object per run for scripts
+    #[arg(long = "format", value_enum, default_value_t = OutputFormatArg::Text, global = true)]
+    pub format: OutputFormatArg,
+    /// Decoder format for an input read from stdin (`-`); `auto` sniffs the content
+    #[arg(long = "stdin-format", value_enum, default_value_t = StdinFormatArg::Auto, global = true)]
+    pub stdin_format: StdinFormatArg,
+    /// Background used when saving a transparent result to a format without alpha, such as JPEG
+    #[arg(
+        long = "flatten-color",
+        value_name = "COLOR",
+        value_parser = parse_opaque_color,
+        default_value = "#ffffff",
+        global = true
+    )]
+    pub flatten_color: [u8; 4],
+    /// Encoder quality (1-100) for `.avif` outputs
+    #[cfg(feature = "avif")]
+    #[arg(
+        long = "avif-quality",
+        value_name = "QUALITY",
+        default_value_t = DEFAULT_AVIF_QUALITY,
+        value_parser = clap::value_parser!(u8).range(1..=100),
+        global = true
+    )]
+    pub avif_quality: u8,
+}
+
+impl GlobalOptions {
+    /// The model input size override from `--model-input-size`, `--input-size`, or
+    /// `--input-width`/`--input-height`.
+    pub fn model_input_size(&self) -> Option<ModelInputSize> {
+        self.model_input_size.or(self.input_size).or_else(|| {
+            let (width, height) = self.input_width.zip(self.input_height)?;
+            Some(ModelInputSize::new(height as usize, width as usize))
+        })
+    }
+}
+
+/// Contents of a `--config` file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    /// Values used by bare mask-processing options and the implicit threshold.
+    pub mask_processing: MaskProcessingDefaults,
+    /// Trace options that trace flags override.
+    pub trace: TraceOptions,
+}
+
+/// Read a TOML `--config` file; sections and fields it omits keep their defaults.
+pub fn load_config_file(path: &Path) -> OutlineResult<ConfigFile> {
+    let contents = fs::read_to_string(path).map_err(|err| {
+        io::Error::new(
+            err.kind(),
+            format!("failed to read config file {}: {err}", path.display()),
+        )
+    })?;
+    toml::from_str(&contents).map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid config file {}: {err}", path.display()),
+        )
+        .into()
+    })
 }
 
 #[derive(Subcommand, Debug)]
@@ -103,37 +376,169 @@ pub enum Commands {
     Cut(CutCommand),
     /// Trace the subject into an SVG outline
     Trace(TraceCommand),
+    /// Re-run mask processing on previously saved mattes without inference
+    Reprocess(ReprocessCommand),
+    /// Cut out the subject and export it as square icons at several sizes
+    Icon(IconCommand),
+    /// Export a three-level trimap (foreground, background, unknown) for alpha matting
+    Trimap(TrimapCommand),
+    /// Export the subject's outlines as polygon coordinates in JSON
+    Contours(ContoursCommand),
+    /// Remove the background from every image in a set of files or directories
+    Batch(BatchCommand),
     /// Download the default model from the network
     #[cfg(feature = "fetch-model")]
     FetchModel(FetchModelCommand),
 }
 
-/// Resampling filters for image resizing.
+/// Strategies accepted by `--matte-combine`.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum MatteCombineArg {
+    /// fg * (1 - bg)
+    Multiply,
+    /// min(fg, 1 - bg)
+    Min,
+    /// (fg + (1 - bg)) / 2
+    Average,
+}
+
+impl From<MatteCombineArg> for MatteCombine {
+    fn from(value: MatteCombineArg) -> Self {
+        match value {
+            MatteCombineArg::Multiply => MatteCombine::Multiply,
+            MatteCombineArg::Min => MatteCombine::Min,
+            MatteCombineArg::Average => MatteCombine::Average,
+        }
+    }
+}
+
+/// Activations accepted by `--matte-activation`.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum MatteActivationArg {
+    /// Keep the values as the model produced them
+    None,
+    /// 1 / (1 + e^-x)
+    Sigmoid,
+    /// Clamp to 0..=1
+    Clamp,
+}
+
+impl From<MatteActivationArg> for MatteActivation {
+    fn from(value: MatteActivationArg) -> Self {
+        match value {
+            MatteActivationArg::None => MatteActivation::None,
+            MatteActivationArg::Sigmoid => MatteActivation::Sigmoid,
+            MatteActivationArg::Clamp => MatteActivation::Clamp,
+        }
+    }
+}
+
+/// Colormaps accepted by `--visualize`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ColorMapArg {
+    /// Dark purple through green to yellow
+    Viridis,
+    /// Plain grayscale
+    Gray,
+}
+
+impl From<ColorMapArg> for ColorMap {
+    fn from(value: ColorMapArg) -> Self {
+        match value {
+            ColorMapArg::Viridis => ColorMap::Viridis,
+            ColorMapArg::Gray => ColorMap::Gray,
+        }
+    }
+}
+
+/// Fit modes accepted by `--bg-fit`.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum BackgroundFitArg {
+    /// Resize to the output size, ignoring the aspect ratio
+    Stretch,
+    /// Scale to fill the output, cropping the overflow
+    Cover,
+    /// Scale to fit inside the output, leaving black bands
+    Contain,
+}
+
+impl From<BackgroundFitArg> for BackgroundFit {
+    fn from(value: BackgroundFitArg) -> Self {
+        match value {
+            BackgroundFitArg::Stretch => BackgroundFit::Stretch,
+            BackgroundFitArg::Cover => BackgroundFit::Cover,
+            BackgroundFitArg::Contain => BackgroundFit::Contain,
+        }
+    }
+}
+
+/// Modes accepted by `--preprocess`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum PreprocessArg {
+    /// Resize to the model input size, ignoring the aspect ratio
+    Stretch,
+    /// Keep the aspect ratio and pad with the normalization mean color
+    Letterbox,
+}
+
+impl From<PreprocessArg> for PreprocessMode {
+    fn from(value: PreprocessArg) -> Self {
+        match value {
+            PreprocessArg::Stretch => PreprocessMode::Stretch,
+            PreprocessArg::Letterbox => PreprocessMode::Letterbox { fill: None },
+        }
+    }
+}
+
+/// Tensor layouts accepted by `--model-input-layout`.
 #[derive(Clone, Copy, Debug, ValueEnum)]
-pub enum ResampleFilter {
-    Nearest,
-    Triangle,
-    CatmullRom,
-    Gaussian,
-    Lanczos3,
+pub enum InputLayoutArg {
+    Nchw,
+    Nhwc,
 }
 
-impl From<ResampleFilter> for FilterType {
-    /// Convert ResampleFilter to image::imageops::FilterType.
-    fn from(value: ResampleFilter) -> Self {
+impl From<InputLayoutArg> for ModelInputLayout {
+    fn from(value: InputLayoutArg) -> Self {
         match value {
-            ResampleFilter::Nearest => FilterType::Nearest,
-            ResampleFilter::Triangle => FilterType::Triangle,
-            ResampleFilter::CatmullRom => FilterType::CatmullRom,
-            ResampleFilter::Gaussian => FilterType::Gaussian,
-            ResampleFilter::Lanczos3 => FilterType::Lanczos3,
+            InputLayoutArg::Nchw => ModelInputLayout::Nchw,
+            InputLayoutArg::Nhwc => ModelInputLayout::Nhwc,
+        }
+    }
+}
+
+/// Result formats accepted by `--format`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormatArg {
+    /// One human-readable line per result
+    Text,
+    /// A single JSON object with the input, outputs, dimensions, and any stats
+    Json,
+}
+
+/// Formats accepted by `--stdin-format`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum StdinFormatArg {
+    Auto,
+    Png,
+    Jpeg,
+    Webp,
+}
+
+impl StdinFormatArg {
+    /// Return the explicit decoder format, or `None` to sniff it from the content.
+    pub fn image_format(self) -> Option<ImageFormat> {
+        match self {
+            Self::Auto => None,
+            Self::Png => Some(ImageFormat::Png),
+            Self::Jpeg => Some(ImageFormat::Jpeg),
+            Self::Webp => Some(ImageFormat::WebP),
         }
     }
 }
 
 #[derive(Args, Debug)]
 pub struct MaskCommand {
-    /// Input image path
+    /// Input image path (`-` reads from stdin)
     pub input: PathBuf,
     /// Output path (defaults to `<name>-matte.png` or `<name>-mask.png`)
     #[arg(short, long)]
@@ -141,13 +546,59 @@ pub struct MaskCommand {
     /// Select which mask to export
     #[arg(long = "mask-source", value_enum, default_value_t = MaskExportSource::Auto)]
     pub mask_source: MaskExportSource,
+    /// PNG bit depth for the exported mask (1 only applies to strictly binary masks, 16 to the raw matte)
+    #[arg(
+        long = "mask-bit-depth",
+        visible_alias = "bit-depth",
+        value_enum,
+        default_value_t = MaskBitDepthArg::Eight
+    )]
+    pub mask_bit_depth: MaskBitDepthArg,
+    /// Save outputs in the input file's stored orientation instead of the EXIF-corrected one
+    #[arg(long = "stored-orientation")]
+    pub stored_orientation: bool,
+    /// Save the source image with the mask tinted in COLOR instead of the bare mask
+    #[arg(
+        long = "overlay",
+        value_name = "COLOR",
+        num_args = 0..=1,
+        value_parser = parse_color,
+        default_missing_value = "#ff0000"
+    )]
+    pub overlay: Option<[u8; 4]>,
+    /// Opacity of the `--overlay` tint over fully masked pixels (0.0-1.0)
+    #[arg(
+        long = "overlay-opacity",
+        value_name = "OPACITY",
+        value_parser = parse_unit_interval,
+        default_value_t = 0.5,
+        requires = "overlay"
+    )]
+    pub overlay_opacity: f32,
+    /// Print the coverage and value range of the exported mask
+    #[arg(long = "stats")]
+    pub stats: bool,
+    /// Save an RGB preview of the mask rendered through COLORMAP instead of the bare mask
+    #[arg(
+        long = "visualize",
+        value_name = "COLORMAP",
+        value_enum,
+        conflicts_with = "overlay"
+    )]
+    pub visualize: Option<ColorMapArg>,
+    /// Also save the matte at model resolution and after resizing into DIR, for debugging
+    #[arg(long = "debug-matte-stages", value_name = "DIR", value_hint = ValueHint::DirPath)]
+    pub debug_matte_stages: Option<PathBuf>,
+    /// Also save a per-pixel confidence map of the raw matte (bright where it is near 0 or 1)
+    #[arg(long = "confidence", value_name = "PATH", value_hint = ValueHint::FilePath)]
+    pub confidence: Option<PathBuf>,
     #[command(flatten)]
     pub mask_processing: MaskProcessingArgs,
 }
 
 #[derive(Args, Debug)]
 pub struct CutCommand {
-    /// Input image path
+    /// Input image path (`-` reads from stdin)
     pub input: PathBuf,
     /// Foreground PNG output path (defaults to `<name>-foreground.png`)
     #[arg(short, long)]
@@ -158,16 +609,166 @@ pub struct CutCommand {
     /// Save the processed mask alongside the foreground PNG
     #[arg(long = "export-mask", value_name = "PATH", num_args = 0..=1)]
     pub export_mask: Option<Option<PathBuf>>,
+    /// PNG bit depth for `--export-mask` (1 only applies to strictly binary masks)
+    #[arg(long = "mask-bit-depth", value_enum, default_value_t = MaskBitDepthArg::Eight)]
+    pub mask_bit_depth: MaskBitDepthArg,
     /// Select which mask is used for the foreground alpha channel
     #[arg(long = "alpha-source", value_enum, default_value_t = AlphaFromArg::Auto)]
     pub alpha_source: AlphaFromArg,
+    /// Print the model's output tensor names and shapes, then exit without processing the input
+    #[arg(long = "list-outputs")]
+    pub list_outputs: bool,
+    /// Fade the foreground alpha toward the canvas edges over this many pixels
+    #[arg(long = "vignette", value_name = "RADIUS", value_parser = parse_non_negative_radius)]
+    pub vignette: Option<f32>,
+    /// Round the corners of the foreground canvas with this radius in pixels
+    #[arg(long = "corner-radius", value_name = "RADIUS", value_parser = parse_non_negative_radius)]
+    pub corner_radius: Option<f32>,
+    /// Apply a slight feather to the alpha when its edges look aliased
+    #[arg(long = "auto-feather")]
+    pub auto_feather: bool,
+    /// Flatten the foreground onto a solid color (`#RRGGBB` or `#RRGGBBAA`)
+    #[arg(long = "background", value_name = "COLOR", value_parser = parse_color)]
+    pub background: Option<[u8; 4]>,
+    /// Flatten the foreground onto a gradient (`linear,START,END[,ANGLE]` or `radial,CENTER,EDGE`)
+    #[arg(
+        long = "bg-gradient",
+        value_name = "SPEC",
+        value_parser = parse_gradient,
+        conflicts_with = "background"
+    )]
+    pub bg_gradient: Option<Gradient>,
+    /// Flatten the foreground onto an image resized to it according to `--bg-fit`
+    #[arg(
+        long = "bg-image",
+        value_name = "PATH",
+        value_hint = ValueHint::FilePath,
+        conflicts_with_all = ["background", "bg_gradient"]
+    )]
+    pub bg_image: Option<PathBuf>,
+    /// Keep the original background but blur it with this Gaussian sigma
+    #[arg(
+        long = "bg-blur",
+        value_name = "SIGMA",
+        value_parser = parse_blur_sigma,
+        conflicts_with_all = ["background", "bg_gradient", "bg_image"]
+    )]
+    pub bg_blur: Option<f32>,
+    /// Keep the original background but convert it to grayscale
+    #[arg(
+        long = "bg-desaturate",
+        conflicts_with_all = ["background", "bg_gradient", "bg_image", "bg_blur"]
+    )]
+    pub bg_desaturate: bool,
+    /// Draw a stroke ring WIDTH pixels wide around the subject, beneath its edge
+    #[arg(
+        long = "stroke",
+        value_name = "WIDTH",
+        value_parser = parse_non_negative_radius,
+        conflicts_with_all = ["bg_blur", "bg_desaturate"]
+    )]
+    pub stroke: Option<f32>,
+    /// Stroke ring color (`#RRGGBB` or `#RRGGBBAA`)
+    #[arg(
+        long = "stroke-color",
+        value_name = "COLOR",
+        value_parser = parse_color,
+        default_value = DEFAULT_STROKE_COLOR,
+        requires = "stroke"
+    )]
+    pub stroke_color: [u8; 4],
+    /// Place a drop shadow beneath the subject, offset by DX,DY pixels and blurred by BLUR
+    #[arg(
+        long = "shadow",
+        value_name = "DX,DY[,BLUR]",
+        value_parser = parse_shadow,
+        conflicts_with_all = ["bg_blur", "bg_desaturate"]
+    )]
+    pub shadow: Option<(i32, i32, f32)>,
+    /// Drop shadow color (`#RRGGBB` or `#RRGGBBAA`)
+    #[arg(
+        long = "shadow-color",
+        value_name = "COLOR",
+        value_parser = parse_color,
+        default_value = DEFAULT_SHADOW_COLOR,
+        requires = "shadow"
+    )]
+    pub shadow_color: [u8; 4],
+    /// How `--bg-image` is fitted to the foreground size
+    #[arg(long = "bg-fit", value_enum, default_value_t = BackgroundFitArg::Cover, requires = "bg_image")]
+    pub bg_fit: BackgroundFitArg,
+    /// Save a 2x2 grayscale montage of the foreground R, G, B, and A channels
+    #[arg(long = "channel-dump", value_name = "PATH", value_hint = ValueHint::FilePath)]
+    pub channel_dump: Option<PathBuf>,
+    /// Also save the foreground over a transparency checkerboard for previewing
+    #[arg(long = "preview", value_name = "PATH", value_hint = ValueHint::FilePath)]
+    pub preview: Option<PathBuf>,
+    /// Checkerboard cell size in pixels for `--preview`
+    #[arg(
+        long = "preview-cell",
+        value_name = "PX",
+        default_value_t = 16,
+        value_parser = clap::value_parser!(u32).range(1..),
+        requires = "preview"
+    )]
+    pub preview_cell: u32,
+    /// Also save a per-pixel confidence map of the raw matte (bright where it is near 0 or 1)
+    #[arg(long = "confidence", value_name = "PATH", value_hint = ValueHint::FilePath)]
+    pub confidence: Option<PathBuf>,
+    /// Keep the full canvas when saving WebP instead of trimming to the subject
+    #[arg(long = "no-trim")]
+    pub no_trim: bool,
+    /// Print the coverage and value range of the matte used as the foreground alpha
+    #[arg(long = "stats")]
+    pub stats: bool,
+    /// Crop the foreground and exported matte and mask to the subject's bounding box
+    #[arg(long = "autocrop")]
+    pub autocrop: bool,
+    /// Transparent margin in pixels kept around the subject by `--autocrop`
+    #[arg(
+        long = "padding",
+        value_name = "PX",
+        default_value_t = 0,
+        requires = "autocrop"
+    )]
+    pub padding: u32,
+    /// Save outputs in the input file's stored orientation instead of the EXIF-corrected one
+    #[arg(long = "stored-orientation")]
+    pub stored_orientation: bool,
+    /// Save only the raw matte to `--output` (defaults to `<name>-matte.png`) and skip the foreground
+    #[arg(
+        long = "matte-only",
+        conflicts_with_all = [
+            "export_matte",
+            "alpha_source",
+            "vignette",
+            "corner_radius",
+            "auto_feather",
+            "background",
+            "bg_gradient",
+            "bg_image",
+            "bg_blur",
+            "bg_desaturate",
+            "shadow",
+            "stroke",
+            "channel_dump",
+            "preview",
+            "no_trim",
+        ]
+    )]
+    pub matte_only: bool,
     #[command(flatten)]
     pub mask_processing: MaskProcessingArgs,
 }
 
+/// Blur used by `--shadow` when the spec has no third value.
+const DEFAULT_SHADOW_BLUR: &str = "4.0";
+const DEFAULT_SHADOW_COLOR: &str = "#00000080";
+const DEFAULT_STROKE_COLOR: &str = "#ffffff";
+
 #[derive(Args, Debug)]
 pub struct TraceCommand {
-    /// Input image path
+    /// Input image path (`-` reads from stdin)
     pub input: PathBuf,
     /// Output SVG path (defaults to input name with `.svg`)
     #[arg(short, long)]
@@ -175,12 +776,175 @@ pub struct TraceCommand {
     /// Which mask to use for tracing (auto prefers processed)
     #[arg(long = "mask-source", value_enum, default_value_t = MaskSourceArg::Auto)]
     pub mask_source: MaskSourceArg,
+    /// Write each traced path to its own SVG in `--out-dir` instead of a single file
+    #[arg(long = "split-paths", requires = "out_dir", conflicts_with = "output")]
+    pub split_paths: bool,
+    /// Directory for the per-path SVGs written by `--split-paths`
+    #[arg(
+        long = "out-dir",
+        value_name = "DIR",
+        value_hint = ValueHint::DirPath,
+        requires = "split_paths"
+    )]
+    pub out_dir: Option<PathBuf>,
+    /// Trace in the file's stored orientation and apply EXIF orientation as an SVG transform
+    #[arg(long = "svg-orient", conflicts_with = "split_paths")]
+    pub svg_orient: bool,
+    /// Print the model's output tensor names and shapes, then exit without processing the input
+    #[arg(long = "list-outputs")]
+    pub list_outputs: bool,
+    /// Load trace options from a JSON profile; explicit trace flags override its values
+    #[arg(long = "trace-profile", value_name = "FILE", value_hint = ValueHint::FilePath)]
+    pub trace_profile: Option<PathBuf>,
+    /// Write the effective trace options to a JSON profile for reuse
+    #[arg(long = "dump-trace-profile", value_name = "FILE", value_hint = ValueHint::FilePath)]
+    pub dump_trace_profile: Option<PathBuf>,
     #[command(flatten)]
     pub mask_processing: MaskProcessingArgs,
     #[command(flatten)]
     pub trace_options: TraceOptionsArgs,
 }
 
+#[derive(Args, Debug)]
+pub struct IconCommand {
+    /// Input image path (`-` reads from stdin)
+    pub input: PathBuf,
+    /// Directory for the icon files (defaults to the input's directory)
+    #[arg(long = "out-dir", value_name = "DIR", value_hint = ValueHint::DirPath)]
+    pub out_dir: Option<PathBuf>,
+    /// Comma-separated icon sizes in pixels
+    #[arg(
+        long = "sizes",
+        value_name = "SIZES",
+        value_delimiter = ',',
+        value_parser = clap::value_parser!(u32).range(1..=MAX_ICON_SIZE),
+        default_value = DEFAULT_ICON_SIZES
+    )]
+    pub sizes: Vec<u32>,
+    /// Also combine the sizes into a multi-size ICO file (defaults to `<name>-icon.ico`)
+    #[arg(long = "ico", value_name = "PATH", num_args = 0..=1)]
+    pub ico: Option<Option<PathBuf>>,
+    /// Select which mask is used for the icon alpha channel
+    #[arg(long = "alpha-source", value_enum, default_value_t = AlphaFromArg::Auto)]
+    pub alpha_source: AlphaFromArg,
+    #[command(flatten)]
+    pub mask_processing: MaskProcessingArgs,
+}
+
+/// Icon sizes exported by `icon` when `--sizes` is not given.
+pub const DEFAULT_ICON_SIZES: &str = "16,32,48,256";
+
+/// Largest size accepted by `icon --sizes`.
+pub const MAX_ICON_SIZE: i64 = 1024;
+
+/// Largest frame an ICO file can hold.
+pub const MAX_ICO_FRAME_SIZE: u32 = 256;
+
+#[derive(Args, Debug)]
+pub struct ContoursCommand {
+    /// Input image path (`-` reads from stdin)
+    pub input: PathBuf,
+    /// Output JSON path (defaults to `<name>-contours.json`)
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    /// Which mask to trace (auto prefers processed)
+    #[arg(long = "mask-source", value_enum, default_value_t = MaskSourceArg::Auto)]
+    pub mask_source: MaskSourceArg,
+    /// Simplify each polygon, dropping vertices within EPS pixels of the simplified outline
+    #[arg(long = "simplify", value_name = "EPS", value_parser = parse_simplify_epsilon)]
+    pub simplify: Option<f64>,
+    /// Map coordinates into 0-1 across the subject's bounding box
+    #[arg(long = "normalize")]
+    pub normalize: bool,
+    #[command(flatten)]
+    pub mask_processing: MaskProcessingArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct TrimapCommand {
+    /// Input image path (`-` reads from stdin)
+    pub input: PathBuf,
+    /// Output path (defaults to `<name>-trimap.png`)
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    /// Matte values at or above this are confident foreground (0-255 or 0.0-1.0)
+    #[arg(
+        long = "fg-threshold",
+        value_name = "VALUE",
+        value_parser = parse_mask_threshold,
+        default_value = DEFAULT_TRIMAP_FG_THRESHOLD
+    )]
+    pub fg_threshold: u8,
+    /// Matte values at or below this are confident background (0-255 or 0.0-1.0)
+    #[arg(
+        long = "bg-threshold",
+        value_name = "VALUE",
+        value_parser = parse_mask_threshold,
+        default_value = DEFAULT_TRIMAP_BG_THRESHOLD
+    )]
+    pub bg_threshold: u8,
+    /// Widen the unknown band by eroding both confident regions by RADIUS pixels
+    #[arg(
+        long = "unknown-dilate",
+        value_name = "RADIUS",
+        value_parser = parse_non_negative_radius,
+        default_value = DEFAULT_TRIMAP_UNKNOWN_DILATE
+    )]
+    pub unknown_dilate: f32,
+}
+
+const DEFAULT_TRIMAP_FG_THRESHOLD: &str = "240";
+const DEFAULT_TRIMAP_BG_THRESHOLD: &str = "15";
+const DEFAULT_TRIMAP_UNKNOWN_DILATE: &str = "5.0";
+
+#[derive(Args, Debug)]
+pub struct BatchCommand {
+    /// Input images, or directories whose images are all processed
+    #[arg(required = true, value_name = "INPUT", value_hint = ValueHint::AnyPath)]
+    pub inputs: Vec<PathBuf>,
+    /// Also process images in subdirectories of directory inputs
+    #[arg(short, long)]
+    pub recursive: bool,
+    /// Directory for the outputs, mirroring subdirectories of directory inputs (defaults to each input's directory)
+    #[arg(long = "out-dir", value_name = "DIR", value_hint = ValueHint::DirPath)]
+    pub out_dir: Option<PathBuf>,
+    /// Also save each processed mask as `<name>-mask.png`
+    #[arg(long = "export-mask")]
+    pub export_mask: bool,
+    /// Number of images processed at once, each job with its own model session
+    #[arg(
+        short,
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..),
+        default_value_t = 1
+    )]
+    pub jobs: u32,
+    /// Select which mask is used for the foreground alpha channel
+    #[arg(long = "alpha-source", value_enum, default_value_t = AlphaFromArg::Auto)]
+    pub alpha_source: AlphaFromArg,
+    #[command(flatten)]
+    pub mask_processing: MaskProcessingArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct ReprocessCommand {
+    /// Directory of raw matte PNGs (e.g. `<name>-matte.png` from `cut --export-matte`)
+    #[arg(value_hint = ValueHint::DirPath)]
+    pub mattes: PathBuf,
+    /// Directory of source images matched to mattes by name; enables foreground output
+    #[arg(long = "sources", value_name = "DIR", value_hint = ValueHint::DirPath)]
+    pub sources: Option<PathBuf>,
+    /// Resize mattes to their source image size (with `--output-resample-filter`) instead of failing on a mismatch
+    #[arg(long = "resize-matte-to-input", requires = "sources")]
+    pub resize_matte_to_input: bool,
+    /// Output directory (defaults to the matte directory)
+    #[arg(short, long, value_name = "DIR", value_hint = ValueHint::DirPath)]
+    pub output: Option<PathBuf>,
+    #[command(flatten)]
+    pub mask_processing: MaskProcessingArgs,
+}
+
 /// Command to download the default model.
 #[cfg(feature = "fetch-model")]
 #[derive(Args, Debug, Clone)]
@@ -203,6 +967,17 @@ pub struct MaskProcessingArgs {
         default_missing_value = DEFAULT_BLUR_SIGMA
     )]
     pub blur: Option<f32>,
+    /// Apply a gaussian blur that feathers outward without thinning the subject (optionally override sigma)
+    #[arg(
+        long = "normalized-blur",
+        value_name = "SIGMA",
+        num_args = 0..=1,
+        default_missing_value = DEFAULT_BLUR_SIGMA
+    )]
+    pub normalized_blur: Option<f32>,
+    /// Soften the hard mask edge with an alpha ramp PX pixels to each side, keeping the interior opaque
+    #[arg(long = "feather", value_name = "PX", value_parser = parse_non_negative_radius)]
+    pub feather: Option<f32>,
     /// Apply thresholding to binarize the mask (0-255 or 0.0-1.0, optionally override threshold value)
     #[arg(
         long = "threshold",
@@ -212,6 +987,12 @@ pub struct MaskProcessingArgs {
         default_missing_value = DEFAULT_MASK_THRESHOLD
     )]
     pub threshold: Option<u8>,
+    /// Binarize the mask at a threshold picked per image with Otsu's method
+    #[arg(long = "auto-threshold")]
+    pub auto_threshold: bool,
+    /// Map values at or below LOW to 0 and at or above HIGH to 255, ramping linearly between
+    #[arg(long = "soft-threshold", value_name = "LOW,HIGH", value_parser = parse_soft_threshold)]
+    pub soft_threshold: Option<(u8, u8)>,
     /// Disable implicit threshold insertion before hard-mask operations
     #[arg(long = "no-implicit-threshold")]
     pub no_implicit_threshold: bool,
@@ -222,6 +1003,12 @@ pub struct MaskProcessingArgs {
         default_missing_value = DEFAULT_DILATION_RADIUS
     )]
     pub dilate: Option<f32>,
+    /// Remove specks and protrusions narrower than RADIUS (erode, then dilate)
+    #[arg(long = "open", value_name = "RADIUS", value_parser = parse_non_negative_radius)]
+    pub open: Option<f32>,
+    /// Fill holes and gaps narrower than RADIUS (dilate, then erode)
+    #[arg(long = "close", value_name = "RADIUS", value_parser = parse_non_negative_radius)]
+    pub close: Option<f32>,
     #[arg(
         long = "erode",
         value_name = "RADIUS",
@@ -237,6 +1024,18 @@ pub struct MaskProcessingArgs {
         requires = "erode"
     )]
     pub erode_border: Option<ErosionBorderArg>,
+    /// Keep only the largest connected region, dropping floating blobs (optionally pick 4- or 8-connectivity)
+    #[arg(
+        long = "keep-largest",
+        value_enum,
+        value_name = "CONNECTIVITY",
+        num_args = 0..=1,
+        default_missing_value = "8"
+    )]
+    pub keep_largest: Option<ConnectivityArg>,
+    /// Drop connected regions smaller than PX pixels, keeping every larger one
+    #[arg(long = "min-island-area", value_name = "PX")]
+    pub min_island_area: Option<u32>,
     /// Fill enclosed holes in the mask before vectorization (optionally override threshold value)
     #[arg(
         long = "fill-holes",
@@ -246,28 +1045,86 @@ pub struct MaskProcessingArgs {
         default_missing_value = DEFAULT_MASK_THRESHOLD
     )]
     pub fill_holes: Option<u8>,
+    /// Make regions enclosed by strong foreground opaque while keeping soft edges (optionally override threshold value)
+    #[arg(
+        long = "solidify",
+        value_name = "THRESHOLD",
+        num_args = 0..=1,
+        value_parser = parse_mask_threshold,
+        default_missing_value = DEFAULT_MASK_THRESHOLD
+    )]
+    pub solidify: Option<u8>,
+    /// Quantize the mask to N evenly spaced alpha levels (2 <= N <= 255)
+    #[arg(
+        long = "posterize",
+        value_name = "LEVELS",
+        value_parser = clap::value_parser!(u8).range(2..)
+    )]
+    pub posterize: Option<u8>,
+    /// Apply a gamma curve to the mask values (G > 1 darkens mid-gray, G < 1 brightens it)
+    #[arg(long = "matte-gamma", value_name = "G", value_parser = parse_gamma)]
+    pub matte_gamma: Option<f32>,
+    /// Invert the mask so the background becomes the foreground
+    #[arg(long = "invert-mask")]
+    pub invert_mask: bool,
     #[arg(skip)]
     pub(crate) ordered_steps: Vec<CliMaskProcessingStep>,
+    #[arg(skip)]
+    pub(crate) defaults: MaskProcessingDefaults,
 }
 
 impl MaskProcessingArgs {
-    fn populate_ordered_steps(&mut self, matches: &ArgMatches) -> Result<(), clap::Error> {
+    fn populate_ordered_steps(
+        &mut self,
+        matches: &ArgMatches,
+        defaults: &MaskProcessingDefaults,
+    ) -> Result<(), clap::Error> {
         let mut entries = Vec::new();
         if let Some(sigma) = self.blur
             && let Some(index) = matches.index_of("blur")
         {
             entries.push((index, CliMaskProcessingStep::Blur(sigma)));
         }
+        if let Some(sigma) = self.normalized_blur
+            && let Some(index) = matches.index_of("normalized_blur")
+        {
+            entries.push((index, CliMaskProcessingStep::NormalizedBlur(sigma)));
+        }
+        if let Some(radius) = self.feather
+            && let Some(index) = matches.index_of("feather")
+        {
+            entries.push((index, CliMaskProcessingStep::Feather(radius)));
+        }
         if let Some(value) = self.threshold
             && let Some(index) = matches.index_of("threshold")
         {
             entries.push((index, CliMaskProcessingStep::Threshold(value)));
         }
+        if self.auto_threshold
+            && let Some(index) = matches.index_of("auto_threshold")
+        {
+            entries.push((index, CliMaskProcessingStep::OtsuThreshold));
+        }
+        if let Some((low, high)) = self.soft_threshold
+            && let Some(index) = matches.index_of("soft_threshold")
+        {
+            entries.push((index, CliMaskProcessingStep::SoftThreshold { low, high }));
+        }
         if let Some(radius) = self.dilate
             && let Some(index) = matches.index_of("dilate")
         {
             entries.push((index, CliMaskProcessingStep::Dilate(radius)));
         }
+        if let Some(radius) = self.open
+            && let Some(index) = matches.index_of("open")
+        {
+            entries.push((index, CliMaskProcessingStep::Open(radius)));
+        }
+        if let Some(radius) = self.close
+            && let Some(index) = matches.index_of("close")
+        {
+            entries.push((index, CliMaskProcessingStep::Close(radius)));
+        }
         if let Some(radius) = self.erode
             && let Some(index) = matches.index_of("erode")
         {
@@ -279,15 +1136,53 @@ impl MaskProcessingArgs {
                 },
             ));
         }
+        if let Some(connectivity) = self.keep_largest
+            && let Some(index) = matches.index_of("keep_largest")
+        {
+            entries.push((
+                index,
+                CliMaskProcessingStep::KeepLargest(connectivity.into()),
+            ));
+        }
+        if let Some(min_area) = self.min_island_area
+            && let Some(index) = matches.index_of("min_island_area")
+        {
+            entries.push((index, CliMaskProcessingStep::MinIslandArea(min_area)));
+        }
         if let Some(threshold) = self.fill_holes
             && let Some(index) = matches.index_of("fill_holes")
         {
             entries.push((index, CliMaskProcessingStep::FillHoles(threshold)));
         }
+        if let Some(threshold) = self.solidify
+            && let Some(index) = matches.index_of("solidify")
+        {
+            entries.push((index, CliMaskProcessingStep::Solidify(threshold)));
+        }
+        if let Some(levels) = self.posterize
+            && let Some(index) = matches.index_of("posterize")
+        {
+            entries.push((index, CliMaskProcessingStep::Posterize(levels)));
+        }
+        if let Some(gamma) = self.matte_gamma
+            && let Some(index) = matches.index_of("matte_gamma")
+        {
+            entries.push((index, CliMaskProcessingStep::Gamma(gamma)));
+        }
+        if self.invert_mask
+            && let Some(index) = matches.index_of("invert_mask")
+        {
+            entries.push((index, CliMaskProcessingStep::Invert));
+        }
 
         entries.sort_by_key(|(index, _)| *index);
         let user_steps = entries.into_iter().map(|(_, step)| step).collect();
-        self.ordered_steps = normalize_mask_steps(user_steps, self.no_implicit_threshold)?;
+        self.ordered_steps = normalize_mask_steps(
+            user_steps,
+            self.no_implicit_threshold,
+            defaults.mask_threshold,
+        )?;
+        self.defaults = defaults.clone();
         Ok(())
     }
 }
@@ -296,6 +1191,7 @@ impl MaskProcessingArgs {
 fn normalize_mask_steps(
     user_steps: Vec<CliMaskProcessingStep>,
     no_implicit_threshold: bool,
+    implicit_threshold: u8,
 ) -> Result<Vec<CliMaskProcessingStep>, clap::Error> {
     let mut steps = Vec::with_capacity(user_steps.len());
     let mut mask_state = MaskState::Soft;
@@ -307,13 +1203,17 @@ fn normalize_mask_steps(
             if no_implicit_threshold {
                 return Err(hard_mask_required_error(spec.option_name));
             }
-            steps.push(CliMaskProcessingStep::Threshold(
-                DEFAULT_MASK_THRESHOLD_VALUE,
-            ));
+            steps.push(CliMaskProcessingStep::Threshold(implicit_threshold));
         }
 
         steps.push(step);
-        mask_state = spec.mask_state_after;
+        // Gamma and inversion keep a hard mask hard and a soft mask soft.
+        if !matches!(
+            step,
+            CliMaskProcessingStep::Gamma(_) | CliMaskProcessingStep::Invert
+        ) {
+            mask_state = spec.mask_state_after;
+        }
     }
 
     Ok(steps)
@@ -346,13 +1246,28 @@ struct MaskStepSpec {
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub(crate) enum CliMaskProcessingStep {
     Blur(f32),
+    NormalizedBlur(f32),
+    Feather(f32),
     Threshold(u8),
+    OtsuThreshold,
+    SoftThreshold {
+        low: u8,
+        high: u8,
+    },
     Dilate(f32),
+    Open(f32),
+    Close(f32),
     Erode {
         radius: f32,
         border_mode: Option<ErosionBorderMode>,
     },
+    KeepLargest(Connectivity),
+    MinIslandArea(u32),
     FillHoles(u8),
+    Solidify(u8),
+    Posterize(u8),
+    Gamma(f32),
+    Invert,
 }
 
 impl CliMaskProcessingStep {
@@ -363,26 +1278,100 @@ impl CliMaskProcessingStep {
                 requires_hard_mask: false,
                 mask_state_after: MaskState::Soft,
             },
+            Self::NormalizedBlur(_) => MaskStepSpec {
+                option_name: "normalized-blur",
+                requires_hard_mask: false,
+                mask_state_after: MaskState::Soft,
+            },
+            // The ramp leaves soft values along the edge.
+            Self::Feather(_) => MaskStepSpec {
+                option_name: "feather",
+                requires_hard_mask: true,
+                mask_state_after: MaskState::Soft,
+            },
             Self::Threshold(_) => MaskStepSpec {
                 option_name: "threshold",
                 requires_hard_mask: false,
                 mask_state_after: MaskState::Hard,
             },
+            Self::OtsuThreshold => MaskStepSpec {
+                option_name: "auto-threshold",
+                requires_hard_mask: false,
+                mask_state_after: MaskState::Hard,
+            },
+            // The ramp keeps soft values unless both bounds are equal.
+            Self::SoftThreshold { low, high } => MaskStepSpec {
+                option_name: "soft-threshold",
+                requires_hard_mask: false,
+                mask_state_after: if low == high {
+                    MaskState::Hard
+                } else {
+                    MaskState::Soft
+                },
+            },
             Self::Dilate(_) => MaskStepSpec {
                 option_name: "dilate",
                 requires_hard_mask: true,
                 mask_state_after: MaskState::Hard,
             },
+            Self::Open(_) => MaskStepSpec {
+                option_name: "open",
+                requires_hard_mask: true,
+                mask_state_after: MaskState::Hard,
+            },
+            Self::Close(_) => MaskStepSpec {
+                option_name: "close",
+                requires_hard_mask: true,
+                mask_state_after: MaskState::Hard,
+            },
             Self::Erode { .. } => MaskStepSpec {
                 option_name: "erode",
                 requires_hard_mask: true,
                 mask_state_after: MaskState::Hard,
             },
+            Self::KeepLargest(_) => MaskStepSpec {
+                option_name: "keep-largest",
+                requires_hard_mask: true,
+                mask_state_after: MaskState::Hard,
+            },
+            Self::MinIslandArea(_) => MaskStepSpec {
+                option_name: "min-island-area",
+                requires_hard_mask: true,
+                mask_state_after: MaskState::Hard,
+            },
             Self::FillHoles(_) => MaskStepSpec {
                 option_name: "fill-holes",
                 requires_hard_mask: true,
                 mask_state_after: MaskState::Hard,
             },
+            // Soft edges are kept, so a later hard-mask step still needs a threshold.
+            Self::Solidify(_) => MaskStepSpec {
+                option_name: "solidify",
+                requires_hard_mask: false,
+                mask_state_after: MaskState::Soft,
+            },
+            // Two levels leave only 0 and 255, so the mask is hard afterwards.
+            Self::Posterize(levels) => MaskStepSpec {
+                option_name: "posterize",
+                requires_hard_mask: false,
+                mask_state_after: if levels == 2 {
+                    MaskState::Hard
+                } else {
+                    MaskState::Soft
+                },
+            },
+            // The states after gamma and inverting are whatever they were before; see
+            // `normalize_mask_steps`.
+            Self::Gamma(_) => MaskStepSpec {
+                option_name: "matte-gamma",
+                requires_hard_mask: false,
+                mask_state_after: MaskState::Soft,
+            },
+            Self::Invert => MaskStepSpec {
+                option_name: "invert-mask",
+                requires_hard_mask: false,
+                mask_state_after: MaskState::Soft,
+            },
         }
     }
 }
@@ -390,6 +1379,7 @@ impl CliMaskProcessingStep {
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) struct CliMaskProcessingRequest {
     steps: Vec<CliMaskProcessingStep>,
+    defaults: MaskProcessingDefaults,
 }
 
 impl CliMaskProcessingRequest {
@@ -398,16 +1388,29 @@ impl CliMaskProcessingRequest {
             assert!(
                 args.blur.is_none()
                     && args.threshold.is_none()
+                    && !args.auto_threshold
+                    && args.soft_threshold.is_none()
                     && args.dilate.is_none()
+                    && args.open.is_none()
+                    && args.close.is_none()
                     && args.erode.is_none()
                     && args.erode_border.is_none()
-                    && args.fill_holes.is_none(),
+                    && args.keep_largest.is_none()
+                    && args.min_island_area.is_none()
+                    && args.fill_holes.is_none()
+                    && args.normalized_blur.is_none()
+                    && args.feather.is_none()
+                    && args.solidify.is_none()
+                    && args.posterize.is_none()
+                    && args.matte_gamma.is_none()
+                    && !args.invert_mask,
                 "MaskProcessingArgs must be populated through Cli::try_parse_from before conversion"
             );
         }
 
         Self {
             steps: args.ordered_steps.clone(),
+            defaults: args.defaults.clone(),
         }
     }
 
@@ -416,14 +1419,24 @@ impl CliMaskProcessingRequest {
     }
 
     pub(crate) fn to_pipeline(&self) -> MaskPipeline {
-        let defaults = MaskProcessingDefaults::default();
+        let defaults = &self.defaults;
         let mut pipeline = MaskPipeline::new();
 
         for step in &self.steps {
             pipeline = match *step {
                 CliMaskProcessingStep::Blur(sigma) => pipeline.blur_with(sigma),
+                CliMaskProcessingStep::NormalizedBlur(sigma) => {
+                    pipeline.normalized_blur_with(sigma)
+                }
+                CliMaskProcessingStep::Feather(radius) => pipeline.feather(radius),
                 CliMaskProcessingStep::Threshold(value) => pipeline.threshold_with(value),
+                CliMaskProcessingStep::OtsuThreshold => pipeline.otsu_threshold(),
+                CliMaskProcessingStep::SoftThreshold { low, high } => {
+                    pipeline.soft_threshold(low, high)
+                }
                 CliMaskProcessingStep::Dilate(radius) => pipeline.dilate_with(radius),
+                CliMaskProcessingStep::Open(radius) => pipeline.open(radius),
+                CliMaskProcessingStep::Close(radius) => pipeline.close(radius),
                 CliMaskProcessingStep::Erode {
                     radius,
                     border_mode,
@@ -431,7 +1444,17 @@ impl CliMaskProcessingRequest {
                     radius,
                     border_mode.unwrap_or(defaults.erosion_border_mode),
                 ),
+                CliMaskProcessingStep::KeepLargest(connectivity) => {
+                    pipeline.keep_largest_with(connectivity)
+                }
+                CliMaskProcessingStep::MinIslandArea(min_area) => {
+                    pipeline.remove_small_components(min_area)
+                }
                 CliMaskProcessingStep::FillHoles(threshold) => pipeline.fill_holes_with(threshold),
+                CliMaskProcessingStep::Solidify(threshold) => pipeline.solidify(threshold),
+                CliMaskProcessingStep::Posterize(levels) => pipeline.posterize(levels),
+                CliMaskProcessingStep::Gamma(gamma) => pipeline.gamma(gamma),
+                CliMaskProcessingStep::Invert => pipeline.invert(),
             };
         }
 
@@ -454,6 +1477,25 @@ impl From<ErosionBorderArg> for ErosionBorderMode {
     }
 }
 
+#[derive(Clone, Copy, Debug, ValueEnum, PartialEq, Eq)]
+pub enum ConnectivityArg {
+    /// Pixels touch through an edge
+    #[value(name = "4")]
+    Four,
+    /// Pixels touch through an edge or a corner
+    #[value(name = "8")]
+    Eight,
+}
+
+impl From<ConnectivityArg> for Connectivity {
+    fn from(value: ConnectivityArg) -> Self {
+        match value {
+            ConnectivityArg::Four => Connectivity::Four,
+            ConnectivityArg::Eight => Connectivity::Eight,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, ValueEnum)]
 pub enum MaskExportSource {
     Auto,
@@ -461,6 +1503,18 @@ pub enum MaskExportSource {
     Processed,
 }
 
+/// PNG bit depth used when saving masks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum MaskBitDepthArg {
+    #[value(name = "8")]
+    Eight,
+    #[value(name = "1")]
+    One,
+    /// Keeps the float matte's precision; other masks are saved as 8-bit.
+    #[value(name = "16")]
+    Sixteen,
+}
+
 fn parse_mask_threshold(value: &str) -> Result<u8, String> {
     if let Ok(int_value) = value.parse::<u8>() {
         return Ok(int_value);
@@ -484,27 +1538,300 @@ fn parse_mask_threshold(value: &str) -> Result<u8, String> {
     ))
 }
 
-fn parse_model_input_size(value: &str) -> Result<ModelInputSize, String> {
-    let Some((height, width)) = value.split_once(['x', 'X']) else {
+fn parse_simplify_epsilon(value: &str) -> Result<f64, String> {
+    let epsilon = value
+        .parse::<f64>()
+        .map_err(|_| format!("simplify epsilon must be numeric, got `{value}`"))?;
+
+    if epsilon >= 0.0 && epsilon.is_finite() {
+        Ok(epsilon)
+    } else {
+        Err(format!(
+            "simplify epsilon must be a non-negative number, got `{value}`"
+        ))
+    }
+}
+
+fn parse_non_negative_radius(value: &str) -> Result<f32, String> {
+    let radius = value
+        .parse::<f32>()
+        .map_err(|_| format!("radius must be numeric, got `{value}`"))?;
+
+    if radius >= 0.0 && radius.is_finite() {
+        Ok(radius)
+    } else {
+        Err(format!(
+            "radius must be a non-negative number, got `{value}`"
+        ))
+    }
+}
+
+fn parse_blur_sigma(value: &str) -> Result<f32, String> {
+    let sigma = value
+        .parse::<f32>()
+        .map_err(|_| format!("sigma must be numeric, got `{value}`"))?;
+
+    if sigma > 0.0 && sigma.is_finite() {
+        Ok(sigma)
+    } else {
+        Err(format!("sigma must be a positive number, got `{value}`"))
+    }
+}
+
+fn parse_stroke_width(value: &str) -> Result<f64, String> {
+    let width = value
+        .parse::<f64>()
+        .map_err(|_| format!("stroke width must be numeric, got `{value}`"))?;
+
+    if width > 0.0 && width.is_finite() {
+        Ok(width)
+    } else {
+        Err(format!(
+            "stroke width must be a positive number, got `{value}`"
+        ))
+    }
+}
+
+fn parse_shadow(value: &str) -> Result<(i32, i32, f32), String> {
+    let parts: Vec<&str> = value.split(',').map(str::trim).collect();
+    let (dx, dy, blur) = match parts.as_slice() {
+        [dx, dy] => (dx, dy, DEFAULT_SHADOW_BLUR),
+        [dx, dy, blur] => (dx, dy, *blur),
+        _ => return Err(format!("expected `DX,DY[,BLUR]`, got `{value}`")),
+    };
+    let offset = |part: &str| {
+        part.parse::<i32>()
+            .map_err(|_| format!("shadow offset must be an integer, got `{part}`"))
+    };
+    let blur = parse_non_negative_radius(blur)
+        .map_err(|_| format!("shadow blur must be a non-negative number, got `{blur}`"))?;
+    Ok((offset(dx)?, offset(dy)?, blur))
+}
+
+fn parse_tile(value: &str) -> Result<TileOptions, String> {
+    let parts: Vec<&str> = value.split(',').map(str::trim).collect();
+    let (size, overlap) = match parts.as_slice() {
+        [size] => (size, None),
+        [size, overlap] => (size, Some(overlap)),
+        _ => return Err(format!("expected `SIZE[,OVERLAP]`, got `{value}`")),
+    };
+    let tile_size = size
+        .parse::<u32>()
+        .ok()
+        .filter(|&size| size > 0)
+        .ok_or_else(|| format!("tile size must be a positive integer, got `{size}`"))?;
+    let overlap = match overlap {
+        Some(overlap) => overlap
+            .parse::<u32>()
+            .map_err(|_| format!("tile overlap must be a non-negative integer, got `{overlap}`"))?,
+        None => tile_size / 8,
+    };
+    if overlap >= tile_size {
         return Err(format!(
-            "model input size must be HEIGHTxWIDTH, got `{value}`"
+            "tile overlap must be smaller than the tile size, got `{value}`"
         ));
-    };
+    }
+    Ok(TileOptions { tile_size, overlap })
+}
+
+fn parse_unit_interval(value: &str) -> Result<f32, String> {
+    let parsed = value
+        .parse::<f32>()
+        .map_err(|_| format!("value must be numeric, got `{value}`"))?;
 
-    let height = height
-        .parse::<usize>()
-        .map_err(|_| format!("model input height must be an integer, got `{height}`"))?;
-    let width = width
-        .parse::<usize>()
-        .map_err(|_| format!("model input width must be an integer, got `{width}`"))?;
+    if (0.0..=1.0).contains(&parsed) {
+        Ok(parsed)
+    } else {
+        Err(format!("value must be between 0.0 and 1.0, got `{value}`"))
+    }
+}
 
-    if height == 0 || width == 0 {
-        return Err(format!("model input size must be non-zero, got `{value}`"));
+fn parse_infer_scale(value: &str) -> Result<f32, String> {
+    let parsed = value
+        .parse::<f32>()
+        .map_err(|_| format!("scale must be numeric, got `{value}`"))?;
+
+    if parsed > 0.0 && parsed <= 1.0 {
+        Ok(parsed)
+    } else {
+        Err(format!(
+            "scale must be greater than 0.0 and at most 1.0, got `{value}`"
+        ))
+    }
+}
+
+fn parse_soft_threshold(value: &str) -> Result<(u8, u8), String> {
+    let (low, high) = value
+        .split_once(',')
+        .ok_or_else(|| format!("expected `LOW,HIGH`, got `{value}`"))?;
+    let low = parse_mask_threshold(low.trim())?;
+    let high = parse_mask_threshold(high.trim())?;
+
+    if low <= high {
+        Ok((low, high))
+    } else {
+        Err(format!("LOW must not be above HIGH, got `{value}`"))
+    }
+}
+
+fn parse_gamma(value: &str) -> Result<f32, String> {
+    let gamma = value
+        .parse::<f32>()
+        .map_err(|_| format!("gamma must be numeric, got `{value}`"))?;
+
+    if gamma > 0.0 && gamma.is_finite() {
+        Ok(gamma)
+    } else {
+        Err(format!("gamma must be greater than 0.0, got `{value}`"))
+    }
+}
+
+fn parse_color(value: &str) -> Result<[u8; 4], String> {
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!(
+            "color must be hex `#RRGGBB` or `#RRGGBBAA`, got `{value}`"
+        ));
+    }
+
+    let mut color = [255u8; 4];
+    for (channel, pair) in color.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        let pair = std::str::from_utf8(pair).expect("hex digits are ASCII");
+        *channel = u8::from_str_radix(pair, 16).expect("validated hex digits");
+    }
+    Ok(color)
+}
+
+// A flattened image has no alpha left to keep, so a translucent color is rejected rather than
+// having its alpha dropped.
+fn parse_opaque_color(value: &str) -> Result<[u8; 4], String> {
+    let color = parse_color(value)?;
+    if color[3] != 255 {
+        return Err(format!(
+            "color must be opaque (`#RRGGBB` or alpha `ff`), got `{value}`"
+        ));
+    }
+    Ok(color)
+}
+
+fn parse_gradient(value: &str) -> Result<Gradient, String> {
+    let parts: Vec<&str> = value.split(',').map(str::trim).collect();
+    match parts.as_slice() {
+        ["linear", start, end, rest @ ..] if rest.len() <= 1 => {
+            let angle = match rest.first() {
+                Some(angle) => angle
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|angle| angle.is_finite())
+                    .ok_or_else(|| format!("gradient angle must be a number, got `{angle}`"))?,
+                None => 0.0,
+            };
+            Ok(Gradient::Linear {
+                start: parse_color(start)?,
+                end: parse_color(end)?,
+                angle,
+            })
+        }
+        ["radial", center, edge] => Ok(Gradient::Radial {
+            center: parse_color(center)?,
+            edge: parse_color(edge)?,
+        }),
+        _ => Err(format!(
+            "gradient must be `linear,START,END[,ANGLE]` or `radial,CENTER,EDGE`, got `{value}`"
+        )),
+    }
+}
+
+fn parse_timeout(value: &str) -> Result<Duration, String> {
+    let seconds = value
+        .parse::<f64>()
+        .map_err(|_| format!("timeout must be a number of seconds, got `{value}`"))?;
+
+    if seconds > 0.0 && seconds.is_finite() {
+        Duration::try_from_secs_f64(seconds)
+            .map_err(|_| format!("timeout is out of range, got `{value}`"))
+    } else {
+        Err(format!("timeout must be a positive number, got `{value}`"))
+    }
+}
+
+fn parse_device(value: &str) -> Result<ExecutionProvider, String> {
+    let (name, index) = match value.split_once(':') {
+        Some((name, index)) => (name, Some(index)),
+        None => (value, None),
+    };
+    match (name.to_ascii_lowercase().as_str(), index) {
+        ("cpu", None) => Ok(ExecutionProvider::Cpu),
+        #[cfg(feature = "cuda")]
+        ("cuda", index) => {
+            let device_id =
+                match index {
+                    Some(index) => index.parse::<i32>().ok().filter(|&id| id >= 0).ok_or_else(
+                        || {
+                            format!(
+                                "CUDA device index must be a non-negative integer, got `{index}`"
+                            )
+                        },
+                    )?,
+                    None => 0,
+                };
+            Ok(ExecutionProvider::Cuda { device_id })
+        }
+        #[cfg(not(feature = "cuda"))]
+        ("cuda", _) => Err("CUDA support requires building with the `cuda` feature".to_string()),
+        #[cfg(feature = "coreml")]
+        ("coreml", None) => Ok(ExecutionProvider::CoreML),
+        #[cfg(not(feature = "coreml"))]
+        ("coreml", None) => {
+            Err("CoreML support requires building with the `coreml` feature".to_string())
+        }
+        _ => Err(format!(
+            "device must be `cpu`, `cuda[:N]`, or `coreml`, got `{value}`"
+        )),
+    }
+}
+
+// Parse an output index, or any other text as an output name.
+fn parse_output_selector(value: &str) -> Result<OutputSelector, String> {
+    if value.is_empty() {
+        return Err("model output must be a name or an index".to_string());
     }
+    Ok(value.parse().map_or_else(
+        |_| OutputSelector::Name(value.to_string()),
+        OutputSelector::Index,
+    ))
+}
+
+fn parse_model_input_size(value: &str) -> Result<ModelInputSize, String> {
+    let [height, width] = parse_size_pair(value, ["height", "width"])?;
+    Ok(ModelInputSize::new(height, width))
+}
 
+fn parse_input_size(value: &str) -> Result<ModelInputSize, String> {
+    let [width, height] = parse_size_pair(value, ["width", "height"])?;
     Ok(ModelInputSize::new(height, width))
 }
 
+// Parse two non-zero sizes separated by `x`, named by `axes` in the order they are written.
+fn parse_size_pair(value: &str, axes: [&str; 2]) -> Result<[usize; 2], String> {
+    let format = format!("{}x{}", axes[0].to_uppercase(), axes[1].to_uppercase());
+    let Some((first, second)) = value.split_once(['x', 'X']) else {
+        return Err(format!("model input size must be {format}, got `{value}`"));
+    };
+
+    let mut sizes = [0; 2];
+    for ((size, text), axis) in sizes.iter_mut().zip([first, second]).zip(axes) {
+        *size = text
+            .parse::<usize>()
+            .map_err(|_| format!("model input {axis} must be an integer, got `{text}`"))?;
+    }
+
+    if sizes.contains(&0) {
+        return Err(format!("model input size must be non-zero, got `{value}`"));
+    }
+    Ok(sizes)
+}
+
 /// The argument to specify which alpha source to use.
 #[derive(Clone, Copy, Debug, ValueEnum)]
 pub enum AlphaFromArg {
@@ -615,6 +1942,112 @@ pub struct TraceOptionsArgs {
     /// Invert foreground/background in the output SVG
     #[arg(long = "invert-svg")]
     pub invert_svg: bool,
+    /// Drop traced paths enclosing less than this many square pixels
+    #[arg(long = "min-path-area", value_name = "AREA", default_value_t = 0.0)]
+    pub min_path_area: f64,
+    /// Smooth the mask outline with this radius in pixels just before tracing
+    #[arg(long = "trace-smooth", value_name = "RADIUS", value_parser = parse_non_negative_radius)]
+    pub trace_smooth: Option<f32>,
+    /// Draw the traced paths as outlines with no fill (for plotters and laser cutters)
+    #[arg(long = "stroke-only")]
+    pub stroke_only: bool,
+    /// Outline width in pixels for --stroke-only
+    #[arg(
+        long = "stroke-width",
+        value_name = "PX",
+        default_value_t = 1.0,
+        value_parser = parse_stroke_width,
+        requires = "stroke_only"
+    )]
+    pub stroke_width: f64,
+    #[arg(skip)]
+    pub(crate) explicit: Vec<&'static str>,
+}
+
+/// Argument ids of the trace options, used to tell explicit flags from clap defaults.
+const TRACE_OPTION_IDS: [&str; 17] = [
+    "color_mode",
+    "hierarchy",
+    "mode",
+    "filter_speckle",
+    "color_precision",
+    "layer_difference",
+    "corner_threshold",
+    "length_threshold",
+    "max_iterations",
+    "splice_threshold",
+    "path_precision",
+    "no_path_precision",
+    "invert_svg",
+    "min_path_area",
+    "trace_smooth",
+    "stroke_only",
+    "stroke_width",
+];
+
+impl TraceOptionsArgs {
+    fn populate_explicit(&mut self, matches: &ArgMatches) {
+        self.explicit = TRACE_OPTION_IDS
+            .into_iter()
+            .filter(|id| matches.value_source(id) == Some(ValueSource::CommandLine))
+            .collect();
+    }
+
+    fn is_explicit(&self, id: &str) -> bool {
+        self.explicit.contains(&id)
+    }
+
+    /// Override `base` (e.g. a loaded trace profile) with the flags given on the command line.
+    pub(crate) fn apply_to(&self, base: TraceOptions) -> TraceOptions {
+        let args = TraceOptions::from(self);
+        let mut options = base;
+        if self.is_explicit("color_mode") {
+            options.tracer_color_mode = args.tracer_color_mode;
+        }
+        if self.is_explicit("hierarchy") {
+            options.tracer_hierarchical = args.tracer_hierarchical;
+        }
+        if self.is_explicit("mode") {
+            options.tracer_mode = args.tracer_mode;
+        }
+        if self.is_explicit("filter_speckle") {
+            options.tracer_filter_speckle = args.tracer_filter_speckle;
+        }
+        if self.is_explicit("color_precision") {
+            options.tracer_color_precision = args.tracer_color_precision;
+        }
+        if self.is_explicit("layer_difference") {
+            options.tracer_layer_difference = args.tracer_layer_difference;
+        }
+        if self.is_explicit("corner_threshold") {
+            options.tracer_corner_threshold = args.tracer_corner_threshold;
+        }
+        if self.is_explicit("length_threshold") {
+            options.tracer_length_threshold = args.tracer_length_threshold;
+        }
+        if self.is_explicit("max_iterations") {
+            options.tracer_max_iterations = args.tracer_max_iterations;
+        }
+        if self.is_explicit("splice_threshold") {
+            options.tracer_splice_threshold = args.tracer_splice_threshold;
+        }
+        if self.is_explicit("path_precision") || self.is_explicit("no_path_precision") {
+            options.tracer_path_precision = args.tracer_path_precision;
+        }
+        if self.is_explicit("invert_svg") {
+            options.invert_svg = args.invert_svg;
+        }
+        if self.is_explicit("min_path_area") {
+            options.min_path_area = args.min_path_area;
+        }
+        if self.is_explicit("trace_smooth") {
+            options.pre_smooth = args.pre_smooth;
+        }
+        if self.is_explicit("stroke_only") || self.is_explicit("stroke_width") {
+            options.output_style = args.output_style;
+        }
+        options
+    }
 }
 
 impl From<&TraceOptionsArgs> for TraceOptions {
@@ -638,6 +2071,13 @@ impl From<&TraceOptionsArgs> for TraceOptions {
             tracer_splice_threshold: args.splice_threshold,
             tracer_path_precision,
             invert_svg: args.invert_svg,
+            min_path_area: args.min_path_area,
+            pre_smooth: args.trace_smooth,
+            output_style: if args.stroke_only {
+                TraceOutputStyle::stroke(args.stroke_width)
+            } else {
+                TraceOutputStyle::Fill
+            },
         }
     }
 }
@@ -645,6 +2085,7 @@ impl From<&TraceOptionsArgs> for TraceOptions {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use image::imageops::FilterType;
 
     mod parse_mask_threshold {
         use super::*;
@@ -743,6 +2184,30 @@ mod tests {
         }
     }
 
+    mod parse_tile {
+        use super::*;
+
+        #[test]
+        fn overlap_defaults_to_an_eighth() {
+            let tile = parse_tile("512").unwrap();
+            assert_eq!(
+                tile,
+                TileOptions {
+                    tile_size: 512,
+                    overlap: 64,
+                }
+            );
+            assert_eq!(parse_tile("320, 16").unwrap().overlap, 16);
+        }
+
+        #[test]
+        fn rejects_invalid_values() {
+            for value in ["0", "512,512", "512,-1", "x", "512,16,2"] {
+                assert!(parse_tile(value).is_err(), "{value}");
+            }
+        }
+    }
+
     mod parse_model_input_size {
         use super::*;
 
@@ -777,6 +2242,152 @@ mod tests {
             assert!(parse_model_input_size("0x1024").is_err());
             assert!(parse_model_input_size("1024x0").is_err());
         }
+
+        #[test]
+        fn input_size_is_width_first_and_echoes_the_input() {
+            let size = parse_input_size("1024x768").unwrap();
+            assert_eq!((size.width(), size.height()), (1024, 768));
+
+            let err = parse_input_size("1024xabc").unwrap_err();
+            assert_eq!(err, "model input height must be an integer, got `abc`");
+            let err = parse_input_size("0x768").unwrap_err();
+            assert_eq!(err, "model input size must be non-zero, got `0x768`");
+            let err = parse_input_size("1024").unwrap_err();
+            assert_eq!(err, "model input size must be WIDTHxHEIGHT, got `1024`");
+        }
+    }
+
+    mod parse_color {
+        use super::*;
+
+        #[test]
+        fn parses_rgb_with_opaque_alpha() {
+            assert_eq!(parse_color("#ff8000").unwrap(), [255, 128, 0, 255]);
+            assert_eq!(parse_color("FF8000").unwrap(), [255, 128, 0, 255]);
+        }
+
+        #[test]
+        fn parses_rgba() {
+            assert_eq!(parse_color("#00000080").unwrap(), [0, 0, 0, 128]);
+        }
+
+        #[test]
+        fn rejects_invalid_values() {
+            assert!(parse_color("#fff").is_err());
+            assert!(parse_color("#gg0000").is_err());
+            assert!(parse_color("white").is_err());
+            assert!(parse_color("").is_err());
+        }
+    }
+
+    mod parse_gradient {
+        use super::*;
+
+        #[test]
+        fn parses_linear_with_angle() {
+            assert_eq!(
+                parse_gradient("linear,#ffffff,#000000,90").unwrap(),
+                Gradient::Linear {
+                    start: [255, 255, 255, 255],
+                    end: [0, 0, 0, 255],
+                    angle: 90.0,
+                }
+            );
+        }
+
+        #[test]
+        fn linear_angle_defaults_to_zero() {
+            assert_eq!(
+                parse_gradient("linear, #ff0000, #0000ff80").unwrap(),
+                Gradient::Linear {
+                    start: [255, 0, 0, 255],
+                    end: [0, 0, 255, 128],
+                    angle: 0.0,
+                }
+            );
+        }
+
+        #[test]
+        fn parses_radial() {
+            assert_eq!(
+                parse_gradient("radial,#ffffff,#808080").unwrap(),
+                Gradient::Radial {
+                    center: [255, 255, 255, 255],
+                    edge: [128, 128, 128, 255],
+                }
+            );
+        }
+
+        #[test]
+        fn rejects_invalid_values() {
+            assert!(parse_gradient("conic,#ffffff,#000000").is_err());
+            assert!(parse_gradient("linear,#ffffff").is_err());
+            assert!(parse_gradient("linear,#ffffff,#000000,north").is_err());
+            assert!(parse_gradient("linear,#ffffff,#000000,90,1").is_err());
+            assert!(parse_gradient("radial,#ffffff,#000000,90").is_err());
+            assert!(parse_gradient("radial,#ffffff,white").is_err());
+        }
+    }
+
+    mod parse_timeout {
+        use super::*;
+
+        #[test]
+        fn accepts_fractional_seconds() {
+            assert_eq!(parse_timeout("1.5").unwrap(), Duration::from_millis(1500));
+            assert_eq!(parse_timeout("30").unwrap(), Duration::from_secs(30));
+        }
+
+        #[test]
+        fn rejects_non_positive_values() {
+            assert!(parse_timeout("0").is_err());
+            assert!(parse_timeout("-1").is_err());
+        }
+
+        #[test]
+        fn rejects_invalid_values() {
+            assert!(parse_timeout("inf").is_err());
+            assert!(parse_timeout("NaN").is_err());
+            assert!(parse_timeout("1e300").is_err());
+            assert!(parse_timeout("soon").is_err());
+        }
+    }
+
+    mod parse_non_negative_radius {
+        use super::*;
+
+        #[test]
+        fn accepts_zero_and_positive_values() {
+            assert_eq!(parse_non_negative_radius("0").unwrap(), 0.0);
+            assert_eq!(parse_non_negative_radius("12.5").unwrap(), 12.5);
+        }
+
+        #[test]
+        fn rejects_negative_and_non_finite_values() {
+            assert!(parse_non_negative_radius("-1").is_err());
+            assert!(parse_non_negative_radius("NaN").is_err());
+            assert!(parse_non_negative_radius("inf").is_err());
+            assert!(parse_non_negative_radius("abc").is_err());
+        }
+    }
+
+    mod parse_stroke_width {
+        use super::*;
+
+        #[test]
+        fn accepts_positive_values() {
+            assert_eq!(parse_stroke_width("0.5").unwrap(), 0.5);
+            assert_eq!(parse_stroke_width("2").unwrap(), 2.0);
+        }
+
+        #[test]
+        fn rejects_zero_negative_and_non_finite_values() {
+            assert!(parse_stroke_width("0").is_err());
+            assert!(parse_stroke_width("-1").is_err());
+            assert!(parse_stroke_width("NaN").is_err());
+            assert!(parse_stroke_width("inf").is_err());
+            assert!(parse_stroke_width("wide").is_err());
+        }
     }
 
     mod from_implementations {
@@ -809,6 +2420,47 @@ mod tests {
                 ));
             }
 
+            #[test]
+            fn matte_activation_arg_to_matte_activation() {
+                assert_eq!(
+                    MatteActivation::from(MatteActivationArg::None),
+                    MatteActivation::None
+                );
+                assert_eq!(
+                    MatteActivation::from(MatteActivationArg::Sigmoid),
+                    MatteActivation::Sigmoid
+                );
+                assert_eq!(
+                    MatteActivation::from(MatteActivationArg::Clamp),
+                    MatteActivation::Clamp
+                );
+            }
+
+            #[test]
+            fn matte_combine_arg_to_matte_combine() {
+                assert_eq!(
+                    MatteCombine::from(MatteCombineArg::Multiply),
+                    MatteCombine::Multiply
+                );
+                assert_eq!(MatteCombine::from(MatteCombineArg::Min), MatteCombine::Min);
+                assert_eq!(
+                    MatteCombine::from(MatteCombineArg::Average),
+                    MatteCombine::Average
+                );
+            }
+
+            #[test]
+            fn input_layout_arg_to_model_input_layout() {
+                assert_eq!(
+                    ModelInputLayout::from(InputLayoutArg::Nchw),
+                    ModelInputLayout::Nchw
+                );
+                assert_eq!(
+                    ModelInputLayout::from(InputLayoutArg::Nhwc),
+                    ModelInputLayout::Nhwc
+                );
+            }
+
             #[test]
             fn tracer_color_mode_to_color_mode() {
                 assert!(matches!(
@@ -858,13 +2510,26 @@ mod tests {
         fn default_args() -> MaskProcessingArgs {
             MaskProcessingArgs {
                 blur: None,
+                normalized_blur: None,
+                feather: None,
                 threshold: None,
+                auto_threshold: false,
+                soft_threshold: None,
                 no_implicit_threshold: false,
                 dilate: None,
+                open: None,
+                close: None,
                 erode: None,
                 erode_border: None,
+                keep_largest: None,
+                min_island_area: None,
                 fill_holes: None,
+                solidify: None,
+                posterize: None,
+                matte_gamma: None,
+                invert_mask: false,
                 ordered_steps: vec![],
+                defaults: MaskProcessingDefaults::default(),
             }
         }
 
@@ -891,7 +2556,6 @@ mod tests {
                     parse_mask_threshold(DEFAULT_MASK_THRESHOLD).unwrap(),
                     defaults.mask_threshold
                 );
-                assert_eq!(DEFAULT_MASK_THRESHOLD_VALUE, defaults.mask_threshold);
                 assert_eq!(
                     DEFAULT_DILATION_RADIUS.parse::<f32>().unwrap(),
                     defaults.dilation_radius
@@ -1201,6 +2865,11 @@ mod tests {
                 path_precision: None,
                 no_path_precision: false,
                 invert_svg: false,
+                min_path_area: 0.0,
+                trace_smooth: None,
+                stroke_only: false,
+                stroke_width: 1.0,
+                explicit: Vec::new(),
             }
         }
 
@@ -1322,9 +2991,97 @@ mod tests {
                 }
 
                 #[test]
-                fn export_mask_absent_is_none() {
+                fn channel_dump_takes_path() {
                     let cmd = parse_cmd!(["outline", "cut", "in.png"], Cut);
-                    assert!(cmd.export_mask.is_none());
+                    assert!(cmd.channel_dump.is_none());
+
+                    let cmd = parse_cmd!(
+                        ["outline", "cut", "in.png", "--channel-dump", "channels.png"],
+                        Cut
+                    );
+                    assert_eq!(cmd.channel_dump, Some(PathBuf::from("channels.png")));
+                }
+
+                #[test]
+                fn stats_flag_on_cut_and_mask() {
+                    let cmd = parse_cmd!(["outline", "cut", "in.png"], Cut);
+                    assert!(!cmd.stats);
+                    let cmd = parse_cmd!(["outline", "cut", "in.png", "--stats"], Cut);
+                    assert!(cmd.stats);
+                    let cmd = parse_cmd!(["outline", "mask", "in.png", "--stats"], Mask);
+                    assert!(cmd.stats);
+                }
+
+                #[test]
+                fn padding_requires_autocrop() {
+                    let cmd = parse_cmd!(["outline", "cut", "in.png", "--autocrop"], Cut);
+                    assert!(cmd.autocrop);
+                    assert_eq!(cmd.padding, 0);
+
+                    let cmd = parse_cmd!(
+                        ["outline", "cut", "in.png", "--autocrop", "--padding", "12"],
+                        Cut
+                    );
+                    assert_eq!(cmd.padding, 12);
+
+                    assert!(
+                        Cli::try_parse_from(["outline", "cut", "in.png", "--padding", "12"])
+                            .is_err()
+                    );
+                }
+
+                #[test]
+                fn preview_takes_path_and_cell_size() {
+                    let cmd = parse_cmd!(["outline", "cut", "in.png", "--preview", "p.png"], Cut);
+                    assert_eq!(cmd.preview, Some(PathBuf::from("p.png")));
+                    assert_eq!(cmd.preview_cell, 16);
+
+                    let cmd = parse_cmd!(
+                        [
+                            "outline",
+                            "cut",
+                            "in.png",
+                            "--preview",
+                            "p.png",
+                            "--preview-cell",
+                            "8"
+                        ],
+                        Cut
+                    );
+                    assert_eq!(cmd.preview_cell, 8);
+
+                    for extra in [
+                        &["--preview-cell", "8"][..],
+                        &["--preview", "p.png", "--preview-cell", "0"],
+                    ] {
+                        let mut args = vec!["outline", "cut", "in.png"];
+                        args.extend_from_slice(extra);
+                        assert!(Cli::try_parse_from(args).is_err(), "{extra:?}");
+                    }
+                }
+
+                #[test]
+                fn confidence_takes_path_on_cut_and_mask() {
+                    let cmd = parse_cmd!(["outline", "cut", "in.png"], Cut);
+                    assert!(cmd.confidence.is_none());
+
+                    let cmd = parse_cmd!(
+                        ["outline", "cut", "in.png", "--confidence", "conf.png"],
+                        Cut
+                    );
+                    assert_eq!(cmd.confidence, Some(PathBuf::from("conf.png")));
+
+                    let cmd = parse_cmd!(
+                        ["outline", "mask", "in.png", "--confidence", "conf.png"],
+                        Mask
+                    );
+                    assert_eq!(cmd.confidence, Some(PathBuf::from("conf.png")));
+                }
+
+                #[test]
+                fn export_mask_absent_is_none() {
+                    let cmd = parse_cmd!(["outline", "cut", "in.png"], Cut);
+                    assert!(cmd.export_mask.is_none());
                 }
 
                 #[test]
@@ -1522,6 +3279,38 @@ mod tests {
                     ));
                 }
 
+                #[test]
+                fn reprocess_preserves_operation_order() {
+                    let cmd = parse_cmd!(
+                        [
+                            "outline",
+                            "reprocess",
+                            "mattes",
+                            "--sources",
+                            "photos",
+                            "--dilate",
+                            "3.0",
+                            "--blur",
+                            "1.5"
+                        ],
+                        Reprocess
+                    );
+                    assert_eq!(cmd.mattes, PathBuf::from("mattes"));
+                    assert_eq!(cmd.sources, Some(PathBuf::from("photos")));
+                    assert!(cmd.output.is_none());
+
+                    let pipeline = pipeline(&cmd.mask_processing);
+                    assert!(matches!(
+                        pipeline.operations(),
+                        [
+                            MaskOperation::Threshold { value: 120 },
+                            MaskOperation::Dilate { radius },
+                            MaskOperation::Blur { sigma },
+                        ] if (*radius - 3.0).abs() < f32::EPSILON
+                            && (*sigma - 1.5).abs() < f32::EPSILON
+                    ));
+                }
+
                 #[test]
                 fn repeated_operation_flag_is_rejected() {
                     let result = Cli::try_parse_from([
@@ -1572,29 +3361,57 @@ mod tests {
                 }
 
                 #[test]
-                fn flag_only_fill_holes_is_preserved_in_ordered_pipeline() {
-                    let cmd = parse_cmd!(["outline", "mask", "in.png", "--fill-holes"], Mask);
+                fn posterize_keeps_its_position_without_implicit_threshold() {
+                    let cmd = parse_cmd!(
+                        ["outline", "cut", "in.png", "--blur", "--posterize", "4"],
+                        Cut
+                    );
                     let pipeline = pipeline(&cmd.mask_processing);
 
                     assert!(matches!(
                         pipeline.operations(),
                         [
-                            MaskOperation::Threshold { value: 120 },
-                            MaskOperation::FillHoles { threshold: 120 },
+                            MaskOperation::Blur { .. },
+                            MaskOperation::Posterize { levels: 4 },
                         ]
                     ));
                 }
 
                 #[test]
-                fn fill_holes_threshold_is_passed_through() {
+                fn two_level_posterize_counts_as_hard_mask() {
+                    let cmd = parse_cmd!(
+                        [
+                            "outline",
+                            "mask",
+                            "in.png",
+                            "--no-implicit-threshold",
+                            "--posterize",
+                            "2",
+                            "--dilate"
+                        ],
+                        Mask
+                    );
+                    let pipeline = pipeline(&cmd.mask_processing);
+
+                    assert!(matches!(
+                        pipeline.operations(),
+                        [
+                            MaskOperation::Posterize { levels: 2 },
+                            MaskOperation::Dilate { .. },
+                        ]
+                    ));
+                }
+
+                #[test]
+                fn invert_mask_keeps_a_hard_mask_hard() {
                     let cmd = parse_cmd!(
                         [
                             "outline",
                             "mask",
                             "in.png",
                             "--threshold",
-                            "--fill-holes",
-                            "180"
+                            "--invert-mask",
+                            "--dilate"
                         ],
                         Mask
                     );
@@ -1604,16 +3421,22 @@ mod tests {
                         pipeline.operations(),
                         [
                             MaskOperation::Threshold { value: 120 },
-                            MaskOperation::FillHoles { threshold: 180 },
+                            MaskOperation::Invert,
+                            MaskOperation::Dilate { .. },
                         ]
                     ));
                 }
 
                 #[test]
-                fn auto_threshold_is_inserted_before_first_hard_mask_operation() {
+                fn matte_gamma_runs_before_the_implicit_threshold() {
                     let cmd = parse_cmd!(
                         [
-                            "outline", "mask", "in.png", "--dilate", "5.0", "--blur", "2.0"
+                            "outline",
+                            "mask",
+                            "in.png",
+                            "--matte-gamma",
+                            "2",
+                            "--dilate"
                         ],
                         Mask
                     );
@@ -1622,36 +3445,60 @@ mod tests {
                     assert!(matches!(
                         pipeline.operations(),
                         [
+                            MaskOperation::Gamma { gamma },
                             MaskOperation::Threshold { value: 120 },
-                            MaskOperation::Dilate { radius },
-                            MaskOperation::Blur { sigma },
-                        ] if (*radius - 5.0).abs() < f32::EPSILON
-                            && (*sigma - 2.0).abs() < f32::EPSILON
+                            MaskOperation::Dilate { .. },
+                        ] if *gamma == 2.0
                     ));
                 }
 
                 #[test]
-                fn threshold_alone_materializes_threshold() {
-                    let cmd = parse_cmd!(["outline", "mask", "in.png", "--threshold", "200"], Mask);
+                fn invert_mask_keeps_a_soft_mask_soft() {
+                    let cmd = parse_cmd!(
+                        ["outline", "cut", "in.png", "--invert-mask", "--dilate"],
+                        Cut
+                    );
                     let pipeline = pipeline(&cmd.mask_processing);
 
                     assert!(matches!(
                         pipeline.operations(),
-                        [MaskOperation::Threshold { value: 200 }]
+                        [
+                            MaskOperation::Invert,
+                            MaskOperation::Threshold { value: 120 },
+                            MaskOperation::Dilate { .. },
+                        ]
                     ));
                 }
 
                 #[test]
-                fn threshold_prevents_duplicate_implicit_threshold() {
+                fn solidify_runs_on_soft_mask_and_keeps_it_soft() {
+                    let cmd = parse_cmd!(
+                        ["outline", "mask", "in.png", "--solidify", "--dilate"],
+                        Mask
+                    );
+                    let pipeline = pipeline(&cmd.mask_processing);
+
+                    assert!(matches!(
+                        pipeline.operations(),
+                        [
+                            MaskOperation::Solidify { threshold: 120 },
+                            MaskOperation::Threshold { value: 120 },
+                            MaskOperation::Dilate { .. },
+                        ]
+                    ));
+                }
+
+                #[test]
+                fn normalized_blur_keeps_order_and_default_sigma() {
                     let cmd = parse_cmd!(
                         [
                             "outline",
                             "mask",
                             "in.png",
                             "--threshold",
-                            "200",
-                            "--dilate",
-                            "5.0"
+                            "--normalized-blur",
+                            "--blur",
+                            "2"
                         ],
                         Mask
                     );
@@ -1660,23 +3507,23 @@ mod tests {
                     assert!(matches!(
                         pipeline.operations(),
                         [
-                            MaskOperation::Threshold { value: 200 },
-                            MaskOperation::Dilate { radius },
-                        ] if (*radius - 5.0).abs() < f32::EPSILON
+                            MaskOperation::Threshold { value: 120 },
+                            MaskOperation::NormalizedBlur { sigma: 6.0 },
+                            MaskOperation::Blur { sigma: 2.0 },
+                        ]
                     ));
                 }
 
                 #[test]
-                fn late_threshold_does_not_prevent_earlier_implicit_threshold() {
+                fn auto_threshold_satisfies_hard_mask_steps() {
                     let cmd = parse_cmd!(
                         [
                             "outline",
                             "mask",
                             "in.png",
-                            "--dilate",
-                            "5.0",
-                            "--threshold",
-                            "200"
+                            "--blur",
+                            "--auto-threshold",
+                            "--dilate"
                         ],
                         Mask
                     );
@@ -1685,273 +3532,1599 @@ mod tests {
                     assert!(matches!(
                         pipeline.operations(),
                         [
-                            MaskOperation::Threshold { value: 120 },
-                            MaskOperation::Dilate { radius },
-                            MaskOperation::Threshold { value: 200 },
-                        ] if (*radius - 5.0).abs() < f32::EPSILON
+                            MaskOperation::Blur { .. },
+                            MaskOperation::OtsuThreshold,
+                            MaskOperation::Dilate { .. },
+                        ]
                     ));
                 }
 
                 #[test]
-                fn no_implicit_threshold_rejects_hard_mask_operation_without_threshold() {
-                    let result = Cli::try_parse_from([
-                        "outline",
-                        "mask",
-                        "in.png",
-                        "--no-implicit-threshold",
-                        "--dilate",
-                        "5.0",
-                    ]);
+                fn open_and_close_need_a_hard_mask() {
+                    let cmd = parse_cmd!(
+                        ["outline", "mask", "in.png", "--close", "2", "--open", "1.5"],
+                        Mask
+                    );
+                    let pipeline = pipeline(&cmd.mask_processing);
 
-                    assert!(result.is_err());
+                    assert!(matches!(
+                        pipeline.operations(),
+                        [
+                            MaskOperation::Threshold { value: 120 },
+                            MaskOperation::Close { radius: 2.0 },
+                            MaskOperation::Open { radius: 1.5 },
+                        ]
+                    ));
+
+                    assert!(
+                        Cli::try_parse_from([
+                            "outline",
+                            "mask",
+                            "in.png",
+                            "--no-implicit-threshold",
+                            "--open",
+                            "1"
+                        ])
+                        .is_err()
+                    );
+                    assert!(
+                        Cli::try_parse_from(["outline", "mask", "in.png", "--close", "-1"])
+                            .is_err()
+                    );
                 }
 
                 #[test]
-                fn no_implicit_threshold_accepts_explicit_threshold_before_hard_mask_operation() {
+                fn keep_largest_defaults_to_eight_connectivity() {
+                    let cmd = parse_cmd!(["outline", "mask", "in.png", "--keep-largest"], Mask);
+
+                    assert!(matches!(
+                        pipeline(&cmd.mask_processing).operations(),
+                        [
+                            MaskOperation::Threshold { value: 120 },
+                            MaskOperation::KeepLargestComponent {
+                                connectivity: Connectivity::Eight
+                            },
+                        ]
+                    ));
+
                     let cmd = parse_cmd!(
                         [
                             "outline",
-                            "mask",
+                            "cut",
                             "in.png",
-                            "--no-implicit-threshold",
                             "--threshold",
-                            "--dilate",
-                            "5.0"
+                            "--keep-largest",
+                            "4"
                         ],
-                        Mask
+                        Cut
                     );
-                    let pipeline = pipeline(&cmd.mask_processing);
 
                     assert!(matches!(
-                        pipeline.operations(),
+                        pipeline(&cmd.mask_processing).operations(),
                         [
                             MaskOperation::Threshold { value: 120 },
-                            MaskOperation::Dilate { radius },
-                        ] if (*radius - 5.0).abs() < f32::EPSILON
+                            MaskOperation::KeepLargestComponent {
+                                connectivity: Connectivity::Four
+                            },
+                        ]
                     ));
-                }
-
-                #[test]
-                fn no_implicit_threshold_rejects_hard_mask_operation_after_blur() {
-                    let result = Cli::try_parse_from([
-                        "outline",
-                        "mask",
-                        "in.png",
-                        "--no-implicit-threshold",
-                        "--threshold",
-                        "--blur",
-                        "2.0",
-                        "--dilate",
-                        "5.0",
-                    ]);
 
-                    assert!(result.is_err());
+                    assert!(
+                        Cli::try_parse_from(["outline", "mask", "in.png", "--keep-largest", "6"])
+                            .is_err()
+                    );
                 }
 
                 #[test]
-                fn explicit_threshold_uses_flag_position() {
+                fn feather_needs_a_hard_mask_and_leaves_it_soft() {
                     let cmd = parse_cmd!(
                         [
                             "outline",
-                            "mask",
+                            "cut",
                             "in.png",
-                            "--blur",
-                            "2.0",
-                            "--threshold",
-                            "--dilate",
-                            "5.0"
+                            "--feather",
+                            "2.5",
+                            "--fill-holes"
                         ],
-                        Mask
+                        Cut
                     );
-                    let pipeline = pipeline(&cmd.mask_processing);
 
                     assert!(matches!(
-                        pipeline.operations(),
+                        pipeline(&cmd.mask_processing).operations(),
                         [
-                            MaskOperation::Blur { sigma },
                             MaskOperation::Threshold { value: 120 },
-                            MaskOperation::Dilate { radius },
-                        ] if (*sigma - 2.0).abs() < f32::EPSILON
-                            && (*radius - 5.0).abs() < f32::EPSILON
+                            MaskOperation::Feather { radius: 2.5 },
+                            MaskOperation::Threshold { value: 120 },
+                            MaskOperation::FillHoles { threshold: 120 },
+                        ]
                     ));
+
+                    assert!(
+                        Cli::try_parse_from(["outline", "cut", "in.png", "--feather", "-1"])
+                            .is_err()
+                    );
                 }
 
                 #[test]
-                fn blur_after_threshold_softens_mask_and_triggers_later_implicit_threshold() {
+                fn min_island_area_runs_in_order_after_threshold() {
                     let cmd = parse_cmd!(
                         [
                             "outline",
                             "mask",
                             "in.png",
-                            "--threshold",
-                            "--blur",
-                            "2.0",
-                            "--dilate",
-                            "5.0"
+                            "--min-island-area",
+                            "64",
+                            "--fill-holes"
                         ],
                         Mask
                     );
-                    let pipeline = pipeline(&cmd.mask_processing);
 
                     assert!(matches!(
-                        pipeline.operations(),
+                        pipeline(&cmd.mask_processing).operations(),
                         [
                             MaskOperation::Threshold { value: 120 },
-                            MaskOperation::Blur { sigma },
-                            MaskOperation::Threshold { value: 120 },
-                            MaskOperation::Dilate { radius },
-                        ] if (*sigma - 2.0).abs() < f32::EPSILON
-                            && (*radius - 5.0).abs() < f32::EPSILON
+                            MaskOperation::RemoveSmallComponents { min_area: 64 },
+                            MaskOperation::FillHoles { threshold: 120 },
+                        ]
                     ));
                 }
 
                 #[test]
-                fn mask_pipeline_string_is_not_supported() {
-                    let result = Cli::try_parse_from([
-                        "outline",
-                        "mask",
-                        "in.png",
+                fn solidify_accepts_custom_threshold() {
+                    let cmd = parse_cmd!(["outline", "cut", "in.png", "--solidify", "0.8"], Cut);
+                    let pipeline = pipeline(&cmd.mask_processing);
+
+                    assert!(matches!(
+                        pipeline.operations(),
+                        [MaskOperation::Solidify { threshold: 204 }]
+                    ));
+                }
+
+                #[test]
+                fn posterize_rejects_levels_out_of_range() {
+                    for levels in ["0", "1", "256"] {
+                        let result = Cli::try_parse_from([
+                            "outline",
+                            "mask",
+                            "in.png",
+                            "--posterize",
+                            levels,
+                        ]);
+                        assert!(result.is_err());
+                    }
+                }
+
+                #[test]
+                fn soft_threshold_accepts_both_scales_and_keeps_the_mask_soft() {
+                    let cmd = parse_cmd!(
+                        [
+                            "outline",
+                            "mask",
+                            "in.png",
+                            "--soft-threshold",
+                            "0.4,200",
+                            "--dilate"
+                        ],
+                        Mask
+                    );
+                    let pipeline = pipeline(&cmd.mask_processing);
+
+                    assert!(matches!(
+                        pipeline.operations(),
+                        [
+                            MaskOperation::SoftThreshold {
+                                low: 102,
+                                high: 200
+                            },
+                            MaskOperation::Threshold { value: 120 },
+                            MaskOperation::Dilate { .. },
+                        ]
+                    ));
+                }
+
+                #[test]
+                fn soft_threshold_rejects_malformed_bounds() {
+                    for bounds in ["120", "200,100", "a,b"] {
+                        let result = Cli::try_parse_from([
+                            "outline",
+                            "mask",
+                            "in.png",
+                            "--soft-threshold",
+                            bounds,
+                        ]);
+                        assert!(result.is_err());
+                    }
+                }
+
+                #[test]
+                fn matte_gamma_rejects_non_positive_values() {
+                    for gamma in ["0", "-1.5", "inf"] {
+                        let result = Cli::try_parse_from([
+                            "outline",
+                            "mask",
+                            "in.png",
+                            "--matte-gamma",
+                            gamma,
+                        ]);
+                        assert!(result.is_err());
+                    }
+                }
+
+                #[test]
+                fn flag_only_fill_holes_is_preserved_in_ordered_pipeline() {
+                    let cmd = parse_cmd!(["outline", "mask", "in.png", "--fill-holes"], Mask);
+                    let pipeline = pipeline(&cmd.mask_processing);
+
+                    assert!(matches!(
+                        pipeline.operations(),
+                        [
+                            MaskOperation::Threshold { value: 120 },
+                            MaskOperation::FillHoles { threshold: 120 },
+                        ]
+                    ));
+                }
+
+                #[test]
+                fn fill_holes_threshold_is_passed_through() {
+                    let cmd = parse_cmd!(
+                        [
+                            "outline",
+                            "mask",
+                            "in.png",
+                            "--threshold",
+                            "--fill-holes",
+                            "180"
+                        ],
+                        Mask
+                    );
+                    let pipeline = pipeline(&cmd.mask_processing);
+
+                    assert!(matches!(
+                        pipeline.operations(),
+                        [
+                            MaskOperation::Threshold { value: 120 },
+                            MaskOperation::FillHoles { threshold: 180 },
+                        ]
+                    ));
+                }
+
+                #[test]
+                fn auto_threshold_is_inserted_before_first_hard_mask_operation() {
+                    let cmd = parse_cmd!(
+                        [
+                            "outline", "mask", "in.png", "--dilate", "5.0", "--blur", "2.0"
+                        ],
+                        Mask
+                    );
+                    let pipeline = pipeline(&cmd.mask_processing);
+
+                    assert!(matches!(
+                        pipeline.operations(),
+                        [
+                            MaskOperation::Threshold { value: 120 },
+                            MaskOperation::Dilate { radius },
+                            MaskOperation::Blur { sigma },
+                        ] if (*radius - 5.0).abs() < f32::EPSILON
+                            && (*sigma - 2.0).abs() < f32::EPSILON
+                    ));
+                }
+
+                #[test]
+                fn threshold_alone_materializes_threshold() {
+                    let cmd = parse_cmd!(["outline", "mask", "in.png", "--threshold", "200"], Mask);
+                    let pipeline = pipeline(&cmd.mask_processing);
+
+                    assert!(matches!(
+                        pipeline.operations(),
+                        [MaskOperation::Threshold { value: 200 }]
+                    ));
+                }
+
+                #[test]
+                fn threshold_prevents_duplicate_implicit_threshold() {
+                    let cmd = parse_cmd!(
+                        [
+                            "outline",
+                            "mask",
+                            "in.png",
+                            "--threshold",
+                            "200",
+                            "--dilate",
+                            "5.0"
+                        ],
+                        Mask
+                    );
+                    let pipeline = pipeline(&cmd.mask_processing);
+
+                    assert!(matches!(
+                        pipeline.operations(),
+                        [
+                            MaskOperation::Threshold { value: 200 },
+                            MaskOperation::Dilate { radius },
+                        ] if (*radius - 5.0).abs() < f32::EPSILON
+                    ));
+                }
+
+                #[test]
+                fn late_threshold_does_not_prevent_earlier_implicit_threshold() {
+                    let cmd = parse_cmd!(
+                        [
+                            "outline",
+                            "mask",
+                            "in.png",
+                            "--dilate",
+                            "5.0",
+                            "--threshold",
+                            "200"
+                        ],
+                        Mask
+                    );
+                    let pipeline = pipeline(&cmd.mask_processing);
+
+                    assert!(matches!(
+                        pipeline.operations(),
+                        [
+                            MaskOperation::Threshold { value: 120 },
+                            MaskOperation::Dilate { radius },
+                            MaskOperation::Threshold { value: 200 },
+                        ] if (*radius - 5.0).abs() < f32::EPSILON
+                    ));
+                }
+
+                #[test]
+                fn no_implicit_threshold_rejects_hard_mask_operation_without_threshold() {
+                    let result = Cli::try_parse_from([
+                        "outline",
+                        "mask",
+                        "in.png",
+                        "--no-implicit-threshold",
+                        "--dilate",
+                        "5.0",
+                    ]);
+
+                    assert!(result.is_err());
+                }
+
+                #[test]
+                fn no_implicit_threshold_accepts_explicit_threshold_before_hard_mask_operation() {
+                    let cmd = parse_cmd!(
+                        [
+                            "outline",
+                            "mask",
+                            "in.png",
+                            "--no-implicit-threshold",
+                            "--threshold",
+                            "--dilate",
+                            "5.0"
+                        ],
+                        Mask
+                    );
+                    let pipeline = pipeline(&cmd.mask_processing);
+
+                    assert!(matches!(
+                        pipeline.operations(),
+                        [
+                            MaskOperation::Threshold { value: 120 },
+                            MaskOperation::Dilate { radius },
+                        ] if (*radius - 5.0).abs() < f32::EPSILON
+                    ));
+                }
+
+                #[test]
+                fn no_implicit_threshold_rejects_hard_mask_operation_after_blur() {
+                    let result = Cli::try_parse_from([
+                        "outline",
+                        "mask",
+                        "in.png",
+                        "--no-implicit-threshold",
+                        "--threshold",
+                        "--blur",
+                        "2.0",
+                        "--dilate",
+                        "5.0",
+                    ]);
+
+                    assert!(result.is_err());
+                }
+
+                #[test]
+                fn explicit_threshold_uses_flag_position() {
+                    let cmd = parse_cmd!(
+                        [
+                            "outline",
+                            "mask",
+                            "in.png",
+                            "--blur",
+                            "2.0",
+                            "--threshold",
+                            "--dilate",
+                            "5.0"
+                        ],
+                        Mask
+                    );
+                    let pipeline = pipeline(&cmd.mask_processing);
+
+                    assert!(matches!(
+                        pipeline.operations(),
+                        [
+                            MaskOperation::Blur { sigma },
+                            MaskOperation::Threshold { value: 120 },
+                            MaskOperation::Dilate { radius },
+                        ] if (*sigma - 2.0).abs() < f32::EPSILON
+                            && (*radius - 5.0).abs() < f32::EPSILON
+                    ));
+                }
+
+                #[test]
+                fn blur_after_threshold_softens_mask_and_triggers_later_implicit_threshold() {
+                    let cmd = parse_cmd!(
+                        [
+                            "outline",
+                            "mask",
+                            "in.png",
+                            "--threshold",
+                            "--blur",
+                            "2.0",
+                            "--dilate",
+                            "5.0"
+                        ],
+                        Mask
+                    );
+                    let pipeline = pipeline(&cmd.mask_processing);
+
+                    assert!(matches!(
+                        pipeline.operations(),
+                        [
+                            MaskOperation::Threshold { value: 120 },
+                            MaskOperation::Blur { sigma },
+                            MaskOperation::Threshold { value: 120 },
+                            MaskOperation::Dilate { radius },
+                        ] if (*sigma - 2.0).abs() < f32::EPSILON
+                            && (*radius - 5.0).abs() < f32::EPSILON
+                    ));
+                }
+
+                #[test]
+                fn mask_pipeline_string_is_not_supported() {
+                    let result = Cli::try_parse_from([
+                        "outline",
+                        "mask",
+                        "in.png",
                         "--mask-pipeline",
                         "blur 2; dilate 5",
                     ]);
 
-                    assert!(result.is_err());
+                    assert!(result.is_err());
+                }
+            }
+        }
+
+        // conflicts_with behavior
+        mod conflicts_with {
+            use super::*;
+
+            mod unit {
+                use super::*;
+
+                #[test]
+                fn path_precision_and_no_path_precision_conflict() {
+                    let result = Cli::try_parse_from([
+                        "outline",
+                        "trace",
+                        "in.png",
+                        "--path-precision",
+                        "5",
+                        "--no-path-precision",
+                    ]);
+                    assert!(result.is_err());
+                }
+
+                #[test]
+                fn path_precision_alone_ok() {
+                    let cmd = parse_cmd!(
+                        ["outline", "trace", "in.png", "--path-precision", "5"],
+                        Trace
+                    );
+                    assert_eq!(cmd.trace_options.path_precision, Some(5));
+                    assert!(!cmd.trace_options.no_path_precision);
+                }
+
+                #[test]
+                fn no_path_precision_alone_ok() {
+                    let cmd =
+                        parse_cmd!(["outline", "trace", "in.png", "--no-path-precision"], Trace);
+                    assert!(cmd.trace_options.no_path_precision);
+                    assert!(cmd.trace_options.path_precision.is_none());
+                }
+
+                #[test]
+                fn matte_only_conflicts_with_foreground_options() {
+                    for extra in [
+                        &["--export-matte"][..],
+                        &["--alpha-source", "raw"],
+                        &["--vignette", "4"],
+                        &["--corner-radius", "4"],
+                        &["--auto-feather"],
+                        &["--background", "#ffffff"],
+                        &["--bg-gradient", "radial,#ffffff,#000000"],
+                        &["--bg-image", "beach.jpg"],
+                        &["--bg-blur", "8"],
+                        &["--bg-desaturate"],
+                        &["--shadow", "4,4"],
+                        &["--stroke", "4"],
+                        &["--channel-dump", "dump.png"],
+                        &["--preview", "preview.png"],
+                        &["--no-trim"],
+                    ] {
+                        let mut args = vec!["outline", "cut", "in.png", "--matte-only"];
+                        args.extend_from_slice(extra);
+                        assert!(Cli::try_parse_from(args).is_err(), "{extra:?}");
+                    }
+                }
+
+                #[test]
+                fn matte_only_allows_output_and_export_mask() {
+                    let cmd = parse_cmd!(
+                        [
+                            "outline",
+                            "cut",
+                            "in.png",
+                            "--matte-only",
+                            "-o",
+                            "matte.png",
+                            "--export-mask",
+                            "--threshold"
+                        ],
+                        Cut
+                    );
+                    assert!(cmd.matte_only);
+                    assert_eq!(cmd.output, Some(PathBuf::from("matte.png")));
+                    assert!(matches!(cmd.export_mask, Some(None)));
+                }
+
+                #[test]
+                fn bg_gradient_conflicts_with_background() {
+                    let result = Cli::try_parse_from([
+                        "outline",
+                        "cut",
+                        "in.png",
+                        "--background",
+                        "#ffffff",
+                        "--bg-gradient",
+                        "radial,#ffffff,#000000",
+                    ]);
+                    assert!(result.is_err());
+                }
+
+                #[test]
+                fn bg_image_conflicts_with_other_backgrounds() {
+                    for extra in [
+                        &["--background", "#ffffff"][..],
+                        &["--bg-gradient", "radial,#ffffff,#000000"],
+                    ] {
+                        let mut args = vec!["outline", "cut", "in.png", "--bg-image", "beach.jpg"];
+                        args.extend_from_slice(extra);
+                        assert!(Cli::try_parse_from(args).is_err(), "{extra:?}");
+                    }
+                    assert!(
+                        Cli::try_parse_from(["outline", "cut", "in.png", "--bg-fit", "contain"])
+                            .is_err()
+                    );
+
+                    let cmd =
+                        parse_cmd!(["outline", "cut", "in.png", "--bg-image", "beach.jpg"], Cut);
+                    assert_eq!(cmd.bg_image, Some(PathBuf::from("beach.jpg")));
+                    assert!(matches!(cmd.bg_fit, BackgroundFitArg::Cover));
+                }
+
+                #[test]
+                fn shadow_spec_and_color() {
+                    let cmd = parse_cmd!(["outline", "cut", "in.png", "--shadow", "4,-6"], Cut);
+                    assert_eq!(cmd.shadow, Some((4, -6, 4.0)));
+                    assert_eq!(cmd.shadow_color, [0, 0, 0, 128]);
+
+                    let cmd = parse_cmd!(
+                        [
+                            "outline",
+                            "cut",
+                            "in.png",
+                            "--shadow",
+                            "0,8,2.5",
+                            "--shadow-color",
+                            "#ff0000"
+                        ],
+                        Cut
+                    );
+                    assert_eq!(cmd.shadow, Some((0, 8, 2.5)));
+                    assert_eq!(cmd.shadow_color, [255, 0, 0, 255]);
+
+                    for extra in [
+                        &["--shadow", "4"][..],
+                        &["--shadow", "4,x"],
+                        &["--shadow", "4,4,-1"],
+                        &["--shadow", "4,4,1,1"],
+                        &["--shadow-color", "#ff0000"],
+                        &["--shadow", "4,4", "--bg-blur", "8"],
+                    ] {
+                        let mut args = vec!["outline", "cut", "in.png"];
+                        args.extend_from_slice(extra);
+                        assert!(Cli::try_parse_from(args).is_err(), "{extra:?}");
+                    }
+                }
+
+                #[test]
+                fn stroke_width_and_color() {
+                    let cmd = parse_cmd!(["outline", "cut", "in.png", "--stroke", "6"], Cut);
+                    assert_eq!(cmd.stroke, Some(6.0));
+                    assert_eq!(cmd.stroke_color, [255, 255, 255, 255]);
+
+                    let cmd = parse_cmd!(
+                        [
+                            "outline",
+                            "cut",
+                            "in.png",
+                            "--stroke",
+                            "3",
+                            "--stroke-color",
+                            "#000000"
+                        ],
+                        Cut
+                    );
+                    assert_eq!(cmd.stroke_color, [0, 0, 0, 255]);
+
+                    for extra in [
+                        &["--stroke", "-1"][..],
+                        &["--stroke-color", "#000000"],
+                        &["--stroke", "4", "--bg-desaturate"],
+                    ] {
+                        let mut args = vec!["outline", "cut", "in.png"];
+                        args.extend_from_slice(extra);
+                        assert!(Cli::try_parse_from(args).is_err(), "{extra:?}");
+                    }
+                }
+
+                #[test]
+                fn bg_blur_takes_a_positive_sigma() {
+                    let cmd = parse_cmd!(["outline", "cut", "in.png", "--bg-blur", "8"], Cut);
+                    assert_eq!(cmd.bg_blur, Some(8.0));
+
+                    for extra in [
+                        &["--bg-blur", "0"][..],
+                        &["--bg-blur", "-1"],
+                        &["--bg-blur", "8", "--background", "#ffffff"],
+                        &["--bg-blur", "8", "--bg-image", "beach.jpg"],
+                        &["--bg-blur", "8", "--bg-desaturate"],
+                    ] {
+                        let mut args = vec!["outline", "cut", "in.png"];
+                        args.extend_from_slice(extra);
+                        assert!(Cli::try_parse_from(args).is_err(), "{extra:?}");
+                    }
+                }
+
+                #[test]
+                fn resize_matte_to_input_requires_sources() {
+                    let result = Cli::try_parse_from([
+                        "outline",
+                        "reprocess",
+                        "mattes",
+                        "--resize-matte-to-input",
+                    ]);
+                    assert!(result.is_err());
+
+                    let cmd = parse_cmd!(
+                        [
+                            "outline",
+                            "reprocess",
+                            "mattes",
+                            "--sources",
+                            "photos",
+                            "--resize-matte-to-input"
+                        ],
+                        Reprocess
+                    );
+                    assert!(cmd.resize_matte_to_input);
+                }
+
+                #[test]
+                fn split_paths_requires_out_dir() {
+                    let result =
+                        Cli::try_parse_from(["outline", "trace", "in.png", "--split-paths"]);
+                    assert!(result.is_err());
+                    let result =
+                        Cli::try_parse_from(["outline", "trace", "in.png", "--out-dir", "paths"]);
+                    assert!(result.is_err());
+                }
+
+                #[test]
+                fn split_paths_conflicts_with_output() {
+                    let result = Cli::try_parse_from([
+                        "outline",
+                        "trace",
+                        "in.png",
+                        "--split-paths",
+                        "--out-dir",
+                        "paths",
+                        "-o",
+                        "out.svg",
+                    ]);
+                    assert!(result.is_err());
+                }
+
+                #[test]
+                fn split_paths_with_out_dir_ok() {
+                    let cmd = parse_cmd!(
+                        [
+                            "outline",
+                            "trace",
+                            "in.png",
+                            "--split-paths",
+                            "--out-dir",
+                            "paths"
+                        ],
+                        Trace
+                    );
+                    assert!(cmd.split_paths);
+                    assert_eq!(cmd.out_dir, Some(PathBuf::from("paths")));
+                }
+            }
+        }
+
+        // Threshold value_parser
+        mod threshold_parsing {
+            use super::*;
+
+            mod unit {
+                use super::*;
+
+                #[test]
+                fn integer_threshold() {
+                    let cmd = parse_cmd!(["outline", "mask", "in.png", "--threshold", "200"], Mask);
+                    assert_eq!(cmd.mask_processing.threshold, Some(200));
+                }
+
+                #[test]
+                fn float_threshold_scaled() {
+                    let cmd = parse_cmd!(["outline", "mask", "in.png", "--threshold", "0.5"], Mask);
+                    assert_eq!(cmd.mask_processing.threshold, Some(128));
+                }
+
+                #[test]
+                fn flag_only_threshold_uses_default_when_materialized() {
+                    let cmd = parse_cmd!(["outline", "mask", "in.png", "--threshold"], Mask);
+                    let pipeline =
+                        CliMaskProcessingRequest::from_args(&cmd.mask_processing).to_pipeline();
+
+                    assert!(matches!(
+                        pipeline.operations(),
+                        [outline::MaskOperation::Threshold { value: 120 }]
+                    ));
+                }
+
+                #[test]
+                fn invalid_threshold_rejected() {
+                    let result =
+                        Cli::try_parse_from(["outline", "mask", "in.png", "--threshold", "1.5"]);
+                    assert!(result.is_err());
+                }
+            }
+        }
+
+        // ValueEnum parsing for global options.
+        mod value_enum_parsing {
+            use super::*;
+
+            mod unit {
+                use super::*;
+
+                #[test]
+                fn input_resample_filter_gaussian() {
+                    let cli = Cli::try_parse_from([
+                        "outline",
+                        "mask",
+                        "in.png",
+                        "--input-resample-filter",
+                        "gaussian",
+                    ])
+                    .unwrap();
+                    assert!(matches!(
+                        cli.global.input_resample_filter,
+                        ResampleFilter::Gaussian
+                    ));
+                }
+
+                #[test]
+                fn output_resample_filter_nearest() {
+                    let cli = Cli::try_parse_from([
+                        "outline",
+                        "mask",
+                        "in.png",
+                        "--output-resample-filter",
+                        "nearest",
+                    ])
+                    .unwrap();
+                    assert!(matches!(
+                        cli.global.output_resample_filter,
+                        ResampleFilter::Nearest
+                    ));
+                }
+
+                #[test]
+                fn model_dir_conflicts_with_model() {
+                    let cli =
+                        Cli::try_parse_from(["outline", "mask", "in.png", "--model-dir", "models"])
+                            .unwrap();
+                    assert_eq!(cli.global.model_dir, Some(PathBuf::from("models")));
+
+                    let result = Cli::try_parse_from([
+                        "outline",
+                        "mask",
+                        "in.png",
+                        "-m",
+                        "a.onnx",
+                        "--model-dir",
+                        "models",
+                    ]);
+                    assert!(result.is_err());
+                }
+
+                #[test]
+                fn model_input_size_override() {
+                    let cli = Cli::try_parse_from([
+                        "outline",
+                        "mask",
+                        "in.png",
+                        "--model-input-size",
+                        "1024x768",
+                    ])
+                    .unwrap();
+                    let size = cli.global.model_input_size.unwrap();
+                    assert_eq!(size.height(), 1024);
+                    assert_eq!(size.width(), 768);
+                }
+
+                #[test]
+                fn input_width_and_height_pair() {
+                    let cli = Cli::try_parse_from([
+                        "outline",
+                        "--input-width",
+                        "768",
+                        "--input-height",
+                        "1024",
+                        "mask",
+                        "in.png",
+                    ])
+                    .unwrap();
+                    let size = cli.global.model_input_size().unwrap();
+                    assert_eq!((size.height(), size.width()), (1024, 768));
+
+                    for extra in [
+                        &["--input-width", "768"][..],
+                        &["--input-height", "1024"][..],
+                        &["--input-width", "0", "--input-height", "1024"][..],
+                        &[
+                            "--input-width",
+                            "768",
+                            "--input-height",
+                            "1024",
+                            "--model-input-size",
+                            "1024x768",
+                        ][..],
+                    ] {
+                        let mut args = vec!["outline", "mask", "in.png"];
+                        args.extend_from_slice(extra);
+                        assert!(Cli::try_parse_from(args).is_err(), "{extra:?}");
+                    }
+                }
+
+                #[test]
+                fn preprocess_values() {
+                    let cli = Cli::try_parse_from(["outline", "cut", "in.png"]).unwrap();
+                    assert_eq!(cli.global.preprocess, PreprocessArg::Stretch);
+
+                    let args = ["outline", "--preprocess", "letterbox", "cut", "in.png"];
+                    let cli = Cli::try_parse_from(args).unwrap();
+                    assert_eq!(
+                        PreprocessMode::from(cli.global.preprocess),
+                        PreprocessMode::Letterbox { fill: None }
+                    );
+                }
+
+                #[test]
+                fn input_size_is_width_first() {
+                    let cli = Cli::try_parse_from([
+                        "outline",
+                        "mask",
+                        "in.png",
+                        "--input-size",
+                        "512x256",
+                    ])
+                    .unwrap();
+                    let size = cli.global.model_input_size().unwrap();
+                    assert_eq!(size.width(), 512);
+                    assert_eq!(size.height(), 256);
+
+                    assert!(
+                        Cli::try_parse_from([
+                            "outline",
+                            "mask",
+                            "in.png",
+                            "--input-size",
+                            "512x256",
+                            "--model-input-size",
+                            "256x512",
+                        ])
+                        .is_err()
+                    );
+                }
+
+                #[test]
+                fn model_input_layout_defaults_to_auto_detection() {
+                    let cli = Cli::try_parse_from(["outline", "mask", "in.png"]).unwrap();
+                    assert!(cli.global.model_input_layout.is_none());
+                }
+
+                #[test]
+                fn bg_model_and_matte_combine() {
+                    let cli = Cli::try_parse_from(["outline", "cut", "in.png"]).unwrap();
+                    assert!(cli.global.bg_model.is_none());
+                    assert!(matches!(
+                        cli.global.matte_combine,
+                        MatteCombineArg::Multiply
+                    ));
+
+                    let cli = Cli::try_parse_from([
+                        "outline",
+                        "cut",
+                        "in.png",
+                        "--bg-model",
+                        "bg.onnx",
+                        "--matte-combine",
+                        "min",
+                    ])
+                    .unwrap();
+                    assert_eq!(cli.global.bg_model, Some(PathBuf::from("bg.onnx")));
+                    assert!(matches!(cli.global.matte_combine, MatteCombineArg::Min));
+                }
+
+                #[test]
+                fn pad_to_multiple_requires_positive_value() {
+                    let cli = Cli::try_parse_from([
+                        "outline",
+                        "mask",
+                        "in.png",
+                        "--pad-to-multiple",
+                        "32",
+                    ])
+                    .unwrap();
+                    assert_eq!(cli.global.pad_to_multiple, Some(32));
+
+                    assert!(
+                        Cli::try_parse_from([
+                            "outline",
+                            "mask",
+                            "in.png",
+                            "--pad-to-multiple",
+                            "0"
+                        ])
+                        .is_err()
+                    );
+                }
+
+                #[test]
+                fn quiet_is_global() {
+                    let cli = Cli::try_parse_from(["outline", "mask", "in.png", "-q"]).unwrap();
+                    assert!(cli.global.quiet);
+
+                    let cli = Cli::try_parse_from(["outline", "mask", "in.png"]).unwrap();
+                    assert!(!cli.global.quiet);
+                }
+
+                #[test]
+                fn timeout_is_global() {
+                    let cli = Cli::try_parse_from(["outline", "--timeout", "2.5", "cut", "in.png"])
+                        .unwrap();
+                    assert_eq!(cli.global.timeout, Some(Duration::from_millis(2500)));
+
+                    let cli = Cli::try_parse_from(["outline", "trace", "in.png"]).unwrap();
+                    assert!(cli.global.timeout.is_none());
+                }
+
+                #[test]
+                fn device_defaults_to_cpu() {
+                    let cli = Cli::try_parse_from(["outline", "cut", "in.png"]).unwrap();
+                    assert_eq!(cli.global.device, ExecutionProvider::Cpu);
+
+                    for device in ["gpu", "cpu:0", "cuda:-1", "cuda:x", "coreml:0"] {
+                        let args = ["outline", "--device", device, "cut", "in.png"];
+                        assert!(Cli::try_parse_from(args).is_err(), "{device}");
+                    }
+                }
+
+                #[cfg(feature = "cuda")]
+                #[test]
+                fn device_selects_cuda_index() {
+                    for (device, device_id) in [("cuda", 0), ("cuda:1", 1), ("CUDA:0", 0)] {
+                        let args = ["outline", "--device", device, "cut", "in.png"];
+                        let cli = Cli::try_parse_from(args).unwrap();
+                        assert_eq!(cli.global.device, ExecutionProvider::Cuda { device_id });
+                    }
+                }
+
+                #[cfg(feature = "coreml")]
+                #[test]
+                fn device_selects_coreml() {
+                    let args = ["outline", "--device", "coreml", "cut", "in.png"];
+                    let cli = Cli::try_parse_from(args).unwrap();
+                    assert_eq!(cli.global.device, ExecutionProvider::CoreML);
+                }
+
+                #[test]
+                fn max_pixels_defaults_to_finite_limit() {
+                    let cli = Cli::try_parse_from(["outline", "cut", "in.png"]).unwrap();
+                    assert_eq!(cli.global.max_pixels, DEFAULT_MAX_PIXELS);
+
+                    let cli =
+                        Cli::try_parse_from(["outline", "cut", "in.png", "--max-pixels", "0"])
+                            .unwrap();
+                    assert_eq!(cli.global.max_pixels, 0);
+                }
+
+                #[test]
+                fn mask_bit_depth_values() {
+                    let cmd = parse_cmd!(["outline", "mask", "in.png"], Mask);
+                    assert_eq!(cmd.mask_bit_depth, MaskBitDepthArg::Eight);
+
+                    let cmd =
+                        parse_cmd!(["outline", "mask", "in.png", "--mask-bit-depth", "1"], Mask);
+                    assert_eq!(cmd.mask_bit_depth, MaskBitDepthArg::One);
+
+                    let cmd = parse_cmd!(["outline", "mask", "in.png", "--bit-depth", "16"], Mask);
+                    assert_eq!(cmd.mask_bit_depth, MaskBitDepthArg::Sixteen);
+
+                    let cmd = parse_cmd!(
+                        [
+                            "outline",
+                            "cut",
+                            "in.png",
+                            "--export-mask",
+                            "--mask-bit-depth",
+                            "1"
+                        ],
+                        Cut
+                    );
+                    assert_eq!(cmd.mask_bit_depth, MaskBitDepthArg::One);
+
+                    assert!(
+                        Cli::try_parse_from(["outline", "mask", "in.png", "--mask-bit-depth", "4"])
+                            .is_err()
+                    );
+                }
+
+                #[test]
+                fn infer_scale_accepts_only_unit_fractions() {
+                    let cli = Cli::try_parse_from(["outline", "cut", "in.png"]).unwrap();
+                    assert_eq!(cli.global.infer_scale, None);
+
+                    let cli =
+                        Cli::try_parse_from(["outline", "cut", "in.png", "--infer-scale", "0.5"])
+                            .unwrap();
+                    assert_eq!(cli.global.infer_scale, Some(0.5));
+
+                    for value in ["0", "1.5", "-0.5", "half"] {
+                        assert!(
+                            Cli::try_parse_from([
+                                "outline",
+                                "cut",
+                                "in.png",
+                                "--infer-scale",
+                                value
+                            ])
+                            .is_err(),
+                            "{value} should be rejected"
+                        );
+                    }
+                }
+
+                #[test]
+                fn strict_flag_is_global() {
+                    let cli = Cli::try_parse_from(["outline", "cut", "in.png"]).unwrap();
+                    assert!(!cli.global.strict);
+
+                    let cli =
+                        Cli::try_parse_from(["outline", "mask", "in.png", "--strict"]).unwrap();
+                    assert!(cli.global.strict);
+                }
+
+                #[test]
+                fn from_luma_is_global() {
+                    let cli = Cli::try_parse_from(["outline", "cut", "in.png"]).unwrap();
+                    assert!(!cli.global.from_luma);
+
+                    let cli =
+                        Cli::try_parse_from(["outline", "trace", "in.png", "--from-luma"]).unwrap();
+                    assert!(cli.global.from_luma);
+
+                    let cli =
+                        Cli::try_parse_from(["outline", "--from-luma", "mask", "in.png"]).unwrap();
+                    assert!(cli.global.from_luma);
+                }
+
+                #[test]
+                fn stdin_format_values() {
+                    let cli = Cli::try_parse_from(["outline", "cut", "-"]).unwrap();
+                    assert_eq!(cli.global.stdin_format, StdinFormatArg::Auto);
+                    assert_eq!(cli.global.stdin_format.image_format(), None);
+                    match cli.command {
+                        Commands::Cut(cmd) => assert_eq!(cmd.input, PathBuf::from("-")),
+                        _ => panic!("expected cut command"),
+                    }
+
+                    for (value, format) in [
+                        ("png", ImageFormat::Png),
+                        ("jpeg", ImageFormat::Jpeg),
+                        ("webp", ImageFormat::WebP),
+                    ] {
+                        let cli =
+                            Cli::try_parse_from(["outline", "trace", "-", "--stdin-format", value])
+                                .unwrap();
+                        assert_eq!(cli.global.stdin_format.image_format(), Some(format));
+                    }
+
+                    assert!(
+                        Cli::try_parse_from(["outline", "mask", "-", "--stdin-format", "gif"])
+                            .is_err()
+                    );
+                }
+
+                #[test]
+                fn flatten_color_is_global_and_defaults_to_white() {
+                    let cli = Cli::try_parse_from(["outline", "cut", "in.png"]).unwrap();
+                    assert_eq!(cli.global.flatten_color, [255, 255, 255, 255]);
+
+                    let cli = Cli::try_parse_from([
+                        "outline",
+                        "cut",
+                        "in.png",
+                        "-o",
+                        "out.jpg",
+                        "--flatten-color",
+                        "#102030",
+                    ])
+                    .unwrap();
+                    assert_eq!(cli.global.flatten_color, [0x10, 0x20, 0x30, 255]);
+
+                    for color in ["red", "#10203080"] {
+                        assert!(
+                            Cli::try_parse_from([
+                                "outline",
+                                "--flatten-color",
+                                color,
+                                "cut",
+                                "in.png"
+                            ])
+                            .is_err(),
+                            "{color}"
+                        );
+                    }
+                    let cli = Cli::try_parse_from([
+                        "outline",
+                        "--flatten-color",
+                        "#102030ff",
+                        "cut",
+                        "in.png",
+                    ])
+                    .unwrap();
+                    assert_eq!(cli.global.flatten_color, [0x10, 0x20, 0x30, 255]);
+                }
+
+                #[test]
+                fn svg_orient_flag() {
+                    let cmd = parse_cmd!(["outline", "trace", "in.jpg"], Trace);
+                    assert!(!cmd.svg_orient);
+
+                    let cmd = parse_cmd!(["outline", "trace", "in.jpg", "--svg-orient"], Trace);
+                    assert!(cmd.svg_orient);
+
+                    assert!(
+                        Cli::try_parse_from([
+                            "outline",
+                            "trace",
+                            "in.jpg",
+                            "--svg-orient",
+                            "--split-paths",
+                            "--out-dir",
+                            "paths"
+                        ])
+                        .is_err()
+                    );
+                }
+
+                #[test]
+                fn icon_sizes_and_ico() {
+                    let cmd = parse_cmd!(["outline", "icon", "logo.png"], Icon);
+                    assert_eq!(cmd.sizes, vec![16, 32, 48, 256]);
+                    assert!(cmd.ico.is_none());
+                    assert!(cmd.out_dir.is_none());
+
+                    let cmd = parse_cmd!(
+                        [
+                            "outline",
+                            "icon",
+                            "logo.png",
+                            "--sizes",
+                            "64,128",
+                            "--ico",
+                            "--out-dir",
+                            "icons"
+                        ],
+                        Icon
+                    );
+                    assert_eq!(cmd.sizes, vec![64, 128]);
+                    assert!(matches!(cmd.ico, Some(None)));
+                    assert_eq!(cmd.out_dir.as_deref(), Some(Path::new("icons")));
+
+                    let cmd = parse_cmd!(
+                        ["outline", "icon", "logo.png", "--ico", "app.ico", "--blur"],
+                        Icon
+                    );
+                    assert_eq!(cmd.ico, Some(Some(PathBuf::from("app.ico"))));
+                    assert!(!cmd.mask_processing.ordered_steps.is_empty());
+
+                    for sizes in ["0", "2048", "16,x"] {
+                        assert!(
+                            Cli::try_parse_from(["outline", "icon", "logo.png", "--sizes", sizes])
+                                .is_err(),
+                            "{sizes} should be rejected"
+                        );
+                    }
+                }
+
+                #[test]
+                fn batch_inputs_and_out_dir() {
+                    let cmd = parse_cmd!(["outline", "batch", "photos", "extra.jpg"], Batch);
+                    assert_eq!(
+                        cmd.inputs,
+                        vec![PathBuf::from("photos"), PathBuf::from("extra.jpg")]
+                    );
+                    assert!(!cmd.recursive);
+                    assert!(cmd.out_dir.is_none());
+                    assert!(!cmd.export_mask);
+                    assert_eq!(cmd.jobs, 1);
+
+                    let cmd = parse_cmd!(
+                        [
+                            "outline",
+                            "batch",
+                            "photos",
+                            "-r",
+                            "--out-dir",
+                            "cutouts",
+                            "--export-mask",
+                            "-j",
+                            "4",
+                            "--threshold"
+                        ],
+                        Batch
+                    );
+                    assert!(cmd.recursive);
+                    assert_eq!(cmd.jobs, 4);
+                    assert_eq!(cmd.out_dir.as_deref(), Some(Path::new("cutouts")));
+                    assert!(cmd.export_mask);
+                    assert!(!cmd.mask_processing.ordered_steps.is_empty());
+
+                    assert!(Cli::try_parse_from(["outline", "batch"]).is_err());
+                    assert!(
+                        Cli::try_parse_from(["outline", "batch", "in", "--jobs", "0"]).is_err()
+                    );
                 }
-            }
-        }
 
-        // conflicts_with behavior
-        mod conflicts_with {
-            use super::*;
+                #[test]
+                fn trimap_defaults_and_overrides() {
+                    let cmd = parse_cmd!(["outline", "trimap", "in.png"], Trimap);
+                    assert_eq!(cmd.fg_threshold, 240);
+                    assert_eq!(cmd.bg_threshold, 15);
+                    assert_eq!(cmd.unknown_dilate, 5.0);
+                    assert!(cmd.output.is_none());
 
-            mod unit {
-                use super::*;
+                    let cmd = parse_cmd!(
+                        [
+                            "outline",
+                            "trimap",
+                            "in.png",
+                            "--fg-threshold",
+                            "0.9",
+                            "--bg-threshold",
+                            "30",
+                            "--unknown-dilate",
+                            "0",
+                            "-o",
+                            "tri.png"
+                        ],
+                        Trimap
+                    );
+                    assert_eq!(cmd.fg_threshold, 230);
+                    assert_eq!(cmd.bg_threshold, 30);
+                    assert_eq!(cmd.unknown_dilate, 0.0);
+                    assert_eq!(cmd.output.as_deref(), Some(Path::new("tri.png")));
+
+                    assert!(
+                        Cli::try_parse_from(["outline", "trimap", "in.png", "--blur"]).is_err()
+                    );
+                }
 
                 #[test]
-                fn path_precision_and_no_path_precision_conflict() {
-                    let result = Cli::try_parse_from([
-                        "outline",
-                        "trace",
-                        "in.png",
-                        "--path-precision",
-                        "5",
-                        "--no-path-precision",
-                    ]);
-                    assert!(result.is_err());
+                fn list_outputs_flag() {
+                    let cmd = parse_cmd!(["outline", "cut", "in.png"], Cut);
+                    assert!(!cmd.list_outputs);
+
+                    let cmd = parse_cmd!(["outline", "cut", "in.png", "--list-outputs"], Cut);
+                    assert!(cmd.list_outputs);
+
+                    let cmd = parse_cmd!(["outline", "trace", "in.png", "--list-outputs"], Trace);
+                    assert!(cmd.list_outputs);
                 }
 
                 #[test]
-                fn path_precision_alone_ok() {
+                fn model_output_takes_an_index_or_a_name() {
+                    let cli = Cli::try_parse_from(["outline", "cut", "in.png"]).unwrap();
+                    assert_eq!(cli.global.model_output, None);
+
+                    let cli =
+                        Cli::try_parse_from(["outline", "cut", "in.png", "--model-output", "2"])
+                            .unwrap();
+                    assert_eq!(cli.global.model_output, Some(OutputSelector::Index(2)));
+
+                    let cli =
+                        Cli::try_parse_from(["outline", "--model-output", "d0", "trace", "in.png"])
+                            .unwrap();
+                    assert_eq!(
+                        cli.global.model_output,
+                        Some(OutputSelector::Name("d0".to_string()))
+                    );
+
+                    assert!(
+                        Cli::try_parse_from(["outline", "cut", "in.png", "--model-output", ""])
+                            .is_err()
+                    );
+                }
+
+                #[test]
+                fn trace_profile_flags() {
+                    let cmd = parse_cmd!(["outline", "trace", "in.jpg"], Trace);
+                    assert!(cmd.trace_profile.is_none());
+                    assert!(cmd.dump_trace_profile.is_none());
+
                     let cmd = parse_cmd!(
-                        ["outline", "trace", "in.png", "--path-precision", "5"],
+                        [
+                            "outline",
+                            "trace",
+                            "in.jpg",
+                            "--trace-profile",
+                            "team.json",
+                            "--dump-trace-profile",
+                            "effective.json"
+                        ],
                         Trace
                     );
-                    assert_eq!(cmd.trace_options.path_precision, Some(5));
-                    assert!(!cmd.trace_options.no_path_precision);
+                    assert_eq!(cmd.trace_profile.as_deref(), Some(Path::new("team.json")));
+                    assert_eq!(
+                        cmd.dump_trace_profile.as_deref(),
+                        Some(Path::new("effective.json"))
+                    );
                 }
 
                 #[test]
-                fn no_path_precision_alone_ok() {
-                    let cmd =
-                        parse_cmd!(["outline", "trace", "in.png", "--no-path-precision"], Trace);
-                    assert!(cmd.trace_options.no_path_precision);
-                    assert!(cmd.trace_options.path_precision.is_none());
+                fn explicit_trace_flags_override_profile() {
+                    let base = TraceOptions {
+                        tracer_filter_speckle: 9,
+                        tracer_path_precision: Some(4),
+                        invert_svg: true,
+                        ..TraceOptions::default()
+                    };
+
+                    let cmd = parse_cmd!(["outline", "trace", "in.jpg"], Trace);
+                    let options = cmd.trace_options.apply_to(base.clone());
+                    assert_eq!(options.tracer_filter_speckle, 9);
+                    assert_eq!(options.tracer_path_precision, Some(4));
+                    assert!(options.invert_svg);
+
+                    let cmd = parse_cmd!(
+                        [
+                            "outline",
+                            "trace",
+                            "in.jpg",
+                            "--mode",
+                            "polygon",
+                            "--filter-speckle",
+                            "4",
+                            "--no-path-precision"
+                        ],
+                        Trace
+                    );
+                    let options = cmd.trace_options.apply_to(base);
+                    assert!(matches!(options.tracer_mode, PathSimplifyMode::Polygon));
+                    assert_eq!(options.tracer_filter_speckle, 4);
+                    assert!(options.tracer_path_precision.is_none());
+                    assert!(options.invert_svg);
                 }
-            }
-        }
 
-        // Threshold value_parser
-        mod threshold_parsing {
-            use super::*;
+                #[test]
+                fn trace_smooth_flag() {
+                    let cmd = parse_cmd!(["outline", "trace", "in.jpg"], Trace);
+                    assert!(cmd.trace_options.trace_smooth.is_none());
+                    assert!(TraceOptions::from(&cmd.trace_options).pre_smooth.is_none());
 
-            mod unit {
-                use super::*;
+                    let cmd = parse_cmd!(
+                        ["outline", "trace", "in.jpg", "--trace-smooth", "1.5"],
+                        Trace
+                    );
+                    assert_eq!(TraceOptions::from(&cmd.trace_options).pre_smooth, Some(1.5));
+                    let base = TraceOptions {
+                        pre_smooth: Some(3.0),
+                        ..TraceOptions::default()
+                    };
+                    assert_eq!(cmd.trace_options.apply_to(base).pre_smooth, Some(1.5));
+
+                    assert!(
+                        Cli::try_parse_from(["outline", "trace", "in.jpg", "--trace-smooth", "-1"])
+                            .is_err()
+                    );
+                }
 
                 #[test]
-                fn integer_threshold() {
-                    let cmd = parse_cmd!(["outline", "mask", "in.png", "--threshold", "200"], Mask);
-                    assert_eq!(cmd.mask_processing.threshold, Some(200));
+                fn stroke_only_flags() {
+                    let cmd = parse_cmd!(["outline", "trace", "in.jpg"], Trace);
+                    assert_eq!(
+                        TraceOptions::from(&cmd.trace_options).output_style,
+                        TraceOutputStyle::Fill
+                    );
+
+                    let cmd = parse_cmd!(
+                        [
+                            "outline",
+                            "trace",
+                            "in.jpg",
+                            "--stroke-only",
+                            "--stroke-width",
+                            "2.5"
+                        ],
+                        Trace
+                    );
+                    assert_eq!(
+                        cmd.trace_options
+                            .apply_to(TraceOptions::default())
+                            .output_style,
+                        TraceOutputStyle::stroke(2.5)
+                    );
+
+                    assert!(
+                        Cli::try_parse_from(["outline", "trace", "in.jpg", "--stroke-width", "2"])
+                            .is_err()
+                    );
                 }
 
                 #[test]
-                fn float_threshold_scaled() {
-                    let cmd = parse_cmd!(["outline", "mask", "in.png", "--threshold", "0.5"], Mask);
-                    assert_eq!(cmd.mask_processing.threshold, Some(128));
+                fn stored_orientation_flag() {
+                    let cmd = parse_cmd!(["outline", "cut", "in.png"], Cut);
+                    assert!(!cmd.stored_orientation);
+
+                    let cmd = parse_cmd!(["outline", "cut", "in.png", "--stored-orientation"], Cut);
+                    assert!(cmd.stored_orientation);
+
+                    let cmd =
+                        parse_cmd!(["outline", "mask", "in.png", "--stored-orientation"], Mask);
+                    assert!(cmd.stored_orientation);
+
+                    assert!(
+                        Cli::try_parse_from(["outline", "trace", "in.png", "--stored-orientation"])
+                            .is_err()
+                    );
                 }
 
                 #[test]
-                fn flag_only_threshold_uses_default_when_materialized() {
-                    let cmd = parse_cmd!(["outline", "mask", "in.png", "--threshold"], Mask);
-                    let pipeline =
-                        CliMaskProcessingRequest::from_args(&cmd.mask_processing).to_pipeline();
+                fn overlay_defaults_to_half_opaque_red() {
+                    let cmd = parse_cmd!(["outline", "mask", "in.png"], Mask);
+                    assert!(cmd.overlay.is_none());
 
-                    assert!(matches!(
-                        pipeline.operations(),
-                        [outline::MaskOperation::Threshold { value: 120 }]
-                    ));
+                    let cmd = parse_cmd!(["outline", "mask", "in.png", "--overlay"], Mask);
+                    assert_eq!(cmd.overlay, Some([255, 0, 0, 255]));
+                    assert_eq!(cmd.overlay_opacity, 0.5);
+
+                    let cmd = parse_cmd!(
+                        [
+                            "outline",
+                            "mask",
+                            "in.png",
+                            "--overlay",
+                            "#00ff00",
+                            "--overlay-opacity",
+                            "0.25"
+                        ],
+                        Mask
+                    );
+                    assert_eq!(cmd.overlay, Some([0, 255, 0, 255]));
+                    assert_eq!(cmd.overlay_opacity, 0.25);
                 }
 
                 #[test]
-                fn invalid_threshold_rejected() {
-                    let result =
-                        Cli::try_parse_from(["outline", "mask", "in.png", "--threshold", "1.5"]);
-                    assert!(result.is_err());
+                fn overlay_opacity_requires_overlay_and_unit_range() {
+                    assert!(
+                        Cli::try_parse_from([
+                            "outline",
+                            "mask",
+                            "in.png",
+                            "--overlay-opacity",
+                            "0.5"
+                        ])
+                        .is_err()
+                    );
+                    assert!(
+                        Cli::try_parse_from([
+                            "outline",
+                            "mask",
+                            "in.png",
+                            "--overlay",
+                            "--overlay-opacity",
+                            "1.5"
+                        ])
+                        .is_err()
+                    );
                 }
-            }
-        }
 
-        // ValueEnum parsing for global options.
-        mod value_enum_parsing {
-            use super::*;
+                #[test]
+                fn visualize_takes_a_colormap_and_conflicts_with_overlay() {
+                    let cmd = parse_cmd!(["outline", "mask", "in.png"], Mask);
+                    assert_eq!(cmd.visualize, None);
 
-            mod unit {
-                use super::*;
+                    let cmd = parse_cmd!(
+                        ["outline", "mask", "in.png", "--visualize", "viridis"],
+                        Mask
+                    );
+                    assert_eq!(cmd.visualize, Some(ColorMapArg::Viridis));
 
-                #[test]
-                fn input_resample_filter_gaussian() {
-                    let cli = Cli::try_parse_from([
-                        "outline",
-                        "mask",
-                        "in.png",
-                        "--input-resample-filter",
-                        "gaussian",
-                    ])
-                    .unwrap();
-                    assert!(matches!(
-                        cli.global.input_resample_filter,
-                        ResampleFilter::Gaussian
-                    ));
+                    assert!(
+                        Cli::try_parse_from([
+                            "outline",
+                            "mask",
+                            "in.png",
+                            "--visualize",
+                            "gray",
+                            "--overlay"
+                        ])
+                        .is_err()
+                    );
                 }
 
                 #[test]
-                fn output_resample_filter_nearest() {
-                    let cli = Cli::try_parse_from([
-                        "outline",
-                        "mask",
-                        "in.png",
-                        "--output-resample-filter",
-                        "nearest",
-                    ])
-                    .unwrap();
-                    assert!(matches!(
-                        cli.global.output_resample_filter,
-                        ResampleFilter::Nearest
-                    ));
+                fn debug_matte_stages_is_mask_only() {
+                    let cmd = parse_cmd!(
+                        ["outline", "mask", "in.png", "--debug-matte-stages", "debug"],
+                        Mask
+                    );
+                    assert_eq!(cmd.debug_matte_stages, Some(PathBuf::from("debug")));
+
+                    assert!(
+                        Cli::try_parse_from([
+                            "outline",
+                            "cut",
+                            "in.png",
+                            "--debug-matte-stages",
+                            "debug"
+                        ])
+                        .is_err()
+                    );
                 }
 
                 #[test]
-                fn model_input_size_override() {
-                    let cli = Cli::try_parse_from([
-                        "outline",
-                        "mask",
-                        "in.png",
-                        "--model-input-size",
-                        "1024x768",
-                    ])
-                    .unwrap();
-                    let size = cli.global.model_input_size.unwrap();
-                    assert_eq!(size.height(), 1024);
-                    assert_eq!(size.width(), 768);
+                fn model_input_layout_override() {
+                    for flag in ["--model-input-layout", "--input-layout"] {
+                        let cli = Cli::try_parse_from(["outline", "cut", "in.png", flag, "nhwc"])
+                            .unwrap();
+                        assert!(matches!(
+                            cli.global.model_input_layout,
+                            Some(InputLayoutArg::Nhwc)
+                        ));
+                    }
                 }
 
                 #[test]
@@ -1992,6 +5165,104 @@ mod tests {
             }
         }
 
+        mod config_file {
+            use super::*;
+            use outline::MaskOperation;
+            use std::io::Write;
+
+            fn config(contents: &str) -> tempfile::NamedTempFile {
+                let mut file = tempfile::Builder::new().suffix(".toml").tempfile().unwrap();
+                file.write_all(contents.as_bytes()).unwrap();
+                file
+            }
+
+            #[test]
+            fn file_values_fill_bare_options_and_flags_override_them() {
+                let file = config(
+                    r#"
+                    [mask_processing]
+                    blur_sigma = 2.5
+                    mask_threshold = 200
+                    erosion_border_mode = "outside-is-unknown"
+
+                    [trace]
+                    tracer_filter_speckle = 9
+                    "#,
+                );
+                let path = file.path().to_str().unwrap();
+
+                let cli = Cli::try_parse_from([
+                    "outline", "--config", path, "mask", "in.png", "--blur", "--erode",
+                ])
+                .unwrap();
+                assert_eq!(cli.global.presets.trace.tracer_filter_speckle, 9);
+                let Commands::Mask(cmd) = cli.command else {
+                    panic!("expected mask command");
+                };
+                let pipeline =
+                    CliMaskProcessingRequest::from_args(&cmd.mask_processing).to_pipeline();
+                assert!(matches!(
+                    pipeline.operations(),
+                    [
+                        MaskOperation::Blur { sigma },
+                        MaskOperation::Threshold { value: 200 },
+                        MaskOperation::Erode {
+                            radius,
+                            border_mode: ErosionBorderMode::OutsideIsUnknown,
+                        },
+                    ] if *sigma == 2.5 && *radius == 5.0
+                ));
+
+                let cmd = parse_cmd!(
+                    [
+                        "outline",
+                        "mask",
+                        "in.png",
+                        "--config",
+                        path,
+                        "--blur",
+                        "1.0",
+                        "--threshold",
+                        "50",
+                        "--erode-border",
+                        "outside-is-background",
+                        "--erode"
+                    ],
+                    Mask
+                );
+                let pipeline =
+                    CliMaskProcessingRequest::from_args(&cmd.mask_processing).to_pipeline();
+                assert!(matches!(
+                    pipeline.operations(),
+                    [
+                        MaskOperation::Blur { sigma },
+                        MaskOperation::Threshold { value: 50 },
+                        MaskOperation::Erode {
+                            border_mode: ErosionBorderMode::OutsideIsBackground,
+                            ..
+                        },
+                    ] if *sigma == 1.0
+                ));
+            }
+
+            #[test]
+            fn missing_or_malformed_files_are_errors() {
+                let missing = load_config_file(Path::new("does-not-exist.toml")).unwrap_err();
+                assert!(missing.to_string().contains("does-not-exist.toml"));
+
+                let file = config("[mask_processing]\nblur_sigma = \"soft\"\n");
+                let malformed = load_config_file(file.path()).unwrap_err();
+                assert!(malformed.to_string().starts_with("invalid config file"));
+
+                let file = config("[mask]\n");
+                assert!(load_config_file(file.path()).is_err());
+
+                let result =
+                    Cli::try_parse_from(["outline", "--config", "missing.toml", "mask", "in.png"]);
+                assert!(result.is_err());
+            }
+        }
+
         /// Integration tests for model path resolution priority through clap.
         ///
         /// Priority: --model flag > OUTLINE_MODEL_PATH env var > (downstream: cached > default)
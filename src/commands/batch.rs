@@ -0,0 +1,429 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use image::{DynamicImage, ImageFormat};
+use outline::OutlineResult;
+use outline::batch::{self, BatchOptions};
+
+use crate::cli::{AlphaFromArg, BatchCommand, GlobalOptions};
+use crate::diagnostics;
+use crate::report::{Report, ReportItem, ReportOutput};
+
+use super::utils::{
+    SaveOptions, build_outline, derive_variant_path, mask_pipeline_from_args, processing_requested,
+    resolve_alpha_source, save_flattened,
+};
+
+/// An image found from the batch inputs, with the directory its outputs are written to.
+#[derive(Debug, PartialEq, Eq)]
+struct BatchInput {
+    path: PathBuf,
+    out_dir: PathBuf,
+}
+
+/// The main function to run the batch command.
+pub fn run(global: &GlobalOptions, cmd: BatchCommand) -> OutlineResult<()> {
+    let report = run_report(global, cmd)?;
+    let total = report.items().len();
+    let failed = report
+        .items()
+        .iter()
+        .filter(|item| item.error.is_some())
+        .count();
+    report.finish()?;
+
+    if failed > 0 {
+        return Err(io::Error::other(format!("{failed} of {total} images failed")).into());
+    }
+    Ok(())
+}
+
+/// Process every batch input and return the report of what was written, in input order.
+fn run_report(global: &GlobalOptions, cmd: BatchCommand) -> OutlineResult<Report> {
+    if global.from_luma {
+        diagnostics::warn("--from-luma is not supported by batch and will be ignored.")?;
+    }
+    let images = collect_inputs(&cmd.inputs, cmd.recursive, cmd.out_dir.as_deref())?;
+    if images.is_empty() {
+        diagnostics::warn("no images found in the batch inputs")?;
+        return Ok(Report::new(global, "batch"));
+    }
+    let suffixes: &[&str] = if cmd.export_mask {
+        &OUTPUT_SUFFIXES
+    } else {
+        &OUTPUT_SUFFIXES[..1]
+    };
+    check_output_collisions(&images, suffixes)?;
+    let paths: Vec<&Path> = images.iter().map(|image| image.path.as_path()).collect();
+    let out_dirs: HashMap<&Path, &Path> = images
+        .iter()
+        .map(|image| (image.path.as_path(), image.out_dir.as_path()))
+        .collect();
+
+    // Each job loads the model once and reuses its session for every image it takes.
+    let mut outline = build_outline(global)?;
+    if cmd.jobs > 1 && global.intra_threads.is_none() {
+        // Every job runs its own session, so split the cores instead of giving each all of them.
+        let cores = thread::available_parallelism().map_or(1, |cores| cores.get());
+        outline = outline.with_intra_threads(Some((cores / cmd.jobs as usize).max(1)));
+    }
+    let mask_pipeline = mask_pipeline_from_args(&cmd.mask_processing);
+    let alpha_source =
+        resolve_alpha_source(cmd.alpha_source, processing_requested(&cmd.mask_processing));
+    let save_options = SaveOptions::from_global(global);
+    let options = BatchOptions::new()
+        .with_concurrency(Some(cmd.jobs as usize))
+        .with_timeout(global.timeout);
+
+    let items = batch::run(&outline, &paths, &options, |input, session| {
+        let out_dir = out_dirs[input];
+        fs::create_dir_all(out_dir)?;
+        let output_path = |suffix: &str| output_path(input, out_dir, suffix);
+
+        let mask = if cmd.export_mask || matches!(alpha_source, AlphaFromArg::Processed) {
+            Some(session.matte().processed_with(&mask_pipeline)?)
+        } else {
+            None
+        };
+        let foreground = match (&mask, alpha_source) {
+            (Some(mask), AlphaFromArg::Processed) => mask.foreground()?,
+            _ => session.matte().foreground()?,
+        };
+
+        let mut saved = Vec::new();
+        let foreground_path = output_path("foreground");
+        let foreground = DynamicImage::ImageRgba8(foreground.into_image());
+        save_flattened(foreground, &foreground_path, save_options)?;
+        saved.push(("foreground", "Foreground PNG", foreground_path));
+        if let Some(mask) = mask.filter(|_| cmd.export_mask) {
+            let mask_path = output_path("mask");
+            mask.save(&mask_path)?;
+            saved.push(("mask", "Processed mask PNG", mask_path));
+        }
+        Ok(saved)
+    })?;
+
+    let mut report = Report::new(global, "batch");
+    let mut failed = 0;
+    for item in &items {
+        let (outputs, error) = match &item.result {
+            Ok(saved) => {
+                let mut outputs = Vec::new();
+                for (kind, label, path) in saved {
+                    if report.is_text() {
+                        println!("{label} saved to {}", path.display());
+                    }
+                    outputs.push(ReportOutput {
+                        kind,
+                        path: path.clone(),
+                    });
+                }
+                (outputs, None)
+            }
+            Err(err) => {
+                failed += 1;
+                diagnostics::error(format!("failed to process {}: {err}", item.input.display()));
+                (Vec::new(), Some(err.to_string()))
+            }
+        };
+        report.item(ReportItem {
+            input: item.input.clone(),
+            outputs,
+            error,
+        });
+    }
+    if report.is_text() {
+        println!(
+            "Processed {} images: {} succeeded, {failed} failed",
+            items.len(),
+            items.len() - failed
+        );
+    }
+    Ok(report)
+}
+
+/// The suffixes batch appends to an image's file stem for its outputs.
+const OUTPUT_SUFFIXES: [&str; 2] = ["foreground", "mask"];
+
+// The path of the `suffix` output for `input` in `out_dir`.
+fn output_path(input: &Path, out_dir: &Path, suffix: &str) -> PathBuf {
+    let variant = derive_variant_path(input, suffix, "png");
+    out_dir.join(variant.file_name().expect("derived path has a file name"))
+}
+
+/// Fail before any image is processed if two images would write the same output file.
+///
+/// This happens for inputs sharing a stem, such as `a.jpg` and `a.png`, or for same-named images
+/// from different directories sent to one `--out-dir`. With `--jobs` those writes would race.
+fn check_output_collisions(images: &[BatchInput], suffixes: &[&str]) -> OutlineResult<()> {
+    let mut outputs: HashMap<PathBuf, &Path> = HashMap::new();
+    for image in images {
+        for suffix in suffixes {
+            let path = output_path(&image.path, &image.out_dir, suffix);
+            if let Some(other) = outputs.insert(path.clone(), &image.path) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "{} and {} would both write {}",
+                        other.display(),
+                        image.path.display(),
+                        path.display()
+                    ),
+                )
+                .into());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Expand the batch inputs into the images to process, skipping duplicates.
+///
+/// Files are taken as given. Directories contribute the files the `image` crate can decode,
+/// sorted by path, and with `recursive` also those of their subdirectories. A file found in a
+/// directory is skipped when it is exactly where another image's output goes, such as
+/// `photo-foreground.png` next to `photo.jpg`, so a rerun does not process earlier outputs; other
+/// `*-foreground.*` or `*-mask.*` files are processed like any image. Outputs go next to each
+/// image, or into `out_dir` with the subdirectories below a directory input mirrored.
+fn collect_inputs(
+    inputs: &[PathBuf],
+    recursive: bool,
+    out_dir: Option<&Path>,
+) -> OutlineResult<Vec<BatchInput>> {
+    let mut images = Vec::new();
+    let mut files = HashSet::new();
+    for input in inputs {
+        if input.is_dir() {
+            let out_dir = out_dir.unwrap_or(input);
+            collect_dir(input, out_dir, recursive, &mut images)?;
+        } else {
+            let out_dir = out_dir.or_else(|| input.parent()).unwrap_or(Path::new(""));
+            files.insert(input.as_path());
+            images.push(BatchInput {
+                path: input.clone(),
+                out_dir: out_dir.to_path_buf(),
+            });
+        }
+    }
+
+    let outputs: HashSet<PathBuf> = images
+        .iter()
+        .flat_map(|image| {
+            OUTPUT_SUFFIXES
+                .iter()
+                .map(|suffix| output_path(&image.path, &image.out_dir, suffix))
+        })
+        .collect();
+    let mut seen = HashSet::new();
+    images.retain(|image| {
+        (files.contains(image.path.as_path()) || !outputs.contains(&image.path))
+            && seen.insert(image.path.clone())
+    });
+    Ok(images)
+}
+
+// Add the decodable images in `dir`, writing their outputs to `out_dir`.
+fn collect_dir(
+    dir: &Path,
+    out_dir: &Path,
+    recursive: bool,
+    images: &mut Vec<BatchInput>,
+) -> OutlineResult<()> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            if recursive {
+                let nested = out_dir.join(path.file_name().expect("directory entry has a name"));
+                collect_dir(&path, &nested, recursive, images)?;
+            }
+        } else if ImageFormat::from_path(&path).is_ok_and(|format| format.reading_enabled()) {
+            images.push(BatchInput {
+                path,
+                out_dir: out_dir.to_path_buf(),
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use image::{Rgb, RgbImage};
+
+    use crate::cli::{Cli, Commands};
+    use crate::commands::tiny_onnx;
+
+    fn write_image(path: &Path) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        RgbImage::from_pixel(2, 2, Rgb([10, 20, 30]))
+            .save(path)
+            .unwrap();
+    }
+
+    fn batch_input(path: PathBuf, out_dir: PathBuf) -> BatchInput {
+        BatchInput { path, out_dir }
+    }
+
+    #[test]
+    fn collect_inputs_lists_images_and_mirrors_subdirectories() {
+        let dir = tempfile::tempdir().unwrap();
+        let photos = dir.path().join("photos");
+        write_image(&photos.join("b.png"));
+        write_image(&photos.join("a.jpg"));
+        write_image(&photos.join("nested/c.png"));
+        fs::write(photos.join("notes.txt"), b"").unwrap();
+        write_image(&photos.join("b-foreground.png"));
+        write_image(&photos.join("b-mask.png"));
+        write_image(&photos.join("x-mask.png"));
+        let single = dir.path().join("single.png");
+        write_image(&single);
+
+        let inputs = [photos.clone(), single.clone(), photos.join("a.jpg")];
+        let images = collect_inputs(&inputs, false, None).unwrap();
+        assert_eq!(
+            images,
+            vec![
+                batch_input(photos.join("a.jpg"), photos.clone()),
+                batch_input(photos.join("b.png"), photos.clone()),
+                batch_input(photos.join("x-mask.png"), photos.clone()),
+                batch_input(single.clone(), dir.path().to_path_buf()),
+            ]
+        );
+
+        let out = dir.path().join("out");
+        let images = collect_inputs(&inputs[..1], true, Some(&out)).unwrap();
+        assert_eq!(
+            images,
+            vec![
+                batch_input(photos.join("a.jpg"), out.clone()),
+                batch_input(photos.join("b-foreground.png"), out.clone()),
+                batch_input(photos.join("b-mask.png"), out.clone()),
+                batch_input(photos.join("b.png"), out.clone()),
+                batch_input(photos.join("nested/c.png"), out.join("nested")),
+                batch_input(photos.join("x-mask.png"), out.clone()),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_output_collisions_rejects_shared_output_paths() {
+        let out = PathBuf::from("out");
+        let images = [
+            batch_input(PathBuf::from("a.jpg"), out.clone()),
+            batch_input(PathBuf::from("a.png"), out.clone()),
+        ];
+        let err = check_output_collisions(&images, &OUTPUT_SUFFIXES).unwrap_err();
+        assert!(err.to_string().contains("a-foreground.png"), "{err}");
+
+        let images = [
+            batch_input(PathBuf::from("x/a.png"), out.clone()),
+            batch_input(PathBuf::from("y/a.png"), out.clone()),
+        ];
+        assert!(check_output_collisions(&images, &OUTPUT_SUFFIXES).is_err());
+
+        let images = [
+            batch_input(PathBuf::from("x/a.png"), PathBuf::from("x")),
+            batch_input(PathBuf::from("y/a.png"), PathBuf::from("y")),
+            batch_input(PathBuf::from("x/b.png"), PathBuf::from("x")),
+        ];
+        assert!(check_output_collisions(&images, &OUTPUT_SUFFIXES).is_ok());
+    }
+
+    #[test]
+    fn summary_follows_input_order_with_parallel_jobs() {
+        let model = tiny_onnx::tiny_matte_model_file();
+        let dir = tempfile::tempdir().unwrap();
+        let names = ["a.png", "b.png", "c.png", "d.png", "e.png"];
+        for name in names {
+            write_image(&dir.path().join(name));
+        }
+        fs::write(dir.path().join("c.png"), b"not a png").unwrap();
+        let cli = Cli::try_parse_from([
+            "outline",
+            "--model",
+            model.path().to_str().unwrap(),
+            "--format",
+            "json",
+            "batch",
+            dir.path().to_str().unwrap(),
+            "--jobs",
+            "3",
+        ])
+        .unwrap();
+        let Commands::Batch(cmd) = cli.command else {
+            panic!("expected batch command");
+        };
+
+        let report = run_report(&cli.global, cmd).unwrap();
+
+        let inputs: Vec<_> = report
+            .items()
+            .iter()
+            .map(|item| item.input.clone())
+            .collect();
+        let expected: Vec<_> = names.iter().map(|name| dir.path().join(name)).collect();
+        assert_eq!(inputs, expected);
+        let failed: Vec<_> = report
+            .items()
+            .iter()
+            .map(|item| item.error.is_some())
+            .collect();
+        assert_eq!(failed, [false, false, true, false, false]);
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(json["items"][3]["input"], expected[3].to_str().unwrap());
+    }
+
+    #[test]
+    fn run_processes_every_image_and_reports_failures() {
+        let model = tiny_onnx::tiny_matte_model_file();
+        let dir = tempfile::tempdir().unwrap();
+        let photos = dir.path().join("photos");
+        write_image(&photos.join("a.png"));
+        write_image(&photos.join("b.png"));
+        let out = dir.path().join("out");
+        let parse = |extra: &[&str]| {
+            let mut args = vec![
+                "outline",
+                "--model",
+                model.path().to_str().unwrap(),
+                "batch",
+            ];
+            args.extend_from_slice(extra);
+            let cli = Cli::try_parse_from(args).unwrap();
+            let Commands::Batch(cmd) = cli.command else {
+                panic!("expected batch command");
+            };
+            (cli.global, cmd)
+        };
+        let photos_arg = photos.to_str().unwrap();
+        let out_arg = out.to_str().unwrap();
+
+        let (global, cmd) = parse(&[photos_arg, "--out-dir", out_arg, "--export-mask", "-j", "2"]);
+        run(&global, cmd).expect("batch should succeed");
+        for name in [
+            "a-foreground.png",
+            "a-mask.png",
+            "b-foreground.png",
+            "b-mask.png",
+        ] {
+            assert!(out.join(name).is_file(), "{name} should be written");
+        }
+
+        fs::write(photos.join("broken.png"), b"not a png").unwrap();
+        fs::remove_dir_all(&out).unwrap();
+        let (global, cmd) = parse(&[photos_arg, "--out-dir", out_arg]);
+        assert!(run(&global, cmd).is_err());
+        assert!(out.join("a-foreground.png").is_file());
+        assert!(out.join("b-foreground.png").is_file());
+        assert!(!out.join("broken-foreground.png").exists());
+    }
+}
@@ -0,0 +1,176 @@
+use std::fs;
+use std::io;
+
+use image::GrayImage;
+use outline::{BoundingBox, OutlineResult, contours, simplify_contour, subject_bbox};
+use serde::Serialize;
+
+use crate::cli::{ContoursCommand, GlobalOptions, MaskSourceArg};
+use crate::report::Report;
+
+use super::utils::{
+    derive_variant_path, mask_pipeline_from_args, matte_for_input, processing_requested,
+    resolve_mask_source_arg,
+};
+
+/// The JSON document written by the contours command.
+#[derive(Debug, Serialize)]
+struct ContoursFile {
+    width: u32,
+    height: u32,
+    /// The box normalized coordinates are relative to, present with `--normalize`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bbox: Option<BoundingBox>,
+    polygons: Vec<Vec<[f64; 2]>>,
+}
+
+/// The main function to run the contours command.
+pub fn run(global: &GlobalOptions, cmd: ContoursCommand) -> OutlineResult<()> {
+    let mut report = Report::new(global, "contours");
+    let session = matte_for_input(global, &cmd.input)?;
+    report.input(&cmd.input, session.rgb_image().dimensions());
+    let matte = session.matte();
+
+    let processing_requested = processing_requested(&cmd.mask_processing);
+    let mask = match resolve_mask_source_arg(cmd.mask_source, processing_requested) {
+        MaskSourceArg::Raw => matte.into_image(),
+        MaskSourceArg::Processed => matte
+            .processed_with(&mask_pipeline_from_args(&cmd.mask_processing))?
+            .into_image(),
+        MaskSourceArg::Auto => unreachable!(),
+    };
+
+    let file = contours_file(&mask, cmd.simplify, cmd.normalize);
+    let output_path = cmd
+        .output
+        .clone()
+        .unwrap_or_else(|| derive_variant_path(&cmd.input, "contours", "json"));
+    let json = serde_json::to_string(&file).map_err(io::Error::other)?;
+    fs::write(&output_path, json + "\n")?;
+    report.saved("contours", "Contours JSON", &output_path);
+    report.finish()
+}
+
+// Trace the mask and convert its contours into the output polygons.
+fn contours_file(mask: &GrayImage, simplify: Option<f64>, normalize: bool) -> ContoursFile {
+    let bbox = normalize.then(|| subject_bbox(mask, 1)).flatten();
+    let polygons = contours(mask)
+        .into_iter()
+        .map(|contour| {
+            let mut points: Vec<(f64, f64)> = contour
+                .into_iter()
+                .map(|(x, y)| (f64::from(x), f64::from(y)))
+                .collect();
+            if let Some(epsilon) = simplify {
+                // Closing the ring keeps the edge back to the start in the simplification.
+                points.push(points[0]);
+                points = simplify_contour(&points, epsilon);
+                if points.len() > 1 {
+                    points.pop();
+                }
+            }
+            points
+                .into_iter()
+                .map(|(x, y)| match bbox {
+                    Some(bbox) => [
+                        (x - f64::from(bbox.x)) / f64::from(bbox.width.max(2) - 1),
+                        (y - f64::from(bbox.y)) / f64::from(bbox.height.max(2) - 1),
+                    ],
+                    None => [x, y],
+                })
+                .collect()
+        })
+        .collect();
+
+    ContoursFile {
+        width: mask.width(),
+        height: mask.height(),
+        bbox,
+        polygons,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use image::{Luma, Rgb, RgbImage};
+
+    use crate::cli::{Cli, Commands};
+
+    fn two_squares() -> GrayImage {
+        GrayImage::from_fn(12, 8, |x, y| {
+            let left = (1..=3).contains(&x) && (2..=4).contains(&y);
+            let right = (7..=10).contains(&x) && (3..=6).contains(&y);
+            Luma([if left || right { 255 } else { 0 }])
+        })
+    }
+
+    #[test]
+    fn simplified_polygons_keep_only_corners() {
+        let file = contours_file(&two_squares(), Some(0.5), false);
+
+        assert_eq!((file.width, file.height), (12, 8));
+        assert_eq!(
+            file.polygons,
+            vec![
+                vec![[1.0, 2.0], [3.0, 2.0], [3.0, 4.0], [1.0, 4.0]],
+                vec![[7.0, 3.0], [10.0, 3.0], [10.0, 6.0], [7.0, 6.0]],
+            ]
+        );
+    }
+
+    #[test]
+    fn normalized_coordinates_span_the_subject_box() {
+        let file = contours_file(&two_squares(), Some(0.5), true);
+
+        assert_eq!(file.bbox, Some(BoundingBox::new(1, 2, 10, 5)));
+        let points = file.polygons.iter().flatten();
+        assert!(
+            points
+                .clone()
+                .all(|point| point.iter().all(|v| (0.0..=1.0).contains(v)))
+        );
+        assert!(points.clone().any(|point| *point == [0.0, 0.0]));
+        assert!(points.clone().any(|point| *point == [1.0, 1.0]));
+    }
+
+    #[test]
+    fn run_writes_one_polygon_per_component() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("shapes.png");
+        // With --from-luma dark pixels become foreground, so each black square is a component.
+        let image = RgbImage::from_fn(12, 8, |x, y| {
+            if two_squares().get_pixel(x, y)[0] == 255 {
+                Rgb([0, 0, 0])
+            } else {
+                Rgb([255, 255, 255])
+            }
+        });
+        image.save(&input).unwrap();
+        let output = dir.path().join("contours.json");
+        let cli = Cli::try_parse_from([
+            "outline",
+            "--from-luma",
+            "contours",
+            input.to_str().unwrap(),
+            "-o",
+            output.to_str().unwrap(),
+            "--simplify",
+            "0.5",
+        ])
+        .unwrap();
+        let Commands::Contours(cmd) = cli.command else {
+            panic!("expected contours command");
+        };
+
+        run(&cli.global, cmd).unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&output).unwrap()).unwrap();
+        assert_eq!(json["width"], 12);
+        assert_eq!(json["height"], 8);
+        assert_eq!(json["polygons"].as_array().unwrap().len(), 2);
+        assert_eq!(json["polygons"][0][0], serde_json::json!([1.0, 2.0]));
+    }
+}
@@ -1,23 +1,54 @@
-use outline::{MaskHandle, MatteHandle, OutlineResult};
+use image::{DynamicImage, GrayImage, Luma, imageops};
+use outline::{
+    BoundingBox, MaskHandle, MatteHandle, OutlineError, OutlineResult, Padding,
+    desaturate_background, edge_hardness, subject_bbox,
+};
 
 use crate::cli::{AlphaFromArg, CutCommand, GlobalOptions};
+use crate::diagnostics;
+use crate::report::Report;
 
 use super::utils::{
-    build_outline, derive_variant_path, mask_pipeline_from_args, processing_requested,
-    resolve_alpha_source, resolve_export_path,
+    SaveOptions, build_outline, compose_over_source_background, derive_variant_path,
+    float_matte_for_input, image_format_name, is_webp_path, mask_pipeline_from_args,
+    matte_for_input, max_pixels, open_image, orient_mask, processing_requested, report_matte_stats,
+    report_model_outputs, resolve_alpha_source, resolve_export_path, resolve_mask_bit_depth,
+    save_flattened, save_mask_png,
 };
 
+/// Transparent margin kept around the subject when auto-trimming WebP output.
+const WEBP_TRIM_MARGIN: u32 = 8;
+
+/// Edge hardness above which `--auto-feather` softens the alpha.
+const AUTO_FEATHER_HARDNESS: f32 = 0.5;
+
+/// Blur sigma used by `--auto-feather`, roughly a sub-pixel to one-pixel feather.
+const AUTO_FEATHER_SIGMA: f32 = 0.75;
+
 /// The main function to run the cut command.
 pub fn run(global: &GlobalOptions, cmd: CutCommand) -> OutlineResult<()> {
-    let outline = build_outline(global);
-    let session = outline.for_image(&cmd.input)?;
+    let mut report = Report::new(global, "cut");
+    if cmd.list_outputs {
+        report_model_outputs(&mut report, &build_outline(global)?)?;
+        return report.finish();
+    }
+    // Only the confidence map reads the float matte, so it is only kept then.
+    let session = if cmd.confidence.is_some() {
+        float_matte_for_input(global, &cmd.input)?
+    } else {
+        matte_for_input(global, &cmd.input)?
+    };
+    report.input(&cmd.input, session.rgb_image().dimensions());
     let matte = session.matte();
-    let output_path = cmd
-        .output
-        .clone()
-        .unwrap_or_else(|| derive_variant_path(&cmd.input, "foreground", "png"));
-
-    let save_mask_path = resolve_export_path(&cmd.export_matte, &cmd.input, "matte");
+    let save_mask_path = if cmd.matte_only {
+        Some(
+            cmd.output
+                .clone()
+                .unwrap_or_else(|| derive_variant_path(&cmd.input, "matte", "png")),
+        )
+    } else {
+        resolve_export_path(&cmd.export_matte, &cmd.input, "matte")
+    };
     let save_processed_mask_path = resolve_export_path(&cmd.export_mask, &cmd.input, "mask");
 
     let mut processed_mask: Option<MaskHandle> = None;
@@ -36,24 +67,300 @@ pub fn run(global: &GlobalOptions, cmd: CutCommand) -> OutlineResult<()> {
         }
     };
 
-    let foreground = match alpha_source {
-        AlphaFromArg::Raw => matte.foreground()?,
-        AlphaFromArg::Processed => ensure_processed(&matte)?.foreground()?,
-        AlphaFromArg::Auto => unreachable!(),
+    if cmd.stats {
+        match alpha_source {
+            AlphaFromArg::Processed => {
+                report_matte_stats(&mut report, ensure_processed(&matte)?.as_raw_mask())
+            }
+            _ => report_matte_stats(&mut report, matte.as_raw_matte()),
+        }
+    }
+
+    // Every output is cropped to the same box, so the foreground, matte, and mask stay aligned.
+    // The box leaves room for the stroke and shadow, which are drawn on the cropped foreground.
+    let autocrop_bounds = if cmd.autocrop {
+        let bounds = match alpha_source {
+            AlphaFromArg::Processed => subject_bbox(ensure_processed(&matte)?.as_raw_mask(), 1),
+            _ => subject_bbox(matte.as_raw_matte(), 1),
+        };
+        if bounds.is_none() {
+            diagnostics::warn("the matte is empty; --autocrop keeps the full canvas.")?;
+        }
+        let margin = effect_margin(cmd.stroke, cmd.shadow);
+        bounds.map(|bounds| grow_bounds(bounds, margin, matte.as_raw_matte().dimensions()))
+    } else {
+        None
+    };
+
+    // Resolved before anything is saved, so a `--strict` bit-depth warning writes nothing.
+    let exported_mask = match &save_processed_mask_path {
+        Some(path) => {
+            let mut mask = ensure_processed(&matte)?;
+            if let Some(bounds) = autocrop_bounds {
+                let canvas = mask.as_raw_mask().dimensions();
+                mask = mask
+                    .crop(bounds)
+                    .ok_or(OutlineError::CropOutOfBounds { bounds, canvas })?
+                    .pad(cmd.padding);
+            }
+            let mask = orient_mask(&session, mask.as_raw_mask(), cmd.stored_orientation);
+            let bit_depth = resolve_mask_bit_depth(&mask, path, cmd.mask_bit_depth)?;
+            Some((path, mask, bit_depth))
+        }
+        None => None,
     };
 
-    foreground.save(&output_path)?;
-    println!("Foreground PNG saved to {}", output_path.display());
+    if !cmd.matte_only {
+        let output_path = cmd
+            .output
+            .clone()
+            .unwrap_or_else(|| derive_variant_path(&cmd.input, "foreground", "png"));
+
+        let mut foreground = match alpha_source {
+            AlphaFromArg::Raw => {
+                if cmd.auto_feather && edge_hardness(matte.as_raw_matte()) > AUTO_FEATHER_HARDNESS {
+                    matte.clone().blur_with(AUTO_FEATHER_SIGMA).foreground()?
+                } else {
+                    matte.foreground()?
+                }
+            }
+            AlphaFromArg::Processed => {
+                let mask = ensure_processed(&matte)?;
+                if cmd.auto_feather && edge_hardness(mask.as_raw_mask()) > AUTO_FEATHER_HARDNESS {
+                    mask.blur_with(AUTO_FEATHER_SIGMA).foreground()?
+                } else {
+                    mask.foreground()?
+                }
+            }
+            AlphaFromArg::Auto => unreachable!(),
+        };
+        if let Some(bounds) = autocrop_bounds {
+            let canvas = foreground.image().dimensions();
+            foreground = foreground
+                .crop(bounds)
+                .ok_or(OutlineError::CropOutOfBounds { bounds, canvas })?
+                .pad(cmd.padding);
+        }
+
+        if cmd.vignette.is_some() || cmd.corner_radius.is_some() {
+            foreground = foreground.vignette(
+                cmd.vignette.unwrap_or(0.0),
+                cmd.corner_radius.unwrap_or(0.0),
+            );
+        }
+
+        // The stroke goes first so the shadow falls from the stroked silhouette.
+        if let Some(width) = cmd.stroke {
+            foreground = foreground.stroke(width, cmd.stroke_color);
+        }
+        if let Some((dx, dy, blur)) = cmd.shadow {
+            foreground = foreground.drop_shadow((dx, dy), blur, cmd.shadow_color);
+        }
+
+        // Only transparent output is trimmed; cropping an opaque background changes the picture.
+        let opaque = cmd.background.is_some()
+            || cmd.bg_gradient.is_some()
+            || cmd.bg_image.is_some()
+            || cmd.bg_blur.is_some()
+            || cmd.bg_desaturate;
+        if is_webp_path(&output_path) && !cmd.no_trim && !cmd.autocrop && !opaque {
+            // A fully transparent foreground has no content to trim to, so keep the full canvas.
+            if let Some(bounds) = foreground.bounding_box() {
+                let canvas = foreground.image().dimensions();
+                foreground = foreground
+                    .crop(bounds)
+                    .ok_or(OutlineError::CropOutOfBounds { bounds, canvas })?
+                    .pad(WEBP_TRIM_MARGIN);
+            }
+        }
+
+        let mut output = if let Some(color) = cmd.background {
+            DynamicImage::ImageRgb8(foreground.compose_over_color(color))
+        } else if let Some(gradient) = &cmd.bg_gradient {
+            DynamicImage::ImageRgb8(foreground.compose_over_gradient(gradient))
+        } else if let Some(path) = &cmd.bg_image {
+            let background = open_image(path, max_pixels(global))?.into_rgba8();
+            DynamicImage::ImageRgb8(foreground.compose_over_image(&background, cmd.bg_fit.into()))
+        } else if cmd.bg_blur.is_some() || cmd.bg_desaturate {
+            // The cropped and padded foreground no longer carries the source colors everywhere,
+            // so render the whole frame (an empty matte keeps only the background).
+            let rgb = session.rgb_image();
+            let background = match cmd.bg_blur {
+                Some(sigma) => imageops::blur(rgb, sigma),
+                None => desaturate_background(rgb, &GrayImage::new(rgb.width(), rgb.height()))?,
+            };
+            DynamicImage::ImageRgb8(compose_over_source_background(
+                foreground.image(),
+                &background,
+                autocrop_bounds,
+                cmd.padding,
+            )?)
+        } else {
+            DynamicImage::ImageRgba8(foreground.image().clone())
+        };
+        if cmd.stored_orientation {
+            output = session.to_stored_orientation(output);
+        }
+        save_flattened(output, &output_path, SaveOptions::from_global(global))?;
+        let label = format!("Foreground {}", image_format_name(&output_path));
+        report.saved("foreground", label, &output_path);
+
+        if let Some(path) = &cmd.channel_dump {
+            foreground.channel_dump().save(path)?;
+            report.saved("channel_dump", "Channel dump PNG", path);
+        }
+        if let Some(path) = &cmd.preview {
+            foreground
+                .composite_on_checkerboard(cmd.preview_cell)
+                .save(path)?;
+            report.saved("preview", "Checkerboard preview PNG", path);
+        }
+    }
 
     if let Some(path) = &save_mask_path {
-        matte.clone().save(path)?;
-        println!("Matte PNG saved to {}", path.display());
+        let matte = match autocrop_bounds {
+            Some(bounds) => matte
+                .clone()
+                .crop(bounds)
+                .ok_or(OutlineError::CropOutOfBounds {
+                    bounds,
+                    canvas: matte.as_raw_matte().dimensions(),
+                })?
+                .pad(cmd.padding),
+            None => matte.clone(),
+        };
+        orient_mask(&session, matte.as_raw_matte(), cmd.stored_orientation).save(path)?;
+        report.saved("matte", "Matte PNG", path);
+    }
+
+    if let Some(path) = &cmd.confidence {
+        let mut confidence = session.confidence_map();
+        if let Some(bounds) = autocrop_bounds {
+            // The padding is transparent in the matte, which is a confident value.
+            let cropped =
+                imageops::crop_imm(&confidence, bounds.x, bounds.y, bounds.width, bounds.height)
+                    .to_image();
+            let padding = cmd.padding;
+            confidence = GrayImage::from_pixel(
+                bounds.width + 2 * padding,
+                bounds.height + 2 * padding,
+                Luma([255]),
+            );
+            imageops::replace(
+                &mut confidence,
+                &cropped,
+                i64::from(padding),
+                i64::from(padding),
+            );
+        }
+        orient_mask(&session, &confidence, cmd.stored_orientation).save(path)?;
+        report.saved("confidence", "Confidence map PNG", path);
+    }
+
+    if let Some((path, mask, bit_depth)) = exported_mask {
+        save_mask_png(&mask, path, bit_depth)?;
+        report.saved("mask", "Processed mask PNG", path);
+    }
+
+    report.finish()
+}
+
+/// How far the stroke and drop shadow reach past the subject on each edge.
+fn effect_margin(stroke: Option<f32>, shadow: Option<(i32, i32, f32)>) -> Padding {
+    let stroke = stroke.map_or(0, |width| width.ceil() as u32);
+    let Some((dx, dy, blur)) = shadow else {
+        return Padding::uniform(stroke);
+    };
+    // The shadow falls from the stroked silhouette, and its blur spreads about three sigma.
+    let spread = i64::from((3.0 * blur).ceil() as u32);
+    let reach = |shift: i32| {
+        let shadow = (spread + i64::from(shift)).clamp(0, i64::from(u32::MAX)) as u32;
+        stroke.saturating_add(shadow)
+    };
+    Padding::new(reach(-dx), reach(-dy), reach(dx), reach(dy))
+}
+
+/// Grow `bounds` by `margin` on each edge without leaving a `width` x `height` canvas.
+fn grow_bounds(bounds: BoundingBox, margin: Padding, (width, height): (u32, u32)) -> BoundingBox {
+    let x = bounds.x.saturating_sub(margin.left);
+    let y = bounds.y.saturating_sub(margin.top);
+    let right = bounds.right().saturating_add(margin.right).min(width);
+    let bottom = bounds.bottom().saturating_add(margin.bottom).min(height);
+    BoundingBox::new(x, y, right - x, bottom - y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use image::{Rgb, RgbImage};
+
+    use crate::cli::{Cli, Commands};
+    use crate::commands::tiny_onnx;
+
+    #[test]
+    fn effect_margin_covers_the_stroke_and_the_offset_blurred_shadow() {
+        assert_eq!(effect_margin(None, None), Padding::uniform(0));
+        assert_eq!(effect_margin(Some(2.5), None), Padding::uniform(3));
+        assert_eq!(
+            effect_margin(Some(2.0), Some((4, -1, 1.0))),
+            Padding::new(2, 6, 9, 4)
+        );
     }
 
-    if let Some(path) = &save_processed_mask_path {
-        ensure_processed(&matte)?.save(path)?;
-        println!("Processed mask PNG saved to {}", path.display());
+    #[test]
+    fn grow_bounds_stays_inside_the_canvas() {
+        let bounds = BoundingBox::new(2, 3, 4, 4);
+        assert_eq!(
+            grow_bounds(bounds, Padding::new(1, 5, 3, 1), (8, 10)),
+            BoundingBox::new(1, 0, 7, 8)
+        );
     }
 
-    Ok(())
+    #[test]
+    fn autocrop_keeps_room_for_the_stroke() {
+        let model = tiny_onnx::tiny_matte_model_file();
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("photo.png");
+        RgbImage::from_pixel(8, 8, Rgb([10, 20, 30]))
+            .save(&input)
+            .unwrap();
+        let output = dir.path().join("cut.png");
+        let matte = dir.path().join("matte.png");
+        // The tiny model's matte is opaque only in its bottom-right quadrant once thresholded.
+        let cli = Cli::try_parse_from([
+            "outline",
+            "--model",
+            model.path().to_str().unwrap(),
+            "--output-resample-filter",
+            "nearest",
+            "cut",
+            input.to_str().unwrap(),
+            "-o",
+            output.to_str().unwrap(),
+            "--export-matte",
+            matte.to_str().unwrap(),
+            "--alpha-source",
+            "processed",
+            "--threshold",
+            "200",
+            "--autocrop",
+            "--stroke",
+            "2",
+            "--stroke-color",
+            "#FF0000",
+        ])
+        .unwrap();
+        let Commands::Cut(cmd) = cli.command else {
+            panic!("expected cut command");
+        };
+
+        run(&cli.global, cmd).unwrap();
+
+        let foreground = image::open(&output).unwrap().into_rgba8();
+        assert_eq!(foreground.dimensions(), (6, 6));
+        assert_eq!(foreground.get_pixel(1, 3).0, [255, 0, 0, 255]);
+        assert_eq!(foreground.get_pixel(4, 4).0, [10, 20, 30, 255]);
+        assert_eq!(image::open(&matte).unwrap().to_luma8().dimensions(), (6, 6));
+    }
 }
@@ -0,0 +1,105 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::Path;
+
+use image::codecs::ico::{IcoEncoder, IcoFrame};
+use image::imageops::{self, FilterType};
+use image::{ExtendedColorType, RgbaImage};
+use outline::OutlineResult;
+
+use crate::cli::{AlphaFromArg, GlobalOptions, IconCommand, MAX_ICO_FRAME_SIZE};
+use crate::report::Report;
+
+use super::utils::{
+    derive_variant_path, mask_pipeline_from_args, matte_for_input, output_base,
+    processing_requested, resolve_alpha_source,
+};
+
+/// The main function to run the icon command.
+pub fn run(global: &GlobalOptions, cmd: IconCommand) -> OutlineResult<()> {
+    let mut sizes = cmd.sizes.clone();
+    sizes.sort_unstable();
+    sizes.dedup();
+    if cmd.ico.is_some()
+        && let Some(&size) = sizes.iter().find(|&&size| size > MAX_ICO_FRAME_SIZE)
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "ICO frames are limited to {MAX_ICO_FRAME_SIZE}px, but --sizes includes {size}"
+            ),
+        )
+        .into());
+    }
+
+    let mut report = Report::new(global, "icon");
+    let session = matte_for_input(global, &cmd.input)?;
+    report.input(&cmd.input, session.rgb_image().dimensions());
+    let matte = session.matte();
+
+    let processing_requested = processing_requested(&cmd.mask_processing);
+    let foreground = match resolve_alpha_source(cmd.alpha_source, processing_requested) {
+        AlphaFromArg::Raw => matte.foreground()?,
+        AlphaFromArg::Processed => matte
+            .processed_with(&mask_pipeline_from_args(&cmd.mask_processing))?
+            .foreground()?,
+        AlphaFromArg::Auto => unreachable!(),
+    };
+    let square = foreground
+        .crop_to_content()
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the mask has no foreground content to build an icon from",
+            )
+        })?
+        .pad_to_square()
+        .into_image();
+
+    let base = match &cmd.out_dir {
+        Some(dir) => {
+            fs::create_dir_all(dir)?;
+            dir.join(output_base(&cmd.input).file_name().unwrap_or_default())
+        }
+        None => output_base(&cmd.input).to_path_buf(),
+    };
+
+    let filter = FilterType::from(global.output_resample_filter);
+    let icons: Vec<RgbaImage> = sizes
+        .iter()
+        .map(|&size| imageops::resize(&square, size, size, filter))
+        .collect();
+    for (size, icon) in sizes.iter().zip(&icons) {
+        let path = derive_variant_path(&base, &format!("icon-{size}"), "png");
+        icon.save(&path)?;
+        report.saved("icon", format!("{size}x{size} icon PNG"), &path);
+    }
+
+    if let Some(ico) = &cmd.ico {
+        let path = ico
+            .clone()
+            .unwrap_or_else(|| derive_variant_path(&base, "icon", "ico"));
+        save_ico(&icons, &path)?;
+        report.saved("ico", format!("ICO with {} sizes", icons.len()), &path);
+    }
+
+    report.finish()
+}
+
+/// Write square RGBA icons as the PNG-encoded frames of one ICO file.
+fn save_ico(icons: &[RgbaImage], path: &Path) -> OutlineResult<()> {
+    let frames = icons
+        .iter()
+        .map(|icon| {
+            IcoFrame::as_png(
+                icon.as_raw(),
+                icon.width(),
+                icon.height(),
+                ExtendedColorType::Rgba8,
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let writer = BufWriter::new(File::create(path)?);
+    IcoEncoder::new(writer).encode_images(&frames)?;
+    Ok(())
+}
@@ -1,22 +1,44 @@
-use outline::OutlineResult;
+use std::fs;
+use std::path::Path;
 
-use crate::cli::{GlobalOptions, MaskCommand, MaskExportSource};
+use image::{DynamicImage, GrayImage};
+use outline::{
+    InferencedMatte, MaskExt, MaskOperation, MaskPipeline, OutlineResult, matte_to_colormap,
+    otsu_threshold,
+};
+
+use crate::cli::{GlobalOptions, MaskBitDepthArg, MaskCommand, MaskExportSource};
+use crate::report::Report;
 
 use super::utils::{
-    build_outline, derive_variant_path, mask_pipeline_from_args, processing_requested,
-    resolve_mask_export_source,
+    derive_variant_path, float_matte_for_input, mask_pipeline_from_args, matte_for_input,
+    orient_mask, processing_requested, report_matte_stats, resolve_mask_bit_depth,
+    resolve_mask_export_source, save_mask_png,
 };
 
 /// The main function to run the mask command.
 pub fn run(global: &GlobalOptions, cmd: MaskCommand) -> OutlineResult<()> {
-    let outline = build_outline(global);
-    let session = outline.for_image(&cmd.input)?;
+    run_report(global, cmd)?.finish()
+}
+
+/// Run the mask command and return the report of what it wrote.
+fn run_report(global: &GlobalOptions, cmd: MaskCommand) -> OutlineResult<Report> {
+    let mut report = Report::new(global, "mask");
+    // Only the 16-bit export and the confidence map read the float matte, so it is only kept then.
+    let session = if cmd.mask_bit_depth == MaskBitDepthArg::Sixteen || cmd.confidence.is_some() {
+        float_matte_for_input(global, &cmd.input)?
+    } else {
+        matte_for_input(global, &cmd.input)?
+    };
+    report.input(&cmd.input, session.rgb_image().dimensions());
     let matte = session.matte();
     let mask_pipeline = mask_pipeline_from_args(&cmd.mask_processing);
     let mask_source =
         resolve_mask_export_source(cmd.mask_source, processing_requested(&cmd.mask_processing));
 
     let default_suffix = match mask_source {
+        _ if cmd.overlay.is_some() => "overlay",
+        _ if cmd.visualize.is_some() => "colormap",
         MaskExportSource::Processed => "mask",
         MaskExportSource::Raw => "matte",
         MaskExportSource::Auto => unreachable!(),
@@ -26,18 +48,154 @@ pub fn run(global: &GlobalOptions, cmd: MaskCommand) -> OutlineResult<()> {
         .clone()
         .unwrap_or_else(|| derive_variant_path(&cmd.input, default_suffix, "png"));
 
-    match mask_source {
+    let (mask, label, kind) = match mask_source {
         MaskExportSource::Processed => {
-            let mask = matte.clone().processed_with(&mask_pipeline)?;
-            mask.save(&output_path)?;
-            println!("Processed mask PNG saved to {}", output_path.display());
+            report_otsu_thresholds(session.raw_matte(), &mask_pipeline, &mut report);
+            (
+                matte.processed_with(&mask_pipeline)?,
+                "Processed mask",
+                "mask",
+            )
         }
         MaskExportSource::Auto => unreachable!(),
-        MaskExportSource::Raw => {
-            matte.save(&output_path)?;
-            println!("Matte PNG saved to {}", output_path.display());
+        // Without operations this shares the raw matte instead of copying it.
+        MaskExportSource::Raw => (matte.processed()?, "Matte", "matte"),
+    };
+
+    if cmd.stats {
+        report_matte_stats(&mut report, mask.as_raw_mask());
+    }
+
+    let save_16bit = cmd.mask_bit_depth == MaskBitDepthArg::Sixteen
+        && matches!(mask_source, MaskExportSource::Raw);
+    // Resolved before anything is saved, so a `--strict` bit-depth warning writes nothing.
+    let mask_png = if cmd.overlay.is_none() && cmd.visualize.is_none() && !save_16bit {
+        let mask = orient_mask(&session, mask.as_raw_mask(), cmd.stored_orientation);
+        let bit_depth = resolve_mask_bit_depth(&mask, &output_path, cmd.mask_bit_depth)?;
+        Some((mask, bit_depth))
+    } else {
+        None
+    };
+
+    if let Some(dir) = &cmd.debug_matte_stages {
+        save_matte_stages(&session, &cmd.input, dir, &mut report)?;
+    }
+
+    if let Some(color) = cmd.overlay {
+        let overlay = mask.overlay_on(session.rgb_image(), color, cmd.overlay_opacity)?;
+        let mut overlay = DynamicImage::ImageRgba8(overlay);
+        if cmd.stored_orientation {
+            overlay = session.to_stored_orientation(overlay);
         }
+        overlay.save(&output_path)?;
+        report.saved("overlay", format!("{label} overlay PNG"), &output_path);
+    } else if let Some(map) = cmd.visualize {
+        let mask = orient_mask(&session, mask.as_raw_mask(), cmd.stored_orientation);
+        matte_to_colormap(&mask, map.into()).save(&output_path)?;
+        report.saved("colormap", format!("{label} colormap PNG"), &output_path);
+    } else if let Some((mask, bit_depth)) = mask_png {
+        save_mask_png(&mask, &output_path, bit_depth)?;
+        report.saved(kind, format!("{label} PNG"), &output_path);
+    } else {
+        let mut matte = DynamicImage::ImageLuma16(session.raw_matte_u16());
+        if cmd.stored_orientation {
+            matte = session.to_stored_orientation(matte);
+        }
+        matte.save(&output_path)?;
+        report.saved(kind, format!("{label} 16-bit PNG"), &output_path);
+    }
+
+    if let Some(path) = &cmd.confidence {
+        orient_mask(&session, &session.confidence_map(), cmd.stored_orientation).save(path)?;
+        report.saved("confidence", "Confidence map PNG", path);
     }
 
+    Ok(report)
+}
+
+/// Report the value each `--auto-threshold` step picks, so it can be reused with `--threshold`.
+fn report_otsu_thresholds(raw_matte: &GrayImage, pipeline: &MaskPipeline, report: &mut Report) {
+    let operations = pipeline.operations();
+    for (index, operation) in operations.iter().enumerate() {
+        if matches!(operation, MaskOperation::OtsuThreshold) {
+            let input = raw_matte.apply_operations(&operations[..index]);
+            report.auto_threshold(otsu_threshold(&input));
+        }
+    }
+}
+
+/// Save the model-resolution and full-resolution mattes side by side for comparison.
+fn save_matte_stages(
+    session: &InferencedMatte,
+    input: &Path,
+    dir: &Path,
+    report: &mut Report,
+) -> OutlineResult<()> {
+    fs::create_dir_all(dir)?;
+    let stage_path = |suffix: &str| {
+        let variant = derive_variant_path(input, suffix, "png");
+        dir.join(variant.file_name().expect("derived path has a file name"))
+    };
+
+    if let Some(model_matte) = session.model_matte() {
+        let path = stage_path("matte-model");
+        model_matte.save(&path)?;
+        report.saved("matte_model", "Model-resolution matte PNG", &path);
+    }
+    let path = stage_path("matte-full");
+    session.raw_matte().save(&path)?;
+    report.saved("matte_full", "Full-resolution matte PNG", &path);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use image::{Rgb, RgbImage};
+
+    use crate::cli::{Cli, Commands};
+    use crate::commands::tiny_onnx;
+
+    #[test]
+    fn json_format_reports_input_outputs_and_stats() {
+        let model = tiny_onnx::tiny_matte_model_file();
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("photo.png");
+        RgbImage::from_pixel(3, 2, Rgb([10, 20, 30]))
+            .save(&input)
+            .unwrap();
+        let output = dir.path().join("matte.png");
+        let cli = Cli::try_parse_from([
+            "outline",
+            "--model",
+            model.path().to_str().unwrap(),
+            "--format",
+            "json",
+            "mask",
+            input.to_str().unwrap(),
+            "-o",
+            output.to_str().unwrap(),
+            "--stats",
+        ])
+        .unwrap();
+        let Commands::Mask(cmd) = cli.command else {
+            panic!("expected mask command");
+        };
+
+        let report = run_report(&cli.global, cmd).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+
+        assert_eq!(json["command"], "mask");
+        assert_eq!(json["input"], input.to_str().unwrap());
+        assert_eq!(
+            (json["width"].as_u64(), json["height"].as_u64()),
+            (Some(3), Some(2))
+        );
+        assert_eq!(json["outputs"][0]["kind"], "matte");
+        assert_eq!(json["outputs"][0]["path"], output.to_str().unwrap());
+        assert_eq!(json["stats"]["pixels"], 6);
+        assert!(json["stats"]["coverage"].is_number());
+        assert!(output.is_file());
+    }
+}
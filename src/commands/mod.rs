@@ -1,14 +1,26 @@
+mod batch;
+mod contours;
 mod cut;
 #[cfg(feature = "fetch-model")]
 mod fetch_model;
+mod icon;
 mod mask;
+mod reprocess;
 mod trace;
+mod trimap;
 mod utils;
 
+#[cfg(test)]
+#[path = "../../tests/support/tiny_onnx.rs"]
+mod tiny_onnx;
+
 use crate::cli::{Cli, Commands, GlobalOptions};
 use outline::OutlineResult;
 
 /// The main function to run the command based on CLI input.
+///
+/// `--timeout` is enforced around inference by
+/// [`infer_input`](utils::infer_input), not around the whole command.
 pub fn run(cli: Cli) -> OutlineResult<()> {
     let Cli { global, command } = cli;
     dispatch(&global, command)
@@ -20,6 +32,11 @@ fn dispatch(global: &GlobalOptions, command: Commands) -> OutlineResult<()> {
         Commands::Mask(cmd) => mask::run(global, cmd),
         Commands::Cut(cmd) => cut::run(global, cmd),
         Commands::Trace(cmd) => trace::run(global, cmd),
+        Commands::Reprocess(cmd) => reprocess::run(global, cmd),
+        Commands::Icon(cmd) => icon::run(global, cmd),
+        Commands::Trimap(cmd) => trimap::run(global, cmd),
+        Commands::Contours(cmd) => contours::run(global, cmd),
+        Commands::Batch(cmd) => batch::run(global, cmd),
         #[cfg(feature = "fetch-model")]
         Commands::FetchModel(cmd) => fetch_model::run(cmd),
     }
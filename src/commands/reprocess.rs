@@ -0,0 +1,76 @@
+use std::fs;
+
+use image::DynamicImage;
+use outline::{InferencedMatte, OutlineResult};
+
+use crate::cli::{GlobalOptions, ReprocessCommand};
+use crate::diagnostics;
+use crate::report::Report;
+
+use super::utils::{
+    SaveOptions, find_source_image, list_matte_files, load_oriented_rgb, mask_pipeline_from_args,
+    max_pixels, open_image, save_flattened,
+};
+
+/// The main function to run the reprocess command.
+pub fn run(global: &GlobalOptions, cmd: ReprocessCommand) -> OutlineResult<()> {
+    let mut report = Report::new(global, "reprocess");
+    let mask_pipeline = mask_pipeline_from_args(&cmd.mask_processing);
+    let output_dir = cmd.output.clone().unwrap_or_else(|| cmd.mattes.clone());
+    fs::create_dir_all(&output_dir)?;
+
+    let mattes = list_matte_files(&cmd.mattes)?;
+    if mattes.is_empty() {
+        diagnostics::warn(format!(
+            "no `*-matte.png` files found in {}",
+            cmd.mattes.display()
+        ))?;
+        return report.finish();
+    }
+
+    for (base, matte_path) in mattes {
+        let raw_matte = open_image(&matte_path, max_pixels(global))?.into_luma8();
+        let mask_path = output_dir.join(format!("{base}-mask.png"));
+
+        let source = match &cmd.sources {
+            Some(dir) => {
+                let source = find_source_image(dir, &base)?;
+                if source.is_none() {
+                    diagnostics::warn(format!(
+                        "no source image named `{base}` in {}; saving the mask only",
+                        dir.display()
+                    ))?;
+                }
+                source
+            }
+            None => None,
+        };
+
+        let Some(source) = source else {
+            mask_pipeline.apply(&raw_matte).save(&mask_path)?;
+            report.saved("mask", "Processed mask PNG", &mask_path);
+            continue;
+        };
+
+        let rgb = load_oriented_rgb(&source, max_pixels(global))?;
+        let session = if cmd.resize_matte_to_input {
+            InferencedMatte::from_parts_resized(rgb, raw_matte, global.output_resample_filter)?
+        } else {
+            InferencedMatte::from_parts(rgb, raw_matte)?
+        };
+        let mask = session.matte().processed_with(&mask_pipeline)?;
+        mask.save(&mask_path)?;
+        report.saved("mask", "Processed mask PNG", &mask_path);
+
+        let foreground_path = output_dir.join(format!("{base}-foreground.png"));
+        let foreground = DynamicImage::ImageRgba8(mask.foreground()?.into_image());
+        save_flattened(
+            foreground,
+            &foreground_path,
+            SaveOptions::from_global(global),
+        )?;
+        report.saved("foreground", "Foreground PNG", &foreground_path);
+    }
+
+    report.finish()
+}
@@ -1,32 +1,80 @@
 use std::fs;
 
-use outline::{OutlineResult, VtracerSvgVectorizer};
+use outline::{
+    OutlineResult, VtracerSplitSvgVectorizer, VtracerSvgVectorizer, trace_to_oriented_svg_string,
+};
 
 use crate::cli::{GlobalOptions, MaskSourceArg, TraceCommand};
+use crate::report::Report;
 
 use super::utils::{
-    build_outline, derive_svg_path, mask_pipeline_from_args, processing_requested,
-    resolve_mask_source_arg,
+    build_outline, derive_svg_path, derive_variant_path, load_trace_profile,
+    mask_pipeline_from_args, matte_for_input, output_base, processing_requested,
+    report_model_outputs, resolve_mask_source_arg, save_trace_profile,
 };
 
 /// The main function to run the trace command.
 pub fn run(global: &GlobalOptions, cmd: TraceCommand) -> OutlineResult<()> {
-    let outline = build_outline(global);
-    let session = outline.for_image(&cmd.input)?;
+    let mut report = Report::new(global, "trace");
+    let options = match &cmd.trace_profile {
+        Some(path) => cmd.trace_options.apply_to(load_trace_profile(path)?),
+        None => cmd.trace_options.apply_to(global.presets.trace.clone()),
+    };
+    if let Some(path) = &cmd.dump_trace_profile {
+        save_trace_profile(&options, path)?;
+        report.saved("trace_profile", "Trace profile", path);
+    }
+
+    if cmd.list_outputs {
+        report_model_outputs(&mut report, &build_outline(global)?)?;
+        return report.finish();
+    }
+    let session = matte_for_input(global, &cmd.input)?;
+    report.input(&cmd.input, session.rgb_image().dimensions());
     let matte = session.matte();
     let output_path = cmd
         .output
         .clone()
         .unwrap_or_else(|| derive_svg_path(&cmd.input));
 
-    let options = (&cmd.trace_options).into();
-
-    let vectorizer = VtracerSvgVectorizer;
     let processing_requested = processing_requested(&cmd.mask_processing);
     let mask_pipeline = mask_pipeline_from_args(&cmd.mask_processing);
 
     let mask_source = resolve_mask_source_arg(cmd.mask_source, processing_requested);
 
+    if let Some(out_dir) = cmd.out_dir.as_deref().filter(|_| cmd.split_paths) {
+        let vectorizer = VtracerSplitSvgVectorizer;
+        let paths = match mask_source {
+            MaskSourceArg::Raw => matte.trace(&vectorizer, &options)?,
+            MaskSourceArg::Processed => matte
+                .clone()
+                .processed_with(&mask_pipeline)?
+                .trace(&vectorizer, &options)?,
+            MaskSourceArg::Auto => unreachable!(),
+        };
+        fs::create_dir_all(out_dir)?;
+        let base = out_dir.join(output_base(&cmd.input).file_name().unwrap_or_default());
+        for (index, svg) in paths.iter().enumerate() {
+            let path = derive_variant_path(&base, &format!("path-{index:03}"), "svg");
+            fs::write(&path, svg)?;
+        }
+        report.saved("svg_paths", format!("{} path SVGs", paths.len()), out_dir);
+        return report.finish();
+    }
+
+    if cmd.svg_orient {
+        let mask = match mask_source {
+            MaskSourceArg::Raw => matte.into_image(),
+            MaskSourceArg::Processed => matte.processed_with(&mask_pipeline)?.into_image(),
+            MaskSourceArg::Auto => unreachable!(),
+        };
+        let svg = trace_to_oriented_svg_string(&mask, &options, session.orientation())?;
+        fs::write(&output_path, &svg)?;
+        report.saved("svg", "SVG", &output_path);
+        return report.finish();
+    }
+
+    let vectorizer = VtracerSvgVectorizer;
     let svg = match mask_source {
         MaskSourceArg::Raw => matte.trace(&vectorizer, &options)?,
         MaskSourceArg::Processed => matte
@@ -36,7 +84,7 @@ pub fn run(global: &GlobalOptions, cmd: TraceCommand) -> OutlineResult<()> {
         MaskSourceArg::Auto => unreachable!(),
     };
     fs::write(&output_path, &svg)?;
-    println!("SVG saved to {}", output_path.display());
+    report.saved("svg", "SVG", &output_path);
 
-    Ok(())
+    report.finish()
 }
@@ -0,0 +1,27 @@
+use outline::{OutlineResult, matte_to_trimap};
+
+use crate::cli::{GlobalOptions, TrimapCommand};
+use crate::report::Report;
+
+use super::utils::{derive_variant_path, matte_for_input};
+
+/// The main function to run the trimap command.
+pub fn run(global: &GlobalOptions, cmd: TrimapCommand) -> OutlineResult<()> {
+    let mut report = Report::new(global, "trimap");
+    let session = matte_for_input(global, &cmd.input)?;
+    report.input(&cmd.input, session.rgb_image().dimensions());
+    let trimap = matte_to_trimap(
+        session.raw_matte(),
+        cmd.fg_threshold,
+        cmd.bg_threshold,
+        cmd.unknown_dilate,
+    )?;
+
+    let output_path = cmd
+        .output
+        .clone()
+        .unwrap_or_else(|| derive_variant_path(&cmd.input, "trimap", "png"));
+    trimap.save(&output_path)?;
+    report.saved("trimap", "Trimap PNG", &output_path);
+    report.finish()
+}
@@ -1,13 +1,27 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, Cursor, Read, Seek};
 use std::path::{Path, PathBuf};
-
-use outline::{MaskPipeline, Outline};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use image::metadata::Orientation;
+use image::{DynamicImage, GrayImage, ImageDecoder, ImageFormat, ImageReader, RgbImage, RgbaImage};
+use outline::{
+    BoundingBox, CompositeOp, ExecutionProvider, InferencedMatte, MaskPipeline, Outline,
+    OutlineError, OutlineResult, TraceOptions, check_pixel_limit, compose_over_color, composite,
+    matte_stats,
+};
 
 use crate::cli::{
-    AlphaFromArg, CliMaskProcessingRequest, GlobalOptions, MaskExportSource, MaskProcessingArgs,
-    MaskSourceArg,
+    AlphaFromArg, CliMaskProcessingRequest, GlobalOptions, MaskBitDepthArg, MaskExportSource,
+    MaskProcessingArgs, MaskSourceArg, StdinFormatArg,
 };
+use crate::diagnostics;
+use crate::report::Report;
 
-fn resolve_model_path(global: &GlobalOptions) -> PathBuf {
+fn resolve_model_path(global: &GlobalOptions) -> io::Result<PathBuf> {
     resolve_model_path_impl(global, default_cached_model_path().as_deref())
 }
 
@@ -21,42 +35,162 @@ fn default_cached_model_path() -> Option<PathBuf> {
     None
 }
 
-fn resolve_model_path_impl(global: &GlobalOptions, cached: Option<&Path>) -> PathBuf {
+fn resolve_model_path_impl(global: &GlobalOptions, cached: Option<&Path>) -> io::Result<PathBuf> {
     if let Some(model) = global.model.clone() {
-        return model;
+        return Ok(model);
+    }
+
+    if let Some(dir) = &global.model_dir {
+        return find_model_in_dir(dir);
     }
 
     if let Some(cached) = cached.filter(|p| p.is_file()) {
         eprintln!("Using cached model at {}", cached.display());
-        return cached.to_path_buf();
+        return Ok(cached.to_path_buf());
+    }
+
+    Ok(PathBuf::from(outline::DEFAULT_MODEL_PATH))
+}
+
+/// Pick the only `.onnx` or `.ort` file in `dir`, listing the candidates when there are several.
+fn find_model_in_dir(dir: &Path) -> io::Result<PathBuf> {
+    let mut candidates = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && is_model_path(&path) {
+            candidates.push(path);
+        }
+    }
+    candidates.sort();
+
+    match candidates.len() {
+        0 => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no .onnx or .ort model file found in {}", dir.display()),
+        )),
+        1 => Ok(candidates.remove(0)),
+        _ => {
+            let listing = candidates
+                .iter()
+                .map(|path| format!("  {}", path.display()))
+                .collect::<Vec<_>>()
+                .join("\n");
+            Err(io::Error::other(format!(
+                "multiple model files found in {}; pick one with --model:\n{listing}",
+                dir.display()
+            )))
+        }
     }
+}
 
-    PathBuf::from(outline::DEFAULT_MODEL_PATH)
+fn is_model_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("onnx") || ext.eq_ignore_ascii_case("ort"))
 }
 
 /// The convenience function to build an Outline instance with the input global options.
-pub fn build_outline(global: &GlobalOptions) -> Outline {
+pub fn build_outline(global: &GlobalOptions) -> OutlineResult<Outline> {
     #[cfg(all(feature = "backend-rten", not(feature = "backend-ort")))]
     if global.intra_threads.is_some() {
-        eprintln!(
-            "Warning: --intra-threads is only supported by the ORT backend and will be ignored by the current backend."
-        );
+        diagnostics::warn(
+            "--intra-threads is only supported by the ORT backend and will be ignored by the current backend.",
+        )?;
+    }
+    #[cfg(all(feature = "backend-rten", not(feature = "backend-ort")))]
+    if global.inter_threads.is_some() {
+        diagnostics::warn(
+            "--inter-threads is only supported by the ORT backend and will be ignored by the current backend.",
+        )?;
+    }
+    if let Ok(available) = std::thread::available_parallelism() {
+        for (option, threads) in [
+            ("intra-threads", global.intra_threads),
+            ("inter-threads", global.inter_threads),
+        ] {
+            if let Some(message) = thread_count_warning(option, threads, available.get()) {
+                diagnostics::warn(message)?;
+            }
+        }
+    }
+    #[cfg(all(feature = "backend-rten", not(feature = "backend-ort")))]
+    if global.device != ExecutionProvider::Cpu {
+        diagnostics::warn(
+            "--device is only supported by the ORT backend and will be ignored by the current backend.",
+        )?;
+    }
+    #[cfg(all(feature = "backend-rten", not(feature = "backend-ort")))]
+    if global.cache_optimized {
+        diagnostics::warn(
+            "--cache-optimized is only supported by the ORT backend and will be ignored by the current backend.",
+        )?;
     }
 
-    let outline = Outline::new(resolve_model_path(global))
-        .with_input_resize_filter(global.input_resample_filter.into())
-        .with_output_resize_filter(global.output_resample_filter.into())
-        .with_intra_threads(global.intra_threads);
+    let mut outline = Outline::new(resolve_model_path(global)?)
+        .with_input_resize_filter(global.input_resample_filter)
+        .with_output_resize_filter(global.output_resample_filter)
+        .with_intra_threads(global.intra_threads)
+        .with_inter_threads(global.inter_threads)
+        .with_preprocess_mode(global.preprocess.into())
+        .with_execution_provider(global.device)
+        .with_matte_combine(global.matte_combine.into())
+        .with_matte_activation(global.matte_activation.into())
+        .with_sanity_check(global.sanity_check)
+        .with_max_pixels(max_pixels(global))
+        .with_mask_processing_defaults(global.presets.mask_processing.clone());
+
+    if let Some(bg_model) = &global.bg_model {
+        outline = outline.with_background_model(bg_model);
+    }
+    if let Some(output) = &global.model_output {
+        outline = outline.with_output(output.clone());
+    }
 
-    if let Some(size) = global.model_input_size {
-        outline.with_model_input_size(size.height(), size.width())
-    } else {
-        outline
+    if global.model_input_size.is_some() {
+        diagnostics::warn(
+            "--model-input-size is deprecated; use --input-size WIDTHxHEIGHT with the axes swapped.",
+        )?;
+    }
+    if let Some(size) = global.model_input_size() {
+        outline = outline.with_model_input_size(size.height(), size.width());
+    }
+    if let Some(tiling) = global.tile {
+        outline = outline.with_tiling(tiling);
+    }
+    if let Some(multiple) = global.pad_to_multiple {
+        outline = outline.with_pad_to_multiple(multiple);
+    }
+    if let Some(scale) = global.infer_scale {
+        outline = outline.with_infer_scale(scale);
     }
+    if let Some(layout) = global.model_input_layout {
+        outline = outline.with_model_input_layout(layout.into());
+    }
+    if global.cache_optimized {
+        outline = outline.with_optimized_model_cache(crate::cache::optimized_model_cache_dir());
+    }
+    #[cfg(feature = "backend-ort")]
+    if global.device != ExecutionProvider::Cpu
+        && let Some(reason) = outline.execution_provider_fallback()?
+    {
+        diagnostics::warn(format!(
+            "--device {:?} could not be initialized, running on the CPU instead: {reason}",
+            global.device
+        ))?;
+    }
+    Ok(outline)
+}
+
+/// Describe a thread count above the logical core count, which only adds contention.
+fn thread_count_warning(option: &str, threads: Option<usize>, available: usize) -> Option<String> {
+    threads.filter(|&n| n > available).map(|n| {
+        format!("--{option} {n} exceeds the {available} logical cores available; extra threads only add contention.")
+    })
 }
 
 /// Derive a variant file path by appending a suffix before the extension.
 pub fn derive_variant_path(input: &Path, suffix: &str, extension: &str) -> PathBuf {
+    let input = output_base(input);
     let mut derived = input.to_path_buf();
     let stem = input
         .file_stem()
@@ -83,11 +217,442 @@ pub fn resolve_export_path(
 
 /// Derive an SVG file path by changing the extension to "svg".
 pub fn derive_svg_path(input: &Path) -> PathBuf {
-    let mut path = input.to_path_buf();
+    let mut path = output_base(input).to_path_buf();
     path.set_extension("svg");
     path
 }
 
+/// Input path that reads the image from stdin.
+const STDIN_INPUT: &str = "-";
+
+/// Base name used for derived output paths when the input is read from stdin.
+const STDIN_OUTPUT_BASE: &str = "stdin";
+
+/// Return true when the input path is `-`, meaning the image is read from stdin.
+pub fn is_stdin_input(input: &Path) -> bool {
+    input == Path::new(STDIN_INPUT)
+}
+
+/// Path that derived output names are based on, replacing stdin with `stdin`.
+pub fn output_base(input: &Path) -> &Path {
+    if is_stdin_input(input) {
+        Path::new(STDIN_OUTPUT_BASE)
+    } else {
+        input
+    }
+}
+
+/// Record the model's output tensors for `--list-outputs`, in graph order.
+pub fn report_model_outputs(report: &mut Report, outline: &Outline) -> OutlineResult<()> {
+    for (index, output) in outline.model_outputs()?.into_iter().enumerate() {
+        report.model_output(index, output);
+    }
+    Ok(())
+}
+
+/// Matte value at or above which `--stats` counts a pixel as foreground.
+const STATS_THRESHOLD: u8 = 128;
+
+/// Record the `--stats` summary of the matte that becomes the output alpha.
+pub fn report_matte_stats(report: &mut Report, matte: &GrayImage) {
+    let pixels = u64::from(matte.width()) * u64::from(matte.height());
+    report.stats(matte_stats(matte, STATS_THRESHOLD), pixels, STATS_THRESHOLD);
+}
+
+/// Produce the matte for the command input: from inverted luminance with `--from-luma`,
+/// otherwise by running the model.
+pub fn matte_for_input(global: &GlobalOptions, input: &Path) -> OutlineResult<InferencedMatte> {
+    if global.from_luma {
+        return luma_input(input, global);
+    }
+    infer_input(build_outline(global)?, input, global)
+}
+
+/// Like [`matte_for_input`], but keep the model's float matte for outputs that read it, such as
+/// 16-bit mattes and confidence maps.
+pub fn float_matte_for_input(
+    global: &GlobalOptions,
+    input: &Path,
+) -> OutlineResult<InferencedMatte> {
+    if global.from_luma {
+        return luma_input(input, global);
+    }
+    infer_input(build_outline(global)?.with_float_matte(true), input, global)
+}
+
+/// Build a luminance matte for the command input without loading a model.
+///
+/// The input is read like [`infer_input`] reads it, including `--stdin-format` and
+/// `--max-pixels`, and mask processing uses the `--config` defaults.
+fn luma_input(input: &Path, global: &GlobalOptions) -> OutlineResult<InferencedMatte> {
+    let (rgb, orientation) = match read_stdin_input(input, global.stdin_format)? {
+        Some((bytes, format)) => {
+            let mut reader = ImageReader::new(Cursor::new(bytes));
+            match format {
+                Some(format) => reader.set_format(format),
+                None => reader = reader.with_guessed_format()?,
+            }
+            decode_oriented(reader, max_pixels(global))?
+        }
+        None => decode_oriented(ImageReader::open(input)?, max_pixels(global))?,
+    };
+    Ok(InferencedMatte::from_luma(rgb)
+        .with_orientation(orientation)
+        .with_mask_processing_defaults(global.presets.mask_processing.clone()))
+}
+
+/// Read the encoded image from stdin when `input` is `-`, with its `--stdin-format` override.
+fn read_stdin_input(
+    input: &Path,
+    stdin_format: StdinFormatArg,
+) -> OutlineResult<Option<(Vec<u8>, Option<ImageFormat>)>> {
+    if !is_stdin_input(input) {
+        return Ok(None);
+    }
+    let mut bytes = Vec::new();
+    io::stdin().lock().read_to_end(&mut bytes)?;
+    Ok(Some((bytes, stdin_format.image_format())))
+}
+
+/// Run inference on the command input, reading encoded bytes from stdin when it is `-`.
+///
+/// With `--timeout`, reading the input, loading the model, and inference are bounded by the
+/// limit; see [`run_with_timeout`]. Outputs are only written from the returned matte, so a
+/// timed-out input writes nothing.
+pub fn infer_input(
+    outline: Outline,
+    input: &Path,
+    global: &GlobalOptions,
+) -> OutlineResult<InferencedMatte> {
+    let input = input.to_path_buf();
+    let stdin_format = global.stdin_format;
+    run_with_timeout(global.timeout, move || {
+        match read_stdin_input(&input, stdin_format)? {
+            Some((bytes, Some(format))) => outline.for_image_bytes_with_format(&bytes, format),
+            Some((bytes, None)) => outline.for_image_bytes(&bytes),
+            None => outline.for_image(&input),
+        }
+    })
+}
+
+/// Run `task` on a worker thread and stop waiting for it once `limit` has elapsed.
+///
+/// Inference cannot be interrupted, so a timed-out worker keeps running in the background until
+/// the process exits; its result is discarded. Without a limit `task` runs on this thread.
+pub fn run_with_timeout<T: Send + 'static>(
+    limit: Option<Duration>,
+    task: impl FnOnce() -> OutlineResult<T> + Send + 'static,
+) -> OutlineResult<T> {
+    let Some(limit) = limit else {
+        return task();
+    };
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        // The receiver is gone once the timeout fires, so a failed send is expected then.
+        let _ = sender.send(task());
+    });
+
+    match receiver.recv_timeout(limit) {
+        Ok(result) => result,
+        Err(mpsc::RecvTimeoutError::Timeout) => Err(OutlineError::Timeout { limit }),
+        Err(mpsc::RecvTimeoutError::Disconnected) => {
+            Err(io::Error::other("inference worker thread panicked").into())
+        }
+    }
+}
+
+/// Check whether the output path has a `.webp` extension (case-insensitive).
+pub fn is_webp_path(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("webp"))
+}
+
+/// Short name of the image format implied by the path's extension, for output labels.
+///
+/// Formats without a short name here are labeled `image`.
+pub fn image_format_name(path: &Path) -> &'static str {
+    match ImageFormat::from_path(path) {
+        Ok(ImageFormat::Png) => "PNG",
+        Ok(ImageFormat::Jpeg) => "JPEG",
+        Ok(ImageFormat::WebP) => "WebP",
+        Ok(ImageFormat::Avif) => "AVIF",
+        Ok(ImageFormat::Tiff) => "TIFF",
+        Ok(ImageFormat::Bmp) => "BMP",
+        _ => "image",
+    }
+}
+
+/// Return true when the path's image format cannot store an alpha channel.
+pub fn is_alpha_less_path(path: &Path) -> bool {
+    matches!(ImageFormat::from_path(path), Ok(ImageFormat::Jpeg))
+}
+
+/// Format-specific settings used when saving results.
+#[derive(Debug, Clone, Copy)]
+pub struct SaveOptions {
+    /// Background for formats without an alpha channel.
+    flatten_color: [u8; 4],
+    /// Encoder quality for AVIF outputs.
+    #[cfg(feature = "avif")]
+    avif_quality: u8,
+}
+
+impl SaveOptions {
+    pub fn new(flatten_color: [u8; 4]) -> Self {
+        Self {
+            flatten_color,
+            #[cfg(feature = "avif")]
+            avif_quality: crate::cli::DEFAULT_AVIF_QUALITY,
+        }
+    }
+
+    #[cfg(feature = "avif")]
+    pub fn with_avif_quality(mut self, quality: u8) -> Self {
+        self.avif_quality = quality;
+        self
+    }
+
+    pub fn from_global(global: &GlobalOptions) -> Self {
+        let options = Self::new(global.flatten_color);
+        #[cfg(feature = "avif")]
+        let options = options.with_avif_quality(global.avif_quality);
+        options
+    }
+}
+
+/// Save a possibly transparent result, flattening it onto the flatten color when the path's
+/// format has no alpha channel.
+pub fn save_flattened(image: DynamicImage, path: &Path, options: SaveOptions) -> OutlineResult<()> {
+    #[cfg(feature = "avif")]
+    if is_avif_path(path) {
+        return save_avif(&image, path, options.avif_quality);
+    }
+
+    if image.color().has_alpha() && is_alpha_less_path(path) {
+        compose_over_color(&image.into_rgba8(), options.flatten_color).save(path)?;
+    } else {
+        image.save(path)?;
+    }
+    Ok(())
+}
+
+/// Return true when the path has an `.avif` extension.
+#[cfg(feature = "avif")]
+pub fn is_avif_path(path: &Path) -> bool {
+    matches!(ImageFormat::from_path(path), Ok(ImageFormat::Avif))
+}
+
+/// Encoder speed for AVIF outputs (1 is slowest, 10 fastest), matching the `image` default.
+#[cfg(feature = "avif")]
+const AVIF_SPEED: u8 = 4;
+
+/// Encode an AVIF with the given quality, keeping the alpha channel when present.
+#[cfg(feature = "avif")]
+fn save_avif(image: &DynamicImage, path: &Path, quality: u8) -> OutlineResult<()> {
+    use image::codecs::avif::AvifEncoder;
+
+    let writer = io::BufWriter::new(fs::File::create(path)?);
+    let encoder = AvifEncoder::new_with_speed_quality(writer, AVIF_SPEED, quality);
+    if image.color().has_alpha() {
+        DynamicImage::ImageRgba8(image.to_rgba8()).write_with_encoder(encoder)?;
+    } else {
+        DynamicImage::ImageRgb8(image.to_rgb8()).write_with_encoder(encoder)?;
+    }
+    Ok(())
+}
+
+/// Strip the `-matte` suffix written by `--export-matte` to recover the source image name.
+/// Load a JSON trace profile; fields missing from the file keep their defaults.
+pub fn load_trace_profile(path: &Path) -> io::Result<TraceOptions> {
+    let contents = fs::read_to_string(path)?;
+    serde_json::from_str(&contents).map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid trace profile {}: {err}", path.display()),
+        )
+    })
+}
+
+/// Write `options` as a pretty-printed JSON trace profile.
+pub fn save_trace_profile(options: &TraceOptions, path: &Path) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(options).map_err(io::Error::other)?;
+    fs::write(path, json + "\n")
+}
+
+/// The base name of a `<base>-matte.png` file, or `None` for any other file name.
+pub fn matte_base_name(matte_path: &Path) -> Option<String> {
+    let is_png = matte_path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("png"));
+    let stem = matte_path.file_stem()?.to_string_lossy();
+    match stem.strip_suffix("-matte") {
+        Some(base) if is_png && !base.is_empty() => Some(base.to_string()),
+        _ => None,
+    }
+}
+
+/// List the `<base>-matte.png` files in a directory with their base names, sorted by path.
+///
+/// Other files, including the `-mask` and `-foreground` outputs of `reprocess`, are skipped.
+/// Two mattes with the same base name are an error, since their outputs would collide.
+pub fn list_matte_files(dir: &Path) -> OutlineResult<Vec<(String, PathBuf)>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if !path.is_file() {
+            continue;
+        }
+        if let Some(base) = matte_base_name(&path) {
+            files.push((base, path));
+        }
+    }
+    files.sort_by(|a, b| a.1.cmp(&b.1));
+
+    let mut seen: HashMap<&str, &Path> = HashMap::new();
+    for (base, path) in &files {
+        if let Some(first) = seen.insert(base, path) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{} and {} both map to the base name `{base}`",
+                    first.display(),
+                    path.display()
+                ),
+            )
+            .into());
+        }
+    }
+    Ok(files)
+}
+
+/// Find the first file in `dir` whose stem equals `base`, ignoring the extension.
+pub fn find_source_image(dir: &Path, base: &str) -> OutlineResult<Option<PathBuf>> {
+    let mut candidates = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.file_stem().is_some_and(|stem| stem == base) {
+            candidates.push(path);
+        }
+    }
+    candidates.sort();
+    Ok(candidates.into_iter().next())
+}
+
+/// Load an RGB image, applying EXIF orientation the same way inference does.
+pub fn load_oriented_rgb(path: &Path, max_pixels: Option<u64>) -> OutlineResult<RgbImage> {
+    Ok(decode_oriented(ImageReader::open(path)?, max_pixels)?.0)
+}
+
+/// Decode an image file, rejecting it before decoding when it has more than `max_pixels` pixels.
+pub fn open_image(path: &Path, max_pixels: Option<u64>) -> OutlineResult<DynamicImage> {
+    let decoder = ImageReader::open(path)?.into_decoder()?;
+    check_pixel_limit(decoder.dimensions(), max_pixels)?;
+    Ok(DynamicImage::from_decoder(decoder)?)
+}
+
+/// The `--max-pixels` limit, or `None` when it is disabled with `0`.
+pub fn max_pixels(global: &GlobalOptions) -> Option<u64> {
+    (global.max_pixels > 0).then_some(global.max_pixels)
+}
+
+// Decode to RGB with EXIF orientation applied, returning the orientation that was applied.
+fn decode_oriented<R: BufRead + Seek>(
+    reader: ImageReader<R>,
+    max_pixels: Option<u64>,
+) -> OutlineResult<(RgbImage, Orientation)> {
+    let mut decoder = reader.into_decoder()?;
+    check_pixel_limit(decoder.dimensions(), max_pixels)?;
+    let orientation = decoder.orientation()?;
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+    Ok((image.into_rgb8(), orientation))
+}
+
+/// Map a mask back to the input file's stored orientation when `stored` is set.
+pub fn orient_mask(session: &InferencedMatte, mask: &GrayImage, stored: bool) -> GrayImage {
+    if stored {
+        session
+            .to_stored_orientation(DynamicImage::ImageLuma8(mask.clone()))
+            .into_luma8()
+    } else {
+        mask.clone()
+    }
+}
+
+/// Flatten `foreground` over a background rendered from the full source frame.
+///
+/// `background` has the source size, while the foreground may have been autocropped to `bounds`
+/// and then padded by `padding`, which only applies along with `bounds`. The background is cut
+/// to the same box grown by `padding`, so the padding shows the scene around the subject; where
+/// that runs past the frame, the nearest frame pixel is repeated. This keeps the transparent
+/// black of the foreground's own padding from showing through.
+pub fn compose_over_source_background(
+    foreground: &RgbaImage,
+    background: &RgbImage,
+    bounds: Option<BoundingBox>,
+    padding: u32,
+) -> OutlineResult<RgbImage> {
+    let (bounds, padding) = match bounds {
+        Some(bounds) => (bounds, padding),
+        None => (
+            BoundingBox::new(0, 0, background.width(), background.height()),
+            0,
+        ),
+    };
+    let framed = RgbaImage::from_fn(
+        bounds.width + 2 * padding,
+        bounds.height + 2 * padding,
+        |x, y| {
+            let sx = (i64::from(bounds.x) + i64::from(x) - i64::from(padding))
+                .clamp(0, i64::from(background.width()) - 1);
+            let sy = (i64::from(bounds.y) + i64::from(y) - i64::from(padding))
+                .clamp(0, i64::from(background.height()) - 1);
+            let [r, g, b] = background.get_pixel(sx as u32, sy as u32).0;
+            image::Rgba([r, g, b, 255])
+        },
+    );
+    let flattened = composite(&framed, foreground, CompositeOp::Over)?;
+    Ok(DynamicImage::ImageRgba8(flattened).into_rgb8())
+}
+
+/// The bit depth [`save_mask_png`] writes `mask` with for the requested `--mask-bit-depth`.
+///
+/// Falls back to 8-bit with a warning when 1-bit output is requested for a non-binary mask, or
+/// when 16-bit output is requested for a mask that only has 8-bit precision. With `--strict`
+/// the warning is an error, so resolve the depth before saving any of the command's outputs.
+pub fn resolve_mask_bit_depth(
+    mask: &GrayImage,
+    path: &Path,
+    bit_depth: MaskBitDepthArg,
+) -> OutlineResult<MaskBitDepthArg> {
+    match bit_depth {
+        MaskBitDepthArg::One if outline::is_binary_mask(mask) => return Ok(MaskBitDepthArg::One),
+        MaskBitDepthArg::One => diagnostics::warn(format!(
+            "mask for {} is not strictly binary (0/255); saving it as 8-bit PNG instead.",
+            path.display()
+        ))?,
+        MaskBitDepthArg::Sixteen => diagnostics::warn(format!(
+            "16-bit output only applies to the raw matte; saving {} as 8-bit PNG instead.",
+            path.display()
+        ))?,
+        MaskBitDepthArg::Eight => {}
+    }
+    Ok(MaskBitDepthArg::Eight)
+}
+
+/// Save a mask PNG at a bit depth from [`resolve_mask_bit_depth`]; 16 saves as 8-bit.
+pub fn save_mask_png(
+    mask: &GrayImage,
+    path: &Path,
+    bit_depth: MaskBitDepthArg,
+) -> OutlineResult<()> {
+    if bit_depth == MaskBitDepthArg::One {
+        return outline::save_1bit_png(mask, path);
+    }
+    mask.save(path)?;
+    Ok(())
+}
+
 /// Determine if any mask processing is requested based on the provided arguments.
 pub fn processing_requested(args: &MaskProcessingArgs) -> bool {
     !CliMaskProcessingRequest::from_args(args).is_empty()
@@ -152,7 +717,10 @@ mod tests {
 
     mod resolve_model_path {
         use super::*;
-        use crate::cli::ResampleFilter;
+        use crate::cli::{
+            ConfigFile, MatteActivationArg, MatteCombineArg, OutputFormatArg, PreprocessArg,
+        };
+        use outline::ResampleFilter;
         use std::fs;
         use tempfile::TempDir;
 
@@ -166,10 +734,39 @@ mod tests {
         fn make_global(model: Option<PathBuf>) -> GlobalOptions {
             GlobalOptions {
                 model,
+                model_dir: None,
+                bg_model: None,
+                matte_combine: MatteCombineArg::Multiply,
+                matte_activation: MatteActivationArg::Clamp,
+                model_output: None,
                 intra_threads: None,
+                inter_threads: None,
+                device: ExecutionProvider::Cpu,
+                cache_optimized: false,
                 model_input_size: None,
+                input_size: None,
+                input_width: None,
+                input_height: None,
+                model_input_layout: None,
+                preprocess: PreprocessArg::Stretch,
+                tile: None,
+                pad_to_multiple: None,
+                from_luma: false,
+                sanity_check: false,
+                max_pixels: 100_000_000,
+                quiet: false,
+                strict: false,
+                config: None,
+                presets: ConfigFile::default(),
+                infer_scale: None,
+                timeout: None,
                 input_resample_filter: ResampleFilter::Triangle,
                 output_resample_filter: ResampleFilter::Lanczos3,
+                flatten_color: [255, 255, 255, 255],
+                #[cfg(feature = "avif")]
+                avif_quality: crate::cli::DEFAULT_AVIF_QUALITY,
+                stdin_format: StdinFormatArg::Auto,
+                format: OutputFormatArg::Text,
             }
         }
 
@@ -177,7 +774,7 @@ mod tests {
         fn explicit_model_is_used_as_is() {
             let global = make_global(Some(PathBuf::from("explicit.onnx")));
             assert_eq!(
-                super::resolve_model_path_impl(&global, None),
+                super::resolve_model_path_impl(&global, None).unwrap(),
                 PathBuf::from("explicit.onnx")
             );
         }
@@ -191,7 +788,7 @@ mod tests {
 
             let global = make_global(None);
             assert_eq!(
-                super::resolve_model_path_impl(&global, Some(&cached)),
+                super::resolve_model_path_impl(&global, Some(&cached)).unwrap(),
                 cached
             );
         }
@@ -200,7 +797,7 @@ mod tests {
         fn default_returned_when_nothing_available() {
             let global = make_global(None);
             assert_eq!(
-                super::resolve_model_path_impl(&global, None),
+                super::resolve_model_path_impl(&global, None).unwrap(),
                 PathBuf::from("model.onnx")
             );
         }
@@ -213,11 +810,28 @@ mod tests {
 
             let global = make_global(None);
             assert_eq!(
-                super::resolve_model_path_impl(&global, Some(&missing_cached)),
+                super::resolve_model_path_impl(&global, Some(&missing_cached)).unwrap(),
                 PathBuf::from("model.onnx")
             );
         }
 
+        #[test]
+        fn model_dir_preferred_over_cache() {
+            let cache_dir = temp_dir("outline-model-resolve-dir-over-cache");
+            let cached = cache_dir.path().join("cached.onnx");
+            fs::write(&cached, b"cached").expect("failed to write cached model");
+            let models = temp_dir("outline-model-resolve-dir");
+            let model = models.path().join("silueta.onnx");
+            fs::write(&model, b"model").expect("failed to write model");
+
+            let mut global = make_global(None);
+            global.model_dir = Some(models.path().to_path_buf());
+            assert_eq!(
+                super::resolve_model_path_impl(&global, Some(&cached)).unwrap(),
+                model
+            );
+        }
+
         #[test]
         fn explicit_model_preferred_over_cache() {
             let cache_dir = temp_dir("outline-model-resolve-prefer-explicit");
@@ -226,7 +840,7 @@ mod tests {
 
             let global = make_global(Some(PathBuf::from("my-model.onnx")));
             assert_eq!(
-                super::resolve_model_path_impl(&global, Some(&cached)),
+                super::resolve_model_path_impl(&global, Some(&cached)).unwrap(),
                 PathBuf::from("my-model.onnx")
             );
         }
@@ -239,16 +853,87 @@ mod tests {
             fs::write(&cached, b"cached").expect("failed to write cached model");
 
             let global = make_global(None);
-            let result = super::resolve_model_path_impl(&global, Some(&cached));
+            let result = super::resolve_model_path_impl(&global, Some(&cached)).unwrap();
             // Should use cached, not fall through to DEFAULT_MODEL_PATH
             assert_eq!(result, cached);
             assert_ne!(result, PathBuf::from(outline::DEFAULT_MODEL_PATH));
         }
     }
 
+    mod thread_count_warning {
+        use super::*;
+
+        #[test]
+        fn warns_above_available_cores() {
+            let message = thread_count_warning("intra-threads", Some(64), 8).unwrap();
+            assert!(message.contains("--intra-threads 64"));
+            assert!(message.contains("8 logical cores"));
+        }
+
+        #[test]
+        fn silent_within_available_cores() {
+            assert!(thread_count_warning("intra-threads", Some(8), 8).is_none());
+            assert!(thread_count_warning("inter-threads", None, 8).is_none());
+        }
+    }
+
+    mod find_model_in_dir {
+        use super::*;
+
+        fn model_dir(files: &[&str]) -> tempfile::TempDir {
+            let dir = tempfile::Builder::new()
+                .prefix("outline-model-dir")
+                .tempdir()
+                .expect("failed to create temp dir");
+            for name in files {
+                fs::write(dir.path().join(name), b"model").expect("failed to write model");
+            }
+            dir
+        }
+
+        #[test]
+        fn picks_the_only_model_file() {
+            let dir = model_dir(&["README.txt", "u2net.ONNX"]);
+            assert_eq!(
+                find_model_in_dir(dir.path()).unwrap(),
+                dir.path().join("u2net.ONNX")
+            );
+        }
+
+        #[test]
+        fn accepts_ort_format_models() {
+            let dir = model_dir(&["silueta.ort"]);
+            assert_eq!(
+                find_model_in_dir(dir.path()).unwrap(),
+                dir.path().join("silueta.ort")
+            );
+        }
+
+        #[test]
+        fn errors_when_no_model_is_present() {
+            let dir = model_dir(&["notes.txt"]);
+            let err = find_model_in_dir(dir.path()).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        }
+
+        #[test]
+        fn errors_with_listing_when_several_models_are_present() {
+            let dir = model_dir(&["a.onnx", "b.ort"]);
+            let message = find_model_in_dir(dir.path()).unwrap_err().to_string();
+            assert!(message.contains("a.onnx"));
+            assert!(message.contains("b.ort"));
+        }
+    }
+
     mod derive_variant_path {
         use super::*;
 
+        #[test]
+        fn stdin_input_uses_stdin_base_name() {
+            let result = derive_variant_path(Path::new("-"), "foreground", "png");
+            assert_eq!(result, PathBuf::from("stdin-foreground.png"));
+        }
+
         #[test]
         fn basic() {
             let input = Path::new("/path/to/image.png");
@@ -337,6 +1022,15 @@ mod tests {
     mod derive_svg_path {
         use super::*;
 
+        #[test]
+        fn stdin_input_uses_stdin_base_name() {
+            assert_eq!(derive_svg_path(Path::new("-")), PathBuf::from("stdin.svg"));
+            assert_eq!(
+                derive_svg_path(Path::new("dir/-.png")),
+                PathBuf::from("dir/-.svg")
+            );
+        }
+
         #[test]
         fn changes_extension() {
             let input = Path::new("/path/to/image.png");
@@ -352,6 +1046,470 @@ mod tests {
         }
     }
 
+    mod image_format_name {
+        use super::*;
+
+        #[test]
+        fn names_follow_the_extension() {
+            assert_eq!(image_format_name(Path::new("out.png")), "PNG");
+            assert_eq!(image_format_name(Path::new("out.JPG")), "JPEG");
+            assert_eq!(image_format_name(Path::new("out.webp")), "WebP");
+            assert_eq!(image_format_name(Path::new("out.avif")), "AVIF");
+            assert_eq!(image_format_name(Path::new("out")), "image");
+        }
+    }
+
+    mod is_webp_path {
+        use super::*;
+
+        #[test]
+        fn matches_webp_extension_case_insensitively() {
+            assert!(is_webp_path(Path::new("out.webp")));
+            assert!(is_webp_path(Path::new("/path/to/out.WebP")));
+        }
+
+        #[test]
+        fn rejects_other_extensions() {
+            assert!(!is_webp_path(Path::new("out.png")));
+            assert!(!is_webp_path(Path::new("out.webp.png")));
+            assert!(!is_webp_path(Path::new("webp")));
+        }
+    }
+
+    mod save_flattened {
+        use super::*;
+        use image::{Luma, Rgba, RgbaImage};
+
+        #[test]
+        fn detects_alpha_less_formats() {
+            assert!(is_alpha_less_path(Path::new("out.jpg")));
+            assert!(is_alpha_less_path(Path::new("out.JPEG")));
+            assert!(!is_alpha_less_path(Path::new("out.png")));
+            assert!(!is_alpha_less_path(Path::new("out.webp")));
+        }
+
+        #[test]
+        fn jpeg_output_is_flattened_onto_color() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("out.jpg");
+            let image = RgbaImage::from_pixel(8, 8, Rgba([0, 0, 0, 0]));
+
+            save_flattened(
+                DynamicImage::ImageRgba8(image),
+                &path,
+                SaveOptions::new([255, 0, 0, 255]),
+            )
+            .unwrap();
+
+            let saved = image::open(&path).unwrap().into_rgb8();
+            let pixel = saved.get_pixel(4, 4);
+            assert!(
+                pixel[0] > 240 && pixel[1] < 16 && pixel[2] < 16,
+                "{pixel:?}"
+            );
+        }
+
+        #[test]
+        fn png_output_keeps_alpha() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("out.png");
+            let image = RgbaImage::from_pixel(2, 2, Rgba([10, 20, 30, 40]));
+
+            save_flattened(
+                DynamicImage::ImageRgba8(image.clone()),
+                &path,
+                SaveOptions::new([255; 4]),
+            )
+            .unwrap();
+
+            assert_eq!(image::open(&path).unwrap().into_rgba8(), image);
+        }
+
+        #[test]
+        fn webp_output_keeps_alpha() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("out.webp");
+            let image = RgbaImage::from_fn(4, 4, |x, y| {
+                let alpha = if x < 2 { 0 } else { 255 };
+                Rgba([10 * x as u8, 20 * y as u8, 30, alpha])
+            });
+
+            save_flattened(
+                DynamicImage::ImageRgba8(image.clone()),
+                &path,
+                SaveOptions::new([255; 4]),
+            )
+            .unwrap();
+
+            let saved = image::open(&path).unwrap();
+            assert!(saved.color().has_alpha());
+            assert_eq!(saved.into_rgba8(), image);
+        }
+
+        #[test]
+        fn webp_mask_round_trips() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("mask.webp");
+            let mask = GrayImage::from_fn(4, 4, |x, y| Luma([(x * 64 + y) as u8]));
+
+            save_mask_png(&mask, &path, MaskBitDepthArg::Eight).unwrap();
+
+            assert_eq!(image::open(&path).unwrap().into_luma8(), mask);
+        }
+
+        #[cfg(feature = "avif")]
+        #[test]
+        fn avif_output_keeps_alpha() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("out.avif");
+            let image = RgbaImage::from_fn(16, 16, |x, _| Rgba([200, 100, 50, (x * 16) as u8]));
+
+            save_flattened(
+                DynamicImage::ImageRgba8(image),
+                &path,
+                SaveOptions::new([255; 4]).with_avif_quality(60),
+            )
+            .unwrap();
+
+            // The encoder stores alpha as an auxiliary image tagged with this URN.
+            let encoded = fs::read(&path).unwrap();
+            let alpha_urn = b"urn:mpeg:mpegB:cicp:systems:auxiliary:alpha";
+            assert!(encoded.windows(alpha_urn.len()).any(|w| w == alpha_urn));
+        }
+    }
+
+    mod trace_profile {
+        use super::*;
+        use visioncortex::PathSimplifyMode;
+
+        #[test]
+        fn saved_profile_loads_back() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("profile.json");
+            let options = TraceOptions {
+                tracer_mode: PathSimplifyMode::Polygon,
+                tracer_filter_speckle: 9,
+                tracer_path_precision: None,
+                ..TraceOptions::default()
+            };
+
+            save_trace_profile(&options, &path).unwrap();
+            assert!(
+                fs::read_to_string(&path)
+                    .unwrap()
+                    .contains("\"tracer_mode\": \"polygon\"")
+            );
+
+            let loaded = load_trace_profile(&path).unwrap();
+            assert!(matches!(loaded.tracer_mode, PathSimplifyMode::Polygon));
+            assert_eq!(loaded.tracer_filter_speckle, 9);
+            assert!(loaded.tracer_path_precision.is_none());
+        }
+
+        #[test]
+        fn missing_fields_keep_defaults() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("profile.json");
+            fs::write(&path, r#"{ "min_path_area": 12.5 }"#).unwrap();
+
+            let loaded = load_trace_profile(&path).unwrap();
+            assert_eq!(loaded.min_path_area, 12.5);
+            assert_eq!(
+                loaded.tracer_filter_speckle,
+                TraceOptions::default().tracer_filter_speckle
+            );
+        }
+
+        #[test]
+        fn invalid_profile_names_the_file() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("profile.json");
+            fs::write(&path, r#"{ "tracer_mode": "curvy" }"#).unwrap();
+
+            let err = load_trace_profile(&path).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            assert!(err.to_string().contains("profile.json"), "{err}");
+            assert!(err.to_string().contains("curvy"), "{err}");
+        }
+    }
+
+    mod matte_base_name {
+        use super::*;
+
+        #[test]
+        fn strips_matte_suffix() {
+            let result = matte_base_name(Path::new("/mattes/photo-matte.png"));
+            assert_eq!(result.as_deref(), Some("photo"));
+        }
+
+        #[test]
+        fn rejects_names_without_suffix() {
+            assert_eq!(matte_base_name(Path::new("/mattes/photo.png")), None);
+            assert_eq!(matte_base_name(Path::new("/mattes/photo-mask.png")), None);
+            assert_eq!(matte_base_name(Path::new("/mattes/photo-matte.jpg")), None);
+        }
+
+        #[test]
+        fn rejects_bare_suffix_name() {
+            assert_eq!(matte_base_name(Path::new("-matte.png")), None);
+        }
+    }
+
+    mod compose_over_source_background {
+        use super::*;
+        use image::{Rgb, Rgba};
+
+        #[test]
+        fn pads_with_the_surrounding_source_pixels() {
+            let background = RgbImage::from_fn(5, 4, |x, y| Rgb([x as u8 * 10, y as u8 * 10, 0]));
+            // A transparent 2x1 crop at (2, 1) padded by 1, as autocrop pads with black.
+            let mut foreground = RgbaImage::new(4, 3);
+            foreground.put_pixel(1, 1, Rgba([200, 200, 200, 255]));
+
+            let bounds = BoundingBox::new(2, 1, 2, 1);
+            let output =
+                compose_over_source_background(&foreground, &background, Some(bounds), 1).unwrap();
+            assert_eq!(output.dimensions(), (4, 3));
+            assert_eq!(output.get_pixel(0, 0).0, [10, 0, 0]);
+            assert_eq!(output.get_pixel(1, 1).0, [200, 200, 200]);
+            assert_eq!(output.get_pixel(2, 1).0, [30, 10, 0]);
+            assert_eq!(output.get_pixel(3, 2).0, [40, 20, 0]);
+        }
+
+        #[test]
+        fn repeats_the_frame_edge_where_padding_runs_past_it() {
+            let background = RgbImage::from_fn(3, 3, |x, y| Rgb([x as u8 * 10, y as u8 * 10, 0]));
+            let bounds = BoundingBox::new(0, 1, 3, 2);
+            let output =
+                compose_over_source_background(&RgbaImage::new(7, 6), &background, Some(bounds), 2)
+                    .unwrap();
+            assert_eq!(output.get_pixel(0, 0).0, [0, 0, 0]);
+            assert_eq!(output.get_pixel(2, 1).0, [0, 0, 0]);
+            assert_eq!(output.get_pixel(3, 2).0, [10, 10, 0]);
+            assert_eq!(output.get_pixel(6, 5).0, [20, 20, 0]);
+        }
+
+        #[test]
+        fn uses_the_full_frame_without_bounds() {
+            let background = RgbImage::from_pixel(2, 2, Rgb([1, 2, 3]));
+            let output =
+                compose_over_source_background(&RgbaImage::new(2, 2), &background, None, 5)
+                    .unwrap();
+            assert_eq!(output, background);
+        }
+    }
+
+    mod report_model_outputs {
+        use super::*;
+        use crate::cli::Cli;
+        use crate::commands::tiny_onnx;
+
+        #[test]
+        fn json_report_lists_outputs_with_their_shapes() {
+            let model = tiny_onnx::tiny_matte_model_file();
+            let cli = Cli::try_parse_from([
+                "outline",
+                "--model",
+                model.path().to_str().unwrap(),
+                "--format",
+                "json",
+                "cut",
+                "in.png",
+                "--list-outputs",
+            ])
+            .unwrap();
+            let mut report = Report::new(&cli.global, "cut");
+
+            report_model_outputs(&mut report, &build_outline(&cli.global).unwrap()).unwrap();
+
+            let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+            assert_eq!(
+                json["model_outputs"],
+                serde_json::json!([{ "index": 0, "name": "matte", "shape": [1, 1, 2, 2] }])
+            );
+        }
+    }
+
+    mod resolve_mask_bit_depth {
+        use super::*;
+
+        #[test]
+        fn falls_back_to_8_bit_unless_the_mask_is_binary() {
+            let path = Path::new("mask.png");
+            let binary =
+                GrayImage::from_fn(2, 1, |x, _| image::Luma([if x == 0 { 0 } else { 255 }]));
+            let soft = GrayImage::from_pixel(2, 1, image::Luma([128]));
+
+            let resolve =
+                |mask: &GrayImage, depth| resolve_mask_bit_depth(mask, path, depth).unwrap();
+            assert_eq!(resolve(&binary, MaskBitDepthArg::One), MaskBitDepthArg::One);
+            assert_eq!(resolve(&soft, MaskBitDepthArg::One), MaskBitDepthArg::Eight);
+            assert_eq!(
+                resolve(&binary, MaskBitDepthArg::Sixteen),
+                MaskBitDepthArg::Eight
+            );
+            assert_eq!(
+                resolve(&soft, MaskBitDepthArg::Eight),
+                MaskBitDepthArg::Eight
+            );
+        }
+    }
+
+    mod luma_input {
+        use super::*;
+        use crate::cli::Cli;
+        use image::Rgb;
+
+        #[test]
+        fn applies_pixel_limit_and_config_defaults() {
+            let dir = tempfile::tempdir().unwrap();
+            let input = dir.path().join("ink.png");
+            RgbImage::from_fn(2, 1, |x, _| Rgb([if x == 0 { 0 } else { 100 }; 3]))
+                .save(&input)
+                .unwrap();
+            let parse = |max_pixels: &str| {
+                Cli::try_parse_from([
+                    "outline",
+                    "--from-luma",
+                    "--max-pixels",
+                    max_pixels,
+                    "mask",
+                    input.to_str().unwrap(),
+                ])
+                .unwrap()
+            };
+
+            assert!(matches!(
+                luma_input(&input, &parse("1").global),
+                Err(OutlineError::ImageTooLarge { .. })
+            ));
+
+            let mut global = parse("2").global;
+            global.presets.mask_processing.mask_threshold = 200;
+            let session = luma_input(&input, &global).unwrap();
+            assert_eq!(session.raw_matte().as_raw(), &[255, 155]);
+            assert_eq!(
+                session
+                    .matte()
+                    .threshold()
+                    .processed()
+                    .unwrap()
+                    .as_raw_mask()
+                    .as_raw(),
+                &[255, 0]
+            );
+        }
+    }
+
+    mod open_image {
+        use super::*;
+
+        #[test]
+        fn rejects_images_over_the_pixel_limit_before_decoding() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("big.png");
+            RgbImage::new(10, 10).save(&path).unwrap();
+
+            assert!(matches!(
+                open_image(&path, Some(99)),
+                Err(OutlineError::ImageTooLarge {
+                    pixels: 100,
+                    limit: 99
+                })
+            ));
+            assert!(matches!(
+                load_oriented_rgb(&path, Some(99)),
+                Err(OutlineError::ImageTooLarge { .. })
+            ));
+            assert_eq!(open_image(&path, Some(100)).unwrap().width(), 10);
+            assert_eq!(load_oriented_rgb(&path, None).unwrap().width(), 10);
+        }
+    }
+
+    mod run_with_timeout {
+        use super::*;
+
+        #[test]
+        fn returns_the_result_within_the_limit() {
+            let result = run_with_timeout(Some(Duration::from_secs(10)), || Ok(7));
+            assert_eq!(result.unwrap(), 7);
+            assert_eq!(run_with_timeout(None, || Ok(8)).unwrap(), 8);
+        }
+
+        #[test]
+        fn fails_once_the_limit_passes() {
+            let limit = Duration::from_millis(10);
+            let result = run_with_timeout(Some(limit), || {
+                thread::sleep(Duration::from_secs(2));
+                Ok(())
+            });
+            assert!(matches!(result, Err(OutlineError::Timeout { limit: l }) if l == limit));
+        }
+    }
+
+    mod directory_lookup {
+        use super::*;
+
+        fn temp_dir() -> tempfile::TempDir {
+            tempfile::Builder::new()
+                .prefix("outline-reprocess-lookup")
+                .tempdir()
+                .expect("failed to create temp dir")
+        }
+
+        #[test]
+        fn list_matte_files_filters_and_sorts() {
+            let dir = temp_dir();
+            for name in [
+                "b-matte.png",
+                "a-matte.PNG",
+                "a.png",
+                "a-mask.png",
+                "a-foreground.png",
+                "notes.txt",
+            ] {
+                fs::write(dir.path().join(name), b"").unwrap();
+            }
+            fs::create_dir(dir.path().join("nested-matte.png")).unwrap();
+
+            let files = list_matte_files(dir.path()).unwrap();
+            assert_eq!(
+                files,
+                vec![
+                    ("a".to_string(), dir.path().join("a-matte.PNG")),
+                    ("b".to_string(), dir.path().join("b-matte.png"))
+                ]
+            );
+        }
+
+        #[test]
+        fn list_matte_files_rejects_duplicate_bases() {
+            let dir = temp_dir();
+            for name in ["a-matte.png", "a-matte.PNG"] {
+                fs::write(dir.path().join(name), b"").unwrap();
+            }
+            // Case-insensitive file systems keep only one of the two names.
+            if fs::read_dir(dir.path()).unwrap().count() < 2 {
+                return;
+            }
+
+            let err = list_matte_files(dir.path()).unwrap_err();
+            assert!(err.to_string().contains("`a`"), "{err}");
+        }
+
+        #[test]
+        fn find_source_image_matches_stem() {
+            let dir = temp_dir();
+            for name in ["photo.jpg", "photo-matte.png", "other.png"] {
+                fs::write(dir.path().join(name), b"").unwrap();
+            }
+
+            let found = find_source_image(dir.path(), "photo").unwrap();
+            assert_eq!(found, Some(dir.path().join("photo.jpg")));
+            assert_eq!(find_source_image(dir.path(), "missing").unwrap(), None);
+        }
+    }
+
     mod resolve_alpha_source {
         use super::*;
 
@@ -382,17 +1540,31 @@ mod tests {
 
     mod processing_requested {
         use crate::cli::{CliMaskProcessingStep, MaskProcessingArgs};
+        use outline::MaskProcessingDefaults;
 
         fn default_args() -> MaskProcessingArgs {
             MaskProcessingArgs {
                 blur: None,
+                normalized_blur: None,
+                feather: None,
                 threshold: None,
+                auto_threshold: false,
+                soft_threshold: None,
                 no_implicit_threshold: false,
                 dilate: None,
+                open: None,
+                close: None,
                 erode: None,
                 erode_border: None,
+                keep_largest: None,
+                min_island_area: None,
                 fill_holes: None,
+                solidify: None,
+                posterize: None,
+                matte_gamma: None,
+                invert_mask: false,
                 ordered_steps: vec![],
+                defaults: MaskProcessingDefaults::default(),
             }
         }
 
@@ -0,0 +1,273 @@
+use image::{DynamicImage, GrayImage, Luma, RgbImage, Rgba, RgbaImage};
+
+use crate::foreground::compose_foreground;
+use crate::layer::{
+    CompositeOp, Gradient, compose_over_color, compose_over_gradient, composite, gradient_fill,
+    render_drop_shadow, vignette,
+};
+use crate::{OutlineError, OutlineResult};
+
+/// Background placed behind the subject by [`compose_image`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum ComposeBackground {
+    /// A solid RGBA color, as in [`compose_over_color`](crate::compose_over_color).
+    Color([u8; 4]),
+    /// A gradient sized to the image, as in [`compose_over_gradient`](crate::compose_over_gradient).
+    Gradient(Gradient),
+}
+
+/// Drop shadow placed beneath the subject by [`compose_image`].
+///
+/// See [`render_drop_shadow`](crate::render_drop_shadow) for how the fields are applied.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ComposeShadow {
+    /// Shadow offset `(dx, dy)` in pixels; positive values move it right and down.
+    pub offset: (i32, i32),
+    /// Gaussian blur sigma in pixels; `0.0` keeps a sharp silhouette.
+    pub blur: f32,
+    /// Shadow color; its alpha scales the shadow's opacity.
+    pub color: [u8; 4],
+}
+
+/// Options for [`compose_image`].
+///
+/// This struct is non-exhaustive; start with [`Default`] and then adjust fields as needed.
+/// The default keeps a transparent background and applies no edge effects.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct ComposeOptions {
+    /// Background to flatten the subject onto; `None` keeps the transparent cut-out.
+    pub background: Option<ComposeBackground>,
+    /// Fade the alpha toward the canvas edges over this many pixels (see [`vignette`](crate::vignette)).
+    pub vignette_radius: f32,
+    /// Round the canvas corners with this radius in pixels (see [`vignette`](crate::vignette)).
+    pub corner_radius: f32,
+    /// Drop shadow rendered from the subject's alpha and placed beneath it.
+    pub shadow: Option<ComposeShadow>,
+    /// How the subject is combined with the background (see [`composite`](crate::composite)).
+    ///
+    /// [`CompositeOp::Over`] flattens to an opaque image. Other operators keep the result's
+    /// alpha, e.g. [`CompositeOp::Atop`] drops the subject outside a translucent background.
+    /// Without a background this has no effect.
+    pub blend: CompositeOp,
+}
+
+impl Default for ComposeOptions {
+    fn default() -> Self {
+        Self {
+            background: None,
+            vignette_radius: 0.0,
+            corner_radius: 0.0,
+            shadow: None,
+            blend: CompositeOp::Over,
+        }
+    }
+}
+
+impl ComposeOptions {
+    /// Create options with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Flatten the subject onto a solid RGBA color.
+    pub fn with_background_color(mut self, color: [u8; 4]) -> Self {
+        self.background = Some(ComposeBackground::Color(color));
+        self
+    }
+
+    /// Flatten the subject onto a gradient sized to the image.
+    pub fn with_background_gradient(mut self, gradient: Gradient) -> Self {
+        self.background = Some(ComposeBackground::Gradient(gradient));
+        self
+    }
+
+    /// Set the vignette fade radius and corner radius in pixels.
+    pub fn with_vignette(mut self, radius: f32, corner_radius: f32) -> Self {
+        self.vignette_radius = radius;
+        self.corner_radius = corner_radius;
+        self
+    }
+
+    /// Place a drop shadow beneath the subject.
+    pub fn with_shadow(mut self, offset: (i32, i32), blur: f32, color: [u8; 4]) -> Self {
+        self.shadow = Some(ComposeShadow {
+            offset,
+            blur,
+            color,
+        });
+        self
+    }
+
+    /// Set the operator that combines the subject with the background.
+    pub fn with_blend(mut self, blend: CompositeOp) -> Self {
+        self.blend = blend;
+        self
+    }
+}
+
+/// Compose a final image from an RGB image and an alpha matte produced elsewhere.
+///
+/// This runs the same compositing as the inference pipeline without loading a model: the matte
+/// becomes the alpha channel, the vignette is applied, the shadow is placed beneath the subject,
+/// and the result is combined with the background when one is set. Results flattened with
+/// [`CompositeOp::Over`] are fully opaque.
+///
+/// Returns [`OutlineError::AlphaMismatch`](crate::OutlineError::AlphaMismatch) if the image and
+/// matte sizes differ, and [`OutlineError::InvalidMaskColor`](crate::OutlineError::InvalidMaskColor)
+/// if the shadow color is fully transparent, since the shadow would be invisible.
+///
+/// # Panics
+///
+/// Panics if `vignette_radius`, `corner_radius`, or the shadow blur is negative or NaN.
+///
+/// ```no_run
+/// use outline::{ComposeOptions, compose_image};
+///
+/// let rgb = image::open("photo.jpg")?.into_rgb8();
+/// let alpha = image::open("photo-matte.png")?.into_luma8();
+/// let options = ComposeOptions::new().with_background_color([255, 255, 255, 255]);
+/// compose_image(&rgb, &alpha, &options)?.save("photo-white.png")?;
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+pub fn compose_image(
+    rgb: &RgbImage,
+    alpha: &GrayImage,
+    options: &ComposeOptions,
+) -> OutlineResult<RgbaImage> {
+    if let Some(shadow) = options.shadow
+        && shadow.color[3] == 0
+    {
+        return Err(OutlineError::InvalidMaskColor(
+            "shadow color alpha is 0, so the shadow is invisible".to_string(),
+        ));
+    }
+    let mut foreground = compose_foreground(rgb, alpha)?;
+    if options.vignette_radius != 0.0 || options.corner_radius != 0.0 {
+        foreground = vignette(&foreground, options.vignette_radius, options.corner_radius);
+    }
+    if let Some(shadow) = options.shadow {
+        let alpha = GrayImage::from_fn(foreground.width(), foreground.height(), |x, y| {
+            Luma([foreground.get_pixel(x, y)[3]])
+        });
+        let shadow = render_drop_shadow(&alpha, shadow.offset, shadow.blur, shadow.color);
+        foreground = composite(&shadow, &foreground, CompositeOp::Over)?;
+    }
+
+    let Some(background) = &options.background else {
+        return Ok(foreground);
+    };
+    if options.blend != CompositeOp::Over {
+        let (w, h) = foreground.dimensions();
+        let layer = match background {
+            ComposeBackground::Color(color) => RgbaImage::from_pixel(w, h, Rgba(*color)),
+            ComposeBackground::Gradient(gradient) => gradient_fill(w, h, gradient),
+        };
+        return composite(&layer, &foreground, options.blend);
+    }
+    let flattened = match background {
+        ComposeBackground::Color(color) => compose_over_color(&foreground, *color),
+        ComposeBackground::Gradient(gradient) => compose_over_gradient(&foreground, gradient),
+    };
+    Ok(DynamicImage::ImageRgb8(flattened).into_rgba8())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Luma, Rgb};
+
+    fn half_alpha() -> (RgbImage, GrayImage) {
+        let rgb = RgbImage::from_pixel(2, 2, Rgb([200, 100, 0]));
+        let alpha = GrayImage::from_fn(2, 2, |x, _| Luma([if x == 0 { 255 } else { 0 }]));
+        (rgb, alpha)
+    }
+
+    #[test]
+    fn default_options_match_compose_foreground() {
+        let (rgb, alpha) = half_alpha();
+        let result = compose_image(&rgb, &alpha, &ComposeOptions::default()).unwrap();
+        assert_eq!(result, compose_foreground(&rgb, &alpha).unwrap());
+    }
+
+    #[test]
+    fn background_color_flattens_to_opaque() {
+        let (rgb, alpha) = half_alpha();
+        let options = ComposeOptions::new().with_background_color([0, 0, 255, 255]);
+        let result = compose_image(&rgb, &alpha, &options).unwrap();
+
+        assert_eq!(result.get_pixel(0, 0).0, [200, 100, 0, 255]);
+        assert_eq!(result.get_pixel(1, 0).0, [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn background_gradient_matches_layer_helper() {
+        let (rgb, alpha) = half_alpha();
+        let gradient = Gradient::Radial {
+            center: [255, 255, 255, 255],
+            edge: [0, 0, 0, 255],
+        };
+        let options = ComposeOptions::new().with_background_gradient(gradient);
+        let result = compose_image(&rgb, &alpha, &options).unwrap();
+
+        let foreground = compose_foreground(&rgb, &alpha).unwrap();
+        let expected = compose_over_gradient(&foreground, &gradient);
+        for (x, y, pixel) in result.enumerate_pixels() {
+            assert_eq!(pixel.0[..3], expected.get_pixel(x, y).0);
+            assert_eq!(pixel[3], 255);
+        }
+    }
+
+    #[test]
+    fn vignette_is_applied_before_flattening() {
+        let rgb = RgbImage::from_pixel(8, 8, Rgb([255, 255, 255]));
+        let alpha = GrayImage::from_pixel(8, 8, Luma([255]));
+        let options = ComposeOptions::new()
+            .with_vignette(0.0, 4.0)
+            .with_background_color([0, 0, 0, 255]);
+        let result = compose_image(&rgb, &alpha, &options).unwrap();
+
+        assert_eq!(result.get_pixel(0, 0).0, [0, 0, 0, 255]);
+        assert_eq!(result.get_pixel(4, 4).0, [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn shadow_falls_beneath_the_subject() {
+        let rgb = RgbImage::from_pixel(6, 6, Rgb([255, 255, 255]));
+        let alpha = GrayImage::from_fn(6, 6, |x, y| Luma([if x < 2 && y < 2 { 255 } else { 0 }]));
+        let options = ComposeOptions::new().with_shadow((2, 2), 0.0, [0, 0, 0, 255]);
+        let result = compose_image(&rgb, &alpha, &options).unwrap();
+
+        assert_eq!(result.get_pixel(0, 0).0, [255, 255, 255, 255]);
+        assert_eq!(result.get_pixel(3, 3).0, [0, 0, 0, 255]);
+        assert_eq!(result.get_pixel(5, 5)[3], 0);
+
+        let invisible = ComposeOptions::new().with_shadow((2, 2), 0.0, [0, 0, 0, 0]);
+        assert!(matches!(
+            compose_image(&rgb, &alpha, &invisible),
+            Err(OutlineError::InvalidMaskColor(_))
+        ));
+    }
+
+    #[test]
+    fn blend_operator_keeps_the_result_alpha() {
+        let (rgb, alpha) = half_alpha();
+        let options = ComposeOptions::new()
+            .with_background_color([0, 0, 255, 255])
+            .with_blend(CompositeOp::Xor);
+        let result = compose_image(&rgb, &alpha, &options).unwrap();
+
+        // The opaque subject and opaque background cancel out; the background shows elsewhere.
+        assert_eq!(result.get_pixel(0, 0)[3], 0);
+        assert_eq!(result.get_pixel(1, 0).0, [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn size_mismatch_is_rejected() {
+        let rgb = RgbImage::new(2, 2);
+        let alpha = GrayImage::new(3, 2);
+        let err = compose_image(&rgb, &alpha, &ComposeOptions::default()).unwrap_err();
+        assert!(matches!(err, OutlineError::AlphaMismatch { .. }));
+    }
+}
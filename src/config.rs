@@ -1,7 +1,10 @@
+use std::fmt;
 use std::path::{Path, PathBuf};
 
 use image::imageops::FilterType;
 
+use crate::error::{OutlineError, OutlineResult};
+
 /// Environment variable name for specifying the model path.
 pub const ENV_MODEL_PATH: &str = "OUTLINE_MODEL_PATH";
 
@@ -25,6 +28,60 @@ pub enum InferenceBackend {
     Rten,
 }
 
+/// Hardware the ORT backend runs the model on.
+///
+/// Providers other than [`Cpu`](ExecutionProvider::Cpu) need the matching crate feature and an
+/// ONNX Runtime build that includes them. When one fails to initialize, the session falls back to
+/// the CPU; see [`Outline::execution_provider_fallback`](crate::Outline::execution_provider_fallback).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum ExecutionProvider {
+    /// Run on the CPU.
+    #[default]
+    Cpu,
+    /// Run on the NVIDIA GPU with this CUDA device index (requires the `cuda` feature).
+    #[cfg(feature = "cuda")]
+    Cuda {
+        /// CUDA device index, `0` for the first GPU.
+        device_id: i32,
+    },
+    /// Run through Apple's CoreML on macOS and iOS, which can use the GPU and Neural Engine
+    /// (requires the `coreml` feature).
+    #[cfg(feature = "coreml")]
+    CoreML,
+}
+
+/// Resampling filter used when resizing images between pipeline stages.
+///
+/// Mirrors the CLI filter names and converts into [`image::imageops::FilterType`], so callers
+/// do not need to depend on the `image` crate to pick a filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ResampleFilter {
+    /// Nearest-neighbor sampling.
+    Nearest,
+    /// Linear (triangle) filter.
+    Triangle,
+    /// Cubic Catmull-Rom filter.
+    CatmullRom,
+    /// Gaussian filter.
+    Gaussian,
+    /// Lanczos filter with a window of 3.
+    Lanczos3,
+}
+
+impl From<ResampleFilter> for FilterType {
+    fn from(value: ResampleFilter) -> Self {
+        match value {
+            ResampleFilter::Nearest => FilterType::Nearest,
+            ResampleFilter::Triangle => FilterType::Triangle,
+            ResampleFilter::CatmullRom => FilterType::CatmullRom,
+            ResampleFilter::Gaussian => FilterType::Gaussian,
+            ResampleFilter::Lanczos3 => FilterType::Lanczos3,
+        }
+    }
+}
+
 /// Height and width used to override the model-declared input size.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ModelInputSize {
@@ -54,6 +111,135 @@ impl ModelInputSize {
     }
 }
 
+/// Tensor layout used to override the model-declared input layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelInputLayout {
+    /// Batch, channels, height, width.
+    Nchw,
+    /// Batch, height, width, channels.
+    Nhwc,
+}
+
+/// How the foreground matte is combined with the matte from a background model.
+///
+/// The background matte is inverted first, so each strategy blends `fg` with `1 - bg`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatteCombine {
+    /// `fg * (1 - bg)`: keep pixels only where both models agree on the subject.
+    #[default]
+    Multiply,
+    /// `min(fg, 1 - bg)`: take the more conservative of the two predictions.
+    Min,
+    /// `(fg + (1 - bg)) / 2`: average the two predictions.
+    Average,
+}
+
+/// How RGB pixel values are normalized into the model input tensor.
+///
+/// Each channel is scaled to `0..=1` first, then mapped to `(value - mean) / std`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[non_exhaustive]
+pub enum Normalization {
+    /// ImageNet statistics: mean `[0.485, 0.456, 0.406]`, std `[0.229, 0.224, 0.225]`.
+    #[default]
+    ImageNet,
+    /// Plain `0..=1` values.
+    ZeroToOne,
+    /// Values mapped to `-1..=1` (mean and std of `0.5`).
+    MinusOneToOne,
+    /// Per-channel mean and standard deviation in RGB order, applied to `0..=1` values.
+    Custom {
+        /// Mean subtracted from each channel.
+        mean: [f32; 3],
+        /// Standard deviation each channel is divided by.
+        std: [f32; 3],
+    },
+}
+
+impl Normalization {
+    /// The per-channel mean and standard deviation in RGB order.
+    pub fn mean_std(&self) -> ([f32; 3], [f32; 3]) {
+        match *self {
+            Self::ImageNet => ([0.485, 0.456, 0.406], [0.229, 0.224, 0.225]),
+            Self::ZeroToOne => ([0.0; 3], [1.0; 3]),
+            Self::MinusOneToOne => ([0.5; 3], [0.5; 3]),
+            Self::Custom { mean, std } => (mean, std),
+        }
+    }
+}
+
+/// How the input image is fit to the model input size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum PreprocessMode {
+    /// Resize to the model input size, ignoring the aspect ratio.
+    #[default]
+    Stretch,
+    /// Scale to fit inside the model input size keeping the aspect ratio, and pad the rest.
+    ///
+    /// The image is centered and the matte is cropped back to it before it is resized to the
+    /// original image size.
+    Letterbox {
+        /// RGB color of the padding, or `None` for the normalization mean so the padding
+        /// normalizes to zero.
+        fill: Option<[u8; 3]>,
+    },
+}
+
+/// Splits large images into overlapping tiles that are inferred separately.
+///
+/// Each tile is resized to the model input on its own, so fine detail such as hair survives in
+/// images much larger than the model input. Pick a `tile_size` near the model input size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileOptions {
+    /// Width and height of each tile in image pixels.
+    pub tile_size: u32,
+    /// Pixels shared by neighboring tiles, blended linearly to hide the seams.
+    pub overlap: u32,
+}
+
+/// How raw model output values are mapped to matte values before resizing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum MatteActivation {
+    /// Keep the values as the model produced them.
+    None,
+    /// Apply a logistic sigmoid, for models that output unbounded logits.
+    Sigmoid,
+    /// Clamp the values to `0..=1`.
+    #[default]
+    Clamp,
+}
+
+/// Which model output tensor the matte is read from.
+///
+/// List a model's outputs with [`Outline::model_outputs`](crate::Outline::model_outputs).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OutputSelector {
+    /// The output at this position in graph order.
+    Index(usize),
+    /// The output with this name.
+    Name(String),
+}
+
+impl Default for OutputSelector {
+    /// The first output.
+    fn default() -> Self {
+        Self::Index(0)
+    }
+}
+
+/// Formats as `index N` or `` `NAME` ``, as used in error messages.
+impl fmt::Display for OutputSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Index(index) => write!(f, "index {index}"),
+            Self::Name(name) => write!(f, "`{name}`"),
+        }
+    }
+}
+
 /// Configuration for ONNX model inference and image preprocessing.
 ///
 /// Controls the model path, image resize filters for input/output, and threading behavior.
@@ -66,6 +252,10 @@ impl ModelInputSize {
 pub struct InferenceSettings {
     /// Path to the ONNX model file.
     model_path: PathBuf,
+    /// Optional background model whose matte is combined with the main model output.
+    background_model_path: Option<PathBuf>,
+    /// Strategy used to combine the main and background mattes.
+    matte_combine: MatteCombine,
     /// Backend used to execute the model.
     backend: InferenceBackend,
     /// Filter to use when resizing the input image for the model.
@@ -76,8 +266,38 @@ pub struct InferenceSettings {
     ///
     /// When set, callers are responsible for choosing a size the model supports.
     model_input_size: Option<ModelInputSize>,
+    /// Override for the tensor layout used as model input.
+    ///
+    /// When set, callers are responsible for choosing a layout the model supports.
+    model_input_layout: Option<ModelInputLayout>,
+    /// Pad the model input up to a multiple of this value and crop the matte back.
+    pad_to_multiple: Option<u32>,
+    /// How pixel values are normalized into the model input tensor.
+    normalization: Normalization,
+    /// How the input image is fit to the model input size.
+    preprocess_mode: PreprocessMode,
+    /// Infer images larger than a tile in overlapping tiles.
+    tiling: Option<TileOptions>,
+    /// How raw model output values are mapped to matte values.
+    matte_activation: MatteActivation,
+    /// Hardware the model runs on (ORT backend).
+    execution_provider: ExecutionProvider,
     /// Number of intra-op threads for the inference (ORT backend).
     intra_threads: Option<usize>,
+    /// Number of inter-op threads for the inference (ORT backend).
+    inter_threads: Option<usize>,
+    /// Directory where optimized models are cached across runs (ORT backend).
+    optimized_model_cache: Option<PathBuf>,
+    /// Reject mattes that are almost entirely opaque or transparent.
+    sanity_check: bool,
+    /// Largest input pixel count accepted before decoding.
+    max_pixels: Option<u64>,
+    /// Scale applied to the image before preprocessing, with the matte resized back to full size.
+    infer_scale: Option<f32>,
+    /// Keep the float matte alongside the quantized raw matte.
+    float_matte: bool,
+    /// Model output tensor the matte is read from.
+    output: OutputSelector,
 }
 
 impl InferenceSettings {
@@ -85,11 +305,27 @@ impl InferenceSettings {
     pub fn new(model_path: impl Into<PathBuf>) -> Self {
         Self {
             model_path: model_path.into(),
+            background_model_path: None,
+            matte_combine: MatteCombine::default(),
             backend: InferenceBackend::default(),
             input_resize_filter: FilterType::Triangle,
             output_resize_filter: FilterType::Lanczos3,
             model_input_size: None,
+            model_input_layout: None,
+            pad_to_multiple: None,
+            normalization: Normalization::default(),
+            preprocess_mode: PreprocessMode::default(),
+            tiling: None,
+            matte_activation: MatteActivation::default(),
+            execution_provider: ExecutionProvider::default(),
             intra_threads: None,
+            inter_threads: None,
+            optimized_model_cache: None,
+            sanity_check: false,
+            max_pixels: None,
+            infer_scale: None,
+            float_matte: false,
+            output: OutputSelector::default(),
         }
     }
 
@@ -98,6 +334,16 @@ impl InferenceSettings {
         &self.model_path
     }
 
+    /// Optional background model whose matte is combined with the main model output.
+    pub fn background_model_path(&self) -> Option<&Path> {
+        self.background_model_path.as_deref()
+    }
+
+    /// Strategy used to combine the main and background mattes.
+    pub fn matte_combine(&self) -> MatteCombine {
+        self.matte_combine
+    }
+
     /// Backend used to execute the model.
     pub fn backend(&self) -> InferenceBackend {
         self.backend
@@ -120,11 +366,109 @@ impl InferenceSettings {
         self.model_input_size
     }
 
+    /// Override for the tensor layout used as model input.
+    ///
+    /// When set, callers are responsible for choosing a layout the model supports.
+    pub fn model_input_layout(&self) -> Option<ModelInputLayout> {
+        self.model_input_layout
+    }
+
+    /// Pad the model input up to a multiple of this value and crop the matte back.
+    pub fn pad_to_multiple(&self) -> Option<u32> {
+        self.pad_to_multiple
+    }
+
+    /// How pixel values are normalized into the model input tensor.
+    pub fn normalization(&self) -> Normalization {
+        self.normalization
+    }
+
+    /// How the input image is fit to the model input size.
+    pub fn preprocess_mode(&self) -> PreprocessMode {
+        self.preprocess_mode
+    }
+
+    /// Infer images larger than a tile in overlapping tiles.
+    pub fn tiling(&self) -> Option<TileOptions> {
+        self.tiling
+    }
+
+    /// How raw model output values are mapped to matte values.
+    pub fn matte_activation(&self) -> MatteActivation {
+        self.matte_activation
+    }
+
+    /// Hardware the model runs on (ORT backend).
+    pub fn execution_provider(&self) -> ExecutionProvider {
+        self.execution_provider
+    }
+
     /// Number of intra-op threads for the inference (ORT backend).
     pub fn intra_threads(&self) -> Option<usize> {
         self.intra_threads
     }
 
+    /// Number of inter-op threads for the inference (ORT backend).
+    pub fn inter_threads(&self) -> Option<usize> {
+        self.inter_threads
+    }
+
+    /// Directory where optimized models are cached across runs (ORT backend).
+    pub fn optimized_model_cache(&self) -> Option<&Path> {
+        self.optimized_model_cache.as_deref()
+    }
+
+    /// Whether mattes that are almost entirely opaque or transparent are rejected.
+    pub fn sanity_check(&self) -> bool {
+        self.sanity_check
+    }
+
+    /// Largest input pixel count accepted before decoding.
+    pub fn max_pixels(&self) -> Option<u64> {
+        self.max_pixels
+    }
+
+    /// Scale applied to the image before preprocessing, with the matte resized back to full size.
+    pub fn infer_scale(&self) -> Option<f32> {
+        self.infer_scale
+    }
+
+    /// Whether results keep the float matte alongside the quantized raw matte.
+    pub fn float_matte(&self) -> bool {
+        self.float_matte
+    }
+
+    /// Model output tensor the matte is read from.
+    pub fn output(&self) -> &OutputSelector {
+        &self.output
+    }
+
+    /// Set a background model whose matte is combined with the main model output.
+    ///
+    /// The background model runs with the same backend, thread, size, and layout settings as
+    /// the main model.
+    pub fn with_background_model(mut self, model_path: impl Into<PathBuf>) -> Self {
+        self.background_model_path = Some(model_path.into());
+        self
+    }
+
+    /// Set the strategy used to combine the main and background mattes.
+    pub fn with_matte_combine(mut self, combine: MatteCombine) -> Self {
+        self.matte_combine = combine;
+        self
+    }
+
+    /// Settings for running the background model, if one is configured.
+    pub(crate) fn background_settings(&self) -> Option<Self> {
+        let model_path = self.background_model_path.clone()?;
+        Some(Self {
+            model_path,
+            background_model_path: None,
+            output: OutputSelector::default(),
+            ..self.clone()
+        })
+    }
+
     /// Set the inference backend.
     pub fn with_backend(mut self, backend: InferenceBackend) -> Self {
         self.backend = backend;
@@ -144,15 +488,112 @@ impl InferenceSettings {
         self
     }
 
+    /// Override the tensor layout used as model input.
+    ///
+    /// This bypasses the layout inferred from the model; callers are responsible for choosing a
+    /// layout the model supports.
+    pub fn with_model_input_layout(mut self, layout: ModelInputLayout) -> Self {
+        self.model_input_layout = Some(layout);
+        self
+    }
+
+    /// Override both the model input size and layout, skipping model metadata entirely.
+    ///
+    /// Equivalent to calling [`with_model_input_size`](Self::with_model_input_size) and
+    /// [`with_model_input_layout`](Self::with_model_input_layout). Use this for models whose
+    /// input metadata is missing or wrong.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either dimension is zero.
+    pub fn with_model_input_spec(
+        self,
+        height: usize,
+        width: usize,
+        layout: ModelInputLayout,
+    ) -> Self {
+        self.with_model_input_size(height, width)
+            .with_model_input_layout(layout)
+    }
+
+    /// Pad the model input height and width up to the nearest multiple of `multiple`.
+    ///
+    /// Fully-convolutional models often require dimensions divisible by 16 or 32. The padding
+    /// is added to the bottom and right edges after normalization, and the matte is cropped
+    /// back before it is resized to the original image size.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `multiple` is zero.
+    pub fn with_pad_to_multiple(mut self, multiple: u32) -> Self {
+        assert!(multiple > 0, "pad multiple must be non-zero");
+        self.pad_to_multiple = Some(multiple);
+        self
+    }
+
+    /// Set how pixel values are normalized into the model input tensor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a [`Normalization::Custom`] mean or std is not finite, or a std is zero.
+    pub fn with_normalization(mut self, normalization: Normalization) -> Self {
+        let (mean, std) = normalization.mean_std();
+        assert!(
+            mean.iter().chain(&std).all(|v| v.is_finite()) && std.iter().all(|&s| s != 0.0),
+            "normalization mean and std must be finite and std non-zero"
+        );
+        self.normalization = normalization;
+        self
+    }
+
+    /// Set how the input image is fit to the model input size.
+    pub fn with_preprocess_mode(mut self, mode: PreprocessMode) -> Self {
+        self.preprocess_mode = mode;
+        self
+    }
+
+    /// Infer images wider or taller than `tiling.tile_size` in overlapping tiles.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tile_size` is zero or `overlap` is not smaller than `tile_size`.
+    pub fn with_tiling(mut self, tiling: TileOptions) -> Self {
+        assert!(tiling.tile_size > 0, "tile size must be non-zero");
+        assert!(
+            tiling.overlap < tiling.tile_size,
+            "tile overlap must be smaller than the tile size"
+        );
+        self.tiling = Some(tiling);
+        self
+    }
+
+    /// Set how raw model output values are mapped to matte values.
+    pub fn with_matte_activation(mut self, activation: MatteActivation) -> Self {
+        self.matte_activation = activation;
+        self
+    }
+
     /// Set the model resize filter.
-    pub fn with_input_resize_filter(mut self, filter: FilterType) -> Self {
-        self.input_resize_filter = filter;
+    ///
+    /// Accepts either a [`ResampleFilter`] or an [`image::imageops::FilterType`].
+    pub fn with_input_resize_filter(mut self, filter: impl Into<FilterType>) -> Self {
+        self.input_resize_filter = filter.into();
         self
     }
 
     /// Set the matte resize filter.
-    pub fn with_output_resize_filter(mut self, filter: FilterType) -> Self {
-        self.output_resize_filter = filter;
+    ///
+    /// Accepts either a [`ResampleFilter`] or an [`image::imageops::FilterType`].
+    pub fn with_output_resize_filter(mut self, filter: impl Into<FilterType>) -> Self {
+        self.output_resize_filter = filter.into();
+        self
+    }
+
+    /// Set the hardware the model runs on (ORT backend).
+    ///
+    /// Ignored by the RTen backend.
+    pub fn with_execution_provider(mut self, provider: ExecutionProvider) -> Self {
+        self.execution_provider = provider;
         self
     }
 
@@ -161,10 +602,103 @@ impl InferenceSettings {
         self.intra_threads = intra_threads;
         self
     }
+
+    /// Set the number of inter-op threads for the inference (ORT backend).
+    ///
+    /// Inter-op threads run independent graph branches in parallel, which most matting models
+    /// do not have, so this rarely needs changing.
+    pub fn with_inter_threads(mut self, inter_threads: Option<usize>) -> Self {
+        self.inter_threads = inter_threads;
+        self
+    }
+
+    /// Reject thread counts of zero before they reach the runtime.
+    ///
+    /// `None` lets the runtime pick; an explicit count must be at least 1.
+    pub(crate) fn validate_thread_counts(&self) -> OutlineResult<()> {
+        for (setting, threads) in [
+            ("intra_threads", self.intra_threads),
+            ("inter_threads", self.inter_threads),
+        ] {
+            if threads == Some(0) {
+                return Err(OutlineError::InvalidThreadCount { setting });
+            }
+        }
+        Ok(())
+    }
+
+    /// Cache the graph-optimized model in `dir` and reuse it on later runs (ORT backend).
+    ///
+    /// The cached file is keyed by a SHA-256 of the source model's contents together with the
+    /// execution provider, ORT build, and optimization level, so replacing the model or switching
+    /// any of those produces a fresh entry. A missing or unreadable cache entry falls
+    /// back to optimizing the source model again and rewriting the entry, which is written to a
+    /// temporary file and renamed into place. Ignored by the RTen backend.
+    pub fn with_optimized_model_cache(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.optimized_model_cache = Some(dir.into());
+        self
+    }
+
+    /// Reject mattes where more than 99% of pixels are fully opaque or fully transparent.
+    ///
+    /// When enabled, inference returns [`OutlineError::DegenerateMatte`](crate::OutlineError::DegenerateMatte)
+    /// instead of a matte that would yield an unchanged or empty cut-out.
+    pub fn with_sanity_check(mut self, enabled: bool) -> Self {
+        self.sanity_check = enabled;
+        self
+    }
+
+    /// Reject inputs with more than `max_pixels` pixels (`None` for no limit, the default).
+    ///
+    /// Files and encoded bytes are checked before decoding, so oversized inputs fail with
+    /// [`OutlineError::ImageTooLarge`](crate::OutlineError::ImageTooLarge) instead of exhausting
+    /// memory later in the pipeline.
+    pub fn with_max_pixels(mut self, max_pixels: Option<u64>) -> Self {
+        self.max_pixels = max_pixels;
+        self
+    }
+
+    /// Run inference on a copy of the image scaled by `scale`, keeping the full-size original.
+    ///
+    /// The copy is resized with the input resize filter before preprocessing, and the matte is
+    /// resized to the original dimensions, so outputs keep their full resolution.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `scale` is in `(0.0, 1.0]`.
+    pub fn with_infer_scale(mut self, scale: f32) -> Self {
+        assert!(
+            scale > 0.0 && scale <= 1.0,
+            "infer scale must be in (0.0, 1.0]"
+        );
+        self.infer_scale = Some(scale);
+        self
+    }
+
+    /// Keep the float matte the raw matte is quantized from (disabled by default).
+    ///
+    /// It takes four bytes per pixel on top of the 8-bit raw matte, so it is only kept on request.
+    pub fn with_float_matte(mut self, enabled: bool) -> Self {
+        self.float_matte = enabled;
+        self
+    }
+
+    /// Read the matte from the selected model output instead of the first one.
+    ///
+    /// Inference fails with [`OutlineError::UnknownModelOutput`](crate::OutlineError::UnknownModelOutput)
+    /// when the model has no such output. The background model always uses its first output.
+    pub fn with_output(mut self, output: OutputSelector) -> Self {
+        self.output = output;
+        self
+    }
 }
 
 /// How erosion treats pixels outside the image bounds.
+///
+/// With the `serde` feature, variants serialize in kebab case, e.g. `"outside-is-unknown"`.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 pub enum ErosionBorderMode {
     /// Treat pixels outside the image as background, allowing edge-touching foreground to shrink.
     #[default]
@@ -173,6 +707,16 @@ pub enum ErosionBorderMode {
     OutsideIsUnknown,
 }
 
+/// Which neighbors count as touching when labeling connected mask regions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Connectivity {
+    /// Pixels touch through an edge (left, right, up, down).
+    Four,
+    /// Pixels touch through an edge or a corner.
+    #[default]
+    Eight,
+}
+
 /// Default parameters used by no-argument mask processing methods.
 ///
 /// This does not define which operations run. Use [`MaskPipeline`](crate::MaskPipeline) or the
@@ -180,7 +724,12 @@ pub enum ErosionBorderMode {
 /// to choose an explicit operation order.
 ///
 /// This struct is non-exhaustive; start with [`Default`] and then adjust fields as needed.
+///
+/// With the `serde` feature, it can be stored as a preset; fields missing from the input keep
+/// their defaults.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 #[non_exhaustive]
 pub struct MaskProcessingDefaults {
     /// Standard deviation (sigma) for Gaussian blur.
@@ -206,3 +755,57 @@ impl Default for MaskProcessingDefaults {
         }
     }
 }
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl MaskProcessingDefaults {
+    /// Parse defaults from JSON; fields missing from `json` keep their default values.
+    pub fn from_json_str(json: &str) -> OutlineResult<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Serialize these defaults as pretty-printed JSON.
+    pub fn to_json_str(&self) -> OutlineResult<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mask_processing_defaults_round_trip_through_json() {
+        let defaults = MaskProcessingDefaults::default();
+        let json = defaults.to_json_str().unwrap();
+        assert_eq!(
+            MaskProcessingDefaults::from_json_str(&json).unwrap(),
+            defaults
+        );
+    }
+
+    #[test]
+    fn missing_fields_keep_their_defaults() {
+        let parsed = MaskProcessingDefaults::from_json_str(
+            r#"{ "mask_threshold": 200, "erosion_border_mode": "outside-is-unknown" }"#,
+        )
+        .unwrap();
+
+        assert_eq!(parsed.mask_threshold, 200);
+        assert_eq!(
+            parsed.erosion_border_mode,
+            ErosionBorderMode::OutsideIsUnknown
+        );
+        assert_eq!(
+            parsed.blur_sigma,
+            MaskProcessingDefaults::default().blur_sigma
+        );
+    }
+
+    #[test]
+    fn malformed_json_is_an_error() {
+        let err =
+            MaskProcessingDefaults::from_json_str(r#"{ "mask_threshold": "high" }"#).unwrap_err();
+        assert!(matches!(err, OutlineError::Json(_)));
+    }
+}
@@ -0,0 +1,262 @@
+use image::GrayImage;
+
+use crate::config::Connectivity;
+use crate::mask::label_components;
+
+// Moore neighborhood in clockwise order (y points down), starting west.
+const NEIGHBORS: [(i64, i64); 8] = [
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+];
+
+/// Trace the outer boundary of every foreground region in a mask.
+///
+/// Nonzero pixels count as foreground and regions are 8-connected. Each contour lists the
+/// boundary pixels clockwise, starting from the region's top-left pixel, without repeating the
+/// start; a single-pixel region yields one point. Holes are not traced. Contours are ordered by
+/// the row-major position of their start pixel.
+pub fn contours(mask: &GrayImage) -> Vec<Vec<(u32, u32)>> {
+    let (labels, sizes) = label_components(mask, Connectivity::Eight);
+    let width = mask.width() as usize;
+    let mut traced = vec![false; sizes.len()];
+    let mut out = Vec::with_capacity(sizes.len());
+
+    for (index, &label) in labels.iter().enumerate() {
+        if label == 0 || traced[label - 1] {
+            continue;
+        }
+        traced[label - 1] = true;
+        // Labels follow row-major order, so this is the region's top-left pixel.
+        let start = ((index % width) as u32, (index / width) as u32);
+        out.push(trace_boundary(mask, start));
+    }
+    out
+}
+
+/// Simplify a polyline with the Ramer-Douglas-Peucker algorithm.
+///
+/// Keeps the endpoints and every point that lies more than `epsilon` from the simplified line,
+/// so collinear runs collapse to their ends while corners survive. To simplify a closed contour
+/// from [`contours`], repeat its first point at the end; the result then also starts and ends
+/// with that point. Inputs with fewer than three points are returned as is.
+///
+/// # Panics
+///
+/// Panics if `epsilon` is negative or NaN.
+pub fn simplify_contour(points: &[(f64, f64)], epsilon: f64) -> Vec<(f64, f64)> {
+    assert!(
+        epsilon >= 0.0,
+        "simplification epsilon must be non-negative"
+    );
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+    // An explicit stack keeps long contours from overflowing the call stack.
+    let mut ranges = vec![(0, points.len() - 1)];
+    while let Some((first, last)) = ranges.pop() {
+        let (farthest, distance) = (first + 1..last)
+            .map(|index| {
+                (
+                    index,
+                    segment_distance(points[index], points[first], points[last]),
+                )
+            })
+            .fold((first, 0.0), |best, candidate| {
+                if candidate.1 > best.1 {
+                    candidate
+                } else {
+                    best
+                }
+            });
+        if distance > epsilon {
+            keep[farthest] = true;
+            ranges.push((first, farthest));
+            ranges.push((farthest, last));
+        }
+    }
+
+    points
+        .iter()
+        .zip(keep)
+        .filter_map(|(&point, kept)| kept.then_some(point))
+        .collect()
+}
+
+// Distance from `point` to the segment from `start` to `end`, or to `start` when they coincide.
+fn segment_distance(point: (f64, f64), start: (f64, f64), end: (f64, f64)) -> f64 {
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let length_sq = dx * dx + dy * dy;
+    if length_sq == 0.0 {
+        return (point.0 - start.0).hypot(point.1 - start.1);
+    }
+    let t = (((point.0 - start.0) * dx + (point.1 - start.1) * dy) / length_sq).clamp(0.0, 1.0);
+    (point.0 - (start.0 + t * dx)).hypot(point.1 - (start.1 + t * dy))
+}
+
+// Moore-neighbor tracing with Jacob's stopping criterion: stop once the start pixel is left in
+// the same direction as on the first step.
+fn trace_boundary(mask: &GrayImage, start: (u32, u32)) -> Vec<(u32, u32)> {
+    let is_foreground = |x: i64, y: i64| {
+        x >= 0
+            && y >= 0
+            && x < i64::from(mask.width())
+            && y < i64::from(mask.height())
+            && mask.get_pixel(x as u32, y as u32)[0] != 0
+    };
+
+    let mut contour = vec![start];
+    let mut current = (i64::from(start.0), i64::from(start.1));
+    // The pixel west of the top-left pixel is background, so the search starts there.
+    let mut backtrack = 0;
+    let mut first_step = None;
+
+    loop {
+        let Some(step) = (1..=8).map(|offset| (backtrack + offset) % 8).find(|&dir| {
+            let (dx, dy) = NEIGHBORS[dir];
+            is_foreground(current.0 + dx, current.1 + dy)
+        }) else {
+            // An isolated pixel has no neighbors to walk to.
+            return contour;
+        };
+
+        if current == (i64::from(start.0), i64::from(start.1)) {
+            match first_step {
+                Some(first) if first == step => return contour,
+                None => first_step = Some(step),
+                Some(_) => {}
+            }
+        }
+
+        // The last background pixel checked becomes the backtrack, seen from the new pixel.
+        let (dx, dy) = NEIGHBORS[step];
+        let (bx, by) = NEIGHBORS[(step + 7) % 8];
+        let next = (current.0 + dx, current.1 + dy);
+        let relative = (bx - dx, by - dy);
+        backtrack = NEIGHBORS
+            .iter()
+            .position(|&neighbor| neighbor == relative)
+            .expect("backtrack pixel neighbors the next pixel");
+        current = next;
+
+        if current != (i64::from(start.0), i64::from(start.1)) {
+            contour.push((current.0 as u32, current.1 as u32));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Luma;
+
+    fn rectangle_mask() -> GrayImage {
+        GrayImage::from_fn(10, 10, |x, y| {
+            let inside = (2..=6).contains(&x) && (3..=7).contains(&y);
+            Luma([if inside { 255 } else { 0 }])
+        })
+    }
+
+    #[test]
+    fn rectangle_contour_encloses_its_corners() {
+        let contours = contours(&rectangle_mask());
+
+        assert_eq!(contours.len(), 1);
+        let contour = &contours[0];
+        for corner in [(2, 3), (6, 3), (6, 7), (2, 7)] {
+            assert!(contour.contains(&corner), "missing corner {corner:?}");
+        }
+        assert!(
+            contour
+                .iter()
+                .all(|&(x, y)| x == 2 || x == 6 || y == 3 || y == 7)
+        );
+        // The perimeter of a 5x5 block has 16 pixels, each visited once.
+        assert_eq!(contour.len(), 16);
+        assert_eq!(contour[0], (2, 3));
+        assert_eq!(contour[1], (3, 3));
+    }
+
+    #[test]
+    fn each_region_gets_one_contour() {
+        let mut mask = rectangle_mask();
+        mask.put_pixel(9, 0, Luma([255]));
+        mask.put_pixel(9, 8, Luma([255]));
+        mask.put_pixel(8, 9, Luma([255]));
+
+        let contours = contours(&mask);
+
+        assert_eq!(contours.len(), 3);
+        assert_eq!(contours[0], vec![(9, 0)]);
+        assert_eq!(contours[2], vec![(9, 8), (8, 9)]);
+    }
+
+    #[test]
+    fn holes_are_not_traced() {
+        let mut mask = rectangle_mask();
+        mask.put_pixel(4, 5, Luma([0]));
+
+        let contours = contours(&mask);
+
+        assert_eq!(contours.len(), 1);
+        assert!(!contours[0].contains(&(4, 4)));
+        assert_eq!(contours[0].len(), 16);
+    }
+
+    #[test]
+    fn empty_mask_has_no_contours() {
+        assert!(contours(&GrayImage::new(4, 4)).is_empty());
+    }
+
+    mod simplify_contour {
+        use super::*;
+
+        #[test]
+        fn collinear_points_collapse_to_endpoints() {
+            let line: Vec<(f64, f64)> = (0..10)
+                .map(|i| (f64::from(i), f64::from(i) * 0.5))
+                .collect();
+            assert_eq!(simplify_contour(&line, 0.1), vec![(0.0, 0.0), (9.0, 4.5)]);
+        }
+
+        #[test]
+        fn sharp_corner_is_preserved() {
+            let mut points: Vec<(f64, f64)> = (0..=5).map(|x| (f64::from(x), 0.0)).collect();
+            points.extend((1..=5).map(|y| (5.0, f64::from(y))));
+
+            assert_eq!(
+                simplify_contour(&points, 0.5),
+                vec![(0.0, 0.0), (5.0, 0.0), (5.0, 5.0)]
+            );
+        }
+
+        #[test]
+        fn closed_rectangle_keeps_its_corners() {
+            let mut ring: Vec<(f64, f64)> = contours(&rectangle_mask())[0]
+                .iter()
+                .map(|&(x, y)| (f64::from(x), f64::from(y)))
+                .collect();
+            ring.push(ring[0]);
+
+            assert_eq!(
+                simplify_contour(&ring, 0.5),
+                vec![(2.0, 3.0), (6.0, 3.0), (6.0, 7.0), (2.0, 7.0), (2.0, 3.0)]
+            );
+        }
+
+        #[test]
+        fn short_inputs_are_returned_as_is() {
+            assert_eq!(simplify_contour(&[(1.0, 2.0)], 1.0), vec![(1.0, 2.0)]);
+            assert!(simplify_contour(&[], 1.0).is_empty());
+        }
+    }
+}
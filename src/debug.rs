@@ -0,0 +1,78 @@
+use image::{GenericImage, GrayImage, RgbaImage};
+
+/// Split an RGBA image into a 2x2 grayscale montage of its channels.
+///
+/// The panels are laid out as red (top left), green (top right), blue (bottom left), and alpha
+/// (bottom right), so color spill and matte problems can be told apart at a glance. Each panel
+/// keeps the source dimensions.
+pub fn channel_dump(image: &RgbaImage) -> GrayImage {
+    let (w, h) = image.dimensions();
+    let panels: [GrayImage; 4] = std::array::from_fn(|channel| {
+        GrayImage::from_fn(w, h, |x, y| image::Luma([image.get_pixel(x, y)[channel]]))
+    });
+    montage(&panels, 2)
+}
+
+// Lay out equally sized panels left to right, top to bottom.
+fn montage(panels: &[GrayImage], columns: u32) -> GrayImage {
+    let Some(first) = panels.first() else {
+        return GrayImage::new(0, 0);
+    };
+    let (w, h) = first.dimensions();
+    let count = u32::try_from(panels.len()).expect("panel count exceeds u32::MAX");
+    let rows = count.div_ceil(columns);
+    let out_width = w
+        .checked_mul(columns.min(count))
+        .expect("montage width exceeds u32::MAX");
+    let out_height = h
+        .checked_mul(rows)
+        .expect("montage height exceeds u32::MAX");
+
+    let mut out = GrayImage::new(out_width, out_height);
+    for (index, panel) in (0..count).zip(panels) {
+        out.copy_from(panel, (index % columns) * w, (index / columns) * h)
+            .expect("panel should fit in montage");
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    mod channel_dump {
+        use super::*;
+
+        #[test]
+        fn panels_hold_each_channel() {
+            let image = RgbaImage::from_pixel(3, 2, Rgba([10, 20, 30, 40]));
+            let dump = channel_dump(&image);
+
+            assert_eq!(dump.dimensions(), (6, 4));
+            assert_eq!(dump.get_pixel(0, 0)[0], 10);
+            assert_eq!(dump.get_pixel(5, 1)[0], 20);
+            assert_eq!(dump.get_pixel(2, 3)[0], 30);
+            assert_eq!(dump.get_pixel(3, 2)[0], 40);
+        }
+
+        #[test]
+        fn panels_keep_pixel_positions() {
+            let mut image = RgbaImage::new(2, 2);
+            image.put_pixel(1, 0, Rgba([255, 0, 0, 128]));
+            let dump = channel_dump(&image);
+
+            assert_eq!(dump.get_pixel(1, 0)[0], 255);
+            assert_eq!(dump.get_pixel(3, 2)[0], 128);
+            assert_eq!(dump.get_pixel(0, 0)[0], 0);
+            assert_eq!(dump.get_pixel(2, 2)[0], 0);
+        }
+
+        #[test]
+        fn empty_image_yields_empty_montage() {
+            let dump = channel_dump(&RgbaImage::new(0, 0));
+            assert_eq!(dump.dimensions(), (0, 0));
+        }
+    }
+}
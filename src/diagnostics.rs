@@ -0,0 +1,151 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+use outline::{OutlineError, OutlineResult};
+
+/// How serious a diagnostic is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A message collected during a run and reported once the command finishes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message: message.into(),
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.severity {
+            Severity::Warning => write!(f, "Warning: {}", self.message),
+            Severity::Error => write!(f, "Error: {}", self.message),
+        }
+    }
+}
+
+/// Thread-safe collector for diagnostics raised while a command runs.
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    entries: Mutex<Vec<Diagnostic>>,
+    strict: AtomicBool,
+}
+
+impl Diagnostics {
+    pub const fn new() -> Self {
+        Self {
+            entries: Mutex::new(Vec::new()),
+            strict: AtomicBool::new(false),
+        }
+    }
+
+    /// Turn later warnings into errors, for `--strict`.
+    pub fn set_strict(&self, strict: bool) {
+        self.strict.store(strict, Ordering::Relaxed);
+    }
+
+    /// Record a warning, or return it as [`OutlineError::StrictWarning`] in strict mode.
+    pub fn warn(&self, message: impl Into<String>) -> OutlineResult<()> {
+        let message = message.into();
+        if self.strict.load(Ordering::Relaxed) {
+            return Err(OutlineError::StrictWarning(message));
+        }
+        self.push(Diagnostic::warning(message));
+        Ok(())
+    }
+
+    pub fn push(&self, diagnostic: Diagnostic) {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(diagnostic);
+    }
+
+    /// Remove and return all collected diagnostics in the order they were raised.
+    pub fn take(&self) -> Vec<Diagnostic> {
+        std::mem::take(&mut *self.entries.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+static DIAGNOSTICS: Diagnostics = Diagnostics::new();
+
+/// Turn every later warning of the current run into an error, for `--strict`.
+pub fn set_strict(strict: bool) {
+    DIAGNOSTICS.set_strict(strict);
+}
+
+/// Record a warning for the current run.
+///
+/// With `--strict`, the warning is returned as an error instead; callers propagate it with `?`
+/// so the command stops at the first warning, before writing anything that follows it.
+pub fn warn(message: impl Into<String>) -> OutlineResult<()> {
+    DIAGNOSTICS.warn(message)
+}
+
+/// Record an error that did not abort the run, such as a failed batch item.
+pub fn error(message: impl Into<String>) {
+    DIAGNOSTICS.push(Diagnostic::error(message));
+}
+
+/// Remove and return every diagnostic recorded for the current run.
+pub fn take_all() -> Vec<Diagnostic> {
+    DIAGNOSTICS.take()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warning_display_has_prefix() {
+        let diagnostic = Diagnostic::warning("something odd");
+        assert_eq!(diagnostic.to_string(), "Warning: something odd");
+        let diagnostic = Diagnostic::error("something broke");
+        assert_eq!(diagnostic.to_string(), "Error: something broke");
+    }
+
+    #[test]
+    fn strict_warnings_fail_instead_of_being_recorded() {
+        let diagnostics = Diagnostics::new();
+        diagnostics.warn("first").unwrap();
+
+        diagnostics.set_strict(true);
+        let err = diagnostics.warn("second").unwrap_err();
+        assert!(matches!(err, OutlineError::StrictWarning(message) if message == "second"));
+
+        assert_eq!(diagnostics.take(), vec![Diagnostic::warning("first")]);
+    }
+
+    #[test]
+    fn take_returns_entries_in_order_and_clears() {
+        let diagnostics = Diagnostics::new();
+        diagnostics.push(Diagnostic::warning("first"));
+        diagnostics.push(Diagnostic::warning("second"));
+
+        let taken = diagnostics.take();
+        assert_eq!(
+            taken,
+            vec![Diagnostic::warning("first"), Diagnostic::warning("second")]
+        );
+        assert!(diagnostics.take().is_empty());
+    }
+}
@@ -0,0 +1,188 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Cursor, Write};
+use std::path::Path;
+
+use image::{EncodableLayout, GrayImage, ImageBuffer, ImageFormat, PixelWithColorType};
+
+use crate::OutlineResult;
+
+/// Return whether every pixel in the mask is either `0` or `255`.
+pub fn is_binary_mask(mask: &GrayImage) -> bool {
+    mask.as_raw()
+        .iter()
+        .all(|&value| value == 0 || value == 255)
+}
+
+/// Encode a mask as an 8-bit grayscale PNG in memory.
+pub(crate) fn encode_png(mask: &GrayImage) -> OutlineResult<Vec<u8>> {
+    encode_image(mask, ImageFormat::Png)
+}
+
+/// Encode an image as `format` in memory.
+pub(crate) fn encode_image<P>(
+    image: &ImageBuffer<P, Vec<P::Subpixel>>,
+    format: ImageFormat,
+) -> OutlineResult<Vec<u8>>
+where
+    P: PixelWithColorType,
+    [P::Subpixel]: EncodableLayout,
+{
+    let mut bytes = Cursor::new(Vec::new());
+    image.write_to(&mut bytes, format)?;
+    Ok(bytes.into_inner())
+}
+
+/// Encode an image as `format` into `writer`, which does not need to be seekable.
+pub(crate) fn write_image<P>(
+    image: &ImageBuffer<P, Vec<P::Subpixel>>,
+    writer: &mut impl Write,
+    format: ImageFormat,
+) -> OutlineResult<()>
+where
+    P: PixelWithColorType,
+    [P::Subpixel]: EncodableLayout,
+{
+    // Some encoders seek, so encode into memory first.
+    writer.write_all(&encode_image(image, format)?)?;
+    Ok(())
+}
+
+/// Save a mask as a 1-bit grayscale PNG.
+///
+/// Pixels at or above `128` are written as white and everything else as black, so callers that
+/// need a lossless result should check [`is_binary_mask`] first. Decoders expand the image back
+/// to `0`/`255` when loading it.
+pub fn save_1bit_png(mask: &GrayImage, path: impl AsRef<Path>) -> OutlineResult<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_1bit_png(mask, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Encode a mask as a 1-bit grayscale PNG into `writer`.
+pub(crate) fn write_1bit_png(mask: &GrayImage, writer: impl Write) -> OutlineResult<()> {
+    let (w, h) = mask.dimensions();
+    let mut encoder = png::Encoder::new(writer, w, h);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::One);
+    let mut png_writer = encoder.write_header().map_err(io::Error::other)?;
+    png_writer
+        .write_image_data(&pack_bits(mask))
+        .map_err(io::Error::other)?;
+    png_writer.finish().map_err(io::Error::other)?;
+    Ok(())
+}
+
+// Pack each row into bytes, most significant bit first, padding the last byte with zeros.
+fn pack_bits(mask: &GrayImage) -> Vec<u8> {
+    let width = mask.width() as usize;
+    let row_bytes = width.div_ceil(8);
+    let mut packed = vec![0u8; row_bytes * mask.height() as usize];
+    if width == 0 {
+        return packed;
+    }
+
+    for (row, out) in mask
+        .as_raw()
+        .chunks_exact(width)
+        .zip(packed.chunks_exact_mut(row_bytes))
+    {
+        for (x, &value) in row.iter().enumerate() {
+            if value >= 128 {
+                out[x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+    }
+
+    packed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Luma;
+
+    fn checker(w: u32, h: u32) -> GrayImage {
+        GrayImage::from_fn(w, h, |x, y| Luma([if (x + y) % 2 == 0 { 255 } else { 0 }]))
+    }
+
+    mod is_binary_mask {
+        use super::*;
+
+        #[test]
+        fn accepts_only_zero_and_full() {
+            assert!(is_binary_mask(&checker(5, 3)));
+            let mut mask = checker(5, 3);
+            mask.put_pixel(1, 1, Luma([254]));
+            assert!(!is_binary_mask(&mask));
+        }
+    }
+
+    mod pack_bits {
+        use super::*;
+
+        #[test]
+        fn packs_msb_first_with_row_padding() {
+            let mask = checker(10, 2);
+            assert_eq!(
+                pack_bits(&mask),
+                vec![0b1010_1010, 0b1000_0000, 0b0101_0101, 0b0100_0000]
+            );
+        }
+
+        #[test]
+        fn uses_midpoint_threshold() {
+            let mask = GrayImage::from_raw(3, 1, vec![127, 128, 0]).unwrap();
+            assert_eq!(pack_bits(&mask), vec![0b0100_0000]);
+        }
+    }
+
+    mod encode_png {
+        use super::*;
+
+        #[test]
+        fn round_trips_through_image_decoder() {
+            let mask = GrayImage::from_fn(9, 4, |x, y| Luma([(x * 25 + y) as u8]));
+
+            let bytes = encode_png(&mask).unwrap();
+            let decoded = image::load_from_memory_with_format(&bytes, ImageFormat::Png)
+                .unwrap()
+                .into_luma8();
+
+            assert_eq!(decoded, mask);
+        }
+    }
+
+    mod save_1bit_png {
+        use super::*;
+
+        #[test]
+        fn round_trips_through_image_decoder() {
+            let dir = tempfile::tempdir().expect("failed to create temp dir");
+            let path = dir.path().join("mask.png");
+            let mask = checker(13, 7);
+
+            save_1bit_png(&mask, &path).unwrap();
+            let decoded = image::open(&path).unwrap().into_luma8();
+
+            assert_eq!(decoded, mask);
+        }
+
+        #[test]
+        fn is_smaller_than_8bit_png() {
+            let dir = tempfile::tempdir().expect("failed to create temp dir");
+            let packed = dir.path().join("packed.png");
+            let full = dir.path().join("full.png");
+            let mask = GrayImage::from_fn(256, 256, |x, y| {
+                Luma([if (x * 7 + y * 13) % 5 < 2 { 255 } else { 0 }])
+            });
+
+            save_1bit_png(&mask, &packed).unwrap();
+            mask.save(&full).unwrap();
+
+            let packed_len = std::fs::metadata(&packed).unwrap().len();
+            let full_len = std::fs::metadata(&full).unwrap().len();
+            assert!(packed_len < full_len, "{packed_len} >= {full_len}");
+        }
+    }
+}
@@ -1,9 +1,12 @@
 use std::path::PathBuf;
+use std::time::Duration;
 
 #[cfg(feature = "backend-ort")]
 use ort::session::builder::SessionBuilder;
 use thiserror::Error;
 
+use crate::geometry::BoundingBox;
+
 /// Result type alias for operations that may fail with [`OutlineError`].
 pub type OutlineResult<T> = std::result::Result<T, OutlineError>;
 
@@ -32,6 +35,10 @@ pub enum OutlineError {
     #[cfg(feature = "backend-rten")]
     #[error("RTen output conversion failed: {0}")]
     RtenValue(#[from] rten::TryFromValueError),
+    /// Option presets could not be read from or written to JSON.
+    #[cfg(feature = "serde")]
+    #[error("Invalid JSON options: {0}")]
+    Json(#[from] serde_json::Error),
     /// Image loading, decoding, or encoding error.
     #[error("Image processing failed: {0}")]
     Image(#[from] image::ImageError),
@@ -44,6 +51,9 @@ pub enum OutlineError {
     /// Vectorization or tracing operation failed.
     #[error("Tracing failed: {0}")]
     Trace(String),
+    /// A [`MaskColor`](crate::MaskColor) would produce a fully transparent layer.
+    #[error("Invalid mask color: {0}")]
+    InvalidMaskColor(String),
     /// Alpha matte dimensions do not match the source image.
     #[error("Alpha matte size {found:?} does not match source image size {expected:?}")]
     AlphaMismatch {
@@ -52,12 +62,92 @@ pub enum OutlineError {
         /// Actual dimensions (width, height).
         found: (u32, u32),
     },
+    /// Crop bounds do not fit inside the image they were applied to.
+    #[error("Crop bounds {bounds:?} do not fit the {}x{} canvas", canvas.0, canvas.1)]
+    CropOutOfBounds {
+        /// The requested crop.
+        bounds: BoundingBox,
+        /// Canvas dimensions (width, height).
+        canvas: (u32, u32),
+    },
+    /// A thread count setting is zero.
+    #[error("{setting} must be at least 1; use None to let the runtime decide")]
+    InvalidThreadCount {
+        /// Name of the offending setting.
+        setting: &'static str,
+    },
+    /// The input image has more pixels than the configured limit.
+    #[error(
+        "Image has {pixels} pixels, exceeding the limit of {limit}; downscale it or raise the limit"
+    )]
+    ImageTooLarge {
+        /// Pixel count of the input image.
+        pixels: u64,
+        /// The configured maximum pixel count.
+        limit: u64,
+    },
+    /// The operation did not finish within the configured time limit.
+    #[error("Operation timed out after {limit:?}")]
+    Timeout {
+        /// The time limit that was exceeded.
+        limit: Duration,
+    },
+    /// The matte is almost entirely opaque or almost entirely transparent.
+    ///
+    /// Only reported when the sanity check is enabled; usually points at a wrong model, input
+    /// size, or normalization.
+    #[error(
+        "Matte looks degenerate with model input {input_spec}: {:.1}% fully opaque, {:.1}% fully transparent",
+        opaque_fraction * 100.0,
+        transparent_fraction * 100.0
+    )]
+    DegenerateMatte {
+        /// Fraction of pixels with alpha 255.
+        opaque_fraction: f32,
+        /// Fraction of pixels with alpha 0.
+        transparent_fraction: f32,
+        /// Model input size and layout used, e.g. `320x320 NCHW`.
+        input_spec: String,
+    },
+    /// Running the model failed, typically because its input does not match the model.
+    #[error("Model run failed with input {input_spec}: {source}")]
+    ModelRun {
+        /// Model input size and layout used, e.g. `320x320 NCHW`.
+        input_spec: String,
+        /// The underlying backend or output-shape error.
+        #[source]
+        source: Box<OutlineError>,
+    },
+    /// The selected model output does not exist.
+    #[error("Model has no output {output}; available outputs: {}", available.join(", "))]
+    UnknownModelOutput {
+        /// The requested output, e.g. `` `matte` `` or `index 3`.
+        output: String,
+        /// Names of the model's outputs in graph order.
+        available: Vec<String>,
+    },
     /// Model file not found at the specified path.
     #[error("Model file not found: {}", path.display())]
     ModelNotFound {
         /// The path that was searched.
         path: PathBuf,
     },
+    /// Trimap thresholds where foreground would start below background.
+    #[error(
+        "Trimap foreground threshold {fg_threshold} must not be below the background threshold {bg_threshold}"
+    )]
+    InvalidTrimapThresholds {
+        /// Values at or above this are confident foreground.
+        fg_threshold: u8,
+        /// Values at or below this are confident background.
+        bg_threshold: u8,
+    },
+    /// Processing one input panicked; the panic was caught so other inputs could continue.
+    #[error("Processing panicked: {0}")]
+    Panicked(String),
+    /// A warning was raised while warnings are treated as errors.
+    #[error("Warning treated as an error in strict mode: {0}")]
+    StrictWarning(String),
 }
 
 // Normalize SessionBuilder-specific ORT errors into OutlineError.
@@ -1,8 +1,16 @@
+use std::io::Write;
 use std::path::Path;
 
-use image::{GrayImage, RgbImage, Rgba, RgbaImage};
+use image::{DynamicImage, GrayImage, ImageFormat, Luma, RgbImage, Rgba, RgbaImage};
 
+use crate::debug::channel_dump;
+use crate::encode::{encode_image, write_image};
 use crate::geometry::{BoundingBox, Padding, alpha_bounding_box, crop_rgba_image, pad_rgba_image};
+use crate::layer::{
+    BackgroundFit, CompositeOp, Gradient, blur_background, compose_over_color,
+    compose_over_gradient, compose_over_image, composite, composite_on_checkerboard,
+    desaturate_background, render_drop_shadow, render_stroke, vignette,
+};
 use crate::{OutlineError, OutlineResult};
 
 /// Compose an RGBA foreground image from an RGB image and a grayscale alpha matte.
@@ -81,6 +89,132 @@ impl ForegroundHandle {
         Ok(())
     }
 
+    /// Encode the RGBA foreground image as a PNG in memory.
+    pub fn to_png_bytes(&self) -> OutlineResult<Vec<u8>> {
+        self.to_bytes(ImageFormat::Png)
+    }
+
+    /// Encode the RGBA foreground image as `format` in memory.
+    ///
+    /// Formats without an alpha channel, such as JPEG, return [`OutlineError::Image`]; flatten
+    /// the foreground with [`compose_over_color`](Self::compose_over_color) first.
+    pub fn to_bytes(&self, format: ImageFormat) -> OutlineResult<Vec<u8>> {
+        encode_image(&self.image, format)
+    }
+
+    /// Encode the RGBA foreground image as `format` into `writer`, such as a socket or buffer.
+    ///
+    /// Formats without an alpha channel, such as JPEG, return an error; flatten the foreground
+    /// with [`compose_over_color`](Self::compose_over_color) first.
+    pub fn write_to<W: Write>(&self, writer: &mut W, format: ImageFormat) -> OutlineResult<()> {
+        write_image(&self.image, writer, format)
+    }
+
+    /// Flatten the foreground onto a solid RGBA color and return an opaque RGB image.
+    ///
+    /// See [`compose_over_color`](crate::compose_over_color) for how translucent fills are
+    /// handled.
+    pub fn compose_over_color(&self, color: [u8; 4]) -> RgbImage {
+        compose_over_color(&self.image, color)
+    }
+
+    /// Flatten the foreground onto a [`Gradient`](crate::Gradient) sized to it and return an
+    /// opaque RGB image.
+    pub fn compose_over_gradient(&self, gradient: &Gradient) -> RgbImage {
+        compose_over_gradient(&self.image, gradient)
+    }
+
+    /// Flatten the foreground onto a transparency checkerboard with `cell`-pixel squares for
+    /// previewing.
+    ///
+    /// See [`composite_on_checkerboard`](crate::composite_on_checkerboard).
+    pub fn composite_on_checkerboard(&self, cell: u32) -> RgbImage {
+        composite_on_checkerboard(&self.image, cell)
+    }
+
+    /// Flatten the foreground onto a background image fitted to it and return an opaque RGB
+    /// image.
+    ///
+    /// See [`compose_over_image`](crate::compose_over_image) for how `fit` resizes the
+    /// background.
+    pub fn compose_over_image(&self, background: &RgbaImage, fit: BackgroundFit) -> RgbImage {
+        compose_over_image(&self.image, background, fit)
+    }
+
+    /// Flatten the foreground onto a Gaussian-blurred copy of its own color channels and return
+    /// an opaque RGB image.
+    ///
+    /// The cut-out keeps the full RGB of its source where the alpha is zero, so this keeps the
+    /// original background, blurred. See [`blur_background`](crate::blur_background).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sigma` is not a positive finite number.
+    pub fn compose_over_blurred(&self, sigma: f32) -> RgbImage {
+        let (rgb, alpha) = self.split_alpha();
+        blur_background(&rgb, &alpha, sigma).expect("color and alpha channels share one size")
+    }
+
+    /// Flatten the foreground onto a grayscale copy of its own color channels and return an
+    /// opaque RGB image.
+    ///
+    /// As with [`compose_over_blurred`](Self::compose_over_blurred), this keeps the original
+    /// background. See [`desaturate_background`](crate::desaturate_background).
+    pub fn compose_over_desaturated(&self) -> RgbImage {
+        let (rgb, alpha) = self.split_alpha();
+        desaturate_background(&rgb, &alpha).expect("color and alpha channels share one size")
+    }
+
+    /// Place a drop shadow beneath the subject.
+    ///
+    /// The shadow is rendered from this foreground's alpha with
+    /// [`render_drop_shadow`](crate::render_drop_shadow) and the subject is composited over it,
+    /// so the canvas size is unchanged and shadow shifted past it is clipped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `blur` is negative or NaN.
+    pub fn drop_shadow(self, offset: (i32, i32), blur: f32, color: [u8; 4]) -> Self {
+        let (_, alpha) = self.split_alpha();
+        let shadow = render_drop_shadow(&alpha, offset, blur, color);
+        let image = composite(&shadow, &self.image, CompositeOp::Over)
+            .expect("shadow is rendered at the foreground size");
+        Self { image }
+    }
+
+    /// Place a stroke ring of `width` pixels beneath the subject's edge.
+    ///
+    /// The ring is rendered from this foreground's alpha with
+    /// [`render_stroke`](crate::render_stroke) and the subject is composited over it, so the
+    /// subject edge stays clean. The canvas size is unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is negative or NaN.
+    pub fn stroke(self, width: f32, color: [u8; 4]) -> Self {
+        let (_, alpha) = self.split_alpha();
+        let stroke = render_stroke(&alpha, width, color);
+        let image = composite(&stroke, &self.image, CompositeOp::Over)
+            .expect("stroke is rendered at the foreground size");
+        Self { image }
+    }
+
+    // Separate the color channels from the alpha channel.
+    fn split_alpha(&self) -> (RgbImage, GrayImage) {
+        let rgb = DynamicImage::ImageRgba8(self.image.clone()).into_rgb8();
+        let alpha = GrayImage::from_fn(self.image.width(), self.image.height(), |x, y| {
+            Luma([self.image.get_pixel(x, y)[3]])
+        });
+        (rgb, alpha)
+    }
+
+    /// Render the R, G, B, and A channels as a 2x2 grayscale montage for debugging.
+    ///
+    /// See [`channel_dump`](crate::channel_dump) for the panel layout.
+    pub fn channel_dump(&self) -> GrayImage {
+        channel_dump(&self.image)
+    }
+
     /// Compute the bounding box of non-transparent content using a non-zero alpha threshold.
     pub fn bounding_box(&self) -> Option<BoundingBox> {
         self.bounding_box_with(1)
@@ -97,6 +231,20 @@ impl ForegroundHandle {
         Self { image }
     }
 
+    /// Pad the shorter side with transparency so the canvas is square, keeping the content centered.
+    ///
+    /// When the extra space is odd, the extra pixel goes to the right or bottom edge.
+    pub fn pad_to_square(self) -> Self {
+        let (width, height) = self.dimensions();
+        let extra = width.abs_diff(height);
+        let (before, after) = (extra / 2, extra - extra / 2);
+        if width < height {
+            self.pad(Padding::new(before, 0, after, 0))
+        } else {
+            self.pad(Padding::new(0, before, 0, after))
+        }
+    }
+
     /// Crop the image to `bounds`.
     ///
     /// Returns `None` when `bounds` is empty or outside the current image canvas.
@@ -124,6 +272,18 @@ impl ForegroundHandle {
         let bounds = alpha_bounding_box(&self.image, threshold)?;
         self.crop(bounds)
     }
+
+    /// Fade the alpha toward the canvas edges and round the canvas corners.
+    ///
+    /// See [`vignette`](crate::vignette) for how `radius` and `corner_radius` are applied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radius` or `corner_radius` is negative or NaN.
+    pub fn vignette(self, radius: f32, corner_radius: f32) -> Self {
+        let image = vignette(&self.image, radius, corner_radius);
+        Self { image }
+    }
 }
 
 #[cfg(test)]
@@ -352,6 +512,30 @@ mod tests {
         assert_eq!(padded.dimensions(), (6, 8));
     }
 
+    #[test]
+    fn foreground_handle_pad_to_square_centers_content() {
+        let mut foreground = ForegroundHandle {
+            image: RgbaImage::from_pixel(3, 6, image::Rgba([0, 0, 0, 0])),
+        };
+        foreground
+            .image
+            .put_pixel(0, 0, image::Rgba([10, 20, 30, 255]));
+
+        let square = foreground.pad_to_square();
+        assert_eq!(square.dimensions(), (6, 6));
+        assert_eq!(square.image().get_pixel(1, 0).0, [10, 20, 30, 255]);
+
+        let wide = ForegroundHandle {
+            image: RgbaImage::from_pixel(5, 2, image::Rgba([0, 0, 0, 0])),
+        };
+        assert_eq!(wide.pad_to_square().dimensions(), (5, 5));
+
+        let already_square = ForegroundHandle {
+            image: RgbaImage::from_pixel(4, 4, image::Rgba([0, 0, 0, 0])),
+        };
+        assert_eq!(already_square.pad_to_square().dimensions(), (4, 4));
+    }
+
     #[test]
     fn foreground_handle_bounding_box_and_crop_work_on_alpha() {
         let mut foreground = ForegroundHandle {
@@ -401,6 +585,38 @@ mod tests {
         assert!(foreground.crop(BoundingBox::new(0, 0, 0, 1)).is_none());
     }
 
+    #[test]
+    fn foreground_handle_write_to_encodes_png() {
+        let foreground = ForegroundHandle::new(RgbaImage::from_pixel(3, 2, Rgba([1, 2, 3, 4])));
+        let mut bytes = Vec::new();
+        foreground
+            .write_to(&mut bytes, ImageFormat::Png)
+            .expect("foreground should encode");
+
+        let decoded = image::load_from_memory(&bytes)
+            .expect("encoded foreground should load")
+            .to_rgba8();
+        assert_eq!(decoded.dimensions(), (3, 2));
+        assert_eq!(decoded.get_pixel(0, 0).0, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn foreground_handle_to_png_bytes_round_trips() {
+        let foreground = ForegroundHandle::new(RgbaImage::from_fn(3, 2, |x, y| {
+            Rgba([x as u8, y as u8, 7, 128])
+        }));
+        let bytes = foreground.to_png_bytes().expect("foreground should encode");
+
+        let decoded = image::load_from_memory(&bytes)
+            .expect("encoded foreground should load")
+            .to_rgba8();
+        assert_eq!(&decoded, foreground.image());
+        assert!(matches!(
+            foreground.to_bytes(ImageFormat::Jpeg),
+            Err(OutlineError::Image(_))
+        ));
+    }
+
     #[test]
     fn foreground_handle_crop_to_content_with_ignores_low_alpha() {
         let mut foreground = ForegroundHandle {
@@ -2,6 +2,7 @@ use image::{GenericImage, GrayImage, Rgb, RgbImage, Rgba, RgbaImage};
 
 /// Integer bounding box around image content.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BoundingBox {
     /// Left edge of the bounding box in pixels.
     pub x: u32,
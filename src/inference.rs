@@ -1,27 +1,43 @@
+use std::borrow::Cow;
 use std::convert::TryFrom;
+use std::fmt;
 use std::io;
 use std::io::Cursor;
 use std::path::Path;
 #[cfg(feature = "backend-ort")]
+use std::path::PathBuf;
+#[cfg(feature = "backend-ort")]
 use std::sync::Mutex;
 
 use image::imageops::FilterType;
-use image::{DynamicImage, GrayImage, ImageBuffer, ImageDecoder, ImageReader, Luma, RgbImage};
-use ndarray::{Array2, Array4, ArrayViewD, Axis, Ix2};
+use image::metadata::Orientation;
+use image::{
+    DynamicImage, GrayImage, ImageBuffer, ImageDecoder, ImageFormat, ImageReader, Luma, Rgb,
+    RgbImage,
+};
+use ndarray::{Array2, Array4, ArrayViewD, Axis, Ix2, s};
 #[cfg(feature = "backend-rten")]
 use ndarray::{ArrayD, IxDyn};
 #[cfg(feature = "backend-ort")]
+use ort::ep::ExecutionProviderDispatch;
+#[cfg(feature = "backend-ort")]
 use ort::session::Session;
 #[cfg(feature = "backend-ort")]
-use ort::session::builder::GraphOptimizationLevel;
+use ort::session::builder::{GraphOptimizationLevel, SessionBuilder};
 #[cfg(feature = "backend-ort")]
 use ort::value::Tensor;
 
+#[cfg(feature = "backend-ort")]
+use crate::config::ExecutionProvider;
 #[cfg(any(feature = "backend-ort", feature = "backend-rten"))]
 use crate::config::InferenceBackend;
-use crate::config::InferenceSettings;
+use crate::config::{
+    InferenceSettings, MatteActivation, MatteCombine, ModelInputLayout, Normalization,
+    OutputSelector, PreprocessMode, TileOptions,
+};
 use crate::error::{OutlineError, OutlineResult};
 use crate::mask::array_to_gray_image;
+use crate::observer::{PipelineObserver, PipelineStage, StageTimer};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ChannelLayout {
@@ -29,6 +45,24 @@ pub enum ChannelLayout {
     Nhwc,
 }
 
+impl fmt::Display for ChannelLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Nchw => "NCHW",
+            Self::Nhwc => "NHWC",
+        })
+    }
+}
+
+impl From<ModelInputLayout> for ChannelLayout {
+    fn from(value: ModelInputLayout) -> Self {
+        match value {
+            ModelInputLayout::Nchw => Self::Nchw,
+            ModelInputLayout::Nhwc => Self::Nhwc,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ModelInputSpec {
     pub height: usize,
@@ -36,6 +70,48 @@ pub struct ModelInputSpec {
     pub layout: ChannelLayout,
 }
 
+/// Formats as `HEIGHTxWIDTH LAYOUT`, e.g. `320x320 NCHW`.
+impl fmt::Display for ModelInputSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{} {}", self.height, self.width, self.layout)
+    }
+}
+
+/// Name and shape of one model output tensor, as listed by
+/// [`Outline::model_outputs`](crate::Outline::model_outputs).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelOutputInfo {
+    /// Output name in the model graph.
+    pub name: String,
+    /// Dimensions, with `None` for dynamic or symbolic ones.
+    ///
+    /// `None` when the output is not a tensor or the model does not declare its shape.
+    pub shape: Option<Vec<Option<usize>>>,
+}
+
+/// Formats as `NAME [1, 1, 320, 320]`, with `?` for dynamic dimensions and `[?]` for an
+/// unknown shape.
+impl fmt::Display for ModelOutputInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [", self.name)?;
+        match &self.shape {
+            Some(dims) => {
+                for (index, dim) in dims.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(", ")?;
+                    }
+                    match dim {
+                        Some(size) => write!(f, "{size}")?,
+                        None => f.write_str("?")?,
+                    }
+                }
+            }
+            None => f.write_str("?")?,
+        }
+        f.write_str("]")
+    }
+}
+
 pub const DEFAULT_MODEL_INPUT_SPEC: ModelInputSpec = ModelInputSpec {
     height: 320,
     width: 320,
@@ -85,15 +161,37 @@ impl BackendSession {
         }
     }
 
-    fn run_model(&self, input_array: Array4<f32>) -> OutlineResult<Array2<f32>> {
+    fn execution_provider_fallback(&self) -> Option<&str> {
+        match self {
+            #[cfg(feature = "backend-ort")]
+            Self::Ort(session) => session.execution_provider_fallback.as_deref(),
+            #[cfg(feature = "backend-rten")]
+            Self::Rten(_) => None,
+            #[cfg(not(any(feature = "backend-ort", feature = "backend-rten")))]
+            _ => unreachable!("at least one inference backend feature must be enabled"),
+        }
+    }
+
+    fn outputs(&self) -> &[ModelOutputInfo] {
+        match self {
+            #[cfg(feature = "backend-ort")]
+            Self::Ort(session) => &session.outputs,
+            #[cfg(feature = "backend-rten")]
+            Self::Rten(session) => &session.outputs,
+            #[cfg(not(any(feature = "backend-ort", feature = "backend-rten")))]
+            _ => unreachable!("at least one inference backend feature must be enabled"),
+        }
+    }
+
+    fn run_model(&self, input_array: Array4<f32>, output: usize) -> OutlineResult<Array2<f32>> {
         #[cfg(not(any(feature = "backend-ort", feature = "backend-rten")))]
-        let _ = &input_array;
+        let _ = (&input_array, output);
 
         match self {
             #[cfg(feature = "backend-ort")]
-            Self::Ort(session) => session.run_model(input_array),
+            Self::Ort(session) => session.run_model(input_array, output),
             #[cfg(feature = "backend-rten")]
-            Self::Rten(session) => session.run_model(input_array),
+            Self::Rten(session) => session.run_model(input_array, output),
             #[cfg(not(any(feature = "backend-ort", feature = "backend-rten")))]
             _ => unreachable!("at least one inference backend feature must be enabled"),
         }
@@ -103,43 +201,369 @@ impl BackendSession {
 impl CachedInferenceSession {
     /// Create a cached inference session.
     pub fn new(settings: &InferenceSettings) -> OutlineResult<Self> {
+        settings.validate_thread_counts()?;
         Ok(Self {
             backend: BackendSession::new(settings)?,
         })
     }
 
-    /// Run the full matte inference pipeline using this cached session.
-    pub fn run_matte_pipeline(
-        &self,
-        settings: &InferenceSettings,
-        image_path: &Path,
-    ) -> OutlineResult<(RgbImage, GrayImage)> {
-        let rgb_input = load_rgb_with_orientation(image_path)?;
-        self.run_matte_pipeline_on_rgb(settings, rgb_input)
+    /// The input spec used for inference: the detected spec with any overrides applied.
+    pub fn input_spec(&self, settings: &InferenceSettings) -> ModelInputSpec {
+        apply_input_spec_overrides(self.backend.input_spec(), settings)
+    }
+
+    /// The model's output tensors in graph order.
+    ///
+    /// The matte is read from the one selected by [`InferenceSettings::output`].
+    pub fn outputs(&self) -> &[ModelOutputInfo] {
+        self.backend.outputs()
+    }
+
+    /// Why the configured execution provider failed to initialize, if the session fell back to
+    /// the CPU.
+    pub fn execution_provider_fallback(&self) -> Option<&str> {
+        self.backend.execution_provider_fallback()
     }
 
     /// Run the full matte inference pipeline using an in-memory RGB image.
+    ///
+    /// Returns the input image, the float matte resized to the image, and the matte before that
+    /// resize: at model resolution, or stitched at the inferred image size when tiled. `observer`
+    /// is notified as the preprocess, infer, and postprocess stages
+    /// finish.
     pub fn run_matte_pipeline_on_rgb(
         &self,
         settings: &InferenceSettings,
         rgb_input: RgbImage,
-    ) -> OutlineResult<(RgbImage, GrayImage)> {
+        observer: &mut dyn PipelineObserver,
+    ) -> OutlineResult<(RgbImage, Array2<f32>, GrayImage)> {
+        let mut timer = StageTimer::new(observer);
         let orig_w = rgb_input.width();
         let orig_h = rgb_input.height();
-        let mut input_spec = self.backend.input_spec();
-        if let Some(size) = settings.model_input_size() {
-            input_spec.width = size.width();
-            input_spec.height = size.height();
+        let input_spec = self.input_spec(settings);
+
+        let scaled = settings
+            .infer_scale()
+            .map(|scale| scale_for_inference(&rgb_input, scale, settings.input_resize_filter()));
+        let model_input = scaled.as_ref().unwrap_or(&rgb_input);
+        let matte_hw = match settings.tiling() {
+            Some(tiling)
+                if model_input.width() > tiling.tile_size
+                    || model_input.height() > tiling.tile_size =>
+            {
+                run_tiled(model_input, tiling, |tile| {
+                    let matte = self.infer_model_matte(settings, input_spec, tile, &mut timer)?;
+                    resize_matte(
+                        &matte,
+                        tile.width(),
+                        tile.height(),
+                        settings.output_resize_filter(),
+                    )
+                })?
+            }
+            _ => self.infer_model_matte(settings, input_spec, model_input, &mut timer)?,
+        };
+        let mut matte_orig =
+            resize_matte(&matte_hw, orig_w, orig_h, settings.output_resize_filter())?;
+        if settings.matte_activation() != MatteActivation::None {
+            // Filters such as Lanczos overshoot, so clamp again after resizing.
+            matte_orig.mapv_inplace(|value| value.clamp(0.0, 1.0));
+        }
+        let model_matte = array_to_gray_image(&matte_hw);
+        timer.finish(PipelineStage::Postprocess);
+
+        Ok((rgb_input, matte_orig, model_matte))
+    }
+
+    /// Preprocess `image`, run the model, and crop any padding from the matte.
+    ///
+    /// Returns the matte at model output resolution.
+    fn infer_model_matte(
+        &self,
+        settings: &InferenceSettings,
+        input_spec: ModelInputSpec,
+        image: &RgbImage,
+        timer: &mut StageTimer<'_>,
+    ) -> OutlineResult<Array2<f32>> {
+        let output = resolve_output(self.outputs(), settings.output())?;
+        let letterboxed = match settings.preprocess_mode() {
+            PreprocessMode::Stretch => None,
+            PreprocessMode::Letterbox { fill } => {
+                let fill = fill.unwrap_or_else(|| mean_color(settings.normalization()));
+                Some(letterbox_image(
+                    image,
+                    input_spec,
+                    settings.input_resize_filter(),
+                    fill,
+                )?)
+            }
+        };
+        let mut input_array = preprocess_image_to_array(
+            letterboxed.as_ref().map_or(image, |(canvas, _)| canvas),
+            settings.input_resize_filter(),
+            input_spec,
+            settings.normalization(),
+        )?;
+        if let Some(multiple) = settings.pad_to_multiple() {
+            input_array = pad_input_array(input_array, input_spec.layout, multiple as usize);
+        }
+        let padded_hw = input_hw(&input_array, input_spec.layout);
+        timer.finish(PipelineStage::Preprocess);
+
+        let mut matte_hw = self
+            .backend
+            .run_model(input_array, output)
+            .map_err(|source| OutlineError::ModelRun {
+                input_spec: input_spec.to_string(),
+                source: Box::new(source),
+            })?;
+        timer.finish(PipelineStage::Infer);
+
+        apply_matte_activation(&mut matte_hw, settings.matte_activation());
+        if settings.pad_to_multiple().is_some() {
+            matte_hw =
+                crop_padded_matte(matte_hw, (input_spec.height, input_spec.width), padded_hw);
         }
+        if let Some((_, region)) = letterboxed {
+            matte_hw =
+                crop_letterbox_matte(matte_hw, region, (input_spec.height, input_spec.width));
+        }
+        Ok(matte_hw)
+    }
+}
+
+/// Map raw model output values to matte values in place.
+fn apply_matte_activation(matte: &mut Array2<f32>, activation: MatteActivation) {
+    match activation {
+        MatteActivation::None => {}
+        MatteActivation::Sigmoid => matte.mapv_inplace(|value| 1.0 / (1.0 + (-value).exp())),
+        MatteActivation::Clamp => matte.mapv_inplace(|value| value.clamp(0.0, 1.0)),
+    }
+}
+
+/// Run `run_tile` on overlapping tiles of `image` and blend the tile mattes into one.
+///
+/// `run_tile` returns the matte of a tile at the tile's resolution. Where tiles overlap, each
+/// tile's weight ramps linearly from its inner edge, so neighboring mattes cross-fade instead of
+/// meeting at a visible seam.
+fn run_tiled(
+    image: &RgbImage,
+    tiling: TileOptions,
+    mut run_tile: impl FnMut(&RgbImage) -> OutlineResult<Array2<f32>>,
+) -> OutlineResult<Array2<f32>> {
+    let (width, height) = image.dimensions();
+    let mut sum = Array2::<f32>::zeros((height as usize, width as usize));
+    let mut weight_sum = Array2::<f32>::zeros((height as usize, width as usize));
+    let xs = tile_starts(width, tiling);
+    let ys = tile_starts(height, tiling);
 
-        let input_array =
-            preprocess_image_to_array(&rgb_input, settings.input_resize_filter(), input_spec)?;
-        let matte_hw = self.backend.run_model(input_array)?;
-        let matte_orig = resize_matte(&matte_hw, orig_w, orig_h, settings.output_resize_filter())?;
-        let raw_matte = array_to_gray_image(&matte_orig);
+    for &y in &ys {
+        for &x in &xs {
+            let tile_w = tiling.tile_size.min(width);
+            let tile_h = tiling.tile_size.min(height);
+            let tile = image::imageops::crop_imm(image, x, y, tile_w, tile_h).to_image();
+            let matte = run_tile(&tile)?;
+            let weights_x = tile_weights(x, tile_w, width, tiling.overlap);
+            let weights_y = tile_weights(y, tile_h, height, tiling.overlap);
+            for ((ty, tx), &value) in matte.indexed_iter() {
+                let weight = weights_y[ty] * weights_x[tx];
+                let index = [y as usize + ty, x as usize + tx];
+                sum[index] += value * weight;
+                weight_sum[index] += weight;
+            }
+        }
+    }
+    Ok(sum / weight_sum)
+}
 
-        Ok((rgb_input, raw_matte))
+/// Tile start offsets covering `len`, stepping by `tile_size - overlap` and ending flush with
+/// the far edge.
+fn tile_starts(len: u32, tiling: TileOptions) -> Vec<u32> {
+    if len <= tiling.tile_size {
+        return vec![0];
     }
+    let last = len - tiling.tile_size;
+    let stride = (tiling.tile_size - tiling.overlap) as usize;
+    let mut starts: Vec<u32> = (0..last).step_by(stride).collect();
+    starts.push(last);
+    starts
+}
+
+/// Blend weights along one axis of a tile starting at `start`.
+///
+/// Edges shared with a neighboring tile ramp up over `overlap` pixels; image edges keep full
+/// weight.
+fn tile_weights(start: u32, size: u32, len: u32, overlap: u32) -> Vec<f32> {
+    let ramp = (overlap + 1) as f32;
+    (0..size)
+        .map(|i| {
+            let mut weight = 1.0f32;
+            if start > 0 {
+                weight = weight.min((i + 1) as f32 / ramp);
+            }
+            if start + size < len {
+                weight = weight.min((size - i) as f32 / ramp);
+            }
+            weight
+        })
+        .collect()
+}
+
+/// Where a letterboxed image sits inside the model input, in model input pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LetterboxRegion {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+/// The normalization mean as an RGB color, which normalizes to zero.
+fn mean_color(normalization: Normalization) -> [u8; 3] {
+    let (mean, _) = normalization.mean_std();
+    mean.map(|value| (value * 255.0).round().clamp(0.0, 255.0) as u8)
+}
+
+/// Scale the image to fit inside the model input keeping its aspect ratio, centered on a
+/// `fill` canvas of the model input size.
+fn letterbox_image(
+    rgb: &RgbImage,
+    spec: ModelInputSpec,
+    filter: FilterType,
+    fill: [u8; 3],
+) -> OutlineResult<(RgbImage, LetterboxRegion)> {
+    let (target_w, target_h) = model_input_dimensions(spec)?;
+    let region = letterbox_region(rgb.dimensions(), (target_w, target_h));
+    let resized = image::imageops::resize(rgb, region.width, region.height, filter);
+    let mut canvas = RgbImage::from_pixel(target_w, target_h, Rgb(fill));
+    image::imageops::replace(&mut canvas, &resized, region.x.into(), region.y.into());
+    Ok((canvas, region))
+}
+
+/// The centered region an image of `width`×`height` fills when letterboxed into the target.
+fn letterbox_region(
+    (width, height): (u32, u32),
+    (target_w, target_h): (u32, u32),
+) -> LetterboxRegion {
+    let scale = (target_w as f64 / width as f64).min(target_h as f64 / height as f64);
+    let fit = |side: u32, target: u32| ((side as f64 * scale).round() as u32).clamp(1, target);
+    let (fit_w, fit_h) = (fit(width, target_w), fit(height, target_h));
+    LetterboxRegion {
+        x: (target_w - fit_w) / 2,
+        y: (target_h - fit_h) / 2,
+        width: fit_w,
+        height: fit_h,
+    }
+}
+
+/// Crop the matte to the letterboxed image, dropping the padding.
+///
+/// Scales the region when the model output resolution differs from its input resolution.
+fn crop_letterbox_matte(
+    matte: Array2<f32>,
+    region: LetterboxRegion,
+    (input_h, input_w): (usize, usize),
+) -> Array2<f32> {
+    let (out_h, out_w) = matte.dim();
+    let scale = |value: u32, out: usize, input: usize| value as usize * out / input;
+    let y0 = scale(region.y, out_h, input_h).min(out_h - 1);
+    let x0 = scale(region.x, out_w, input_w).min(out_w - 1);
+    let y1 = scale(region.y + region.height, out_h, input_h).clamp(y0 + 1, out_h);
+    let x1 = scale(region.x + region.width, out_w, input_w).clamp(x0 + 1, out_w);
+    if (y0, x0, y1, x1) == (0, 0, out_h, out_w) {
+        return matte;
+    }
+    matte.slice(s![y0..y1, x0..x1]).to_owned()
+}
+
+/// Position of the output `selector` picks among the model's `outputs`.
+fn resolve_output(outputs: &[ModelOutputInfo], selector: &OutputSelector) -> OutlineResult<usize> {
+    let index = match selector {
+        OutputSelector::Index(index) => Some(*index).filter(|&index| index < outputs.len()),
+        OutputSelector::Name(name) => outputs.iter().position(|output| &output.name == name),
+    };
+    index.ok_or_else(|| OutlineError::UnknownModelOutput {
+        output: selector.to_string(),
+        available: outputs.iter().map(|output| output.name.clone()).collect(),
+    })
+}
+
+/// Resize the image by `scale` for inference, keeping at least one pixel on each side.
+fn scale_for_inference(rgb: &RgbImage, scale: f32, filter: FilterType) -> RgbImage {
+    let scaled = |side: u32| ((side as f32 * scale).round() as u32).max(1);
+    image::imageops::resize(rgb, scaled(rgb.width()), scaled(rgb.height()), filter)
+}
+
+/// Reject images with more than `max_pixels` pixels before they are decoded or processed.
+///
+/// `None` disables the limit. This is the check behind [`crate::Outline::with_max_pixels`];
+/// callers that decode images themselves can apply the same limit to a decoder's dimensions.
+///
+/// # Errors
+///
+/// Returns [`OutlineError::ImageTooLarge`] when `width * height` exceeds the limit.
+pub fn check_pixel_limit(
+    (width, height): (u32, u32),
+    max_pixels: Option<u64>,
+) -> OutlineResult<()> {
+    let pixels = u64::from(width) * u64::from(height);
+    match max_pixels {
+        Some(limit) if pixels > limit => Err(OutlineError::ImageTooLarge { pixels, limit }),
+        _ => Ok(()),
+    }
+}
+
+/// Fraction of fully opaque or fully transparent pixels above which a matte is degenerate.
+const DEGENERATE_MATTE_FRACTION: f32 = 0.99;
+
+/// Reject a matte that is almost entirely opaque or almost entirely transparent.
+///
+/// `input_spec` is the model input the matte was produced with, reported in the error.
+pub(crate) fn check_matte_sanity(
+    matte: &GrayImage,
+    input_spec: ModelInputSpec,
+) -> OutlineResult<()> {
+    let total = matte.as_raw().len();
+    if total == 0 {
+        return Ok(());
+    }
+
+    let (opaque, transparent) = matte.as_raw().iter().fold(
+        (0usize, 0usize),
+        |(opaque, transparent), &value| match value {
+            255 => (opaque + 1, transparent),
+            0 => (opaque, transparent + 1),
+            _ => (opaque, transparent),
+        },
+    );
+    let opaque_fraction = opaque as f32 / total as f32;
+    let transparent_fraction = transparent as f32 / total as f32;
+
+    if opaque_fraction > DEGENERATE_MATTE_FRACTION
+        || transparent_fraction > DEGENERATE_MATTE_FRACTION
+    {
+        return Err(OutlineError::DegenerateMatte {
+            opaque_fraction,
+            transparent_fraction,
+            input_spec: input_spec.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Replace the detected input size and layout with any overrides from the settings.
+fn apply_input_spec_overrides(
+    mut spec: ModelInputSpec,
+    settings: &InferenceSettings,
+) -> ModelInputSpec {
+    if let Some(size) = settings.model_input_size() {
+        spec.width = size.width();
+        spec.height = size.height();
+    }
+    if let Some(layout) = settings.model_input_layout() {
+        spec.layout = layout.into();
+    }
+    spec
 }
 
 /// ONNX Runtime-backed model session.
@@ -148,23 +572,47 @@ impl CachedInferenceSession {
 struct OrtInferenceSession {
     session: Mutex<Session>,
     input_spec: ModelInputSpec,
+    outputs: Vec<ModelOutputInfo>,
+    /// Registration error of the requested execution provider, if it fell back to the CPU.
+    execution_provider_fallback: Option<String>,
 }
 
 #[cfg(feature = "backend-ort")]
 impl OrtInferenceSession {
     /// Create an ONNX Runtime-backed session.
     fn new(settings: &InferenceSettings) -> OutlineResult<Self> {
-        let mut builder =
-            Session::builder()?.with_optimization_level(GraphOptimizationLevel::Level3)?;
-        if let Some(n) = settings.intra_threads() {
-            builder = builder.with_intra_threads(n)?;
-        }
-        let session = builder.commit_from_file(settings.model_path())?;
+        let mut execution_provider_fallback = None;
+        let session = match settings.optimized_model_cache() {
+            Some(cache_dir) => {
+                commit_with_optimized_cache(settings, cache_dir, &mut execution_provider_fallback)?
+            }
+            None => ort_session_builder(
+                settings,
+                GraphOptimizationLevel::Level3,
+                &mut execution_provider_fallback,
+            )?
+            .commit_from_file(settings.model_path())?,
+        };
         let input_spec = determine_model_input_spec(&session);
+        let outputs = session
+            .outputs()
+            .iter()
+            .map(|output| ModelOutputInfo {
+                name: output.name().to_string(),
+                shape: output.dtype().tensor_shape().map(|shape| {
+                    shape
+                        .iter()
+                        .map(|&dim| positive_dim_to_usize(dim))
+                        .collect()
+                }),
+            })
+            .collect();
 
         Ok(Self {
             session: Mutex::new(session),
             input_spec,
+            outputs,
+            execution_provider_fallback,
         })
     }
 
@@ -173,24 +621,141 @@ impl OrtInferenceSession {
     }
 
     /// Execute the model for one preprocessed input array while holding the session lock.
-    fn run_model(&self, input_array: Array4<f32>) -> OutlineResult<Array2<f32>> {
+    fn run_model(&self, input_array: Array4<f32>, output: usize) -> OutlineResult<Array2<f32>> {
         let mut session = self
             .session
             .lock()
             .map_err(|_| io::Error::other("cached inference session mutex poisoned"))?;
         let input_tensor = Tensor::from_array(input_array)?;
         let outputs = session.run(ort::inputs![input_tensor])?;
-        let matte = outputs[0].try_extract_array::<f32>()?;
+        let matte = outputs[output].try_extract_array::<f32>()?;
         extract_matte_hw(matte)
     }
 }
 
+/// Create a session builder with the configured optimization, threads, and execution provider.
+///
+/// If the execution provider fails to register, the builder keeps running on the CPU and the
+/// error is stored in `ep_fallback`.
+#[cfg(feature = "backend-ort")]
+fn ort_session_builder(
+    settings: &InferenceSettings,
+    level: GraphOptimizationLevel,
+    ep_fallback: &mut Option<String>,
+) -> OutlineResult<SessionBuilder> {
+    let mut builder = Session::builder()?.with_optimization_level(level)?;
+    if let Some(n) = settings.intra_threads() {
+        builder = builder.with_intra_threads(n)?;
+    }
+    if let Some(n) = settings.inter_threads() {
+        builder = builder.with_inter_threads(n)?;
+    }
+    if let Some(provider) = ort_execution_provider(settings.execution_provider()) {
+        builder = match builder.with_execution_providers([provider.error_on_failure()]) {
+            Ok(builder) => builder,
+            Err(err) => {
+                *ep_fallback = Some(err.to_string());
+                err.recover()
+            }
+        };
+    }
+    Ok(builder)
+}
+
+/// The ORT execution provider for `provider`, or `None` for ORT's default CPU provider.
+#[cfg(feature = "backend-ort")]
+fn ort_execution_provider(provider: ExecutionProvider) -> Option<ExecutionProviderDispatch> {
+    match provider {
+        ExecutionProvider::Cpu => None,
+        #[cfg(feature = "cuda")]
+        ExecutionProvider::Cuda { device_id } => {
+            Some(ort::ep::CUDA::default().with_device_id(device_id).build())
+        }
+        #[cfg(feature = "coreml")]
+        ExecutionProvider::CoreML => Some(ort::ep::CoreML::default().build()),
+    }
+}
+
+/// Load the optimized model from `cache_dir`, or optimize the source model and save it there.
+#[cfg(feature = "backend-ort")]
+fn commit_with_optimized_cache(
+    settings: &InferenceSettings,
+    cache_dir: &Path,
+    ep_fallback: &mut Option<String>,
+) -> OutlineResult<Session> {
+    let mut builder = ort_session_builder(settings, GraphOptimizationLevel::Disable, ep_fallback)?;
+    // The optimized graph is specific to the provider it was optimized for, so a provider that
+    // fell back to the CPU is keyed as the CPU.
+    let provider = if ep_fallback.is_some() {
+        ExecutionProvider::Cpu
+    } else {
+        settings.execution_provider()
+    };
+    let key = format!(
+        "{provider:?}|{OPTIMIZED_CACHE_LEVEL:?}|{}|{}",
+        ort::info(),
+        std::env::consts::ARCH
+    );
+    let cached = optimized_model_cache_path(settings.model_path(), cache_dir, &key)?;
+    if cached.is_file() {
+        // The cached graph is already optimized; an unreadable file falls through to
+        // re-optimizing the source model below.
+        if let Ok(session) = builder.commit_from_file(&cached) {
+            return Ok(session);
+        }
+    }
+
+    // ORT writes the optimized model while committing, so write it under a temporary name and
+    // rename it into place only once it is complete. Concurrent runs each write their own file.
+    std::fs::create_dir_all(cache_dir)?;
+    let partial = cached.with_extension(format!("ort.{}.tmp", std::process::id()));
+    let session = ort_session_builder(settings, OPTIMIZED_CACHE_LEVEL, ep_fallback)?
+        .with_optimized_model_path(&partial)?
+        .commit_from_file(settings.model_path());
+    match session {
+        Ok(session) => {
+            if std::fs::rename(&partial, &cached).is_err() {
+                let _ = std::fs::remove_file(&partial);
+            }
+            Ok(session)
+        }
+        Err(err) => {
+            let _ = std::fs::remove_file(&partial);
+            Err(err.into())
+        }
+    }
+}
+
+/// Optimization level of the graphs saved by [`commit_with_optimized_cache`].
+#[cfg(feature = "backend-ort")]
+const OPTIMIZED_CACHE_LEVEL: GraphOptimizationLevel = GraphOptimizationLevel::Level3;
+
+/// Cache entry for the optimized form of `model_path` under the session settings in `key`.
+///
+/// The entry is named by a SHA-256 of the model's contents together with `key`, so replacing the
+/// model or changing the execution provider, ORT build, or optimization level produces a fresh
+/// entry, while copies of one model share it wherever they live.
+#[cfg(feature = "backend-ort")]
+fn optimized_model_cache_path(
+    model_path: &Path,
+    cache_dir: &Path,
+    key: &str,
+) -> OutlineResult<PathBuf> {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut std::fs::File::open(model_path)?, &mut hasher)?;
+    hasher.update(key.as_bytes());
+    Ok(cache_dir.join(format!("{:x}.ort", hasher.finalize())))
+}
+
 /// RTen-backed model session.
 #[cfg(feature = "backend-rten")]
 #[derive(Debug)]
 struct RtenInferenceSession {
     model: rten::Model,
     input_spec: ModelInputSpec,
+    outputs: Vec<ModelOutputInfo>,
 }
 
 #[cfg(feature = "backend-rten")]
@@ -198,16 +763,42 @@ impl RtenInferenceSession {
     fn new(settings: &InferenceSettings) -> OutlineResult<Self> {
         let model = rten::Model::load_file(settings.model_path())?;
         let input_spec = determine_rten_model_input_spec(&model);
+        let outputs = model
+            .output_ids()
+            .iter()
+            .enumerate()
+            .map(|(index, &id)| {
+                let info = model.node_info(id);
+                ModelOutputInfo {
+                    name: info
+                        .as_ref()
+                        .and_then(|info| info.name())
+                        .map_or_else(|| format!("output{index}"), str::to_string),
+                    shape: info.and_then(|info| info.shape()).map(|dims| {
+                        dims.into_iter()
+                            .map(|dim| match dim {
+                                rten::Dimension::Fixed(value) => Some(value),
+                                rten::Dimension::Symbolic(_) => None,
+                            })
+                            .collect()
+                    }),
+                }
+            })
+            .collect();
 
-        Ok(Self { model, input_spec })
+        Ok(Self {
+            model,
+            input_spec,
+            outputs,
+        })
     }
 
     fn input_spec(&self) -> ModelInputSpec {
         self.input_spec
     }
 
-    /// Execute the model for one preprocessed input array.
-    fn run_model(&self, input_array: Array4<f32>) -> OutlineResult<Array2<f32>> {
+    /// Execute the model for one preprocessed input array, returning the selected output.
+    fn run_model(&self, input_array: Array4<f32>, output: usize) -> OutlineResult<Array2<f32>> {
         let shape = input_array.shape().to_vec();
         let (data, offset) = input_array.into_raw_vec_and_offset();
         if offset != Some(0) {
@@ -215,8 +806,16 @@ impl RtenInferenceSession {
         }
 
         let input = rten::Value::from_shape(shape, data).map_err(io::Error::other)?;
-        let output = self.model.run_one(input.into(), None)?;
-        let matte = rten_value_to_array(output)?;
+        let input_id = *self
+            .model
+            .input_ids()
+            .first()
+            .ok_or_else(|| io::Error::other("model has no inputs"))?;
+        let output_id = self.model.output_ids()[output];
+        let mut values = self
+            .model
+            .run(vec![(input_id, input.into())], &[output_id], None)?;
+        let matte = rten_value_to_array(values.remove(0))?;
         extract_matte_hw(matte.view())
     }
 }
@@ -331,33 +930,63 @@ fn positive_dim_to_usize(dim: i64) -> Option<usize> {
     }
 }
 
+/// Combine a foreground matte with the matte predicted by a background model.
+pub(crate) fn combine_mattes(
+    foreground: &Array2<f32>,
+    background: &Array2<f32>,
+    combine: MatteCombine,
+) -> Array2<f32> {
+    ndarray::Zip::from(foreground)
+        .and(background)
+        .map_collect(|&fg, &bg| {
+            let not_bg = 1.0 - bg;
+            match combine {
+                MatteCombine::Multiply => fg * not_bg,
+                MatteCombine::Min => fg.min(not_bg),
+                MatteCombine::Average => (fg + not_bg) / 2.0,
+            }
+        })
+}
+
 /// Load an RGB image from the given path, applying orientation from EXIF data.
-fn load_rgb_with_orientation(path: &Path) -> OutlineResult<RgbImage> {
+///
+/// Also returns the applied orientation so callers can map results back to the stored layout.
+pub(crate) fn load_rgb_with_orientation(
+    path: &Path,
+    max_pixels: Option<u64>,
+) -> OutlineResult<(RgbImage, Orientation)> {
     let mut decoder = ImageReader::open(path)?.into_decoder()?;
+    check_pixel_limit(decoder.dimensions(), max_pixels)?;
     let orientation = decoder.orientation()?;
     let mut image = DynamicImage::from_decoder(decoder)?;
     image.apply_orientation(orientation);
-    Ok(image.into_rgb8())
+    Ok((image.into_rgb8(), orientation))
 }
 
 /// Decode an RGB image from encoded bytes, applying orientation from EXIF data when present.
-pub(crate) fn load_rgb_from_memory_with_orientation(bytes: &[u8]) -> OutlineResult<RgbImage> {
-    let cursor = Cursor::new(bytes);
-    let mut decoder = ImageReader::new(cursor)
-        .with_guessed_format()?
-        .into_decoder()?;
+///
+/// The format is sniffed from the content unless `format` is given. Also returns the applied
+/// orientation so callers can map results back to the stored layout.
+pub(crate) fn load_rgb_from_memory_with_orientation(
+    bytes: &[u8],
+    format: Option<ImageFormat>,
+    max_pixels: Option<u64>,
+) -> OutlineResult<(RgbImage, Orientation)> {
+    let mut reader = ImageReader::new(Cursor::new(bytes));
+    match format {
+        Some(format) => reader.set_format(format),
+        None => reader = reader.with_guessed_format()?,
+    }
+    let mut decoder = reader.into_decoder()?;
+    check_pixel_limit(decoder.dimensions(), max_pixels)?;
     let orientation = decoder.orientation()?;
     let mut image = DynamicImage::from_decoder(decoder)?;
     image.apply_orientation(orientation);
-    Ok(image.into_rgb8())
+    Ok((image.into_rgb8(), orientation))
 }
 
-/// Resize and normalizes the RGB image into an array that matches the model spec.
-pub fn preprocess_image_to_array(
-    rgb: &RgbImage,
-    filter: FilterType,
-    spec: ModelInputSpec,
-) -> OutlineResult<Array4<f32>> {
+/// The model input width and height as image dimensions.
+fn model_input_dimensions(spec: ModelInputSpec) -> OutlineResult<(u32, u32)> {
     let target_w = u32::try_from(spec.width).map_err(|_| {
         io::Error::new(
             io::ErrorKind::InvalidInput,
@@ -377,12 +1006,25 @@ pub fn preprocess_image_to_array(
         )
         .into());
     }
+    Ok((target_w, target_h))
+}
 
-    let resized = image::imageops::resize(rgb, target_w, target_h, filter);
+/// Resize and normalizes the RGB image into an array that matches the model spec.
+pub fn preprocess_image_to_array(
+    rgb: &RgbImage,
+    filter: FilterType,
+    spec: ModelInputSpec,
+    normalization: Normalization,
+) -> OutlineResult<Array4<f32>> {
+    let (target_w, target_h) = model_input_dimensions(spec)?;
+    let resized = if rgb.dimensions() == (target_w, target_h) {
+        Cow::Borrowed(rgb)
+    } else {
+        Cow::Owned(image::imageops::resize(rgb, target_w, target_h, filter))
+    };
     let w = resized.width() as usize;
     let h = resized.height() as usize;
-    let mean = [0.485f32, 0.456, 0.406];
-    let std = [0.229f32, 0.224, 0.225];
+    let (mean, std) = normalization.mean_std();
     let inv255 = 1.0 / 255.0;
 
     let (shape, data) = match spec.layout {
@@ -418,6 +1060,54 @@ pub fn preprocess_image_to_array(
     Ok(Array4::from_shape_vec(shape, data)?)
 }
 
+fn input_hw(array: &Array4<f32>, layout: ChannelLayout) -> (usize, usize) {
+    let shape = array.shape();
+    match layout {
+        ChannelLayout::Nchw => (shape[2], shape[3]),
+        ChannelLayout::Nhwc => (shape[1], shape[2]),
+    }
+}
+
+/// Zero-pad the bottom and right of a preprocessed input up to a multiple of `multiple`.
+fn pad_input_array(array: Array4<f32>, layout: ChannelLayout, multiple: usize) -> Array4<f32> {
+    let (h, w) = input_hw(&array, layout);
+    let padded_h = h.div_ceil(multiple) * multiple;
+    let padded_w = w.div_ceil(multiple) * multiple;
+    if (padded_h, padded_w) == (h, w) {
+        return array;
+    }
+
+    let shape = array.dim();
+    let padded_shape = match layout {
+        ChannelLayout::Nchw => (shape.0, shape.1, padded_h, padded_w),
+        ChannelLayout::Nhwc => (shape.0, padded_h, padded_w, shape.3),
+    };
+    let mut padded = Array4::<f32>::zeros(padded_shape);
+    let region = match layout {
+        ChannelLayout::Nchw => s![.., .., ..h, ..w],
+        ChannelLayout::Nhwc => s![.., ..h, ..w, ..],
+    };
+    padded.slice_mut(region).assign(&array);
+    padded
+}
+
+/// Crop the matte region that corresponds to the unpadded input.
+///
+/// Scales the crop when the model output resolution differs from its input resolution.
+fn crop_padded_matte(
+    matte: Array2<f32>,
+    (h, w): (usize, usize),
+    (padded_h, padded_w): (usize, usize),
+) -> Array2<f32> {
+    let (out_h, out_w) = matte.dim();
+    let crop_h = (h * out_h).div_ceil(padded_h).clamp(1, out_h);
+    let crop_w = (w * out_w).div_ceil(padded_w).clamp(1, out_w);
+    if (crop_h, crop_w) == (out_h, out_w) {
+        return matte;
+    }
+    matte.slice(s![..crop_h, ..crop_w]).to_owned()
+}
+
 /// Remove singleton axes to get the raw H×W matte from the model output.
 pub fn extract_matte_hw(matte: ArrayViewD<f32>) -> OutlineResult<Array2<f32>> {
     let original_shape: Vec<usize> = matte.shape().to_vec();
@@ -465,7 +1155,7 @@ pub fn resize_matte(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use image::{ImageFormat, Rgb, RgbImage, Rgba, RgbaImage};
+    use image::{Rgb, RgbImage, Rgba, RgbaImage};
 
     #[test]
     fn load_rgb_from_memory_decodes_png() {
@@ -476,12 +1166,33 @@ mod tests {
             .write_to(&mut encoded, ImageFormat::Png)
             .expect("png encoding should succeed");
 
-        let decoded = load_rgb_from_memory_with_orientation(encoded.get_ref())
-            .expect("memory decode should succeed");
+        let (decoded, orientation) =
+            load_rgb_from_memory_with_orientation(encoded.get_ref(), None, None)
+                .expect("memory decode should succeed");
+        assert_eq!(orientation, Orientation::NoTransforms);
         assert_eq!(decoded.dimensions(), (3, 2));
         assert_eq!(decoded.get_pixel(0, 0).0, [12, 34, 56]);
     }
 
+    #[test]
+    fn load_rgb_from_memory_honors_explicit_format() {
+        let rgb = RgbImage::from_pixel(3, 2, Rgb([12, 34, 56]));
+        let mut encoded = Cursor::new(Vec::new());
+        DynamicImage::ImageRgb8(rgb)
+            .write_to(&mut encoded, ImageFormat::Png)
+            .expect("png encoding should succeed");
+
+        let (decoded, _) =
+            load_rgb_from_memory_with_orientation(encoded.get_ref(), Some(ImageFormat::Png), None)
+                .expect("explicit png decode should succeed");
+        assert_eq!(decoded.dimensions(), (3, 2));
+
+        assert!(
+            load_rgb_from_memory_with_orientation(encoded.get_ref(), Some(ImageFormat::Jpeg), None)
+                .is_err()
+        );
+    }
+
     #[test]
     fn load_rgb_from_memory_discards_alpha() {
         let rgba = RgbaImage::from_pixel(4, 1, Rgba([10, 20, 30, 40]));
@@ -491,9 +1202,567 @@ mod tests {
             .write_to(&mut encoded, ImageFormat::Png)
             .expect("png encoding should succeed");
 
-        let decoded = load_rgb_from_memory_with_orientation(encoded.get_ref())
+        let (decoded, _) = load_rgb_from_memory_with_orientation(encoded.get_ref(), None, None)
             .expect("memory decode should succeed");
         assert_eq!(decoded.dimensions(), (4, 1));
         assert_eq!(decoded.get_pixel(0, 0).0, [10, 20, 30]);
     }
+
+    #[test]
+    fn load_rgb_from_memory_rejects_images_over_pixel_limit() {
+        let mut encoded = Cursor::new(Vec::new());
+        DynamicImage::ImageRgb8(RgbImage::new(4, 3))
+            .write_to(&mut encoded, ImageFormat::Png)
+            .expect("png encoding should succeed");
+
+        let err =
+            load_rgb_from_memory_with_orientation(encoded.get_ref(), None, Some(11)).unwrap_err();
+        assert!(matches!(
+            err,
+            OutlineError::ImageTooLarge {
+                pixels: 12,
+                limit: 11
+            }
+        ));
+        assert!(load_rgb_from_memory_with_orientation(encoded.get_ref(), None, Some(12)).is_ok());
+    }
+
+    mod pad_to_multiple {
+        use super::*;
+
+        #[test]
+        fn nchw_pads_bottom_and_right_with_zeros() {
+            let array = Array4::from_elem((1, 3, 5, 6), 1.0f32);
+            let padded = pad_input_array(array, ChannelLayout::Nchw, 4);
+
+            assert_eq!(padded.shape(), &[1, 3, 8, 8]);
+            assert_eq!(padded[[0, 2, 4, 5]], 1.0);
+            assert_eq!(padded[[0, 2, 5, 0]], 0.0);
+            assert_eq!(padded[[0, 0, 0, 6]], 0.0);
+        }
+
+        #[test]
+        fn nhwc_pads_spatial_axes_only() {
+            let array = Array4::from_elem((1, 5, 6, 3), 1.0f32);
+            let padded = pad_input_array(array, ChannelLayout::Nhwc, 4);
+
+            assert_eq!(padded.shape(), &[1, 8, 8, 3]);
+            assert_eq!(padded[[0, 4, 5, 2]], 1.0);
+            assert_eq!(padded[[0, 7, 7, 2]], 0.0);
+        }
+
+        #[test]
+        fn aligned_input_is_unchanged() {
+            let array = Array4::from_elem((1, 3, 8, 16), 0.5f32);
+            let padded = pad_input_array(array.clone(), ChannelLayout::Nchw, 8);
+            assert_eq!(padded, array);
+        }
+
+        #[test]
+        fn crop_matches_same_resolution_output() {
+            let matte = Array2::from_shape_fn((8, 8), |(y, x)| (y * 8 + x) as f32);
+            let cropped = crop_padded_matte(matte, (5, 6), (8, 8));
+
+            assert_eq!(cropped.dim(), (5, 6));
+            assert_eq!(cropped[[4, 5]], 37.0);
+        }
+
+        #[test]
+        fn crop_scales_with_downsampled_output() {
+            let matte = Array2::<f32>::zeros((4, 4));
+            let cropped = crop_padded_matte(matte, (5, 6), (8, 8));
+            assert_eq!(cropped.dim(), (3, 3));
+        }
+    }
+
+    mod matte_activation {
+        use super::*;
+
+        fn activated(values: &[f32], activation: MatteActivation) -> Vec<f32> {
+            let mut matte = Array2::from_shape_vec((1, values.len()), values.to_vec()).unwrap();
+            apply_matte_activation(&mut matte, activation);
+            matte.into_iter().collect()
+        }
+
+        #[test]
+        fn sigmoid_maps_logits_to_probabilities() {
+            let values = activated(&[0.0, 20.0, -20.0, 2.0], MatteActivation::Sigmoid);
+            assert_eq!(values[0], 0.5);
+            assert!(values[1] > 0.999_999);
+            assert!(values[2] < 1e-6);
+            assert!((values[3] - 0.880_797).abs() < 1e-5);
+        }
+
+        #[test]
+        fn clamp_and_none() {
+            let values = [-0.5, 0.25, 1.5];
+            assert_eq!(activated(&values, MatteActivation::Clamp), [0.0, 0.25, 1.0]);
+            assert_eq!(activated(&values, MatteActivation::None), values);
+        }
+    }
+
+    mod tiling {
+        use super::*;
+
+        fn tiling(tile_size: u32, overlap: u32) -> TileOptions {
+            TileOptions { tile_size, overlap }
+        }
+
+        #[test]
+        fn tiles_cover_the_image_and_end_flush() {
+            assert_eq!(tile_starts(10, tiling(4, 1)), vec![0, 3, 6]);
+            assert_eq!(tile_starts(11, tiling(4, 1)), vec![0, 3, 6, 7]);
+            assert_eq!(tile_starts(4, tiling(4, 1)), vec![0]);
+            assert_eq!(tile_starts(3, tiling(4, 1)), vec![0]);
+        }
+
+        #[test]
+        fn pixel_exact_model_reproduces_gradient() {
+            let image = RgbImage::from_fn(20, 9, |x, y| Rgb([(x * 10 + y) as u8, 0, 0]));
+            let mut tiles = 0;
+            let matte = run_tiled(&image, tiling(8, 3), |tile| {
+                tiles += 1;
+                Ok(Array2::from_shape_fn(
+                    (tile.height() as usize, tile.width() as usize),
+                    |(y, x)| f32::from(tile.get_pixel(x as u32, y as u32)[0]),
+                ))
+            })
+            .unwrap();
+
+            assert_eq!(tiles, 4 * 2);
+            for ((y, x), &value) in matte.indexed_iter() {
+                let expected = (x * 10 + y) as f32;
+                assert!((value - expected).abs() < 1e-3, "({x}, {y}): {value}");
+            }
+        }
+
+        #[test]
+        fn disagreeing_tiles_cross_fade_without_a_seam() {
+            let image = RgbImage::new(12, 1);
+            let mut next = 0.0;
+            let matte = run_tiled(&image, tiling(8, 4), |tile| {
+                let value = next;
+                next += 1.0;
+                Ok(Array2::from_elem((1, tile.width() as usize), value))
+            })
+            .unwrap();
+
+            // Tile 0 covers 0..8 and tile 1 covers 4..12; the overlap ramps in even steps.
+            assert_eq!(matte[[0, 3]], 0.0);
+            assert_eq!(matte[[0, 8]], 1.0);
+            let steps: Vec<f32> = matte
+                .row(0)
+                .windows(2)
+                .into_iter()
+                .map(|pair| pair[1] - pair[0])
+                .collect();
+            assert!(
+                steps.iter().all(|&step| (0.0..0.21).contains(&step)),
+                "{matte:?}"
+            );
+        }
+    }
+
+    mod letterbox {
+        use super::*;
+
+        #[test]
+        fn region_keeps_aspect_and_centers() {
+            let region = letterbox_region((400, 200), (320, 320));
+            assert_eq!(
+                region,
+                LetterboxRegion {
+                    x: 0,
+                    y: 80,
+                    width: 320,
+                    height: 160,
+                }
+            );
+
+            let region = letterbox_region((100, 300), (320, 320));
+            assert_eq!((region.x, region.width, region.height), (106, 107, 320));
+        }
+
+        #[test]
+        fn image_is_padded_with_fill() {
+            let spec = ModelInputSpec {
+                height: 4,
+                width: 4,
+                layout: ChannelLayout::Nchw,
+            };
+            let rgb = RgbImage::from_pixel(4, 2, Rgb([200, 100, 50]));
+            let (canvas, region) =
+                letterbox_image(&rgb, spec, FilterType::Nearest, [1, 2, 3]).unwrap();
+
+            assert_eq!(canvas.dimensions(), (4, 4));
+            assert_eq!(canvas.get_pixel(0, 0).0, [1, 2, 3]);
+            assert_eq!(canvas.get_pixel(0, 1).0, [200, 100, 50]);
+            assert_eq!(canvas.get_pixel(3, 2).0, [200, 100, 50]);
+            assert_eq!(canvas.get_pixel(3, 3).0, [1, 2, 3]);
+            assert_eq!((region.y, region.height), (1, 2));
+        }
+
+        #[test]
+        fn mean_fill_normalizes_to_about_zero() {
+            assert_eq!(mean_color(Normalization::ImageNet), [124, 116, 104]);
+            assert_eq!(mean_color(Normalization::ZeroToOne), [0, 0, 0]);
+        }
+
+        #[test]
+        fn crop_scales_with_downsampled_output() {
+            let region = LetterboxRegion {
+                x: 0,
+                y: 2,
+                width: 8,
+                height: 4,
+            };
+            let matte = Array2::from_shape_fn((4, 4), |(y, _)| y as f32);
+            let cropped = crop_letterbox_matte(matte, region, (8, 8));
+
+            assert_eq!(cropped.dim(), (2, 4));
+            assert_eq!(cropped[[0, 0]], 1.0);
+            assert_eq!(cropped[[1, 3]], 2.0);
+        }
+    }
+
+    mod normalization {
+        use super::*;
+
+        fn preprocess_pixel(layout: ChannelLayout, normalization: Normalization) -> Vec<f32> {
+            let spec = ModelInputSpec {
+                height: 1,
+                width: 1,
+                layout,
+            };
+            let rgb = RgbImage::from_pixel(1, 1, Rgb([255, 0, 51]));
+            preprocess_image_to_array(&rgb, FilterType::Nearest, spec, normalization)
+                .expect("preprocessing should succeed")
+                .into_iter()
+                .collect()
+        }
+
+        #[test]
+        fn each_mode_maps_a_known_pixel() {
+            let cases = [
+                (
+                    Normalization::ImageNet,
+                    [(1.0 - 0.485) / 0.229, -0.456 / 0.224, (0.2 - 0.406) / 0.225],
+                ),
+                (Normalization::ZeroToOne, [1.0, 0.0, 0.2]),
+                (Normalization::MinusOneToOne, [1.0, -1.0, -0.6]),
+                (
+                    Normalization::Custom {
+                        mean: [0.5, 0.0, 0.0],
+                        std: [0.25, 1.0, 2.0],
+                    },
+                    [2.0, 0.0, 0.1],
+                ),
+            ];
+            for (normalization, expected) in cases {
+                for layout in [ChannelLayout::Nchw, ChannelLayout::Nhwc] {
+                    let values = preprocess_pixel(layout, normalization);
+                    for (value, expected) in values.iter().zip(expected) {
+                        assert!(
+                            (value - expected).abs() < 1e-5,
+                            "{normalization:?} {layout:?}: {values:?}"
+                        );
+                    }
+                }
+            }
+        }
+
+        #[test]
+        #[should_panic(expected = "std non-zero")]
+        fn custom_rejects_zero_std() {
+            let _ =
+                InferenceSettings::new("model.onnx").with_normalization(Normalization::Custom {
+                    mean: [0.0; 3],
+                    std: [1.0, 0.0, 1.0],
+                });
+        }
+    }
+
+    #[cfg(feature = "backend-ort")]
+    mod optimized_model_cache_path {
+        use super::*;
+
+        #[test]
+        fn keyed_by_model_contents_and_settings() {
+            let dir = tempfile::tempdir().unwrap();
+            let model = dir.path().join("model.onnx");
+            let other = dir.path().join("other.onnx");
+            std::fs::write(&model, b"model-a").unwrap();
+            std::fs::write(&other, b"model-a").unwrap();
+
+            let cache = dir.path().join("cache");
+            let path = optimized_model_cache_path(&model, &cache, "Cpu").unwrap();
+            assert_eq!(path.parent(), Some(cache.as_path()));
+            assert_eq!(path.extension().and_then(|ext| ext.to_str()), Some("ort"));
+            assert_eq!(
+                optimized_model_cache_path(&model, &cache, "Cpu").unwrap(),
+                path
+            );
+            assert_ne!(
+                optimized_model_cache_path(&model, &cache, "Cuda { device_id: 0 }").unwrap(),
+                path
+            );
+            // A copy of the same model shares the entry.
+            assert_eq!(
+                optimized_model_cache_path(&other, &cache, "Cpu").unwrap(),
+                path
+            );
+
+            // Same size, restored modification time: only the contents tell them apart.
+            let modified = std::fs::metadata(&model).unwrap().modified().unwrap();
+            std::fs::write(&model, b"model-b").unwrap();
+            std::fs::File::options()
+                .write(true)
+                .open(&model)
+                .unwrap()
+                .set_modified(modified)
+                .unwrap();
+            assert_ne!(
+                optimized_model_cache_path(&model, &cache, "Cpu").unwrap(),
+                path
+            );
+        }
+
+        #[test]
+        fn missing_model_is_an_error() {
+            let dir = tempfile::tempdir().unwrap();
+            assert!(
+                optimized_model_cache_path(&dir.path().join("missing.onnx"), dir.path(), "Cpu")
+                    .is_err()
+            );
+        }
+    }
+
+    mod model_input_spec_display {
+        use super::*;
+
+        #[test]
+        fn formats_output_name_and_shape() {
+            let output = ModelOutputInfo {
+                name: "matte".to_string(),
+                shape: Some(vec![None, Some(1), Some(320), Some(320)]),
+            };
+            assert_eq!(output.to_string(), "matte [?, 1, 320, 320]");
+
+            let unknown = ModelOutputInfo {
+                name: "scores".to_string(),
+                shape: None,
+            };
+            assert_eq!(unknown.to_string(), "scores [?]");
+        }
+
+        #[test]
+        fn formats_size_and_layout() {
+            let spec = ModelInputSpec {
+                height: 1024,
+                width: 768,
+                layout: ChannelLayout::Nhwc,
+            };
+            assert_eq!(spec.to_string(), "1024x768 NHWC");
+            assert_eq!(DEFAULT_MODEL_INPUT_SPEC.to_string(), "320x320 NCHW");
+        }
+    }
+
+    mod resolve_output {
+        use super::*;
+
+        fn outputs() -> Vec<ModelOutputInfo> {
+            ["d0", "d1"]
+                .into_iter()
+                .map(|name| ModelOutputInfo {
+                    name: name.to_string(),
+                    shape: None,
+                })
+                .collect()
+        }
+
+        #[test]
+        fn selects_by_index_or_name() {
+            assert_eq!(
+                resolve_output(&outputs(), &OutputSelector::default()).unwrap(),
+                0
+            );
+            assert_eq!(
+                resolve_output(&outputs(), &OutputSelector::Index(1)).unwrap(),
+                1
+            );
+            let name = OutputSelector::Name("d1".to_string());
+            assert_eq!(resolve_output(&outputs(), &name).unwrap(), 1);
+        }
+
+        #[test]
+        fn rejects_unknown_outputs_listing_the_available_ones() {
+            let err =
+                resolve_output(&outputs(), &OutputSelector::Name("alpha".to_string())).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Model has no output `alpha`; available outputs: d0, d1"
+            );
+
+            let err = resolve_output(&outputs(), &OutputSelector::Index(2)).unwrap_err();
+            assert!(matches!(
+                err,
+                OutlineError::UnknownModelOutput { output, .. } if output == "index 2"
+            ));
+        }
+    }
+
+    mod check_matte_sanity {
+        use super::*;
+
+        #[test]
+        fn all_foreground_is_degenerate() {
+            let matte = GrayImage::from_pixel(10, 10, Luma([255]));
+            let err = check_matte_sanity(&matte, DEFAULT_MODEL_INPUT_SPEC).unwrap_err();
+            assert!(matches!(
+                err,
+                OutlineError::DegenerateMatte { opaque_fraction, transparent_fraction, .. }
+                    if opaque_fraction == 1.0 && transparent_fraction == 0.0
+            ));
+            assert!(err.to_string().contains("320x320 NCHW"), "{err}");
+        }
+
+        #[test]
+        fn all_background_is_degenerate() {
+            let matte = GrayImage::new(10, 10);
+            assert!(check_matte_sanity(&matte, DEFAULT_MODEL_INPUT_SPEC).is_err());
+        }
+
+        #[test]
+        fn exactly_ninety_nine_percent_is_accepted() {
+            let mut matte = GrayImage::from_pixel(10, 10, Luma([255]));
+            matte.put_pixel(0, 0, Luma([0]));
+            check_matte_sanity(&matte, DEFAULT_MODEL_INPUT_SPEC).unwrap();
+        }
+
+        #[test]
+        fn hard_subject_on_background_is_accepted() {
+            let matte = GrayImage::from_fn(10, 10, |x, _| Luma([if x < 4 { 255 } else { 0 }]));
+            check_matte_sanity(&matte, DEFAULT_MODEL_INPUT_SPEC).unwrap();
+        }
+
+        #[test]
+        fn empty_matte_is_accepted() {
+            check_matte_sanity(&GrayImage::new(0, 0), DEFAULT_MODEL_INPUT_SPEC).unwrap();
+        }
+    }
+
+    mod combine_mattes {
+        use super::*;
+        use crate::mask::gray_image_to_array;
+
+        fn matte(values: Vec<u8>) -> Array2<f32> {
+            let width = values.len() as u32;
+            gray_image_to_array(&GrayImage::from_raw(width, 1, values).unwrap())
+        }
+
+        fn pair() -> (Array2<f32>, Array2<f32>) {
+            (matte(vec![255, 255, 128]), matte(vec![0, 255, 0]))
+        }
+
+        fn combined_bytes(fg: &Array2<f32>, bg: &Array2<f32>, combine: MatteCombine) -> Vec<u8> {
+            array_to_gray_image(&combine_mattes(fg, bg, combine)).into_raw()
+        }
+
+        #[test]
+        fn multiply_suppresses_background_pixels() {
+            let (fg, bg) = pair();
+            let combined = combined_bytes(&fg, &bg, MatteCombine::Multiply);
+            assert_eq!(combined, [255, 0, 128]);
+        }
+
+        #[test]
+        fn min_takes_conservative_value() {
+            let fg = matte(vec![200, 100]);
+            let bg = matte(vec![155, 0]);
+            let combined = combined_bytes(&fg, &bg, MatteCombine::Min);
+            assert_eq!(combined, [100, 100]);
+        }
+
+        #[test]
+        fn average_blends_predictions() {
+            let (fg, bg) = pair();
+            let combined = combined_bytes(&fg, &bg, MatteCombine::Average);
+            assert_eq!(combined, [255, 128, 192]);
+        }
+    }
+
+    mod apply_input_spec_overrides {
+        use super::*;
+
+        #[test]
+        fn keeps_detected_spec_without_overrides() {
+            let settings = InferenceSettings::new("model.onnx");
+            let spec = apply_input_spec_overrides(DEFAULT_MODEL_INPUT_SPEC, &settings);
+            assert_eq!(spec, DEFAULT_MODEL_INPUT_SPEC);
+        }
+
+        #[test]
+        fn size_override_keeps_detected_layout() {
+            let settings = InferenceSettings::new("model.onnx").with_model_input_size(1024, 768);
+            let spec = apply_input_spec_overrides(DEFAULT_MODEL_INPUT_SPEC, &settings);
+            assert_eq!(
+                spec,
+                ModelInputSpec {
+                    height: 1024,
+                    width: 768,
+                    layout: ChannelLayout::Nchw,
+                }
+            );
+        }
+
+        #[test]
+        fn layout_override_keeps_detected_size() {
+            let settings = InferenceSettings::new("model.onnx")
+                .with_model_input_layout(ModelInputLayout::Nhwc);
+            let spec = apply_input_spec_overrides(DEFAULT_MODEL_INPUT_SPEC, &settings);
+            assert_eq!(
+                spec,
+                ModelInputSpec {
+                    height: 320,
+                    width: 320,
+                    layout: ChannelLayout::Nhwc,
+                }
+            );
+        }
+
+        #[test]
+        fn full_spec_override_replaces_detected_spec() {
+            let settings = InferenceSettings::new("model.onnx").with_model_input_spec(
+                512,
+                256,
+                ModelInputLayout::Nhwc,
+            );
+            let spec = apply_input_spec_overrides(DEFAULT_MODEL_INPUT_SPEC, &settings);
+            assert_eq!(
+                spec,
+                ModelInputSpec {
+                    height: 512,
+                    width: 256,
+                    layout: ChannelLayout::Nhwc,
+                }
+            );
+        }
+
+        #[test]
+        fn nhwc_override_changes_preprocessed_shape() {
+            let settings = InferenceSettings::new("model.onnx")
+                .with_model_input_size(4, 6)
+                .with_model_input_layout(ModelInputLayout::Nhwc);
+            let spec = apply_input_spec_overrides(DEFAULT_MODEL_INPUT_SPEC, &settings);
+            let rgb = RgbImage::from_pixel(8, 8, Rgb([1, 2, 3]));
+            let array = preprocess_image_to_array(
+                &rgb,
+                FilterType::Nearest,
+                spec,
+                Normalization::default(),
+            )
+            .expect("preprocessing should succeed");
+            assert_eq!(array.shape(), &[1, 4, 6, 3]);
+        }
+    }
 }
@@ -0,0 +1,983 @@
+use image::imageops::{self, FilterType};
+use image::{GrayImage, Luma, Rgb, RgbImage, Rgba, RgbaImage};
+use imageproc::filter::gaussian_blur_f32;
+
+use crate::config::MaskProcessingDefaults;
+use crate::error::{OutlineError, OutlineResult};
+use crate::foreground::compose_foreground;
+use crate::mask::{dilate_euclidean, threshold_mask};
+
+/// A two-color gradient used as a background fill.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum Gradient {
+    /// Blend from `start` to `end` along a line at `angle` degrees.
+    ///
+    /// `0` runs left to right and angles increase clockwise, so `90` runs top to bottom.
+    /// The gradient spans the whole canvas, reaching `start` and `end` exactly at the
+    /// corners furthest along the direction.
+    Linear {
+        start: [u8; 4],
+        end: [u8; 4],
+        angle: f32,
+    },
+    /// Blend from `center` at the canvas center to `edge` at the corners.
+    Radial { center: [u8; 4], edge: [u8; 4] },
+}
+
+/// Render a [`Gradient`] into an RGBA buffer of the given size.
+pub fn gradient_fill(width: u32, height: u32, gradient: &Gradient) -> RgbaImage {
+    let half_w = width as f32 / 2.0;
+    let half_h = height as f32 / 2.0;
+    match *gradient {
+        Gradient::Linear { start, end, angle } => {
+            let (sin, cos) = angle.to_radians().sin_cos();
+            let half_extent = half_w * cos.abs() + half_h * sin.abs();
+            RgbaImage::from_fn(width, height, |x, y| {
+                let dx = x as f32 + 0.5 - half_w;
+                let dy = y as f32 + 0.5 - half_h;
+                let t = if half_extent > 0.0 {
+                    0.5 + (dx * cos + dy * sin) / (2.0 * half_extent)
+                } else {
+                    0.0
+                };
+                Rgba(lerp_color(start, end, t))
+            })
+        }
+        Gradient::Radial { center, edge } => {
+            let max_distance = half_w.hypot(half_h);
+            RgbaImage::from_fn(width, height, |x, y| {
+                let distance = (x as f32 + 0.5 - half_w).hypot(y as f32 + 0.5 - half_h);
+                let t = if max_distance > 0.0 {
+                    distance / max_distance
+                } else {
+                    0.0
+                };
+                Rgba(lerp_color(center, edge, t))
+            })
+        }
+    }
+}
+
+fn lerp_color(from: [u8; 4], to: [u8; 4], t: f32) -> [u8; 4] {
+    let t = t.clamp(0.0, 1.0);
+    std::array::from_fn(|channel| {
+        let (a, b) = (f32::from(from[channel]), f32::from(to[channel]));
+        (a + (b - a) * t).round() as u8
+    })
+}
+
+/// Alpha-composite a foreground over a solid color and return an opaque RGB image.
+///
+/// `color` is an RGBA fill. A translucent fill is flattened over black, matching the
+/// premultiplied result of compositing onto a transparent canvas.
+pub fn compose_over_color(foreground: &RgbaImage, color: [u8; 4]) -> RgbImage {
+    compose_over_fill(foreground, |_, _| color)
+}
+
+/// Alpha-composite a foreground over a [`Gradient`] sized to it and return an opaque RGB image.
+///
+/// Translucent gradient colors are flattened over black, as in [`compose_over_color`].
+pub fn compose_over_gradient(foreground: &RgbaImage, gradient: &Gradient) -> RgbImage {
+    let (w, h) = foreground.dimensions();
+    let fill = gradient_fill(w, h, gradient);
+    compose_over_fill(foreground, |x, y| fill.get_pixel(x, y).0)
+}
+
+/// Alpha-composite a foreground over a gray and white checkerboard for previewing transparency.
+///
+/// Cells are `cell` pixels square, starting with a white cell in the top-left corner.
+///
+/// # Panics
+///
+/// Panics if `cell` is zero.
+pub fn composite_on_checkerboard(fg: &RgbaImage, cell: u32) -> RgbImage {
+    assert!(cell > 0, "checkerboard cell size must be positive");
+    compose_over_fill(fg, |x, y| {
+        if (x / cell + y / cell).is_multiple_of(2) {
+            CHECKERBOARD_LIGHT
+        } else {
+            CHECKERBOARD_DARK
+        }
+    })
+}
+
+const CHECKERBOARD_LIGHT: [u8; 4] = [255, 255, 255, 255];
+const CHECKERBOARD_DARK: [u8; 4] = [204, 204, 204, 255];
+
+/// How [`compose_over_image`] fits a background image to the foreground canvas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BackgroundFit {
+    /// Resize to exactly the canvas size, ignoring the aspect ratio.
+    Stretch,
+    /// Keep the aspect ratio and scale to cover the whole canvas, cropping the overflow evenly
+    /// from both sides.
+    Cover,
+    /// Keep the aspect ratio and scale to fit inside the canvas, centered. The bands it leaves
+    /// uncovered are transparent, so they flatten to black.
+    Contain,
+}
+
+/// Alpha-composite a foreground over a background image and return an opaque RGB image.
+///
+/// The background is resized to the foreground's dimensions with a triangle filter according to
+/// `fit`. Translucent background pixels are flattened over black, as in [`compose_over_color`].
+pub fn compose_over_image(
+    foreground: &RgbaImage,
+    background: &RgbaImage,
+    fit: BackgroundFit,
+) -> RgbImage {
+    let (w, h) = foreground.dimensions();
+    let fill = fit_background(background, w, h, fit);
+    compose_over_fill(foreground, |x, y| fill.get_pixel(x, y).0)
+}
+
+/// Keep the subject sharp over a Gaussian-blurred copy of its own image.
+///
+/// `matte` becomes the alpha of the subject as in
+/// [`compose_foreground`](crate::compose_foreground), and `rgb` blurred with `sigma` fills in
+/// behind it, so fully opaque pixels keep their original color and soft edges blend into the
+/// blur.
+///
+/// Returns [`OutlineError::AlphaMismatch`] if the image and matte sizes differ.
+///
+/// # Panics
+///
+/// Panics if `sigma` is not a positive finite number.
+pub fn blur_background(rgb: &RgbImage, matte: &GrayImage, sigma: f32) -> OutlineResult<RgbImage> {
+    assert!(
+        sigma > 0.0 && sigma.is_finite(),
+        "background blur sigma must be > 0.0 and finite"
+    );
+    let foreground = compose_foreground(rgb, matte)?;
+    let blurred = imageops::blur(rgb, sigma);
+    Ok(compose_over_fill(&foreground, |x, y| {
+        let [r, g, b] = blurred.get_pixel(x, y).0;
+        [r, g, b, 255]
+    }))
+}
+
+/// Keep the subject in color over a grayscale copy of its own image.
+///
+/// `matte` becomes the alpha of the subject as in
+/// [`compose_foreground`](crate::compose_foreground), and `rgb` converted to luma with the
+/// Rec. 601 weights (0.299, 0.587, 0.114) fills in behind it, so soft edges fade from color to
+/// gray.
+///
+/// Returns [`OutlineError::AlphaMismatch`] if the image and matte sizes differ.
+pub fn desaturate_background(rgb: &RgbImage, matte: &GrayImage) -> OutlineResult<RgbImage> {
+    let foreground = compose_foreground(rgb, matte)?;
+    Ok(compose_over_fill(&foreground, |x, y| {
+        let [r, g, b] = rgb.get_pixel(x, y).0;
+        let luma = 0.299 * f32::from(r) + 0.587 * f32::from(g) + 0.114 * f32::from(b);
+        let luma = luma.round().clamp(0.0, 255.0) as u8;
+        [luma, luma, luma, 255]
+    }))
+}
+
+fn fit_background(
+    background: &RgbaImage,
+    width: u32,
+    height: u32,
+    fit: BackgroundFit,
+) -> RgbaImage {
+    let (bg_w, bg_h) = background.dimensions();
+    if width == 0 || height == 0 || bg_w == 0 || bg_h == 0 {
+        return RgbaImage::new(width, height);
+    }
+
+    let scale_x = width as f32 / bg_w as f32;
+    let scale_y = height as f32 / bg_h as f32;
+    let scaled = |scale: f32| {
+        let w = ((bg_w as f32 * scale).round() as u32).max(1);
+        let h = ((bg_h as f32 * scale).round() as u32).max(1);
+        (w, h)
+    };
+    match fit {
+        BackgroundFit::Stretch => imageops::resize(background, width, height, FilterType::Triangle),
+        BackgroundFit::Cover => {
+            let (w, h) = scaled(scale_x.max(scale_y));
+            let (w, h) = (w.max(width), h.max(height));
+            let resized = imageops::resize(background, w, h, FilterType::Triangle);
+            imageops::crop_imm(&resized, (w - width) / 2, (h - height) / 2, width, height)
+                .to_image()
+        }
+        BackgroundFit::Contain => {
+            let (w, h) = scaled(scale_x.min(scale_y));
+            let (w, h) = (w.min(width), h.min(height));
+            let resized = imageops::resize(background, w, h, FilterType::Triangle);
+            let mut canvas = RgbaImage::new(width, height);
+            imageops::replace(
+                &mut canvas,
+                &resized,
+                i64::from((width - w) / 2),
+                i64::from((height - h) / 2),
+            );
+            canvas
+        }
+    }
+}
+
+fn compose_over_fill(foreground: &RgbaImage, fill: impl Fn(u32, u32) -> [u8; 4]) -> RgbImage {
+    let (w, h) = foreground.dimensions();
+    RgbImage::from_fn(w, h, |x, y| {
+        let pixel = foreground.get_pixel(x, y);
+        let color = fill(x, y);
+        let alpha = f32::from(pixel[3]) / 255.0;
+        let fill_alpha = f32::from(color[3]) / 255.0;
+        let blend = |channel: usize| {
+            let fg = f32::from(pixel[channel]) * alpha;
+            let bg = f32::from(color[channel]) * fill_alpha * (1.0 - alpha);
+            (fg + bg).round().clamp(0.0, 255.0) as u8
+        };
+        Rgb([blend(0), blend(1), blend(2)])
+    })
+}
+
+/// Render a drop shadow: the matte shifted by `offset`, blurred, and filled with `color`.
+///
+/// `offset` is `(dx, dy)` in pixels, positive values moving the shadow right and down. The result
+/// keeps the matte's size, so parts shifted past the canvas are clipped. A `blur` of zero keeps
+/// the shifted silhouette sharp. The alpha of `color` scales the shadow's opacity.
+///
+/// Composite the subject over the result with [`composite`] to place the shadow beneath it.
+///
+/// # Panics
+///
+/// Panics if `blur` is negative or NaN.
+pub fn render_drop_shadow(
+    matte: &GrayImage,
+    offset: (i32, i32),
+    blur: f32,
+    color: [u8; 4],
+) -> RgbaImage {
+    assert!(blur >= 0.0, "shadow blur must be >= 0.0");
+    let (w, h) = matte.dimensions();
+    let (dx, dy) = offset;
+    let mut shadow = GrayImage::from_fn(w, h, |x, y| {
+        let source_x = i64::from(x) - i64::from(dx);
+        let source_y = i64::from(y) - i64::from(dy);
+        if (0..i64::from(w)).contains(&source_x) && (0..i64::from(h)).contains(&source_y) {
+            *matte.get_pixel(source_x as u32, source_y as u32)
+        } else {
+            Luma([0])
+        }
+    });
+    if blur > 0.0 {
+        shadow = gaussian_blur_f32(&shadow, blur);
+    }
+
+    let opacity = f32::from(color[3]) / 255.0;
+    RgbaImage::from_fn(w, h, |x, y| {
+        let alpha = (f32::from(shadow.get_pixel(x, y)[0]) * opacity).round() as u8;
+        Rgba([color[0], color[1], color[2], alpha])
+    })
+}
+
+/// Render a stroke ring of `width` pixels around the subject, filled with `color`.
+///
+/// The matte is binarized at the default mask threshold and dilated by `width`, then the
+/// original matte is subtracted, leaving a ring that hugs the outside of the subject and fades
+/// in under its soft edges. The alpha of `color` scales the ring's opacity. A ring reaching past
+/// the canvas is clipped.
+///
+/// Composite the subject over the result with [`composite`] so the stroke sits beneath it.
+///
+/// # Panics
+///
+/// Panics if `width` is negative or NaN.
+pub fn render_stroke(matte: &GrayImage, width: f32, color: [u8; 4]) -> RgbaImage {
+    let binary = threshold_mask(matte, MaskProcessingDefaults::default().mask_threshold);
+    let dilated = dilate_euclidean(&binary, width);
+    let opacity = f32::from(color[3]) / 255.0;
+    RgbaImage::from_fn(matte.width(), matte.height(), |x, y| {
+        let ring = dilated.get_pixel(x, y)[0].saturating_sub(matte.get_pixel(x, y)[0]);
+        let alpha = (f32::from(ring) * opacity).round() as u8;
+        Rgba([color[0], color[1], color[2], alpha])
+    })
+}
+
+/// Porter-Duff operator used by [`composite`] to combine a top layer with a bottom layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CompositeOp {
+    /// The top layer over the bottom layer.
+    Over,
+    /// The top layer where the bottom layer is opaque; the bottom layer is dropped.
+    In,
+    /// The top layer where the bottom layer is transparent; the bottom layer is dropped.
+    Out,
+    /// The top layer where the bottom layer is opaque, over the bottom layer.
+    Atop,
+    /// Each layer where the other is transparent.
+    Xor,
+}
+
+/// Combine two RGBA layers of the same size with a Porter-Duff operator.
+///
+/// Colors are blended in premultiplied form and the result is stored straight (unpremultiplied);
+/// pixels that end up fully transparent are black.
+///
+/// Returns [`OutlineError::AlphaMismatch`] if the layer sizes differ.
+pub fn composite(bottom: &RgbaImage, top: &RgbaImage, op: CompositeOp) -> OutlineResult<RgbaImage> {
+    let expected = bottom.dimensions();
+    let found = top.dimensions();
+    if expected != found {
+        return Err(OutlineError::AlphaMismatch { expected, found });
+    }
+
+    let (w, h) = bottom.dimensions();
+    Ok(RgbaImage::from_fn(w, h, |x, y| {
+        let bottom = bottom.get_pixel(x, y);
+        let top = top.get_pixel(x, y);
+        let top_alpha = f32::from(top[3]) / 255.0;
+        let bottom_alpha = f32::from(bottom[3]) / 255.0;
+        let (top_factor, bottom_factor) = match op {
+            CompositeOp::Over => (1.0, 1.0 - top_alpha),
+            CompositeOp::In => (bottom_alpha, 0.0),
+            CompositeOp::Out => (1.0 - bottom_alpha, 0.0),
+            CompositeOp::Atop => (bottom_alpha, 1.0 - top_alpha),
+            CompositeOp::Xor => (1.0 - bottom_alpha, 1.0 - top_alpha),
+        };
+        let top_weight = top_alpha * top_factor;
+        let bottom_weight = bottom_alpha * bottom_factor;
+        let alpha = top_weight + bottom_weight;
+        if alpha <= 0.0 {
+            return Rgba([0, 0, 0, 0]);
+        }
+        let blend = |channel: usize| {
+            let value =
+                f32::from(top[channel]) * top_weight + f32::from(bottom[channel]) * bottom_weight;
+            (value / alpha).round().clamp(0.0, 255.0) as u8
+        };
+        let alpha = (alpha * 255.0).round().clamp(0.0, 255.0) as u8;
+        Rgba([blend(0), blend(1), blend(2), alpha])
+    }))
+}
+
+/// Fade the alpha channel toward the canvas edges using a rounded-rectangle falloff.
+///
+/// Pixels further than `radius` from the rounded canvas edge keep their alpha, pixels closer to
+/// the edge fade linearly toward zero, and pixels outside the rounded corners become fully
+/// transparent. A `radius` of zero keeps a hard edge, so only `corner_radius` takes effect.
+///
+/// # Panics
+///
+/// Panics if `radius` or `corner_radius` is negative or NaN.
+pub fn vignette(image: &RgbaImage, radius: f32, corner_radius: f32) -> RgbaImage {
+    assert!(radius >= 0.0, "vignette radius must be >= 0.0");
+    assert!(corner_radius >= 0.0, "corner radius must be >= 0.0");
+
+    let (w, h) = image.dimensions();
+    let half_w = w as f32 / 2.0;
+    let half_h = h as f32 / 2.0;
+    let corner = corner_radius.min(half_w).min(half_h);
+
+    let mut out = image.clone();
+    for (x, y, pixel) in out.enumerate_pixels_mut() {
+        let inside = rounded_rect_inside_distance(
+            x as f32 + 0.5 - half_w,
+            y as f32 + 0.5 - half_h,
+            half_w,
+            half_h,
+            corner,
+        );
+        let factor = if radius > 0.0 {
+            (inside / radius).clamp(0.0, 1.0)
+        } else if inside > 0.0 {
+            1.0
+        } else {
+            0.0
+        };
+        pixel[3] = (f32::from(pixel[3]) * factor).round() as u8;
+    }
+
+    out
+}
+
+/// Tint the masked region of an image with a translucent color for visual inspection.
+///
+/// Each pixel is blended toward `color` by `opacity` scaled by the mask value and by the alpha of
+/// `color`, so unmasked pixels keep the source color and soft mask edges fade out. The result is
+/// fully opaque.
+///
+/// Returns [`OutlineError::AlphaMismatch`] if the image and mask sizes differ.
+///
+/// # Panics
+///
+/// Panics if `opacity` is outside `0.0..=1.0` or NaN.
+pub fn overlay_mask_on_rgb(
+    rgb: &RgbImage,
+    mask: &GrayImage,
+    color: [u8; 4],
+    opacity: f32,
+) -> OutlineResult<RgbaImage> {
+    assert!(
+        (0.0..=1.0).contains(&opacity),
+        "overlay opacity must be within 0.0..=1.0"
+    );
+    let expected = rgb.dimensions();
+    let found = mask.dimensions();
+    if expected != found {
+        return Err(OutlineError::AlphaMismatch { expected, found });
+    }
+
+    let strength = opacity * f32::from(color[3]) / 255.0;
+    let (w, h) = rgb.dimensions();
+    Ok(RgbaImage::from_fn(w, h, |x, y| {
+        let source = rgb.get_pixel(x, y);
+        let t = strength * f32::from(mask.get_pixel(x, y)[0]) / 255.0;
+        let blend = |channel: usize| {
+            let (a, b) = (f32::from(source[channel]), f32::from(color[channel]));
+            (a + (b - a) * t).round() as u8
+        };
+        Rgba([blend(0), blend(1), blend(2), 255])
+    }))
+}
+
+// Distance from a point (relative to the rectangle center) to the rounded-rectangle edge,
+// positive inside and negative outside.
+fn rounded_rect_inside_distance(dx: f32, dy: f32, half_w: f32, half_h: f32, corner: f32) -> f32 {
+    let qx = dx.abs() - (half_w - corner);
+    let qy = dy.abs() - (half_h - corner);
+    let outside = qx.max(0.0).hypot(qy.max(0.0));
+    let inside = qx.max(qy).min(0.0);
+    corner - outside - inside
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn opaque(w: u32, h: u32) -> RgbaImage {
+        RgbaImage::from_pixel(w, h, Rgba([10, 20, 30, 255]))
+    }
+
+    mod compose_over_color {
+        use super::*;
+
+        #[test]
+        fn opaque_pixels_keep_their_color() {
+            let image = opaque(2, 2);
+            let result = compose_over_color(&image, [255, 255, 255, 255]);
+            assert!(result.pixels().all(|pixel| pixel.0 == [10, 20, 30]));
+        }
+
+        #[test]
+        fn transparent_pixels_take_the_fill() {
+            let image = RgbaImage::from_pixel(2, 1, Rgba([10, 20, 30, 0]));
+            let result = compose_over_color(&image, [200, 100, 50, 255]);
+            assert!(result.pixels().all(|pixel| pixel.0 == [200, 100, 50]));
+        }
+
+        #[test]
+        fn partial_alpha_blends_linearly() {
+            let image = RgbaImage::from_pixel(1, 1, Rgba([255, 0, 0, 128]));
+            let result = compose_over_color(&image, [0, 0, 255, 255]);
+            assert_eq!(result.get_pixel(0, 0).0, [128, 0, 127]);
+        }
+
+        #[test]
+        fn translucent_fill_is_flattened_over_black() {
+            let image = RgbaImage::from_pixel(1, 1, Rgba([0, 0, 0, 0]));
+            let result = compose_over_color(&image, [200, 200, 200, 128]);
+            assert_eq!(result.get_pixel(0, 0).0, [100, 100, 100]);
+        }
+    }
+
+    mod composite_on_checkerboard {
+        use super::*;
+
+        #[test]
+        fn transparent_pixels_show_the_checkerboard() {
+            let image = RgbaImage::from_pixel(4, 4, Rgba([10, 20, 30, 0]));
+            let result = composite_on_checkerboard(&image, 2);
+
+            assert_eq!(result.dimensions(), (4, 4));
+            assert_eq!(result.get_pixel(0, 0).0, [255; 3]);
+            assert_eq!(result.get_pixel(1, 1).0, [255; 3]);
+            assert_eq!(result.get_pixel(2, 0).0, [204; 3]);
+            assert_eq!(result.get_pixel(0, 3).0, [204; 3]);
+            assert_eq!(result.get_pixel(3, 3).0, [255; 3]);
+        }
+
+        #[test]
+        fn opaque_pixels_keep_their_color() {
+            let result = composite_on_checkerboard(&opaque(4, 4), 1);
+            assert!(result.pixels().all(|pixel| pixel.0 == [10, 20, 30]));
+        }
+
+        #[test]
+        #[should_panic(expected = "cell size must be positive")]
+        fn rejects_zero_cell() {
+            composite_on_checkerboard(&opaque(1, 1), 0);
+        }
+    }
+
+    mod compose_over_image {
+        use super::*;
+
+        fn half_transparent() -> RgbaImage {
+            RgbaImage::from_fn(4, 2, |x, _| {
+                Rgba(if x < 2 {
+                    [10, 20, 30, 255]
+                } else {
+                    [0, 0, 0, 0]
+                })
+            })
+        }
+
+        #[test]
+        fn solid_background_matches_compose_over_color() {
+            let foreground = half_transparent();
+            let background = RgbaImage::from_pixel(8, 4, Rgba([200, 100, 50, 255]));
+            let expected = compose_over_color(&foreground, [200, 100, 50, 255]);
+            for fit in [
+                BackgroundFit::Stretch,
+                BackgroundFit::Cover,
+                BackgroundFit::Contain,
+            ] {
+                let result = compose_over_image(&foreground, &background, fit);
+                assert_eq!(result, expected, "{fit:?}");
+            }
+        }
+
+        #[test]
+        fn cover_crops_the_overflow_around_the_center() {
+            // A 4x1 strip scaled to cover a 2x1 canvas keeps its two middle pixels.
+            let background = RgbaImage::from_fn(4, 1, |x, _| Rgba([x as u8 * 50, 0, 0, 255]));
+            let foreground = RgbaImage::new(2, 1);
+            let result = compose_over_image(&foreground, &background, BackgroundFit::Cover);
+            assert_eq!(result.get_pixel(0, 0).0, [50, 0, 0]);
+            assert_eq!(result.get_pixel(1, 0).0, [100, 0, 0]);
+        }
+
+        #[test]
+        fn contain_leaves_black_bands() {
+            let background = RgbaImage::from_pixel(1, 1, Rgba([255, 255, 255, 255]));
+            let foreground = RgbaImage::new(3, 1);
+            let result = compose_over_image(&foreground, &background, BackgroundFit::Contain);
+            let row: Vec<_> = result.pixels().map(|pixel| pixel.0).collect();
+            assert_eq!(row, vec![[0, 0, 0], [255, 255, 255], [0, 0, 0]]);
+        }
+    }
+
+    mod blur_background {
+        use super::*;
+        use image::Luma;
+
+        #[test]
+        fn subject_stays_sharp_over_a_blurred_background() {
+            let rgb =
+                RgbImage::from_fn(9, 9, |x, _| Rgb(if x % 2 == 0 { [0; 3] } else { [255; 3] }));
+            let matte = GrayImage::from_fn(9, 9, |x, y| {
+                Luma([if (3..6).contains(&x) && (3..6).contains(&y) {
+                    255
+                } else {
+                    0
+                }])
+            });
+
+            let result = blur_background(&rgb, &matte, 2.0).unwrap();
+
+            assert_eq!(result.get_pixel(4, 4), rgb.get_pixel(4, 4));
+            let corner = result.get_pixel(0, 0).0[0];
+            assert!(
+                (60..200).contains(&corner),
+                "corner should be blurred, got {corner}"
+            );
+        }
+
+        #[test]
+        fn size_mismatch_is_rejected() {
+            let err =
+                blur_background(&RgbImage::new(2, 2), &GrayImage::new(3, 2), 1.0).unwrap_err();
+            assert!(matches!(err, OutlineError::AlphaMismatch { .. }));
+        }
+    }
+
+    mod desaturate_background {
+        use super::*;
+        use image::Luma;
+
+        #[test]
+        fn subject_keeps_its_color_and_background_turns_gray() {
+            let rgb = RgbImage::from_pixel(2, 1, Rgb([200, 40, 10]));
+            let matte = GrayImage::from_fn(2, 1, |x, _| Luma([if x == 0 { 255 } else { 0 }]));
+
+            let result = desaturate_background(&rgb, &matte).unwrap();
+
+            assert_eq!(result.get_pixel(0, 0).0, [200, 40, 10]);
+            // 0.299 * 200 + 0.587 * 40 + 0.114 * 10 = 84.42
+            assert_eq!(result.get_pixel(1, 0).0, [84, 84, 84]);
+        }
+
+        #[test]
+        fn soft_edges_blend_color_and_gray() {
+            let rgb = RgbImage::from_pixel(1, 1, Rgb([255, 0, 0]));
+            let matte = GrayImage::from_pixel(1, 1, Luma([128]));
+
+            let result = desaturate_background(&rgb, &matte).unwrap();
+
+            // Half of pure red plus half of its luma (76).
+            assert_eq!(result.get_pixel(0, 0).0, [166, 38, 38]);
+        }
+    }
+
+    mod render_drop_shadow {
+        use super::*;
+
+        fn matte() -> GrayImage {
+            GrayImage::from_fn(4, 3, |x, y| Luma([if x == 1 && y == 1 { 255 } else { 0 }]))
+        }
+
+        #[test]
+        fn zero_offset_and_blur_match_the_silhouette() {
+            let matte = matte();
+            let shadow = render_drop_shadow(&matte, (0, 0), 0.0, [10, 20, 30, 255]);
+
+            for (x, y, pixel) in shadow.enumerate_pixels() {
+                assert_eq!(pixel.0[..3], [10, 20, 30]);
+                assert_eq!(pixel[3], matte.get_pixel(x, y)[0], "at ({x}, {y})");
+            }
+        }
+
+        #[test]
+        fn offset_moves_and_clips_the_shadow() {
+            let shadow = render_drop_shadow(&matte(), (2, 1), 0.0, [0, 0, 0, 128]);
+            let opaque: Vec<_> = shadow
+                .enumerate_pixels()
+                .filter(|(_, _, pixel)| pixel[3] > 0)
+                .map(|(x, y, pixel)| (x, y, pixel[3]))
+                .collect();
+            assert_eq!(opaque, vec![(3, 2, 128)]);
+
+            let shadow = render_drop_shadow(&matte(), (-2, 0), 0.0, [0, 0, 0, 255]);
+            assert!(shadow.pixels().all(|pixel| pixel[3] == 0));
+        }
+
+        #[test]
+        fn blur_spreads_the_shadow() {
+            let shadow = render_drop_shadow(&matte(), (0, 0), 1.0, [0, 0, 0, 255]);
+            assert!(shadow.get_pixel(1, 1)[3] < 255);
+            assert!(shadow.get_pixel(2, 1)[3] > 0);
+        }
+    }
+
+    mod render_stroke {
+        use super::*;
+
+        fn square() -> GrayImage {
+            GrayImage::from_fn(9, 9, |x, y| {
+                Luma([if (3..6).contains(&x) && (3..6).contains(&y) {
+                    255
+                } else {
+                    0
+                }])
+            })
+        }
+
+        #[test]
+        fn ring_surrounds_the_subject_only() {
+            let stroke = render_stroke(&square(), 2.0, [255, 255, 255, 255]);
+
+            assert_eq!(stroke.get_pixel(4, 4)[3], 0, "subject interior");
+            assert_eq!(stroke.get_pixel(2, 4).0, [255, 255, 255, 255]);
+            assert_eq!(stroke.get_pixel(1, 4)[3], 255);
+            assert_eq!(stroke.get_pixel(0, 4)[3], 0, "beyond the width");
+        }
+
+        #[test]
+        fn stroke_sits_beneath_the_subject() {
+            let matte = square();
+            let rgb = RgbImage::from_pixel(9, 9, Rgb([200, 0, 0]));
+            let foreground = compose_foreground(&rgb, &matte).unwrap();
+            let stroke = render_stroke(&matte, 2.0, [0, 0, 255, 255]);
+
+            let layered = composite(&stroke, &foreground, CompositeOp::Over).unwrap();
+
+            assert_eq!(layered.get_pixel(3, 4).0, [200, 0, 0, 255], "subject edge");
+            assert_eq!(layered.get_pixel(2, 4).0, [0, 0, 255, 255], "ring");
+            assert_eq!(layered.get_pixel(0, 0)[3], 0, "background");
+        }
+    }
+
+    mod composite {
+        use super::*;
+
+        const TOP: [u8; 4] = [255, 0, 0, 255];
+        const BOTTOM: [u8; 4] = [0, 0, 255, 255];
+        const CLEAR: [u8; 4] = [0, 0, 0, 0];
+
+        fn pixel(color: [u8; 4]) -> RgbaImage {
+            RgbaImage::from_pixel(1, 1, Rgba(color))
+        }
+
+        fn assert_cases(op: CompositeOp, cases: [([u8; 4], [u8; 4], [u8; 4]); 4]) {
+            for (bottom, top, expected) in cases {
+                let result = composite(&pixel(bottom), &pixel(top), op).unwrap();
+                assert_eq!(
+                    result.get_pixel(0, 0).0,
+                    expected,
+                    "{op:?} of {top:?} onto {bottom:?}"
+                );
+            }
+        }
+
+        #[test]
+        fn over() {
+            assert_cases(
+                CompositeOp::Over,
+                [
+                    (BOTTOM, TOP, TOP),
+                    (BOTTOM, CLEAR, BOTTOM),
+                    (CLEAR, TOP, TOP),
+                    (CLEAR, CLEAR, CLEAR),
+                ],
+            );
+        }
+
+        #[test]
+        fn in_keeps_top_inside_bottom() {
+            assert_cases(
+                CompositeOp::In,
+                [
+                    (BOTTOM, TOP, TOP),
+                    (BOTTOM, CLEAR, CLEAR),
+                    (CLEAR, TOP, CLEAR),
+                    (CLEAR, CLEAR, CLEAR),
+                ],
+            );
+        }
+
+        #[test]
+        fn out_keeps_top_outside_bottom() {
+            assert_cases(
+                CompositeOp::Out,
+                [
+                    (BOTTOM, TOP, CLEAR),
+                    (BOTTOM, CLEAR, CLEAR),
+                    (CLEAR, TOP, TOP),
+                    (CLEAR, CLEAR, CLEAR),
+                ],
+            );
+        }
+
+        #[test]
+        fn atop_keeps_the_bottom_coverage() {
+            assert_cases(
+                CompositeOp::Atop,
+                [
+                    (BOTTOM, TOP, TOP),
+                    (BOTTOM, CLEAR, BOTTOM),
+                    (CLEAR, TOP, CLEAR),
+                    (CLEAR, CLEAR, CLEAR),
+                ],
+            );
+        }
+
+        #[test]
+        fn xor_keeps_the_non_overlapping_parts() {
+            assert_cases(
+                CompositeOp::Xor,
+                [
+                    (BOTTOM, TOP, CLEAR),
+                    (BOTTOM, CLEAR, BOTTOM),
+                    (CLEAR, TOP, TOP),
+                    (CLEAR, CLEAR, CLEAR),
+                ],
+            );
+        }
+
+        #[test]
+        fn over_blends_partial_alpha_in_premultiplied_form() {
+            let result =
+                composite(&pixel(BOTTOM), &pixel([255, 0, 0, 128]), CompositeOp::Over).unwrap();
+            assert_eq!(result.get_pixel(0, 0).0, [128, 0, 127, 255]);
+
+            let result =
+                composite(&pixel(CLEAR), &pixel([255, 0, 0, 128]), CompositeOp::Over).unwrap();
+            assert_eq!(result.get_pixel(0, 0).0, [255, 0, 0, 128]);
+        }
+
+        #[test]
+        fn size_mismatch_is_rejected() {
+            let err = composite(
+                &RgbaImage::new(2, 2),
+                &RgbaImage::new(1, 2),
+                CompositeOp::Over,
+            )
+            .unwrap_err();
+            assert!(matches!(err, OutlineError::AlphaMismatch { .. }));
+        }
+    }
+
+    mod gradient_fill {
+        use super::*;
+
+        const BLACK: [u8; 4] = [0, 0, 0, 255];
+        const WHITE: [u8; 4] = [255, 255, 255, 255];
+
+        #[test]
+        fn linear_zero_degrees_runs_left_to_right() {
+            let gradient = Gradient::Linear {
+                start: BLACK,
+                end: WHITE,
+                angle: 0.0,
+            };
+            let fill = gradient_fill(4, 2, &gradient);
+            let row: Vec<u8> = (0..4).map(|x| fill.get_pixel(x, 0)[0]).collect();
+            assert_eq!(row, [32, 96, 159, 223]);
+            assert_eq!(fill.get_pixel(2, 0), fill.get_pixel(2, 1));
+        }
+
+        #[test]
+        fn linear_ninety_degrees_runs_top_to_bottom() {
+            let gradient = Gradient::Linear {
+                start: BLACK,
+                end: WHITE,
+                angle: 90.0,
+            };
+            let fill = gradient_fill(2, 4, &gradient);
+            let column: Vec<u8> = (0..4).map(|y| fill.get_pixel(0, y)[0]).collect();
+            assert_eq!(column, [32, 96, 159, 223]);
+            assert_eq!(fill.get_pixel(0, 1), fill.get_pixel(1, 1));
+        }
+
+        #[test]
+        fn radial_is_symmetric_and_darkest_at_center() {
+            let gradient = Gradient::Radial {
+                center: BLACK,
+                edge: WHITE,
+            };
+            let fill = gradient_fill(5, 5, &gradient);
+            assert_eq!(fill.get_pixel(2, 2).0, BLACK);
+            assert_eq!(fill.get_pixel(0, 0), fill.get_pixel(4, 4));
+            assert_eq!(fill.get_pixel(0, 2), fill.get_pixel(2, 0));
+            assert!(fill.get_pixel(0, 0)[0] > fill.get_pixel(1, 1)[0]);
+        }
+    }
+
+    mod compose_over_gradient {
+        use super::*;
+
+        #[test]
+        fn transparent_pixels_take_the_gradient() {
+            let gradient = Gradient::Radial {
+                center: [255, 0, 0, 255],
+                edge: [0, 0, 255, 255],
+            };
+            let image = RgbaImage::new(3, 3);
+            let result = compose_over_gradient(&image, &gradient);
+            let fill = gradient_fill(3, 3, &gradient);
+            for (x, y, pixel) in result.enumerate_pixels() {
+                assert_eq!(pixel.0, fill.get_pixel(x, y).0[..3]);
+            }
+        }
+
+        #[test]
+        fn opaque_pixels_keep_their_color() {
+            let gradient = Gradient::Linear {
+                start: [0, 0, 0, 255],
+                end: [255, 255, 255, 255],
+                angle: 45.0,
+            };
+            let result = compose_over_gradient(&opaque(3, 2), &gradient);
+            assert!(result.pixels().all(|pixel| pixel.0 == [10, 20, 30]));
+        }
+    }
+
+    mod overlay_mask_on_rgb {
+        use super::*;
+        use image::Luma;
+
+        #[test]
+        fn tints_masked_pixels_only() {
+            let rgb = RgbImage::from_pixel(2, 1, Rgb([0, 0, 200]));
+            let mask = GrayImage::from_fn(2, 1, |x, _| Luma([if x == 0 { 255 } else { 0 }]));
+
+            let result = overlay_mask_on_rgb(&rgb, &mask, [255, 0, 0, 255], 0.5).unwrap();
+
+            assert_eq!(result.get_pixel(0, 0).0, [128, 0, 100, 255]);
+            assert_eq!(result.get_pixel(1, 0).0, [0, 0, 200, 255]);
+        }
+
+        #[test]
+        fn color_alpha_scales_opacity() {
+            let rgb = RgbImage::from_pixel(1, 1, Rgb([0, 0, 0]));
+            let mask = GrayImage::from_pixel(1, 1, Luma([255]));
+
+            let result = overlay_mask_on_rgb(&rgb, &mask, [200, 200, 200, 0], 1.0).unwrap();
+            assert_eq!(result.get_pixel(0, 0).0, [0, 0, 0, 255]);
+        }
+
+        #[test]
+        fn mismatched_sizes_are_an_error() {
+            let result = overlay_mask_on_rgb(
+                &RgbImage::new(2, 2),
+                &GrayImage::new(3, 2),
+                [255, 0, 0, 255],
+                0.5,
+            );
+            assert!(matches!(result, Err(OutlineError::AlphaMismatch { .. })));
+        }
+    }
+
+    mod vignette {
+        use super::*;
+
+        #[test]
+        fn zero_radii_is_identity() {
+            let image = opaque(6, 4);
+            let result = vignette(&image, 0.0, 0.0);
+            assert_eq!(result, image);
+        }
+
+        #[test]
+        fn corner_radius_clears_corners_only() {
+            let result = vignette(&opaque(20, 20), 0.0, 8.0);
+
+            assert_eq!(result.get_pixel(0, 0)[3], 0);
+            assert_eq!(result.get_pixel(19, 19)[3], 0);
+            assert_eq!(result.get_pixel(10, 0)[3], 255);
+            assert_eq!(result.get_pixel(0, 10)[3], 255);
+            assert_eq!(result.get_pixel(10, 10)[3], 255);
+        }
+
+        #[test]
+        fn radius_fades_edges_and_keeps_interior() {
+            let result = vignette(&opaque(40, 40), 10.0, 0.0);
+
+            let edge = result.get_pixel(0, 20)[3];
+            let near_edge = result.get_pixel(5, 20)[3];
+            assert!(edge < near_edge, "{edge} should be below {near_edge}");
+            assert!(near_edge < 255);
+            assert_eq!(result.get_pixel(20, 20)[3], 255);
+        }
+
+        #[test]
+        fn color_channels_are_preserved() {
+            let result = vignette(&opaque(10, 10), 4.0, 3.0);
+            for pixel in result.pixels() {
+                assert_eq!(&pixel.0[..3], &[10, 20, 30]);
+            }
+        }
+
+        #[test]
+        fn existing_alpha_is_multiplied() {
+            let image = RgbaImage::from_pixel(40, 40, Rgba([0, 0, 0, 100]));
+            let result = vignette(&image, 10.0, 0.0);
+
+            assert_eq!(result.get_pixel(20, 20)[3], 100);
+            assert!(result.get_pixel(0, 20)[3] < 100);
+        }
+
+        #[test]
+        #[should_panic(expected = "vignette radius must be >= 0.0")]
+        fn negative_radius_panics() {
+            let _ = vignette(&opaque(2, 2), -1.0, 0.0);
+        }
+    }
+}
@@ -55,48 +55,94 @@
 #[cfg(not(any(feature = "backend-ort", feature = "backend-rten")))]
 compile_error!("enable at least one inference backend feature: `backend-ort` or `backend-rten`.");
 
+pub mod batch;
+mod compose;
 mod config;
+mod contour;
+mod debug;
+mod encode;
 mod error;
 mod foreground;
 mod geometry;
 mod inference;
+mod layer;
 mod mask;
 mod matte;
+mod observer;
 #[cfg(feature = "backend-ort")]
 pub mod runtime;
 mod vectorizer;
+mod visualize;
 
+#[doc(inline)]
+pub use crate::compose::{ComposeBackground, ComposeOptions, ComposeShadow, compose_image};
 #[doc(inline)]
 pub use crate::config::{
-    DEFAULT_MODEL_PATH, ENV_MODEL_PATH, ErosionBorderMode, InferenceBackend, InferenceSettings,
-    MaskProcessingDefaults, ModelInputSize,
+    Connectivity, DEFAULT_MODEL_PATH, ENV_MODEL_PATH, ErosionBorderMode, ExecutionProvider,
+    InferenceBackend, InferenceSettings, MaskProcessingDefaults, MatteActivation, MatteCombine,
+    ModelInputLayout, ModelInputSize, Normalization, OutputSelector, PreprocessMode,
+    ResampleFilter, TileOptions,
 };
 #[doc(inline)]
+pub use crate::contour::{contours, simplify_contour};
+#[doc(inline)]
+pub use crate::debug::channel_dump;
+#[doc(inline)]
+pub use crate::encode::{is_binary_mask, save_1bit_png};
+#[doc(inline)]
 pub use crate::error::{OutlineError, OutlineResult};
 #[doc(inline)]
 pub use crate::foreground::ForegroundHandle;
 #[doc(inline)]
 pub use crate::geometry::{BoundingBox, Padding};
 #[doc(inline)]
+pub use crate::inference::{ModelOutputInfo, check_pixel_limit};
+#[doc(inline)]
+pub use crate::layer::{
+    BackgroundFit, CompositeOp, Gradient, blur_background, compose_over_color,
+    compose_over_gradient, compose_over_image, composite, composite_on_checkerboard,
+    desaturate_background, gradient_fill, overlay_mask_on_rgb, render_drop_shadow, render_stroke,
+    vignette,
+};
+#[doc(inline)]
 pub use crate::mask::{
-    MaskAlphaMode, MaskColor, MaskHandle, MaskOperation, MaskPipeline, colorize_mask,
+    MaskAlphaMode, MaskColor, MaskExt, MaskHandle, MaskOperation, MaskPipeline, MatteStats,
+    TRIMAP_UNKNOWN, array_to_gray_image, array_to_gray16_image, colorize_mask, edge_hardness,
+    gray_image_to_array, matte_stats, matte_to_trimap, otsu_threshold, subject_bbox,
+    try_colorize_mask,
 };
 #[doc(inline)]
 pub use crate::matte::{InferencedMatte, MatteHandle};
+#[doc(inline)]
+pub use crate::observer::{PipelineObserver, PipelineStage};
+#[doc(inline)]
+pub use crate::visualize::{ColorMap, matte_to_colormap};
 pub use vectorizer::MaskVectorizer;
 
+#[cfg(feature = "vectorizer-simple")]
+#[cfg_attr(docsrs, doc(cfg(feature = "vectorizer-simple")))]
+#[doc(inline)]
+pub use vectorizer::simple::{SimpleSvgOptions, SimpleSvgStyle, SimpleSvgVectorizer};
 #[cfg(feature = "vectorizer-vtracer")]
 #[cfg_attr(docsrs, doc(cfg(feature = "vectorizer-vtracer")))]
 #[doc(inline)]
-pub use vectorizer::vtracer::{TraceOptions, VtracerSvgVectorizer, trace_to_svg_string};
+pub use vectorizer::vtracer::{
+    TraceOptions, TraceOutputStyle, TracedPath, VtracerSplitSvgVectorizer, VtracerSvgVectorizer,
+    split_svg_paths, trace_paths, trace_to_oriented_svg_string, trace_to_svg_paths,
+    trace_to_svg_string,
+};
 
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
 use image::imageops::FilterType;
-use image::{DynamicImage, RgbImage, RgbaImage};
+use image::{DynamicImage, ImageFormat, RgbImage, RgbaImage};
 
-use crate::inference::{CachedInferenceSession, load_rgb_from_memory_with_orientation};
+use crate::inference::{
+    CachedInferenceSession, check_matte_sanity, combine_mattes,
+    load_rgb_from_memory_with_orientation, load_rgb_with_orientation,
+};
+use crate::observer::{NoopObserver, StageTimer};
 
 /// Entry point for configuring and running background matting inference.
 ///
@@ -113,6 +159,8 @@ pub struct Outline {
     mask_processing_defaults: MaskProcessingDefaults,
     /// Lazily initialized cached session for this configured model.
     cached_session: Mutex<Option<Arc<CachedInferenceSession>>>,
+    /// Lazily initialized cached session for the optional background model.
+    cached_background_session: Mutex<Option<Arc<CachedInferenceSession>>>,
 }
 
 impl Clone for Outline {
@@ -121,6 +169,7 @@ impl Clone for Outline {
             settings: self.settings.clone(),
             mask_processing_defaults: self.mask_processing_defaults.clone(),
             cached_session: Mutex::new(None),
+            cached_background_session: Mutex::new(None),
         }
     }
 }
@@ -132,6 +181,7 @@ impl Outline {
             settings: InferenceSettings::new(model_path),
             mask_processing_defaults: MaskProcessingDefaults::default(),
             cached_session: Mutex::new(None),
+            cached_background_session: Mutex::new(None),
         }
     }
 
@@ -162,10 +212,31 @@ impl Outline {
         if self.settings.backend() != backend {
             self.settings = self.settings.with_backend(backend);
             self.cached_session = Mutex::new(None);
+            self.cached_background_session = Mutex::new(None);
+        }
+        self
+    }
+
+    /// Set a background model whose matte is combined with the main model output.
+    ///
+    /// Each image then runs through both models, and the mattes are blended with the
+    /// [`MatteCombine`] strategy (default [`MatteCombine::Multiply`], i.e. `fg * (1 - bg)`).
+    /// The background model shares the backend, thread, size, and layout settings.
+    pub fn with_background_model(mut self, model_path: impl Into<PathBuf>) -> Self {
+        let model_path = model_path.into();
+        if self.settings.background_model_path() != Some(model_path.as_path()) {
+            self.settings = self.settings.with_background_model(model_path);
+            self.cached_background_session = Mutex::new(None);
         }
         self
     }
 
+    /// Set the strategy used to combine the main and background mattes.
+    pub fn with_matte_combine(mut self, combine: MatteCombine) -> Self {
+        self.settings = self.settings.with_matte_combine(combine);
+        self
+    }
+
     /// Override the image size used as model input.
     ///
     /// This bypasses the size inferred from the model; callers are responsible for choosing a
@@ -179,14 +250,103 @@ impl Outline {
         self
     }
 
+    /// Override the tensor layout used as model input.
+    ///
+    /// This bypasses the layout inferred from the model; callers are responsible for choosing a
+    /// layout the model supports.
+    pub fn with_model_input_layout(mut self, layout: ModelInputLayout) -> Self {
+        self.settings = self.settings.with_model_input_layout(layout);
+        self
+    }
+
+    /// Override both the model input size and layout, skipping model metadata entirely.
+    ///
+    /// Equivalent to calling [`with_model_input_size`](Self::with_model_input_size) and
+    /// [`with_model_input_layout`](Self::with_model_input_layout). Use this for models whose
+    /// input metadata is missing or wrong.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either dimension is zero.
+    pub fn with_model_input_spec(
+        mut self,
+        height: usize,
+        width: usize,
+        layout: ModelInputLayout,
+    ) -> Self {
+        self.settings = self.settings.with_model_input_spec(height, width, layout);
+        self
+    }
+
+    /// Pad the model input height and width up to the nearest multiple of `multiple`.
+    ///
+    /// Fully-convolutional models often require dimensions divisible by 16 or 32. The matte is
+    /// cropped back to the unpadded size before it is resized to the original image.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `multiple` is zero.
+    pub fn with_pad_to_multiple(mut self, multiple: u32) -> Self {
+        self.settings = self.settings.with_pad_to_multiple(multiple);
+        self
+    }
+
+    /// Set how pixel values are normalized into the model input tensor.
+    ///
+    /// Defaults to [`Normalization::ImageNet`]; match whatever the model was trained with.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a [`Normalization::Custom`] mean or std is not finite, or a std is zero.
+    pub fn with_normalization(mut self, normalization: Normalization) -> Self {
+        self.settings = self.settings.with_normalization(normalization);
+        self
+    }
+
+    /// Set how the input image is fit to the model input size.
+    ///
+    /// Defaults to [`PreprocessMode::Stretch`]. [`PreprocessMode::Letterbox`] keeps the aspect
+    /// ratio, which avoids distorting subjects in images far from the model's shape.
+    pub fn with_preprocess_mode(mut self, mode: PreprocessMode) -> Self {
+        self.settings = self.settings.with_preprocess_mode(mode);
+        self
+    }
+
+    /// Infer images wider or taller than `tiling.tile_size` in overlapping tiles.
+    ///
+    /// Each tile is resized to the model input separately and the tile mattes are blended
+    /// linearly where they overlap. This keeps fine edges in large images at the cost of one
+    /// model run per tile.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tile_size` is zero or `overlap` is not smaller than `tile_size`.
+    pub fn with_tiling(mut self, tiling: TileOptions) -> Self {
+        self.settings = self.settings.with_tiling(tiling);
+        self
+    }
+
+    /// Set how raw model output values are mapped to matte values before resizing.
+    ///
+    /// Defaults to [`MatteActivation::Clamp`]. Use [`MatteActivation::Sigmoid`] for models that
+    /// output logits instead of probabilities.
+    pub fn with_matte_activation(mut self, activation: MatteActivation) -> Self {
+        self.settings = self.settings.with_matte_activation(activation);
+        self
+    }
+
     /// Set the filter used to resize the input image for the model.
-    pub fn with_input_resize_filter(mut self, filter: FilterType) -> Self {
+    ///
+    /// Accepts either a [`ResampleFilter`] or an [`image::imageops::FilterType`].
+    pub fn with_input_resize_filter(mut self, filter: impl Into<FilterType>) -> Self {
         self.settings = self.settings.with_input_resize_filter(filter);
         self
     }
 
     /// Set the filter used to resize the output matte to the original image size.
-    pub fn with_output_resize_filter(mut self, filter: FilterType) -> Self {
+    ///
+    /// Accepts either a [`ResampleFilter`] or an [`image::imageops::FilterType`].
+    pub fn with_output_resize_filter(mut self, filter: impl Into<FilterType>) -> Self {
         self.settings = self.settings.with_output_resize_filter(filter);
         self
     }
@@ -196,10 +356,105 @@ impl Outline {
         if self.settings.intra_threads() != intra_threads {
             self.settings = self.settings.with_intra_threads(intra_threads);
             self.cached_session = Mutex::new(None);
+            self.cached_background_session = Mutex::new(None);
         }
         self
     }
 
+    /// Set the hardware the model runs on (ORT backend).
+    ///
+    /// If the provider cannot be initialized, for example because no GPU is present or the
+    /// ONNX Runtime build lacks it, the session falls back to the CPU instead of failing. Check
+    /// [`execution_provider_fallback`](Self::execution_provider_fallback) to find out. Ignored by
+    /// the RTen backend.
+    pub fn with_execution_provider(mut self, provider: ExecutionProvider) -> Self {
+        if self.settings.execution_provider() != provider {
+            self.settings = self.settings.with_execution_provider(provider);
+            self.cached_session = Mutex::new(None);
+            self.cached_background_session = Mutex::new(None);
+        }
+        self
+    }
+
+    /// Set the number of inter-op threads for the inference (ORT backend).
+    pub fn with_inter_threads(mut self, inter_threads: Option<usize>) -> Self {
+        if self.settings.inter_threads() != inter_threads {
+            self.settings = self.settings.with_inter_threads(inter_threads);
+            self.cached_session = Mutex::new(None);
+            self.cached_background_session = Mutex::new(None);
+        }
+        self
+    }
+
+    /// Cache the graph-optimized model in `dir` and reuse it on later runs (ORT backend).
+    ///
+    /// ORT's `Level3` graph optimization can take seconds for large models. With a cache
+    /// directory set, the optimized model is saved there on first load, keyed by a SHA-256 of
+    /// the source model's contents and the execution provider and ORT build, and loaded directly
+    /// by later processes. Ignored by the RTen backend.
+    pub fn with_optimized_model_cache(mut self, dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        if self.settings.optimized_model_cache() != Some(dir.as_path()) {
+            self.settings = self.settings.with_optimized_model_cache(dir);
+            self.cached_session = Mutex::new(None);
+            self.cached_background_session = Mutex::new(None);
+        }
+        self
+    }
+
+    /// Reject mattes where more than 99% of pixels are fully opaque or fully transparent.
+    ///
+    /// When enabled, inference returns [`OutlineError::DegenerateMatte`] instead of a matte that
+    /// would yield a cut-out identical to the input or an empty one. This catches a wrong model,
+    /// input size, or normalization early in automated pipelines.
+    pub fn with_sanity_check(mut self, enabled: bool) -> Self {
+        self.settings = self.settings.with_sanity_check(enabled);
+        self
+    }
+
+    /// Reject inputs with more than `max_pixels` pixels (`None` for no limit, the default).
+    ///
+    /// Oversized inputs fail with [`OutlineError::ImageTooLarge`]; files and encoded bytes are
+    /// checked before decoding.
+    pub fn with_max_pixels(mut self, max_pixels: Option<u64>) -> Self {
+        self.settings = self.settings.with_max_pixels(max_pixels);
+        self
+    }
+
+    /// Compute the matte from a copy of the image scaled by `scale`, but keep the full-size
+    /// image for compositing.
+    ///
+    /// Only the matte is upsampled, so cut-outs keep the original resolution while preprocessing
+    /// works on fewer pixels. Edges may come out slightly softer.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `scale` is in `(0.0, 1.0]`.
+    pub fn with_infer_scale(mut self, scale: f32) -> Self {
+        self.settings = self.settings.with_infer_scale(scale);
+        self
+    }
+
+    /// Keep the float matte on each result for
+    /// [`InferencedMatte::raw_matte_f32`] and [`InferencedMatte::raw_matte_u16`].
+    ///
+    /// Off by default, since it adds four bytes per pixel to every result that is kept alive,
+    /// such as the images in flight during [`batch::run`].
+    pub fn with_float_matte(mut self, enabled: bool) -> Self {
+        self.settings = self.settings.with_float_matte(enabled);
+        self
+    }
+
+    /// Read the matte from the selected model output instead of the first one.
+    ///
+    /// Multi-output models, such as those that also return intermediate side outputs, may put the
+    /// final matte elsewhere; list them with [`model_outputs`](Self::model_outputs). Inference
+    /// fails with [`OutlineError::UnknownModelOutput`] when the model has no such output.
+    pub fn with_output(mut self, output: OutputSelector) -> Self {
+        self.settings = self.settings.with_output(output);
+        self
+    }
+
     /// Set the default parameter values for no-argument mask processing methods.
     pub fn with_mask_processing_defaults(mut self, defaults: MaskProcessingDefaults) -> Self {
         self.mask_processing_defaults = defaults;
@@ -212,41 +467,131 @@ impl Outline {
     }
 
     fn get_or_init_cached_session(&self) -> OutlineResult<Arc<CachedInferenceSession>> {
-        let mut cached_session = self
-            .cached_session
-            .lock()
-            .map_err(|_| std::io::Error::other("outline session cache mutex poisoned"))?;
+        get_or_init_session(&self.cached_session, &self.settings)
+    }
 
-        if let Some(session) = cached_session.as_ref() {
-            return Ok(Arc::clone(session));
-        }
+    /// List the main model's output tensors with their declared shapes, in graph order.
+    ///
+    /// The matte is read from the first output unless [`with_output`](Self::with_output) selects
+    /// another. This loads the model session if it is not already cached.
+    pub fn model_outputs(&self) -> OutlineResult<Vec<ModelOutputInfo>> {
+        Ok(self.get_or_init_cached_session()?.outputs().to_vec())
+    }
 
-        let session = Arc::new(CachedInferenceSession::new(&self.settings)?);
-        *cached_session = Some(Arc::clone(&session));
-        Ok(session)
+    /// Why the configured execution provider could not be used, if the main model session fell
+    /// back to the CPU.
+    ///
+    /// Returns `None` when the provider was registered or none was requested. This loads the
+    /// model session if it is not already cached.
+    pub fn execution_provider_fallback(&self) -> OutlineResult<Option<String>> {
+        Ok(self
+            .get_or_init_cached_session()?
+            .execution_provider_fallback()
+            .map(str::to_string))
+    }
+
+    /// Initialize the main and, if configured, background sessions ahead of inference.
+    fn init_sessions(&self) -> OutlineResult<()> {
+        self.get_or_init_cached_session()?;
+        if let Some(background_settings) = self.settings.background_settings() {
+            get_or_init_session(&self.cached_background_session, &background_settings)?;
+        }
+        Ok(())
     }
 
     /// Run the inference pipeline for a single image, returning the original image, raw matte, and processing defaults,
     /// wrapped in an `InferencedMatte`.
     pub fn for_image(&self, image_path: impl AsRef<Path>) -> OutlineResult<InferencedMatte> {
-        let session = self.get_or_init_cached_session()?;
-        let (rgb, matte) = session.run_matte_pipeline(&self.settings, image_path.as_ref())?;
-        Ok(InferencedMatte::new(
-            rgb,
-            matte,
-            self.mask_processing_defaults.clone(),
-        ))
+        self.for_image_observed(image_path, &mut NoopObserver)
+    }
+
+    /// Run [`for_image`](Outline::for_image) while reporting each finished
+    /// [`PipelineStage`] to `observer`.
+    pub fn for_image_observed(
+        &self,
+        image_path: impl AsRef<Path>,
+        observer: &mut dyn PipelineObserver,
+    ) -> OutlineResult<InferencedMatte> {
+        let mut timer = StageTimer::new(observer);
+        self.init_sessions()?;
+        let (rgb, orientation) =
+            load_rgb_with_orientation(image_path.as_ref(), self.settings.max_pixels())?;
+        timer.finish(PipelineStage::Load);
+        Ok(self
+            .run_loaded_rgb_image(rgb, &mut timer)?
+            .with_orientation(orientation))
     }
 
     /// Run the inference pipeline for an in-memory RGB image.
     pub fn for_rgb_image(&self, rgb_image: RgbImage) -> OutlineResult<InferencedMatte> {
+        self.for_rgb_image_observed(rgb_image, &mut NoopObserver)
+    }
+
+    /// Run [`for_rgb_image`](Outline::for_rgb_image) while reporting each finished
+    /// [`PipelineStage`] to `observer`.
+    ///
+    /// [`PipelineStage::Load`] is reported once the model session is ready, since there is no
+    /// file to decode.
+    pub fn for_rgb_image_observed(
+        &self,
+        rgb_image: RgbImage,
+        observer: &mut dyn PipelineObserver,
+    ) -> OutlineResult<InferencedMatte> {
+        let mut timer = StageTimer::new(observer);
+        self.init_sessions()?;
+        timer.finish(PipelineStage::Load);
+        self.run_loaded_rgb_image(rgb_image, &mut timer)
+    }
+
+    /// Run the stages after [`PipelineStage::Load`], reporting them through `timer`.
+    fn run_loaded_rgb_image(
+        &self,
+        rgb_image: RgbImage,
+        timer: &mut StageTimer<'_>,
+    ) -> OutlineResult<InferencedMatte> {
+        check_pixel_limit(rgb_image.dimensions(), self.settings.max_pixels())?;
         let session = self.get_or_init_cached_session()?;
-        let (rgb, matte) = session.run_matte_pipeline_on_rgb(&self.settings, rgb_image)?;
-        Ok(InferencedMatte::new(
-            rgb,
-            matte,
-            self.mask_processing_defaults.clone(),
-        ))
+        let (rgb, matte, model_matte) = match self.settings.background_settings() {
+            Some(background_settings) => {
+                let background_session =
+                    get_or_init_session(&self.cached_background_session, &background_settings)?;
+                let (_, background_matte, _) = background_session.run_matte_pipeline_on_rgb(
+                    &self.settings,
+                    rgb_image.clone(),
+                    timer.observer(),
+                )?;
+                let (rgb, matte, model_matte) = session.run_matte_pipeline_on_rgb(
+                    &self.settings,
+                    rgb_image,
+                    timer.observer(),
+                )?;
+                timer.restart();
+                let combined =
+                    combine_mattes(&matte, &background_matte, self.settings.matte_combine());
+                (rgb, combined, model_matte)
+            }
+            None => {
+                let result = session.run_matte_pipeline_on_rgb(
+                    &self.settings,
+                    rgb_image,
+                    timer.observer(),
+                )?;
+                timer.restart();
+                result
+            }
+        };
+        let raw_matte = array_to_gray_image(&matte);
+        if self.settings.sanity_check() {
+            check_matte_sanity(&raw_matte, session.input_spec(&self.settings))?;
+        }
+        let mut result =
+            InferencedMatte::new(rgb, raw_matte, self.mask_processing_defaults.clone())
+                .with_model_matte(model_matte);
+        if self.settings.float_matte() {
+            result = result.with_raw_matte_f32(matte);
+        }
+        timer.finish(PipelineStage::Matte);
+        Ok(result)
     }
 
     /// Run the inference pipeline for an in-memory RGBA image.
@@ -264,13 +609,79 @@ impl Outline {
     /// Run the inference pipeline for encoded image bytes in memory.
     ///
     /// This mirrors [`for_image`](Outline::for_image) by decoding with the `image` crate and
-    /// applying EXIF orientation metadata when present.
+    /// applying EXIF orientation metadata when present; images without it keep
+    /// [`Orientation::NoTransforms`](image::metadata::Orientation::NoTransforms).
+    ///
+    /// The format is guessed from the content, so any format enabled in the `image` crate works,
+    /// including PNG, JPEG, WebP, GIF, BMP, and TIFF. Use
+    /// [`for_image_bytes_with_format`](Outline::for_image_bytes_with_format) when the signature
+    /// is missing or ambiguous.
     pub fn for_image_bytes(&self, bytes: &[u8]) -> OutlineResult<InferencedMatte> {
-        let rgb = load_rgb_from_memory_with_orientation(bytes)?;
-        self.for_rgb_image(rgb)
+        let (rgb, orientation) =
+            load_rgb_from_memory_with_orientation(bytes, None, self.settings.max_pixels())?;
+        Ok(self.for_rgb_image(rgb)?.with_orientation(orientation))
+    }
+
+    /// Run the inference pipeline for encoded image bytes in a known format.
+    ///
+    /// Like [`for_image_bytes`](Outline::for_image_bytes), but decodes as `format` instead of
+    /// sniffing it from the content, for streams whose signature is missing or ambiguous.
+    pub fn for_image_bytes_with_format(
+        &self,
+        bytes: &[u8],
+        format: ImageFormat,
+    ) -> OutlineResult<InferencedMatte> {
+        let (rgb, orientation) =
+            load_rgb_from_memory_with_orientation(bytes, Some(format), self.settings.max_pixels())?;
+        Ok(self.for_rgb_image(rgb)?.with_orientation(orientation))
     }
 }
 
+/// Remove the background from an image file in a single call.
+///
+/// Loads `model` with default [`InferenceSettings`], runs inference on `image`, and composes
+/// the foreground from the raw matte. This is shorthand for:
+///
+/// ```no_run
+/// # use outline::Outline;
+/// # let (model, image) = ("model.onnx", "input.jpg");
+/// let cutout = Outline::new(model).for_image(image)?.matte().foreground()?.into_image();
+/// # Ok::<_, outline::OutlineError>(())
+/// ```
+///
+/// Each call builds a fresh inference session, so construct an [`Outline`] directly when
+/// processing several images or when mask processing is needed.
+///
+/// ```no_run
+/// let cutout = outline::remove_background("model.onnx", "input.jpg")?;
+/// cutout.save("foreground.png")?;
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+pub fn remove_background(
+    model: impl Into<PathBuf>,
+    image: impl AsRef<Path>,
+) -> OutlineResult<RgbaImage> {
+    let foreground = Outline::new(model).for_image(image)?.matte().foreground()?;
+    Ok(foreground.into_image())
+}
+
+fn get_or_init_session(
+    cache: &Mutex<Option<Arc<CachedInferenceSession>>>,
+    settings: &InferenceSettings,
+) -> OutlineResult<Arc<CachedInferenceSession>> {
+    let mut cached_session = cache
+        .lock()
+        .map_err(|_| std::io::Error::other("outline session cache mutex poisoned"))?;
+
+    if let Some(session) = cached_session.as_ref() {
+        return Ok(Arc::clone(session));
+    }
+
+    let session = Arc::new(CachedInferenceSession::new(settings)?);
+    *cached_session = Some(Arc::clone(&session));
+    Ok(session)
+}
+
 #[cfg(test)]
 #[path = "../tests/support/tiny_onnx.rs"]
 mod tiny_onnx;
@@ -306,6 +717,35 @@ mod tests {
         }
     }
 
+    mod resample_filter {
+        use super::*;
+
+        #[test]
+        fn converts_to_filter_type() {
+            for (filter, expected) in [
+                (ResampleFilter::Nearest, FilterType::Nearest),
+                (ResampleFilter::Triangle, FilterType::Triangle),
+                (ResampleFilter::CatmullRom, FilterType::CatmullRom),
+                (ResampleFilter::Gaussian, FilterType::Gaussian),
+                (ResampleFilter::Lanczos3, FilterType::Lanczos3),
+            ] {
+                assert_eq!(FilterType::from(filter), expected);
+            }
+        }
+
+        #[test]
+        fn outline_accepts_crate_and_image_filters() {
+            let outline = Outline::new("model.onnx")
+                .with_input_resize_filter(ResampleFilter::Nearest)
+                .with_output_resize_filter(FilterType::Gaussian);
+            assert_eq!(outline.settings.input_resize_filter(), FilterType::Nearest);
+            assert_eq!(
+                outline.settings.output_resize_filter(),
+                FilterType::Gaussian
+            );
+        }
+    }
+
     mod outline_from_env_or_default {
         use super::*;
 
@@ -414,6 +854,23 @@ mod tests {
             assert!(Arc::ptr_eq(&cached, &reused));
         }
 
+        #[test]
+        fn input_spec_override_beats_static_model_shape() {
+            let model = tiny_onnx::tiny_matte_model_file();
+            let outline =
+                Outline::new(model.path()).with_model_input_spec(4, 6, ModelInputLayout::Nhwc);
+            let session = outline
+                .get_or_init_cached_session()
+                .expect("should initialize cached session");
+
+            let detected = session.input_spec(&InferenceSettings::new(model.path()));
+            assert_eq!(detected.to_string(), "2x2 NCHW");
+            assert_eq!(
+                session.input_spec(&outline.settings).to_string(),
+                "4x6 NHWC"
+            );
+        }
+
         #[test]
         fn intra_threads_change_clears_cached_session() {
             let model = tiny_onnx::tiny_matte_model_file();
@@ -429,5 +886,35 @@ mod tests {
 
             assert!(!Arc::ptr_eq(&cached, &rebuilt));
         }
+
+        #[test]
+        fn missing_model_is_reported_with_its_path() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("missing.onnx");
+
+            let err = Outline::new(&path)
+                .for_rgb_image(RgbImage::new(2, 2))
+                .unwrap_err();
+
+            assert!(matches!(err, OutlineError::ModelNotFound { path: found } if found == path));
+        }
+
+        #[test]
+        fn zero_thread_counts_are_rejected() {
+            let model = tiny_onnx::tiny_matte_model_file();
+            for outline in [
+                Outline::new(model.path()).with_intra_threads(Some(0)),
+                Outline::new(model.path()).with_inter_threads(Some(0)),
+            ] {
+                let err = outline.get_or_init_cached_session().unwrap_err();
+                assert!(matches!(err, OutlineError::InvalidThreadCount { .. }));
+            }
+
+            Outline::new(model.path())
+                .with_intra_threads(Some(1))
+                .with_inter_threads(Some(1))
+                .get_or_init_cached_session()
+                .expect("explicit non-zero thread counts are accepted");
+        }
     }
 }
@@ -1,5 +1,7 @@
+mod cache;
 mod cli;
 mod commands;
+mod diagnostics;
 #[cfg(feature = "fetch-model")]
 mod model_fetch;
 mod report;
@@ -7,7 +9,17 @@ mod report;
 use std::process::ExitCode;
 
 fn main() -> ExitCode {
-    match try_main() {
+    let cli = cli::Cli::parse();
+    let quiet = cli.global.quiet;
+    diagnostics::set_strict(cli.global.strict);
+    let result = commands::run(cli);
+
+    let diagnostics = diagnostics::take_all();
+    if !quiet {
+        report::report_diagnostics(&diagnostics);
+    }
+
+    match result {
         Ok(()) => ExitCode::SUCCESS,
         Err(err) => {
             report::report_error(&err);
@@ -15,8 +27,3 @@ fn main() -> ExitCode {
         }
     }
 }
-
-fn try_main() -> outline::OutlineResult<()> {
-    let cli = cli::Cli::parse();
-    commands::run(cli)
-}
@@ -1,22 +1,26 @@
 use std::borrow::Cow;
 use std::collections::VecDeque;
+use std::io::Write;
 use std::path::Path;
 use std::sync::Arc;
 
-use image::{GrayImage, Luma, Rgb, RgbImage, Rgba, RgbaImage};
-use imageproc::contrast::{ThresholdType, threshold as ip_threshold};
+use image::{GrayImage, ImageBuffer, ImageFormat, Luma, Rgb, RgbImage, Rgba, RgbaImage};
+use imageproc::contrast::{ThresholdType, otsu_level, threshold as ip_threshold};
 use imageproc::distance_transform::euclidean_squared_distance_transform;
 use imageproc::filter::gaussian_blur_f32;
 use ndarray::Array2;
 
 use crate::MaskVectorizer;
-use crate::OutlineResult;
-use crate::config::{ErosionBorderMode, MaskProcessingDefaults};
+use crate::config::{Connectivity, ErosionBorderMode, MaskProcessingDefaults};
+use crate::contour::contours;
+use crate::encode::{encode_image, encode_png, is_binary_mask, save_1bit_png, write_image};
 use crate::foreground::{ForegroundHandle, compose_foreground};
 use crate::geometry::{
     BoundingBox, Padding, crop_bounds_fit_image, crop_gray_image, crop_rgb_image,
     mask_bounding_box, pad_gray_image, pad_rgb_image,
 };
+use crate::layer::overlay_mask_on_rgb;
+use crate::{OutlineError, OutlineResult};
 
 #[cfg(feature = "vectorizer-vtracer")]
 use vtracer::ColorImage;
@@ -30,11 +34,36 @@ pub enum MaskOperation {
         /// Gaussian sigma. Must be greater than zero.
         sigma: f32,
     },
+    /// Apply a coverage-normalized Gaussian blur that does not thin the subject.
+    ///
+    /// See [`normalized_blur_mask`].
+    NormalizedBlur {
+        /// Gaussian sigma. Must be greater than zero.
+        sigma: f32,
+    },
+    /// Soften a hard mask's outline with a linear alpha ramp across the edge.
+    ///
+    /// See [`feather_mask`].
+    Feather {
+        /// Half-width of the ramp in pixels. Must be non-negative and not NaN.
+        radius: f32,
+    },
     /// Threshold the mask into a binary mask.
     Threshold {
         /// Threshold value in the 0-255 range.
         value: u8,
     },
+    /// Threshold the mask at the value picked by [`otsu_threshold`] for this mask.
+    OtsuThreshold,
+    /// Map values at or below `low` to 0 and at or above `high` to 255, with a linear ramp between.
+    ///
+    /// See [`soft_threshold_mask`].
+    SoftThreshold {
+        /// Values at or below this become 0.
+        low: u8,
+        /// Values at or above this become 255. Must not be below `low`.
+        high: u8,
+    },
     /// Expand white mask regions.
     Dilate {
         /// Dilation radius in pixels. Must be non-negative and not NaN.
@@ -47,11 +76,62 @@ pub enum MaskOperation {
         /// How pixels outside the image bounds are treated.
         border_mode: ErosionBorderMode,
     },
+    /// Remove foreground protrusions and specks narrower than the radius (erode, then dilate).
+    ///
+    /// Assumes a binary mask; threshold first when starting from a soft matte.
+    Open {
+        /// Structuring radius in pixels. Must be non-negative and not NaN.
+        radius: f32,
+    },
+    /// Bridge background gaps and holes narrower than the radius (dilate, then erode).
+    ///
+    /// Assumes a binary mask; threshold first when starting from a soft matte.
+    Close {
+        /// Structuring radius in pixels. Must be non-negative and not NaN.
+        radius: f32,
+    },
+    /// Keep only the largest connected foreground region.
+    ///
+    /// See [`keep_largest_component`].
+    KeepLargestComponent {
+        /// Which neighbors count as connected.
+        connectivity: Connectivity,
+    },
+    /// Drop connected foreground regions smaller than an area, keeping all larger ones.
+    ///
+    /// See [`remove_small_components`].
+    RemoveSmallComponents {
+        /// Regions with fewer pixels than this are removed.
+        min_area: u32,
+    },
     /// Fill enclosed background regions.
     FillHoles {
         /// Threshold used to distinguish foreground from background.
         threshold: u8,
     },
+    /// Make regions enclosed by strong foreground fully opaque, keeping soft edges.
+    Solidify {
+        /// Values at or above this count as strong foreground.
+        threshold: u8,
+    },
+    /// Quantize the mask to evenly spaced alpha levels.
+    Posterize {
+        /// Number of output levels, including 0 and 255. Must be at least 2; the `u8` caps it at
+        /// 255, since 256 levels would leave every value unchanged.
+        levels: u8,
+    },
+    /// Apply a gamma curve, mapping each value `v` to `255 * (v / 255)^gamma`.
+    ///
+    /// See [`gamma_mask`].
+    Gamma {
+        /// Curve exponent. Values above 1 darken mid-tones, below 1 brighten them. Must be
+        /// greater than zero and finite.
+        gamma: f32,
+    },
+    /// Invert the mask so each pixel becomes `255 - value`.
+    ///
+    /// See [`invert_mask`].
+    Invert,
 }
 
 impl MaskOperation {
@@ -59,18 +139,36 @@ impl MaskOperation {
     ///
     /// # Panics
     ///
-    /// Panics if this is a blur operation whose `sigma` is not greater than zero, or a dilation or
-    /// erosion operation whose radius is negative or NaN.
+    /// Panics if this is a blur operation whose `sigma` is not greater than zero, a dilation or
+    /// erosion operation whose radius is negative or NaN, a posterize operation with fewer than
+    /// two levels, a gamma operation whose exponent is not a positive finite number, or a soft
+    /// threshold whose `low` is above `high`.
     pub fn apply(&self, input: &GrayImage) -> GrayImage {
         match self {
             MaskOperation::Blur { sigma } => gaussian_blur_f32(input, *sigma),
+            MaskOperation::NormalizedBlur { sigma } => normalized_blur_mask(input, *sigma),
+            MaskOperation::Feather { radius } => feather_mask(input, *radius),
             MaskOperation::Threshold { value } => threshold_mask(input, *value),
+            MaskOperation::OtsuThreshold => threshold_mask(input, otsu_threshold(input)),
+            MaskOperation::SoftThreshold { low, high } => soft_threshold_mask(input, *low, *high),
             MaskOperation::Dilate { radius } => dilate_euclidean(input, *radius),
             MaskOperation::Erode {
                 radius,
                 border_mode,
             } => erode_euclidean_with_border_mode(input, *radius, *border_mode),
+            MaskOperation::Open { radius } => open_mask(input, *radius),
+            MaskOperation::Close { radius } => close_mask(input, *radius),
+            MaskOperation::KeepLargestComponent { connectivity } => {
+                keep_largest_component(input, *connectivity)
+            }
+            MaskOperation::RemoveSmallComponents { min_area } => {
+                remove_small_components(input, *min_area)
+            }
             MaskOperation::FillHoles { threshold } => fill_mask_holes(input, *threshold),
+            MaskOperation::Solidify { threshold } => solidify_mask(input, *threshold),
+            MaskOperation::Posterize { levels } => posterize_mask(input, *levels),
+            MaskOperation::Gamma { gamma } => gamma_mask(input, *gamma),
+            MaskOperation::Invert => invert_mask(input),
         }
     }
 }
@@ -79,8 +177,9 @@ impl MaskOperation {
 ///
 /// # Panics
 ///
-/// Panics if `operations` contains a blur operation whose `sigma` is not greater than zero, or a
-/// dilation or erosion operation whose radius is negative or NaN.
+/// Panics if `operations` contains a blur operation whose `sigma` is not greater than zero, a
+/// dilation or erosion operation whose radius is negative or NaN, or a posterize operation with
+/// fewer than two levels.
 pub fn apply_operations(source: &GrayImage, operations: &[MaskOperation]) -> GrayImage {
     let mut current = source.clone();
     for op in operations {
@@ -111,6 +210,11 @@ impl MaskPipeline {
         &self.operations
     }
 
+    /// Apply the pipeline operations in order to a grayscale mask.
+    pub fn apply(&self, mask: &GrayImage) -> GrayImage {
+        apply_operations(mask, &self.operations)
+    }
+
     /// Add a blur operation with a custom sigma.
     ///
     /// # Panics
@@ -122,12 +226,53 @@ impl MaskPipeline {
         self
     }
 
+    /// Add a coverage-normalized blur operation with a custom sigma.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sigma` is not greater than zero.
+    pub fn normalized_blur_with(mut self, sigma: f32) -> Self {
+        assert!(sigma > 0.0, "sigma must be > 0.0");
+        self.operations
+            .push(MaskOperation::NormalizedBlur { sigma });
+        self
+    }
+
+    /// Add an operation that feathers the mask edge over `radius` pixels on each side.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radius` is negative or NaN.
+    pub fn feather(mut self, radius: f32) -> Self {
+        assert_nonnegative_radius(radius);
+        self.operations.push(MaskOperation::Feather { radius });
+        self
+    }
+
     /// Add a threshold operation with a custom value.
     pub fn threshold_with(mut self, value: u8) -> Self {
         self.operations.push(MaskOperation::Threshold { value });
         self
     }
 
+    /// Add a threshold operation whose value is picked per mask with Otsu's method.
+    pub fn otsu_threshold(mut self) -> Self {
+        self.operations.push(MaskOperation::OtsuThreshold);
+        self
+    }
+
+    /// Add a soft threshold that ramps linearly from 0 at `low` to 255 at `high`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `low` is greater than `high`.
+    pub fn soft_threshold(mut self, low: u8, high: u8) -> Self {
+        assert_soft_threshold_bounds(low, high);
+        self.operations
+            .push(MaskOperation::SoftThreshold { low, high });
+        self
+    }
+
     /// Add a dilation operation with a custom radius.
     ///
     /// A radius of zero leaves the mask unchanged.
@@ -168,11 +313,81 @@ impl MaskPipeline {
         self
     }
 
+    /// Add a morphological opening that removes specks and protrusions narrower than `radius`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radius` is negative or NaN.
+    pub fn open(mut self, radius: f32) -> Self {
+        assert_nonnegative_radius(radius);
+        self.operations.push(MaskOperation::Open { radius });
+        self
+    }
+
+    /// Add a morphological closing that bridges gaps and holes narrower than `radius`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radius` is negative or NaN.
+    pub fn close(mut self, radius: f32) -> Self {
+        assert_nonnegative_radius(radius);
+        self.operations.push(MaskOperation::Close { radius });
+        self
+    }
+
+    /// Add an operation that keeps only the largest connected foreground region.
+    pub fn keep_largest_with(mut self, connectivity: Connectivity) -> Self {
+        self.operations
+            .push(MaskOperation::KeepLargestComponent { connectivity });
+        self
+    }
+
+    /// Add an operation that drops connected foreground regions smaller than `min_area` pixels.
+    pub fn remove_small_components(mut self, min_area: u32) -> Self {
+        self.operations
+            .push(MaskOperation::RemoveSmallComponents { min_area });
+        self
+    }
+
     /// Add a hole-filling operation with a custom threshold.
     pub fn fill_holes_with(mut self, threshold: u8) -> Self {
         self.operations.push(MaskOperation::FillHoles { threshold });
         self
     }
+
+    /// Add an operation that makes regions enclosed by strong foreground opaque.
+    pub fn solidify(mut self, threshold: u8) -> Self {
+        self.operations.push(MaskOperation::Solidify { threshold });
+        self
+    }
+
+    /// Add an operation that quantizes the mask to `levels` evenly spaced values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `levels` is less than 2.
+    pub fn posterize(mut self, levels: u8) -> Self {
+        assert_posterize_levels(levels);
+        self.operations.push(MaskOperation::Posterize { levels });
+        self
+    }
+
+    /// Add an operation that applies a gamma curve to the mask.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `gamma` is not greater than zero or not finite.
+    pub fn gamma(mut self, gamma: f32) -> Self {
+        assert_positive_gamma(gamma);
+        self.operations.push(MaskOperation::Gamma { gamma });
+        self
+    }
+
+    /// Add an operation that inverts the mask.
+    pub fn invert(mut self) -> Self {
+        self.operations.push(MaskOperation::Invert);
+        self
+    }
 }
 
 /// Convert a 2D array of f32 values in [0.0, 1.0] to a grayscale image.
@@ -185,6 +400,28 @@ pub fn array_to_gray_image(array: &Array2<f32>) -> GrayImage {
     })
 }
 
+/// Convert a 2D array of f32 values in [0.0, 1.0] to a 16-bit grayscale image.
+///
+/// Values are clamped and mapped to `0..=65535` with rounding, keeping the gradient detail that
+/// [`array_to_gray_image`] quantizes away.
+pub fn array_to_gray16_image(array: &Array2<f32>) -> ImageBuffer<Luma<u16>, Vec<u16>> {
+    let (h, w) = array.dim();
+    ImageBuffer::from_fn(w as u32, h as u32, |x, y| {
+        let value = array[[y as usize, x as usize]].clamp(0.0, 1.0);
+        Luma([(value * 65535.0).round() as u16])
+    })
+}
+
+/// Convert a grayscale image to a 2D array of f32 values in [0.0, 1.0].
+///
+/// This is the inverse of [`array_to_gray_image`]; the array is indexed as `[[y, x]]`.
+pub fn gray_image_to_array(image: &GrayImage) -> Array2<f32> {
+    let (w, h) = image.dimensions();
+    Array2::from_shape_fn((h as usize, w as usize), |(y, x)| {
+        f32::from(image.get_pixel(x as u32, y as u32)[0]) / 255.0
+    })
+}
+
 /// Convert a grayscale image to an RGBA color image.
 #[cfg(feature = "vectorizer-vtracer")]
 pub fn gray_to_color_image_rgba(
@@ -222,12 +459,238 @@ pub fn gray_to_color_image_rgba(
     }
 }
 
+/// Value marking the unknown region of a trimap.
+pub const TRIMAP_UNKNOWN: u8 = 128;
+
+/// Build a trimap for alpha matting from a soft matte.
+///
+/// Pixels at or above `fg_threshold` are confident foreground (255) and pixels at or below
+/// `bg_threshold` are confident background (0); when the thresholds are equal, pixels at that
+/// value count as foreground. Everything else is unknown ([`TRIMAP_UNKNOWN`]). Both confident
+/// regions are then eroded by `unknown_dilate` pixels, widening the unknown band around the
+/// edge so the matting step has room to work. Pixels outside the image do not erode either
+/// region.
+///
+/// Returns [`OutlineError::InvalidTrimapThresholds`] if `fg_threshold < bg_threshold`.
+///
+/// # Panics
+///
+/// Panics if `unknown_dilate` is negative or NaN.
+pub fn matte_to_trimap(
+    matte: &GrayImage,
+    fg_threshold: u8,
+    bg_threshold: u8,
+    unknown_dilate: f32,
+) -> OutlineResult<GrayImage> {
+    if fg_threshold < bg_threshold {
+        return Err(OutlineError::InvalidTrimapThresholds {
+            fg_threshold,
+            bg_threshold,
+        });
+    }
+    assert_nonnegative_radius(unknown_dilate);
+
+    let region = |inside: &dyn Fn(u8) -> bool| {
+        let mut out = matte.clone();
+        for pixel in out.pixels_mut() {
+            pixel[0] = if inside(pixel[0]) { 255 } else { 0 };
+        }
+        erode_euclidean_with_border_mode(&out, unknown_dilate, ErosionBorderMode::OutsideIsUnknown)
+    };
+    let foreground = region(&|value| value >= fg_threshold);
+    let background = region(&|value| value < fg_threshold && value <= bg_threshold);
+
+    let mut trimap = GrayImage::from_pixel(matte.width(), matte.height(), Luma([TRIMAP_UNKNOWN]));
+    for ((pixel, fg), bg) in trimap
+        .pixels_mut()
+        .zip(foreground.pixels())
+        .zip(background.pixels())
+    {
+        if fg[0] == 255 {
+            pixel[0] = 255;
+        } else if bg[0] == 255 {
+            pixel[0] = 0;
+        }
+    }
+    Ok(trimap)
+}
+
+/// Feather a hard mask's outline with a linear alpha ramp.
+///
+/// The mask is split at the midpoint, then each pixel's alpha follows its signed Euclidean
+/// distance to the outline: 0.5 on the edge, rising to 255 at `radius` pixels inside and falling
+/// to 0 at `radius` pixels outside. Only that band changes, so the interior stays exactly 255
+/// and the background exactly 0, unlike a Gaussian blur that touches the whole image. A radius of
+/// zero returns the binarized mask.
+///
+/// # Panics
+///
+/// Panics if `radius` is negative or NaN.
+pub fn feather_mask(mask: &GrayImage, radius: f32) -> GrayImage {
+    assert_nonnegative_radius(radius);
+
+    let binary = threshold_mask(mask, 127);
+    if radius == 0.0 {
+        return binary;
+    }
+
+    // Squared distances to the nearest foreground and background pixel centers.
+    let to_foreground = euclidean_squared_distance_transform(&binary);
+    let to_background = euclidean_squared_distance_transform(&invert_mask(&binary));
+    let radius = f64::from(radius);
+    let mut out = GrayImage::new(mask.width(), mask.height());
+    for ((pixel, fg), (bg, inside)) in out
+        .pixels_mut()
+        .zip(to_foreground.pixels())
+        .zip(to_background.pixels().zip(binary.pixels()))
+    {
+        // The outline runs half a pixel from the centers on either side of it.
+        let signed = if inside[0] == 255 {
+            bg[0].sqrt() - 0.5
+        } else {
+            0.5 - fg[0].sqrt()
+        };
+        let alpha = ((signed + radius) / (2.0 * radius)).clamp(0.0, 1.0);
+        pixel[0] = (alpha * 255.0).round() as u8;
+    }
+    out
+}
+
+/// Blur a mask without pulling the subject's edge alpha toward zero.
+///
+/// A plain Gaussian blur averages foreground pixels with the background around them, so the
+/// edges of the subject lose alpha and the subject looks thinner. Here pixels inside the
+/// subject (non-zero alpha) are divided by the blurred coverage of the subject, so only
+/// foreground pixels contribute to their average. Background pixels get the plain blur, which
+/// feathers the edge outward instead of inward.
+///
+/// # Panics
+///
+/// Panics if `sigma` is not greater than zero.
+pub fn normalized_blur_mask(mask: &GrayImage, sigma: f32) -> GrayImage {
+    assert!(sigma > 0.0, "sigma must be > 0.0");
+    let (w, h) = mask.dimensions();
+    let alpha = ImageBuffer::from_fn(w, h, |x, y| Luma([f32::from(mask.get_pixel(x, y)[0])]));
+    let coverage = ImageBuffer::from_fn(w, h, |x, y| {
+        Luma([if mask.get_pixel(x, y)[0] > 0 {
+            1.0f32
+        } else {
+            0.0
+        }])
+    });
+    let blurred_alpha = gaussian_blur_f32(&alpha, sigma);
+    let blurred_coverage = gaussian_blur_f32(&coverage, sigma);
+
+    GrayImage::from_fn(w, h, |x, y| {
+        let blurred = blurred_alpha.get_pixel(x, y)[0];
+        let value = if mask.get_pixel(x, y)[0] > 0 {
+            blurred / blurred_coverage.get_pixel(x, y)[0].max(f32::EPSILON)
+        } else {
+            blurred
+        };
+        Luma([value.round().clamp(0.0, 255.0) as u8])
+    })
+}
+
 /// Threshold the grayscale image to produce a binary mask.
 pub fn threshold_mask(gray: &GrayImage, thr: u8) -> GrayImage {
     ip_threshold(gray, thr, ThresholdType::Binary)
 }
 
-fn assert_nonnegative_radius(radius: f32) {
+/// Threshold with a linear ramp instead of a hard step.
+///
+/// Values at or below `low` become 0, values at or above `high` become 255, and values in
+/// between are stretched linearly across the full range. The result is mostly binary but keeps
+/// a narrow feathered band at the boundary. With `low == high` this matches
+/// [`threshold_mask`] at that value.
+///
+/// # Panics
+///
+/// Panics if `low` is greater than `high`.
+pub fn soft_threshold_mask(mask: &GrayImage, low: u8, high: u8) -> GrayImage {
+    assert_soft_threshold_bounds(low, high);
+    let span = f32::from(high - low);
+    let mut out = mask.clone();
+    for pixel in out.pixels_mut() {
+        let value = pixel[0];
+        pixel[0] = if value <= low {
+            0
+        } else if value >= high {
+            255
+        } else {
+            (f32::from(value - low) / span * 255.0).round() as u8
+        };
+    }
+    out
+}
+
+fn assert_soft_threshold_bounds(low: u8, high: u8) {
+    assert!(low <= high, "soft threshold low must be <= high");
+}
+
+/// Pick a threshold for `gray` with Otsu's method.
+///
+/// The returned value maximizes the between-class variance of the histogram when pixels at or
+/// below it count as background, so passing it to [`threshold_mask`] separates the two classes.
+/// Uniform and empty images return 0.
+pub fn otsu_threshold(gray: &GrayImage) -> u8 {
+    otsu_level(gray)
+}
+
+/// Quantize a mask to `levels` evenly spaced values between 0 and 255.
+///
+/// Each pixel maps to the nearest level, so `levels == 2` is a threshold at the midpoint and
+/// `levels == 4` produces alpha values 0, 85, 170, and 255. At most 255 levels fit in the `u8`;
+/// 256 would be the identity.
+///
+/// # Panics
+///
+/// Panics if `levels` is less than 2.
+pub fn posterize_mask(mask: &GrayImage, levels: u8) -> GrayImage {
+    assert_posterize_levels(levels);
+    let steps = f32::from(levels - 1);
+    let mut out = mask.clone();
+    for pixel in out.pixels_mut() {
+        let level = (f32::from(pixel[0]) / 255.0 * steps).round();
+        pixel[0] = (level / steps * 255.0).round() as u8;
+    }
+    out
+}
+
+pub(crate) fn assert_posterize_levels(levels: u8) {
+    assert!(levels >= 2, "posterize levels must be >= 2");
+}
+
+/// Apply a gamma curve so each value `v` becomes `255 * (v / 255)^gamma`, rounded.
+///
+/// With `gamma > 1.0` mid-gray values move toward 0 and with `gamma < 1.0` toward 255, while
+/// 0 and 255 stay fixed, so a hard mask stays hard. Useful before thresholding a matte whose
+/// soft values cluster around mid-gray. `gamma == 1.0` leaves the mask unchanged.
+///
+/// # Panics
+///
+/// Panics if `gamma` is not greater than zero or not finite.
+pub fn gamma_mask(mask: &GrayImage, gamma: f32) -> GrayImage {
+    assert_positive_gamma(gamma);
+    let mut lut = [0u8; 256];
+    for (value, entry) in lut.iter_mut().enumerate() {
+        *entry = ((value as f32 / 255.0).powf(gamma) * 255.0).round() as u8;
+    }
+    let mut out = mask.clone();
+    for pixel in out.pixels_mut() {
+        pixel[0] = lut[usize::from(pixel[0])];
+    }
+    out
+}
+
+fn assert_positive_gamma(gamma: f32) {
+    assert!(
+        gamma > 0.0 && gamma.is_finite(),
+        "gamma must be > 0.0 and finite"
+    );
+}
+
+pub(crate) fn assert_nonnegative_radius(radius: f32) {
     assert!(radius >= 0.0, "radius must be >= 0.0");
 }
 
@@ -296,6 +759,34 @@ pub fn erode_euclidean_with_border_mode(
     }
 }
 
+/// Morphologically open a binary mask: erode, then dilate by the same radius.
+///
+/// Foreground specks and protrusions narrower than the radius disappear while larger shapes
+/// keep their outline. Pixels outside the image count as unknown during erosion, so shapes
+/// touching the border are not trimmed. A radius of zero leaves the mask unchanged.
+///
+/// # Panics
+///
+/// Panics if `r` is negative or NaN.
+pub fn open_mask(mask_bin: &GrayImage, r: f32) -> GrayImage {
+    let eroded = erode_euclidean_with_border_mode(mask_bin, r, ErosionBorderMode::OutsideIsUnknown);
+    dilate_euclidean(&eroded, r)
+}
+
+/// Morphologically close a binary mask: dilate, then erode by the same radius.
+///
+/// Background holes and gaps narrower than the radius are filled while the outer outline is
+/// kept. Pixels outside the image count as unknown during erosion, so shapes touching the
+/// border are not trimmed. A radius of zero leaves the mask unchanged.
+///
+/// # Panics
+///
+/// Panics if `r` is negative or NaN.
+pub fn close_mask(mask_bin: &GrayImage, r: f32) -> GrayImage {
+    let dilated = dilate_euclidean(mask_bin, r);
+    erode_euclidean_with_border_mode(&dilated, r, ErosionBorderMode::OutsideIsUnknown)
+}
+
 /// Invert a grayscale mask so each pixel becomes `255 - value`.
 pub fn invert_mask(mask: &GrayImage) -> GrayImage {
     let (w, h) = mask.dimensions();
@@ -306,6 +797,108 @@ pub fn invert_mask(mask: &GrayImage) -> GrayImage {
     out
 }
 
+/// Measure how aliased the edges of a matte are.
+///
+/// Looks at every pair of horizontally or vertically adjacent pixels whose values differ and
+/// returns the fraction that jump directly between `0` and `255`. A hard, thresholded mask
+/// scores `1.0`, a smoothly ramped matte scores close to `0.0`, and a matte with no
+/// transitions at all scores `0.0`.
+pub fn edge_hardness(mask: &GrayImage) -> f32 {
+    let (w, h) = mask.dimensions();
+    let mut transitions = 0u64;
+    let mut hard = 0u64;
+    let mut count = |a: u8, b: u8| {
+        if a != b {
+            transitions += 1;
+            if a.abs_diff(b) == u8::MAX {
+                hard += 1;
+            }
+        }
+    };
+
+    for y in 0..h {
+        for x in 0..w {
+            let value = mask.get_pixel(x, y)[0];
+            if x + 1 < w {
+                count(value, mask.get_pixel(x + 1, y)[0]);
+            }
+            if y + 1 < h {
+                count(value, mask.get_pixel(x, y + 1)[0]);
+            }
+        }
+    }
+
+    if transitions == 0 {
+        0.0
+    } else {
+        hard as f32 / transitions as f32
+    }
+}
+
+/// Summary of a matte's values, returned by [`matte_stats`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MatteStats {
+    /// Number of pixels at or above the foreground threshold.
+    pub foreground_pixels: u64,
+    /// Fraction of pixels at or above the foreground threshold, in `0.0..=1.0`.
+    pub coverage: f32,
+    /// Smallest matte value.
+    pub min: u8,
+    /// Largest matte value.
+    pub max: u8,
+    /// Mean matte value in `0.0..=255.0`.
+    pub mean: f32,
+}
+
+/// Count the foreground pixels of a matte and summarize its values.
+///
+/// Pixels at or above `threshold` count as foreground. A coverage near `0.0` or `1.0` usually
+/// means the model found nothing or failed to separate the subject. An empty matte reports zero
+/// for every field.
+pub fn matte_stats(matte: &GrayImage, threshold: u8) -> MatteStats {
+    let mut foreground_pixels = 0u64;
+    let mut min = u8::MAX;
+    let mut max = u8::MIN;
+    let mut sum = 0u64;
+    for pixel in matte.pixels() {
+        let value = pixel[0];
+        if value >= threshold {
+            foreground_pixels += 1;
+        }
+        min = min.min(value);
+        max = max.max(value);
+        sum += u64::from(value);
+    }
+
+    let total = u64::from(matte.width()) * u64::from(matte.height());
+    if total == 0 {
+        return MatteStats {
+            foreground_pixels: 0,
+            coverage: 0.0,
+            min: 0,
+            max: 0,
+            mean: 0.0,
+        };
+    }
+    MatteStats {
+        foreground_pixels,
+        coverage: (foreground_pixels as f64 / total as f64) as f32,
+        min,
+        max,
+        mean: (sum as f64 / total as f64) as f32,
+    }
+}
+
+/// Find the subject's bounding box: the smallest rectangle holding every matte pixel at or above
+/// `threshold`.
+///
+/// Returns `None` for a matte with no such pixels, such as an empty mask. Crop foregrounds and
+/// masks to the box with their `crop` methods to drop the transparent border.
+pub fn subject_bbox(matte: &GrayImage, threshold: u8) -> Option<BoundingBox> {
+    mask_bounding_box(matte, threshold)
+}
+
 /// Fill holes in a binary mask using a flood-fill algorithm from the borders.
 pub fn fill_mask_holes(mask: &GrayImage, threshold: u8) -> GrayImage {
     let (w, h) = mask.dimensions();
@@ -313,28 +906,161 @@ pub fn fill_mask_holes(mask: &GrayImage, threshold: u8) -> GrayImage {
         return mask.clone();
     }
 
-    let (w_usize, h_usize) = (w as usize, h as usize);
-    let mut visited = vec![false; w_usize * h_usize];
-    let mut queue = VecDeque::new();
+    let background = border_connected_below(mask, threshold);
+    let mut out = GrayImage::new(w, h);
+    for ((out_pixel, mask_pixel), outside) in out.pixels_mut().zip(mask.pixels()).zip(background) {
+        // A pixel is part of a hole if it's dark but was not visited
+        let filled = if mask_pixel[0] >= threshold || !outside {
+            255
+        } else {
+            0
+        };
+        *out_pixel = Luma([filled]);
+    }
 
-    let idx = |x: u32, y: u32| -> usize { (y as usize) * w_usize + x as usize };
-    let mask_raw = mask.as_raw();
+    out
+}
 
-    // Start flood-fill from all dark pixels at the image borders
-    for x in 0..w {
-        if mask_raw[idx(x, 0)] < threshold {
-            queue.push_back((x, 0));
-        }
-        if mask_raw[idx(x, h - 1)] < threshold {
-            queue.push_back((x, h - 1));
+/// Keep only the largest connected foreground region, zeroing every other region.
+///
+/// Nonzero pixels count as foreground, so this is meant for binary masks; on a soft matte the
+/// faint values around the subject tend to join everything into one region. Pixels of the kept
+/// region keep their values. When several regions share the largest size, the one whose first
+/// pixel comes earliest in row-major order wins. A mask with no foreground is returned as is.
+pub fn keep_largest_component(mask: &GrayImage, connectivity: Connectivity) -> GrayImage {
+    let (labels, sizes) = label_components(mask, connectivity);
+    // Labels follow row-major order of each region's first pixel, so keeping the first region
+    // that reaches the largest size breaks ties deterministically.
+    let (mut best_label, mut best_size) = (0, 0);
+    for (index, &size) in sizes.iter().enumerate() {
+        if size > best_size {
+            best_label = index + 1;
+            best_size = size;
         }
     }
+    retain_labels(mask, &labels, |label| label == best_label)
+}
 
-    for y in 0..h {
-        if mask_raw[idx(0, y)] < threshold {
-            queue.push_back((0, y));
+/// Zero every 8-connected foreground region with fewer than `min_area` pixels.
+///
+/// Like [`keep_largest_component`], nonzero pixels count as foreground and the kept regions keep
+/// their values, but every region that is large enough survives. Use it when the image holds
+/// several legitimate subjects and only stray specks should go.
+pub fn remove_small_components(mask: &GrayImage, min_area: u32) -> GrayImage {
+    let (labels, sizes) = label_components(mask, Connectivity::Eight);
+    retain_labels(mask, &labels, |label| sizes[label - 1] >= min_area as usize)
+}
+
+// Keep the pixels whose component label passes `keep`, zeroing background and dropped regions.
+fn retain_labels(mask: &GrayImage, labels: &[usize], keep: impl Fn(usize) -> bool) -> GrayImage {
+    let mut out = mask.clone();
+    for (pixel, &label) in out.pixels_mut().zip(labels) {
+        if label == 0 || !keep(label) {
+            pixel[0] = 0;
         }
-        if mask_raw[idx(w - 1, y)] < threshold {
+    }
+    out
+}
+
+// Label connected nonzero regions in row-major order of their first pixel. Returns one label per
+// pixel (0 for background, regions from 1) and each region's pixel count, indexed by label - 1.
+pub(crate) fn label_components(
+    mask: &GrayImage,
+    connectivity: Connectivity,
+) -> (Vec<usize>, Vec<usize>) {
+    let (w, h) = mask.dimensions();
+    let (w_usize, h_usize) = (w as usize, h as usize);
+    let mask_raw = mask.as_raw();
+    let mut labels = vec![0usize; w_usize * h_usize];
+    let mut sizes = Vec::new();
+    let mut queue = VecDeque::new();
+
+    for start in 0..mask_raw.len() {
+        if mask_raw[start] == 0 || labels[start] != 0 {
+            continue;
+        }
+        let next_label = sizes.len() + 1;
+        labels[start] = next_label;
+        queue.push_back(start);
+        let mut size = 0usize;
+
+        while let Some(id) = queue.pop_front() {
+            size += 1;
+            let (x, y) = ((id % w_usize) as i64, (id / w_usize) as i64);
+            for dy in -1i64..=1 {
+                for dx in -1i64..=1 {
+                    if (dx, dy) == (0, 0)
+                        || (connectivity == Connectivity::Four && dx != 0 && dy != 0)
+                    {
+                        continue;
+                    }
+                    let (nx, ny) = (x + dx, y + dy);
+                    if nx < 0 || ny < 0 || nx >= w as i64 || ny >= h as i64 {
+                        continue;
+                    }
+                    let nid = ny as usize * w_usize + nx as usize;
+                    if mask_raw[nid] != 0 && labels[nid] == 0 {
+                        labels[nid] = next_label;
+                        queue.push_back(nid);
+                    }
+                }
+            }
+        }
+
+        sizes.push(size);
+    }
+
+    (labels, sizes)
+}
+
+/// Make regions enclosed by strong foreground fully opaque while keeping soft edges.
+///
+/// Pixels below `threshold` that cannot reach the image border without crossing a pixel at or
+/// above `threshold` are set to 255. Every other pixel keeps its value, so unlike
+/// [`fill_mask_holes`] the anti-aliased outline of the subject survives.
+pub fn solidify_mask(mask: &GrayImage, threshold: u8) -> GrayImage {
+    let (w, h) = mask.dimensions();
+    if w == 0 || h == 0 {
+        return mask.clone();
+    }
+
+    let background = border_connected_below(mask, threshold);
+    let mut out = mask.clone();
+    for (pixel, outside) in out.pixels_mut().zip(background) {
+        if pixel[0] < threshold && !outside {
+            pixel[0] = 255;
+        }
+    }
+
+    out
+}
+
+// Flag every pixel below `threshold` that is 4-connected to the image border through other
+// pixels below `threshold`. The mask must not be empty.
+fn border_connected_below(mask: &GrayImage, threshold: u8) -> Vec<bool> {
+    let (w, h) = mask.dimensions();
+    let (w_usize, h_usize) = (w as usize, h as usize);
+    let mut visited = vec![false; w_usize * h_usize];
+    let mut queue = VecDeque::new();
+
+    let idx = |x: u32, y: u32| -> usize { (y as usize) * w_usize + x as usize };
+    let mask_raw = mask.as_raw();
+
+    // Start flood-fill from all dark pixels at the image borders
+    for x in 0..w {
+        if mask_raw[idx(x, 0)] < threshold {
+            queue.push_back((x, 0));
+        }
+        if mask_raw[idx(x, h - 1)] < threshold {
+            queue.push_back((x, h - 1));
+        }
+    }
+
+    for y in 0..h {
+        if mask_raw[idx(0, y)] < threshold {
+            queue.push_back((0, y));
+        }
+        if mask_raw[idx(w - 1, y)] < threshold {
             queue.push_back((w - 1, y));
         }
     }
@@ -378,20 +1104,206 @@ pub fn fill_mask_holes(mask: &GrayImage, threshold: u8) -> GrayImage {
         }
     }
 
-    let mut out = GrayImage::new(w, h);
-    for ((x, y, out_pixel), mask_pixel) in out.enumerate_pixels_mut().zip(mask.pixels()) {
-        let id = idx(x, y);
-        let value = mask_pixel[0];
-        // A pixel is part of a hole if it's dark but was not visited
-        let filled = if value >= threshold || !visited[id] {
-            255
-        } else {
-            0
-        };
-        *out_pixel = Luma([filled]);
+    visited
+}
+
+/// Mask processing methods on plain [`GrayImage`]s.
+///
+/// Each method returns a new image, so steps chain without building a handle:
+///
+/// ```
+/// use image::GrayImage;
+/// use outline::MaskExt;
+///
+/// let matte = GrayImage::from_fn(16, 16, |x, _| image::Luma([(x * 16) as u8]));
+/// let mask = matte.threshold(120).dilate(3.0).fill_holes(128);
+/// assert!(mask.pixels().all(|p| p[0] == 0 || p[0] == 255));
+/// ```
+///
+/// The methods mirror the [`MaskOperation`] variants and panic on the same invalid arguments.
+pub trait MaskExt {
+    /// Apply Gaussian blur with the given sigma.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sigma` is not greater than zero.
+    fn blur(&self, sigma: f32) -> GrayImage;
+
+    /// Apply a Gaussian blur normalized by the subject's coverage, see [`normalized_blur_mask`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sigma` is not greater than zero.
+    fn normalized_blur(&self, sigma: f32) -> GrayImage;
+
+    /// Feather the outline over `radius` pixels on each side, see [`feather_mask`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radius` is negative or NaN.
+    fn feather(&self, radius: f32) -> GrayImage;
+
+    /// Threshold into a binary mask; pixels above `value` become 255.
+    fn threshold(&self, value: u8) -> GrayImage;
+
+    /// Threshold into a binary mask at the value picked by [`otsu_threshold`].
+    fn otsu_threshold(&self) -> GrayImage;
+
+    /// Threshold with a linear ramp between `low` and `high`, see [`soft_threshold_mask`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `low` is greater than `high`.
+    fn soft_threshold(&self, low: u8, high: u8) -> GrayImage;
+
+    /// Expand white regions by a Euclidean radius.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radius` is negative or NaN.
+    fn dilate(&self, radius: f32) -> GrayImage;
+
+    /// Shrink white regions by a Euclidean radius, treating pixels outside the image as background.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radius` is negative or NaN.
+    fn erode(&self, radius: f32) -> GrayImage;
+
+    /// Shrink white regions by a Euclidean radius with the given boundary behavior.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radius` is negative or NaN.
+    fn erode_with_border_mode(&self, radius: f32, border_mode: ErosionBorderMode) -> GrayImage;
+
+    /// Erode, then dilate, removing specks narrower than `radius`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radius` is negative or NaN.
+    fn open(&self, radius: f32) -> GrayImage;
+
+    /// Dilate, then erode, filling holes narrower than `radius`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radius` is negative or NaN.
+    fn close(&self, radius: f32) -> GrayImage;
+
+    /// Keep only the largest connected foreground region.
+    fn keep_largest(&self, connectivity: Connectivity) -> GrayImage;
+
+    /// Drop connected foreground regions smaller than `min_area` pixels.
+    fn remove_small_components(&self, min_area: u32) -> GrayImage;
+
+    /// Fill background regions not connected to the image border.
+    fn fill_holes(&self, threshold: u8) -> GrayImage;
+
+    /// Set regions enclosed by values at or above `threshold` to 255, keeping soft edges.
+    fn solidify(&self, threshold: u8) -> GrayImage;
+
+    /// Quantize to `levels` evenly spaced values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `levels` is less than 2.
+    fn posterize(&self, levels: u8) -> GrayImage;
+
+    /// Apply a gamma curve, see [`gamma_mask`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `gamma` is not greater than zero or not finite.
+    fn gamma(&self, gamma: f32) -> GrayImage;
+
+    /// Invert so each pixel becomes `255 - value`.
+    fn invert(&self) -> GrayImage;
+
+    /// Apply a sequence of operations in order.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`apply_operations`].
+    fn apply_operations(&self, operations: &[MaskOperation]) -> GrayImage;
+}
+
+impl MaskExt for GrayImage {
+    fn blur(&self, sigma: f32) -> GrayImage {
+        gaussian_blur_f32(self, sigma)
     }
 
-    out
+    fn normalized_blur(&self, sigma: f32) -> GrayImage {
+        normalized_blur_mask(self, sigma)
+    }
+
+    fn feather(&self, radius: f32) -> GrayImage {
+        feather_mask(self, radius)
+    }
+
+    fn threshold(&self, value: u8) -> GrayImage {
+        threshold_mask(self, value)
+    }
+
+    fn otsu_threshold(&self) -> GrayImage {
+        threshold_mask(self, otsu_threshold(self))
+    }
+
+    fn soft_threshold(&self, low: u8, high: u8) -> GrayImage {
+        soft_threshold_mask(self, low, high)
+    }
+
+    fn dilate(&self, radius: f32) -> GrayImage {
+        dilate_euclidean(self, radius)
+    }
+
+    fn erode(&self, radius: f32) -> GrayImage {
+        erode_euclidean_with_border_mode(self, radius, ErosionBorderMode::default())
+    }
+
+    fn erode_with_border_mode(&self, radius: f32, border_mode: ErosionBorderMode) -> GrayImage {
+        erode_euclidean_with_border_mode(self, radius, border_mode)
+    }
+
+    fn open(&self, radius: f32) -> GrayImage {
+        open_mask(self, radius)
+    }
+
+    fn close(&self, radius: f32) -> GrayImage {
+        close_mask(self, radius)
+    }
+
+    fn keep_largest(&self, connectivity: Connectivity) -> GrayImage {
+        keep_largest_component(self, connectivity)
+    }
+
+    fn remove_small_components(&self, min_area: u32) -> GrayImage {
+        remove_small_components(self, min_area)
+    }
+
+    fn fill_holes(&self, threshold: u8) -> GrayImage {
+        fill_mask_holes(self, threshold)
+    }
+
+    fn solidify(&self, threshold: u8) -> GrayImage {
+        solidify_mask(self, threshold)
+    }
+
+    fn posterize(&self, levels: u8) -> GrayImage {
+        posterize_mask(self, levels)
+    }
+
+    fn gamma(&self, gamma: f32) -> GrayImage {
+        gamma_mask(self, gamma)
+    }
+
+    fn invert(&self) -> GrayImage {
+        invert_mask(self)
+    }
+
+    fn apply_operations(&self, operations: &[MaskOperation]) -> GrayImage {
+        apply_operations(self, operations)
+    }
 }
 
 /// How mask values are converted into output alpha.
@@ -459,6 +1371,33 @@ impl MaskColor {
         self.alpha_mode = alpha_mode;
         self
     }
+
+    /// Reject combinations that make every colorized pixel fully transparent.
+    ///
+    /// [`colorize_mask`] accepts any color, so a zero base alpha, `Solid(0)`, or a `Scale`
+    /// factor that is zero, negative (clamped to zero), or not finite silently yields an
+    /// invisible layer. [`try_colorize_mask`] and the `colorize` methods of [`MaskHandle`] and
+    /// [`MatteHandle`](crate::MatteHandle) call this first and return
+    /// [`OutlineError::InvalidMaskColor`] describing the problem instead.
+    pub fn validate(&self) -> OutlineResult<()> {
+        if self.color[3] == 0 {
+            return Err(OutlineError::InvalidMaskColor(
+                "base alpha is 0, so the layer is fully transparent".to_string(),
+            ));
+        }
+        match self.alpha_mode {
+            MaskAlphaMode::Scale(scale) if !scale.is_finite() => Err(
+                OutlineError::InvalidMaskColor(format!("alpha scale must be finite, got {scale}")),
+            ),
+            MaskAlphaMode::Scale(scale) if scale <= 0.0 => Err(OutlineError::InvalidMaskColor(
+                format!("alpha scale {scale} is clamped to 0, so the layer is fully transparent"),
+            )),
+            MaskAlphaMode::Solid(0) => Err(OutlineError::InvalidMaskColor(
+                "solid alpha is 0, so the layer is fully transparent".to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
 }
 
 impl Default for MaskColor {
@@ -527,6 +1466,19 @@ pub fn colorize_mask(mask: &GrayImage, color: impl Into<MaskColor>) -> RgbaImage
     out
 }
 
+/// Convert a grayscale mask into a flat-color RGBA image after checking the color.
+///
+/// Same as [`colorize_mask`], but returns [`OutlineError::InvalidMaskColor`] when
+/// [`MaskColor::validate`] rejects the color instead of producing an invisible layer.
+pub fn try_colorize_mask(
+    mask: &GrayImage,
+    color: impl Into<MaskColor>,
+) -> OutlineResult<RgbaImage> {
+    let color = color.into();
+    color.validate()?;
+    Ok(colorize_mask(mask, color))
+}
+
 /// Processed mask image with optional further refinement and output generation.
 ///
 /// Represents a concrete mask image (typically binary after thresholding) produced by executing
@@ -551,7 +1503,7 @@ pub fn colorize_mask(mask: &GrayImage, color: impl Into<MaskColor>) -> RgbaImage
 #[derive(Debug, Clone)]
 pub struct MaskHandle {
     rgb_image: Arc<RgbImage>,
-    mask: GrayImage,
+    mask: Arc<GrayImage>,
     mask_processing_defaults: MaskProcessingDefaults,
     operations: Vec<MaskOperation>,
 }
@@ -559,12 +1511,12 @@ pub struct MaskHandle {
 impl MaskHandle {
     pub(crate) fn new(
         rgb_image: Arc<RgbImage>,
-        mask: GrayImage,
+        mask: impl Into<Arc<GrayImage>>,
         mask_processing_defaults: MaskProcessingDefaults,
     ) -> Self {
         Self {
             rgb_image,
-            mask,
+            mask: mask.into(),
             mask_processing_defaults,
             operations: Vec::new(),
         }
@@ -576,26 +1528,26 @@ impl MaskHandle {
         }
 
         let operations = std::mem::take(&mut self.operations);
-        let mask = apply_operations(&self.mask, &operations);
+        let mask = apply_operations(self.mask.as_ref(), &operations);
         Self::new(self.rgb_image, mask, self.mask_processing_defaults)
     }
 
     fn resolved_mask(&self) -> Cow<'_, GrayImage> {
         if self.operations.is_empty() {
-            Cow::Borrowed(&self.mask)
+            Cow::Borrowed(self.mask.as_ref())
         } else {
-            Cow::Owned(apply_operations(&self.mask, &self.operations))
+            Cow::Owned(apply_operations(self.mask.as_ref(), &self.operations))
         }
     }
 
     /// Clone and return the raw mask.
     pub fn to_raw_mask(&self) -> GrayImage {
-        self.mask.clone()
+        (*self.mask).clone()
     }
 
     /// Get a reference to the raw mask.
     pub fn as_raw_mask(&self) -> &GrayImage {
-        &self.mask
+        self.mask.as_ref()
     }
 
     /// Return the current mask canvas dimensions.
@@ -617,7 +1569,7 @@ impl MaskHandle {
 
     /// Consume the handle and return the current mask.
     pub fn into_image(self) -> GrayImage {
-        self.resolve_pending_operations().mask
+        Arc::unwrap_or_clone(self.resolve_pending_operations().mask)
     }
 
     /// Save the current mask to the specified path.
@@ -626,6 +1578,36 @@ impl MaskHandle {
         Ok(())
     }
 
+    /// Encode the current mask as an 8-bit grayscale PNG in memory.
+    ///
+    /// Use this instead of [`save`](Self::save) to return the mask over a network or store it
+    /// without touching the disk.
+    pub fn to_png_bytes(&self) -> OutlineResult<Vec<u8>> {
+        encode_png(&self.resolved_mask())
+    }
+
+    /// Encode the current mask as `format` in memory.
+    pub fn to_bytes(&self, format: ImageFormat) -> OutlineResult<Vec<u8>> {
+        encode_image(&self.resolved_mask(), format)
+    }
+
+    /// Encode the current mask as `format` into `writer`, such as a socket or buffer.
+    pub fn write_to<W: Write>(&self, writer: &mut W, format: ImageFormat) -> OutlineResult<()> {
+        write_image(&self.resolved_mask(), writer, format)
+    }
+
+    /// Return whether the current mask only contains `0` and `255` pixels.
+    pub fn is_binary(&self) -> bool {
+        is_binary_mask(&self.resolved_mask())
+    }
+
+    /// Save the current mask as a 1-bit grayscale PNG.
+    ///
+    /// See [`save_1bit_png`](crate::save_1bit_png) for how non-binary pixels are written.
+    pub fn save_1bit(&self, path: impl AsRef<Path>) -> OutlineResult<()> {
+        save_1bit_png(&self.resolved_mask(), path)
+    }
+
     /// Compute the bounding box of the current mask using a non-zero threshold.
     pub fn bounding_box(&self) -> Option<BoundingBox> {
         self.bounding_box_with(1)
@@ -637,6 +1619,13 @@ impl MaskHandle {
         mask_bounding_box(&mask, threshold)
     }
 
+    /// Trace the outer boundary of each foreground region of the current mask as pixel polygons.
+    ///
+    /// See [`contours`](crate::contours) for the tracing rules.
+    pub fn contours(&self) -> Vec<Vec<(u32, u32)>> {
+        contours(&self.resolved_mask())
+    }
+
     /// Add a blur operation using the default sigma.
     ///
     /// # Panics
@@ -660,6 +1649,48 @@ impl MaskHandle {
         self
     }
 
+    /// Add a coverage-normalized blur using the default sigma.
+    ///
+    /// Unlike [`blur`](MaskHandle::blur), this does not thin the subject; see
+    /// [`MaskOperation::NormalizedBlur`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configured default blur sigma is not greater than zero.
+    pub fn normalized_blur(self) -> Self {
+        let sigma = self.mask_processing_defaults.blur_sigma;
+        self.normalized_blur_with(sigma)
+    }
+
+    /// Add a coverage-normalized blur with a custom sigma.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sigma` is not greater than zero.
+    pub fn normalized_blur_with(mut self, sigma: f32) -> Self {
+        assert!(sigma > 0.0, "sigma must be > 0.0");
+        self.operations
+            .push(MaskOperation::NormalizedBlur { sigma });
+        self
+    }
+
+    /// Feather the mask edge with a linear alpha ramp `radius` pixels to each side of the outline.
+    ///
+    /// Unlike a blur, pixels farther than `radius` from the edge keep their value, so the
+    /// opaque interior stays exactly 255; see [`feather_mask`].
+    ///
+    /// **Note**: Feathering assumes a hard mask. If this mask is still grayscale, call
+    /// [`threshold`](MaskHandle::threshold) first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radius` is negative or NaN.
+    pub fn feather(mut self, radius: f32) -> Self {
+        assert_nonnegative_radius(radius);
+        self.operations.push(MaskOperation::Feather { radius });
+        self
+    }
+
     /// Add a threshold operation using the default mask threshold.
     pub fn threshold(mut self) -> Self {
         let value = self.mask_processing_defaults.mask_threshold;
@@ -673,6 +1704,27 @@ impl MaskHandle {
         self
     }
 
+    /// Add a threshold operation whose value is picked per mask with Otsu's method.
+    ///
+    /// See [`otsu_threshold`](crate::otsu_threshold) to read the picked value.
+    pub fn otsu_threshold(mut self) -> Self {
+        self.operations.push(MaskOperation::OtsuThreshold);
+        self
+    }
+
+    /// Add a soft threshold: values at or below `low` become 0, at or above `high` become 255,
+    /// and values in between ramp linearly, keeping a feathered band at the boundary.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `low` is greater than `high`.
+    pub fn soft_threshold(mut self, low: u8, high: u8) -> Self {
+        assert_soft_threshold_bounds(low, high);
+        self.operations
+            .push(MaskOperation::SoftThreshold { low, high });
+        self
+    }
+
     /// Add a dilation operation using the default radius.
     ///
     /// **Note**: Dilation typically works best on binary masks. If this mask is still grayscale,
@@ -766,21 +1818,126 @@ impl MaskHandle {
         self
     }
 
-    /// Add a hole-filling operation to the processing pipeline.
+    /// Add a morphological opening (erode, then dilate) that removes specks and protrusions
+    /// narrower than `radius`.
     ///
-    /// **Note**: Hole-filling typically works best on binary masks. If this mask is still grayscale,
-    /// consider calling [`threshold`](MaskHandle::threshold) first.
-    pub fn fill_holes(self) -> Self {
-        let threshold = self.mask_processing_defaults.mask_threshold;
-        self.fill_holes_with(threshold)
+    /// **Note**: Opening assumes a binary mask. If this mask is still grayscale, call
+    /// [`threshold`](MaskHandle::threshold) first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radius` is negative or NaN.
+    pub fn open(mut self, radius: f32) -> Self {
+        assert_nonnegative_radius(radius);
+        self.operations.push(MaskOperation::Open { radius });
+        self
     }
 
-    /// Add a hole-filling operation with a custom threshold.
+    /// Add a morphological closing (dilate, then erode) that fills holes and gaps narrower
+    /// than `radius`.
     ///
-    /// **Note**: Hole-filling typically works best on binary masks. If this mask is still grayscale,
-    /// consider calling [`threshold`](MaskHandle::threshold) first.
-    pub fn fill_holes_with(mut self, threshold: u8) -> Self {
-        self.operations.push(MaskOperation::FillHoles { threshold });
+    /// **Note**: Closing assumes a binary mask. If this mask is still grayscale, call
+    /// [`threshold`](MaskHandle::threshold) first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radius` is negative or NaN.
+    pub fn close(mut self, radius: f32) -> Self {
+        assert_nonnegative_radius(radius);
+        self.operations.push(MaskOperation::Close { radius });
+        self
+    }
+
+    /// Keep only the largest connected foreground region, using 8-connectivity.
+    ///
+    /// **Note**: Nonzero pixels count as foreground. If this mask is still grayscale, call
+    /// [`threshold`](MaskHandle::threshold) first.
+    pub fn keep_largest(self) -> Self {
+        self.keep_largest_with(Connectivity::default())
+    }
+
+    /// Keep only the largest connected foreground region with the given connectivity.
+    pub fn keep_largest_with(mut self, connectivity: Connectivity) -> Self {
+        self.operations
+            .push(MaskOperation::KeepLargestComponent { connectivity });
+        self
+    }
+
+    /// Drop connected foreground regions smaller than `min_area` pixels.
+    ///
+    /// **Note**: Nonzero pixels count as foreground. If this mask is still grayscale, call
+    /// [`threshold`](MaskHandle::threshold) first.
+    pub fn remove_small_components(mut self, min_area: u32) -> Self {
+        self.operations
+            .push(MaskOperation::RemoveSmallComponents { min_area });
+        self
+    }
+
+    /// Add a hole-filling operation to the processing pipeline.
+    ///
+    /// **Note**: Hole-filling typically works best on binary masks. If this mask is still grayscale,
+    /// consider calling [`threshold`](MaskHandle::threshold) first.
+    pub fn fill_holes(self) -> Self {
+        let threshold = self.mask_processing_defaults.mask_threshold;
+        self.fill_holes_with(threshold)
+    }
+
+    /// Add a hole-filling operation with a custom threshold.
+    ///
+    /// **Note**: Hole-filling typically works best on binary masks. If this mask is still grayscale,
+    /// consider calling [`threshold`](MaskHandle::threshold) first.
+    pub fn fill_holes_with(mut self, threshold: u8) -> Self {
+        self.operations.push(MaskOperation::FillHoles { threshold });
+        self
+    }
+
+    /// Add an operation that makes regions enclosed by strong foreground opaque.
+    ///
+    /// Unlike [`fill_holes`](MaskHandle::fill_holes), pixels outside the enclosed regions keep
+    /// their values, so soft edges are preserved.
+    pub fn solidify(self) -> Self {
+        let threshold = self.mask_processing_defaults.mask_threshold;
+        self.solidify_with(threshold)
+    }
+
+    /// Add a solidify operation with a custom threshold.
+    pub fn solidify_with(mut self, threshold: u8) -> Self {
+        self.operations.push(MaskOperation::Solidify { threshold });
+        self
+    }
+
+    /// Add an operation that quantizes the mask to `levels` evenly spaced values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `levels` is less than 2.
+    pub fn posterize(mut self, levels: u8) -> Self {
+        assert_posterize_levels(levels);
+        self.operations.push(MaskOperation::Posterize { levels });
+        self
+    }
+
+    /// Add an operation that applies a gamma curve, mapping each value `v` to
+    /// `255 * (v / 255)^gamma`.
+    ///
+    /// A gamma above 1 pushes a matte that clusters around mid-gray toward 0 before a
+    /// threshold; see [`gamma_mask`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `gamma` is not greater than zero or not finite.
+    pub fn gamma(mut self, gamma: f32) -> Self {
+        assert_positive_gamma(gamma);
+        self.operations.push(MaskOperation::Gamma { gamma });
+        self
+    }
+
+    /// Add an operation that inverts the mask so each pixel becomes `255 - value`.
+    ///
+    /// Useful to cut out the background instead of the subject; inverting twice restores the
+    /// original mask exactly.
+    pub fn invert(mut self) -> Self {
+        self.operations.push(MaskOperation::Invert);
         self
     }
 
@@ -803,7 +1960,12 @@ impl MaskHandle {
             ops.extend_from_slice(custom.operations());
         }
 
-        let mask = apply_operations(&self.mask, &ops);
+        // Without operations the processed mask is the current one, so share it.
+        let mask = if ops.is_empty() {
+            self.mask
+        } else {
+            Arc::new(apply_operations(self.mask.as_ref(), &ops))
+        };
         Ok(MaskHandle::new(
             self.rgb_image,
             mask,
@@ -819,9 +1981,30 @@ impl MaskHandle {
     }
 
     /// Colorize the current mask into a flat-color RGBA image.
-    pub fn colorize(&self, color: impl Into<MaskColor>) -> RgbaImage {
+    ///
+    /// Returns [`OutlineError::InvalidMaskColor`] if [`MaskColor::validate`] rejects the color,
+    /// instead of producing an invisible layer.
+    pub fn colorize(&self, color: impl Into<MaskColor>) -> OutlineResult<RgbaImage> {
+        try_colorize_mask(&self.resolved_mask(), color)
+    }
+
+    /// Tint the masked region of `rgb` with a translucent color to check the mask by eye.
+    ///
+    /// Pass [`rgb_image`](crate::InferencedMatte::rgb_image) from the inference result to see the
+    /// mask over its source. See [`overlay_mask_on_rgb`](crate::overlay_mask_on_rgb) for how
+    /// `color` and `opacity` combine.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `opacity` is outside `0.0..=1.0` or NaN.
+    pub fn overlay_on(
+        &self,
+        rgb: &RgbImage,
+        color: [u8; 4],
+        opacity: f32,
+    ) -> OutlineResult<RgbaImage> {
         let mask = self.resolved_mask();
-        colorize_mask(&mask, color)
+        overlay_mask_on_rgb(rgb, &mask, color, opacity)
     }
 
     /// Trace the current mask using the specified vectorizer and options.
@@ -841,7 +2024,7 @@ impl MaskHandle {
     pub fn pad(self, padding: impl Into<Padding>) -> Self {
         let this = self.resolve_pending_operations();
         let padding = padding.into();
-        let mask = pad_gray_image(&this.mask, padding, 0);
+        let mask = pad_gray_image(this.mask.as_ref(), padding, 0);
         let rgb = Arc::new(pad_rgb_image(this.rgb_image.as_ref(), padding, [0, 0, 0]));
         Self::new(rgb, mask, this.mask_processing_defaults)
     }
@@ -855,7 +2038,7 @@ impl MaskHandle {
             return None;
         }
 
-        let mask = crop_gray_image(&this.mask, bounds);
+        let mask = crop_gray_image(this.mask.as_ref(), bounds);
         let rgb = Arc::new(crop_rgb_image(this.rgb_image.as_ref(), bounds));
         Some(Self::new(rgb, mask, this.mask_processing_defaults))
     }
@@ -872,7 +2055,7 @@ impl MaskHandle {
     /// Returns `None` when the current mask has no pixels at or above `threshold`.
     pub fn crop_to_content_with(self, threshold: u8) -> Option<Self> {
         let this = self.resolve_pending_operations();
-        let bounds = mask_bounding_box(&this.mask, threshold)?;
+        let bounds = mask_bounding_box(this.mask.as_ref(), threshold)?;
         this.crop(bounds)
     }
 }
@@ -1022,6 +2205,163 @@ mod tests {
         }
     }
 
+    mod soft_threshold_mask {
+        use super::*;
+
+        mod unit {
+            use super::*;
+
+            #[test]
+            fn ramps_linearly_between_low_and_high() {
+                let input = GrayImage::from_raw(6, 1, vec![0, 100, 101, 150, 200, 255]).unwrap();
+                let result = soft_threshold_mask(&input, 100, 200);
+                assert_eq!(result.as_raw(), &[0, 0, 3, 128, 255, 255]);
+            }
+
+            #[test]
+            fn equal_bounds_match_hard_threshold() {
+                let input = GrayImage::from_fn(16, 16, |x, y| Luma([(x * 16 + y) as u8]));
+                assert_eq!(
+                    soft_threshold_mask(&input, 120, 120),
+                    threshold_mask(&input, 120)
+                );
+            }
+
+            #[test]
+            #[should_panic(expected = "soft threshold low must be <= high")]
+            fn low_above_high_panics() {
+                soft_threshold_mask(&GrayImage::new(1, 1), 200, 100);
+            }
+        }
+    }
+
+    mod posterize_mask {
+        use super::*;
+
+        mod unit {
+            use super::*;
+
+            #[test]
+            fn four_levels_snap_to_nearest() {
+                let input = GrayImage::from_raw(6, 1, vec![0, 42, 43, 128, 213, 255]).unwrap();
+                let result = posterize_mask(&input, 4);
+                assert_eq!(result.as_raw(), &[0, 0, 85, 170, 255, 255]);
+            }
+
+            #[test]
+            fn two_levels_is_binary() {
+                let input = GrayImage::from_raw(4, 1, vec![0, 127, 128, 255]).unwrap();
+                let result = posterize_mask(&input, 2);
+                assert_eq!(result.as_raw(), &[0, 0, 255, 255]);
+            }
+
+            #[test]
+            #[should_panic(expected = "posterize levels must be >= 2")]
+            fn fewer_than_two_levels_panics() {
+                posterize_mask(&GrayImage::new(1, 1), 1);
+            }
+        }
+
+        mod prop {
+            use super::*;
+            use proptest::prelude::*;
+
+            proptest! {
+                /// posterize_mask: output uses at most `levels` distinct values, including the
+                /// extremes unchanged
+                #[test]
+                fn output_uses_at_most_levels_values(
+                    values in proptest::collection::vec(proptest::num::u8::ANY, 1..64),
+                    levels in 2u8..=16
+                ) {
+                    let input = GrayImage::from_raw(values.len() as u32, 1, values).unwrap();
+                    let result = posterize_mask(&input, levels);
+
+                    let mut distinct: Vec<u8> = result.pixels().map(|px| px.0[0]).collect();
+                    distinct.sort_unstable();
+                    distinct.dedup();
+                    prop_assert!(distinct.len() <= usize::from(levels));
+
+                    let extremes = GrayImage::from_raw(2, 1, vec![0, 255]).unwrap();
+                    let extremes = posterize_mask(&extremes, levels);
+                    prop_assert_eq!(extremes.as_raw().as_slice(), &[0, 255]);
+                }
+
+                /// posterize_mask: posterizing twice with the same levels changes nothing
+                #[test]
+                fn is_idempotent(value in proptest::num::u8::ANY, levels in 2u8..=255) {
+                    let input = GrayImage::from_pixel(1, 1, Luma([value]));
+                    let once = posterize_mask(&input, levels);
+                    prop_assert_eq!(posterize_mask(&once, levels), once);
+                }
+            }
+        }
+    }
+
+    mod gamma_mask {
+        use super::*;
+
+        mod unit {
+            use super::*;
+
+            #[test]
+            fn gamma_one_is_a_no_op() {
+                let input = GrayImage::from_fn(16, 16, |x, y| Luma([(x * 16 + y) as u8]));
+                assert_eq!(gamma_mask(&input, 1.0), input);
+            }
+
+            #[test]
+            fn gamma_two_darkens_mid_gray() {
+                let input = GrayImage::from_raw(3, 1, vec![0, 128, 255]).unwrap();
+                let result = gamma_mask(&input, 2.0);
+                // 255 * (128 / 255)^2 = 64.25
+                assert_eq!(result.as_raw(), &[0, 64, 255]);
+            }
+
+            #[test]
+            #[should_panic(expected = "gamma must be > 0.0 and finite")]
+            fn zero_gamma_panics() {
+                gamma_mask(&GrayImage::new(1, 1), 0.0);
+            }
+        }
+    }
+
+    mod invert_mask {
+        use super::*;
+
+        mod unit {
+            use super::*;
+
+            #[test]
+            fn maps_each_value_to_its_complement() {
+                let input = GrayImage::from_raw(4, 1, vec![0, 1, 128, 255]).unwrap();
+                let result = invert_mask(&input);
+                assert_eq!(result.as_raw(), &[255, 254, 127, 0]);
+            }
+        }
+
+        mod prop {
+            use super::*;
+            use proptest::prelude::*;
+
+            proptest! {
+                /// invert_mask: inverting twice is an exact identity
+                #[test]
+                fn double_invert_is_identity(
+                    w in 1u32..16,
+                    values in proptest::collection::vec(proptest::num::u8::ANY, 1..256)
+                ) {
+                    let h = (values.len() as u32).div_ceil(w);
+                    let mut raw = values;
+                    raw.resize((w * h) as usize, 0);
+                    let input = GrayImage::from_raw(w, h, raw).unwrap();
+                    let twice = MaskOperation::Invert.apply(&MaskOperation::Invert.apply(&input));
+                    prop_assert_eq!(twice, input);
+                }
+            }
+        }
+    }
+
     mod array_to_gray_image {
         use super::*;
 
@@ -1127,6 +2467,173 @@ mod tests {
         }
     }
 
+    mod array_to_gray16_image {
+        use super::*;
+
+        #[test]
+        fn maps_unit_range_to_full_u16_range() {
+            let arr = arr2(&[[0.0, 0.5, 1.0]]);
+            let result = array_to_gray16_image(&arr);
+            let values: Vec<u16> = result.pixels().map(|px| px.0[0]).collect();
+            assert_eq!(values, vec![0, 32768, 65535]);
+        }
+
+        #[test]
+        fn clamps_out_of_range_values() {
+            let arr = arr2(&[[-0.5, 1.5, f32::NAN]]);
+            let result = array_to_gray16_image(&arr);
+            let values: Vec<u16> = result.pixels().map(|px| px.0[0]).collect();
+            assert_eq!(values, vec![0, 65535, 0]);
+        }
+    }
+
+    mod gray_image_to_array {
+        use super::*;
+
+        mod unit {
+            use super::*;
+
+            #[test]
+            fn normalizes_to_unit_range() {
+                let image = GrayImage::from_raw(3, 1, vec![0, 51, 255]).unwrap();
+                let array = gray_image_to_array(&image);
+                assert_eq!(array.dim(), (1, 3));
+                assert_eq!(array[[0, 0]], 0.0);
+                assert!((array[[0, 1]] - 0.2).abs() < 1e-6);
+                assert_eq!(array[[0, 2]], 1.0);
+            }
+
+            #[test]
+            fn indexes_rows_by_y() {
+                let image = GrayImage::from_fn(2, 3, |x, y| Luma([(y * 2 + x) as u8 * 40]));
+                let array = gray_image_to_array(&image);
+                assert_eq!(array.dim(), (3, 2));
+                assert!((array[[2, 1]] - 200.0 / 255.0).abs() < 1e-6);
+            }
+        }
+
+        mod prop {
+            use super::*;
+            use proptest::prelude::*;
+
+            proptest! {
+                /// gray_image_to_array followed by array_to_gray_image is lossless
+                #[test]
+                fn round_trips_through_array_to_gray_image(
+                    pixels in proptest::collection::vec(any::<u8>(), 1..64)
+                ) {
+                    let len = pixels.len() as u32;
+                    let image = GrayImage::from_raw(len, 1, pixels).unwrap();
+                    let round_tripped = array_to_gray_image(&gray_image_to_array(&image));
+                    prop_assert_eq!(round_tripped, image);
+                }
+            }
+        }
+    }
+
+    mod matte_stats {
+        use super::*;
+
+        #[test]
+        fn half_filled_mask_covers_half() {
+            let mask = GrayImage::from_fn(10, 6, |x, _| Luma([if x < 5 { 255 } else { 0 }]));
+            let stats = matte_stats(&mask, 128);
+
+            assert_eq!(stats.foreground_pixels, 30);
+            assert!((stats.coverage - 0.5).abs() < 1e-6);
+            assert_eq!((stats.min, stats.max), (0, 255));
+            assert!((stats.mean - 127.5).abs() < 1e-4);
+        }
+
+        #[test]
+        fn threshold_is_inclusive() {
+            let mask = GrayImage::from_fn(4, 1, |x, _| Luma([x as u8 * 40]));
+            let stats = matte_stats(&mask, 80);
+
+            assert_eq!(stats.foreground_pixels, 2);
+            assert_eq!((stats.min, stats.max), (0, 120));
+        }
+
+        #[test]
+        fn empty_matte_reports_zeros() {
+            let stats = matte_stats(&GrayImage::new(0, 0), 128);
+            assert_eq!(stats.foreground_pixels, 0);
+            assert_eq!(stats.coverage, 0.0);
+            assert_eq!((stats.min, stats.max), (0, 0));
+        }
+    }
+
+    mod subject_bbox {
+        use super::*;
+
+        #[test]
+        fn finds_off_center_square() {
+            let mut matte = GrayImage::new(20, 12);
+            for y in 3..8 {
+                for x in 11..16 {
+                    matte.put_pixel(x, y, Luma([200]));
+                }
+            }
+            matte.put_pixel(2, 2, Luma([40]));
+
+            assert_eq!(
+                subject_bbox(&matte, 128),
+                Some(BoundingBox::new(11, 3, 5, 5))
+            );
+            assert_eq!(subject_bbox(&matte, 1), Some(BoundingBox::new(2, 2, 14, 6)));
+        }
+
+        #[test]
+        fn empty_matte_has_no_box() {
+            assert_eq!(subject_bbox(&GrayImage::new(4, 4), 1), None);
+        }
+    }
+
+    mod edge_hardness {
+        use super::*;
+
+        fn disk(soft: bool) -> GrayImage {
+            GrayImage::from_fn(32, 32, |x, y| {
+                let d = (x as f32 - 15.5).hypot(y as f32 - 15.5);
+                let value = if soft {
+                    ((12.0 - d) / 4.0).clamp(0.0, 1.0)
+                } else if d < 10.0 {
+                    1.0
+                } else {
+                    0.0
+                };
+                Luma([(value * 255.0).round() as u8])
+            })
+        }
+
+        #[test]
+        fn hard_edges_score_one() {
+            assert_eq!(edge_hardness(&disk(false)), 1.0);
+        }
+
+        #[test]
+        fn soft_edges_score_low() {
+            let hardness = edge_hardness(&disk(true));
+            assert!(hardness < 0.1, "soft disk scored {hardness}");
+        }
+
+        #[test]
+        fn blurred_hard_edges_score_lower() {
+            let hard = disk(false);
+            let blurred = MaskOperation::Blur { sigma: 1.0 }.apply(&hard);
+            assert!(edge_hardness(&blurred) < edge_hardness(&hard));
+        }
+
+        #[test]
+        fn uniform_mask_scores_zero() {
+            assert_eq!(
+                edge_hardness(&GrayImage::from_pixel(4, 4, Luma([255]))),
+                0.0
+            );
+            assert_eq!(edge_hardness(&GrayImage::new(0, 0)), 0.0);
+        }
+    }
+
     mod fill_mask_holes {
         use super::*;
 
@@ -1166,127 +2673,569 @@ mod tests {
                     }
                 }
 
-                let result = fill_mask_holes(&input, 128);
+                let result = fill_mask_holes(&input, 128);
+
+                // The interior black region is NOT connected to border, so it gets filled
+                for px in result.pixels() {
+                    assert_eq!(px.0[0], 255);
+                }
+            }
+
+            #[test]
+            fn border_connected_black_not_filled() {
+                // Create a 4x4 image with black on left edge
+                // B W W W
+                // B W W W
+                // B W W W
+                // B W W W
+                let mut input = gray_image(4, 4, 255);
+                for y in 0..4 {
+                    input.put_pixel(0, y, Luma([0]));
+                }
+
+                let result = fill_mask_holes(&input, 128);
+
+                // Left column is connected to border, stays black (=0 in output)
+                for y in 0..4 {
+                    assert_eq!(result.get_pixel(0, y).0[0], 0);
+                }
+                // Rest stays white
+                for y in 0..4 {
+                    for x in 1..4 {
+                        assert_eq!(result.get_pixel(x, y).0[0], 255);
+                    }
+                }
+            }
+
+            #[test]
+            fn dimensions_preserved() {
+                let input = gray_image(7, 5, 128);
+                let result = fill_mask_holes(&input, 128);
+                assert_eq!(result.dimensions(), (7, 5));
+            }
+
+            #[test]
+            fn empty_image_is_unchanged() {
+                let input = GrayImage::new(0, 0);
+                let result = fill_mask_holes(&input, 128);
+                assert_eq!(result.dimensions(), (0, 0));
+            }
+
+            #[test]
+            fn diagonal_only_connection_not_traversed() {
+                // 4-connectivity: diagonal doesn't count as connected
+                // W W W
+                // W B W
+                // B W W  (bottom-left black only diagonally connected to center)
+                let mut input = GrayImage::new(3, 3);
+                for y in 0..3 {
+                    for x in 0..3 {
+                        input.put_pixel(x, y, Luma([255]));
+                    }
+                }
+                input.put_pixel(1, 1, Luma([0])); // center black
+                input.put_pixel(0, 2, Luma([0])); // corner black (touches border)
+
+                let result = fill_mask_holes(&input, 128);
+
+                // corner (0,2) is on border, stays black
+                assert_eq!(result.get_pixel(0, 2).0[0], 0);
+                // center (1,1) is NOT 4-connected to border, gets filled
+                assert_eq!(result.get_pixel(1, 1).0[0], 255);
+            }
+
+            #[test]
+            fn threshold_changes_border_connectivity() {
+                // pixel value 110 on border:
+                // thr=128: 110 < 128, dark, BFS marks visited, output 0
+                // thr=100: 110 >= 100, light, not visited by BFS, output 255
+                let mut input = gray_image(3, 3, 255);
+                input.put_pixel(0, 1, Luma([110])); // left border
+
+                let r128 = fill_mask_holes(&input, 128);
+                assert_eq!(r128.get_pixel(0, 1).0[0], 0);
+
+                let r100 = fill_mask_holes(&input, 100);
+                assert_eq!(r100.get_pixel(0, 1).0[0], 255);
+            }
+
+            #[test]
+            fn output_is_always_binary() {
+                // fill_mask_holes should always produce binary output (0 or 255)
+                let mut input = gray_image(5, 5, 200); // non-binary input
+                input.put_pixel(2, 2, Luma([50])); // interior dark pixel
+
+                let result = fill_mask_holes(&input, 128);
+
+                let is_binary = result.pixels().all(|p| p.0[0] == 0 || p.0[0] == 255);
+                assert!(is_binary);
+            }
+        }
+
+        mod prop {
+            use super::*;
+            use proptest::prelude::*;
+
+            proptest! {
+                /// fill_mask_holes: output is always binary and dimensions preserved
+                #[test]
+                fn output_is_binary(
+                    w in 1u32..15,
+                    h in 1u32..15,
+                    fill_value in proptest::num::u8::ANY,
+                    threshold in proptest::num::u8::ANY
+                ) {
+                    let input = GrayImage::from_pixel(w, h, Luma([fill_value]));
+                    let result = fill_mask_holes(&input, threshold);
+
+                    prop_assert_eq!(result.dimensions(), (w, h));
+                    for px in result.pixels() {
+                        prop_assert!(px.0[0] == 0 || px.0[0] == 255);
+                    }
+                }
+            }
+        }
+    }
+
+    mod otsu_threshold {
+        use super::*;
+
+        #[test]
+        fn splits_bimodal_mask_between_peaks() {
+            let input =
+                GrayImage::from_fn(10, 10, |x, _| if x < 4 { Luma([30]) } else { Luma([200]) });
+
+            let level = otsu_threshold(&input);
+
+            assert!((30..200).contains(&level), "{level}");
+            let result = MaskOperation::OtsuThreshold.apply(&input);
+            assert_eq!(result.get_pixel(0, 0)[0], 0);
+            assert_eq!(result.get_pixel(9, 0)[0], 255);
+        }
+
+        #[test]
+        fn adapts_to_overall_brightness() {
+            let dark =
+                GrayImage::from_fn(10, 10, |x, _| if x < 5 { Luma([5]) } else { Luma([60]) });
+            let result = dark.otsu_threshold();
+
+            // A fixed threshold of 120 would drop the whole subject.
+            assert_eq!(threshold_mask(&dark, 120).get_pixel(9, 0)[0], 0);
+            assert_eq!(result.get_pixel(9, 0)[0], 255);
+            assert_eq!(result.get_pixel(0, 0)[0], 0);
+        }
+
+        #[test]
+        fn uniform_mask_returns_zero() {
+            assert_eq!(otsu_threshold(&gray_image(4, 4, 90)), 0);
+            assert_eq!(otsu_threshold(&GrayImage::new(0, 0)), 0);
+        }
+    }
+
+    mod open_close_mask {
+        use super::*;
+
+        fn filled_square(hole: Option<(u32, u32)>) -> GrayImage {
+            let mut mask = GrayImage::from_fn(15, 15, |x, y| {
+                if (3..12).contains(&x) && (3..12).contains(&y) {
+                    Luma([255])
+                } else {
+                    Luma([0])
+                }
+            });
+            if let Some((x, y)) = hole {
+                mask.put_pixel(x, y, Luma([0]));
+            }
+            mask
+        }
+
+        #[test]
+        fn close_fills_single_pixel_hole() {
+            let input = filled_square(Some((7, 7)));
+
+            let result = close_mask(&input, 1.0);
+
+            assert_eq!(result, filled_square(None));
+        }
+
+        #[test]
+        fn open_removes_single_pixel_speck() {
+            let mut input = filled_square(None);
+            input.put_pixel(0, 14, Luma([255]));
+
+            let result = open_mask(&input, 1.0);
+
+            assert_eq!(result.get_pixel(0, 14)[0], 0);
+            // The square keeps its edges; only its corner pixels are rounded off.
+            assert_eq!(result.get_pixel(3, 7)[0], 255);
+            assert_eq!(result.get_pixel(7, 7)[0], 255);
+            assert_eq!(result.get_pixel(2, 7)[0], 0);
+        }
+
+        #[test]
+        fn border_touching_shape_is_not_trimmed() {
+            let input =
+                GrayImage::from_fn(10, 10, |x, _| if x < 5 { Luma([255]) } else { Luma([0]) });
+
+            assert_eq!(open_mask(&input, 2.0), input);
+            assert_eq!(close_mask(&input, 2.0), input);
+        }
+
+        #[test]
+        fn zero_radius_is_identity() {
+            let input = filled_square(Some((7, 7)));
+            assert_eq!(open_mask(&input, 0.0), input);
+            assert_eq!(close_mask(&input, 0.0), input);
+        }
+
+        #[test]
+        fn operations_match_free_functions() {
+            let input = filled_square(Some((7, 7)));
+            assert_eq!(
+                MaskOperation::Close { radius: 1.0 }.apply(&input),
+                close_mask(&input, 1.0)
+            );
+            assert_eq!(
+                MaskOperation::Open { radius: 1.0 }.apply(&input),
+                open_mask(&input, 1.0)
+            );
+        }
+    }
+
+    mod keep_largest_component {
+        use super::*;
+
+        fn mask_from(rows: &[&str]) -> GrayImage {
+            GrayImage::from_fn(rows[0].len() as u32, rows.len() as u32, |x, y| {
+                Luma([if rows[y as usize].as_bytes()[x as usize] == b'#' {
+                    255
+                } else {
+                    0
+                }])
+            })
+        }
+
+        #[test]
+        fn removes_smaller_blobs() {
+            let input = mask_from(&["##...", "##..#", ".....", "...##"]);
+
+            let result = keep_largest_component(&input, Connectivity::Four);
+
+            assert_eq!(result, mask_from(&["##...", "##...", ".....", "....."]));
+        }
+
+        #[test]
+        fn diagonal_neighbors_join_only_with_eight_connectivity() {
+            let input = mask_from(&["#...", ".#..", "..#.", "...#", "##..", "##.."]);
+
+            assert_eq!(
+                keep_largest_component(&input, Connectivity::Eight),
+                mask_from(&["#...", ".#..", "..#.", "...#", "....", "...."])
+            );
+            assert_eq!(
+                keep_largest_component(&input, Connectivity::Four),
+                mask_from(&["....", "....", "....", "....", "##..", "##.."])
+            );
+        }
+
+        #[test]
+        fn ties_keep_the_region_starting_first() {
+            let input = mask_from(&["...##", "##...", "....."]);
+
+            let result = keep_largest_component(&input, Connectivity::Eight);
+
+            assert_eq!(result, mask_from(&["...##", ".....", "....."]));
+        }
+
+        #[test]
+        fn empty_mask_stays_empty() {
+            let input = GrayImage::new(5, 4);
+            assert_eq!(keep_largest_component(&input, Connectivity::Eight), input);
+            let input = GrayImage::new(0, 0);
+            assert_eq!(keep_largest_component(&input, Connectivity::Four), input);
+        }
+
+        #[test]
+        fn kept_region_keeps_its_values() {
+            let mut input = mask_from(&["###..", "###.#"]);
+            input.put_pixel(1, 1, Luma([90]));
+
+            let result = MaskOperation::KeepLargestComponent {
+                connectivity: Connectivity::Four,
+            }
+            .apply(&input);
+
+            assert_eq!(result.get_pixel(1, 1)[0], 90);
+            assert_eq!(result.get_pixel(4, 1)[0], 0);
+        }
+    }
+
+    mod remove_small_components {
+        use super::*;
+
+        fn three_blobs() -> GrayImage {
+            // Blobs of 2, 10, and 100 pixels, separated by background.
+            GrayImage::from_fn(30, 12, |x, y| {
+                let tiny = y == 0 && x < 2;
+                let small = (2..4).contains(&y) && (3..8).contains(&x);
+                let large = (2..12).contains(&y) && (20..30).contains(&x);
+                Luma([if tiny || small || large { 255 } else { 0 }])
+            })
+        }
+
+        #[test]
+        fn keeps_regions_at_or_above_min_area() {
+            let input = three_blobs();
+
+            let result = remove_small_components(&input, 5);
+
+            let mut expected = input.clone();
+            expected.put_pixel(0, 0, Luma([0]));
+            expected.put_pixel(1, 0, Luma([0]));
+            assert_eq!(result, expected);
+            assert_eq!(result.pixels().filter(|p| p[0] == 255).count(), 110);
+        }
+
+        #[test]
+        fn min_area_is_inclusive() {
+            let input = three_blobs();
+            assert_eq!(remove_small_components(&input, 2), input);
+            assert_eq!(
+                remove_small_components(&input, 11)
+                    .pixels()
+                    .filter(|p| p[0] == 255)
+                    .count(),
+                100
+            );
+        }
+
+        #[test]
+        fn operation_matches_free_function() {
+            let input = three_blobs();
+            assert_eq!(
+                MaskOperation::RemoveSmallComponents { min_area: 5 }.apply(&input),
+                remove_small_components(&input, 5)
+            );
+        }
+    }
+
+    mod matte_to_trimap {
+        use super::*;
+
+        fn ramp() -> GrayImage {
+            GrayImage::from_fn(11, 1, |x, _| Luma([(x * 25) as u8]))
+        }
+
+        #[test]
+        fn splits_into_three_levels() {
+            let trimap = matte_to_trimap(&ramp(), 200, 50, 0.0).unwrap();
+
+            assert_eq!(
+                trimap.as_raw(),
+                &[0, 0, 0, 128, 128, 128, 128, 128, 255, 255, 255]
+            );
+        }
+
+        #[test]
+        fn unknown_dilate_widens_the_band() {
+            let trimap = matte_to_trimap(&ramp(), 200, 50, 1.0).unwrap();
+
+            assert_eq!(
+                trimap.as_raw(),
+                &[0, 0, 128, 128, 128, 128, 128, 128, 128, 255, 255]
+            );
+        }
+
+        #[test]
+        fn equal_thresholds_leave_no_unknown_pixels() {
+            let trimap = matte_to_trimap(&ramp(), 125, 125, 0.0).unwrap();
+
+            assert_eq!(
+                trimap.as_raw(),
+                &[0, 0, 0, 0, 0, 255, 255, 255, 255, 255, 255]
+            );
+        }
+
+        #[test]
+        fn rejects_foreground_threshold_below_background() {
+            let err = matte_to_trimap(&ramp(), 40, 60, 0.0).unwrap_err();
+
+            assert!(matches!(
+                err,
+                OutlineError::InvalidTrimapThresholds {
+                    fg_threshold: 40,
+                    bg_threshold: 60
+                }
+            ));
+        }
+    }
+
+    mod feather_mask {
+        use super::*;
 
-                // The interior black region is NOT connected to border, so it gets filled
-                for px in result.pixels() {
-                    assert_eq!(px.0[0], 255);
-                }
-            }
+        fn circle() -> GrayImage {
+            GrayImage::from_fn(101, 101, |x, y| {
+                let (dx, dy) = (x as f32 - 50.0, y as f32 - 50.0);
+                Luma([if dx * dx + dy * dy <= 40.0 * 40.0 {
+                    255
+                } else {
+                    0
+                }])
+            })
+        }
 
-            #[test]
-            fn border_connected_black_not_filled() {
-                // Create a 4x4 image with black on left edge
-                // B W W W
-                // B W W W
-                // B W W W
-                // B W W W
-                let mut input = gray_image(4, 4, 255);
-                for y in 0..4 {
-                    input.put_pixel(0, y, Luma([0]));
-                }
+        #[test]
+        fn interior_of_solid_circle_stays_opaque() {
+            let input = circle();
 
-                let result = fill_mask_holes(&input, 128);
+            let result = feather_mask(&input, 4.0);
 
-                // Left column is connected to border, stays black (=0 in output)
-                for y in 0..4 {
-                    assert_eq!(result.get_pixel(0, y).0[0], 0);
-                }
-                // Rest stays white
-                for y in 0..4 {
-                    for x in 1..4 {
-                        assert_eq!(result.get_pixel(x, y).0[0], 255);
-                    }
+            for (x, y, pixel) in result.enumerate_pixels() {
+                let distance = ((x as f32 - 50.0).powi(2) + (y as f32 - 50.0).powi(2)).sqrt();
+                if distance <= 35.0 {
+                    assert_eq!(pixel[0], 255, "interior pixel ({x}, {y})");
+                } else if distance >= 46.0 {
+                    assert_eq!(pixel[0], 0, "exterior pixel ({x}, {y})");
                 }
             }
+            let band = result
+                .pixels()
+                .filter(|pixel| pixel[0] > 0 && pixel[0] < 255)
+                .count();
+            assert!(band > 0, "feathering should leave a soft edge band");
+        }
 
-            #[test]
-            fn dimensions_preserved() {
-                let input = gray_image(7, 5, 128);
-                let result = fill_mask_holes(&input, 128);
-                assert_eq!(result.dimensions(), (7, 5));
-            }
+        #[test]
+        fn ramp_is_monotonic_across_a_straight_edge() {
+            let input = GrayImage::from_fn(20, 1, |x, _| Luma([if x < 10 { 255 } else { 0 }]));
 
-            #[test]
-            fn empty_image_is_unchanged() {
-                let input = GrayImage::new(0, 0);
-                let result = fill_mask_holes(&input, 128);
-                assert_eq!(result.dimensions(), (0, 0));
-            }
+            let result = feather_mask(&input, 3.0);
+            let row = result.as_raw();
 
-            #[test]
-            fn diagonal_only_connection_not_traversed() {
-                // 4-connectivity: diagonal doesn't count as connected
-                // W W W
-                // W B W
-                // B W W  (bottom-left black only diagonally connected to center)
-                let mut input = GrayImage::new(3, 3);
-                for y in 0..3 {
-                    for x in 0..3 {
-                        input.put_pixel(x, y, Luma([255]));
-                    }
-                }
-                input.put_pixel(1, 1, Luma([0])); // center black
-                input.put_pixel(0, 2, Luma([0])); // corner black (touches border)
+            assert!(row.windows(2).all(|pair| pair[0] >= pair[1]), "{row:?}");
+            assert_eq!(row[5], 255);
+            assert_eq!(row[14], 0);
+            assert!(row[9] > 128 && row[10] < 128, "{row:?}");
+        }
 
-                let result = fill_mask_holes(&input, 128);
+        #[test]
+        fn zero_radius_binarizes() {
+            let input = GrayImage::from_fn(4, 1, |x, _| Luma([x as u8 * 60]));
+            assert_eq!(feather_mask(&input, 0.0).as_raw(), &[0, 0, 0, 255]);
+        }
+    }
 
-                // corner (0,2) is on border, stays black
-                assert_eq!(result.get_pixel(0, 2).0[0], 0);
-                // center (1,1) is NOT 4-connected to border, gets filled
-                assert_eq!(result.get_pixel(1, 1).0[0], 255);
+    mod normalized_blur_mask {
+        use super::*;
+
+        fn square_mask() -> GrayImage {
+            GrayImage::from_fn(21, 21, |x, y| {
+                if (5..16).contains(&x) && (5..16).contains(&y) {
+                    Luma([255])
+                } else {
+                    Luma([0])
+                }
+            })
+        }
+
+        #[test]
+        fn subject_keeps_full_alpha() {
+            let input = square_mask();
+            let result = normalized_blur_mask(&input, 2.0);
+            let plain = gaussian_blur_f32(&input, 2.0);
+
+            // The plain blur halves the edge alpha; the normalized blur keeps it.
+            assert!(plain.get_pixel(5, 10)[0] < 200);
+            for (x, y, pixel) in input.enumerate_pixels() {
+                if pixel[0] == 255 {
+                    assert_eq!(result.get_pixel(x, y)[0], 255, "({x}, {y})");
+                }
             }
+        }
 
-            #[test]
-            fn threshold_changes_border_connectivity() {
-                // pixel value 110 on border:
-                // thr=128: 110 < 128, dark, BFS marks visited, output 0
-                // thr=100: 110 >= 100, light, not visited by BFS, output 255
-                let mut input = gray_image(3, 3, 255);
-                input.put_pixel(0, 1, Luma([110])); // left border
+        #[test]
+        fn background_is_feathered_like_plain_blur() {
+            let input = square_mask();
+            let result = normalized_blur_mask(&input, 2.0);
+            let plain = gaussian_blur_f32(&input, 2.0);
+
+            // Only rounding may differ from the plain blur outside the subject.
+            let (feathered, expected) = (result.get_pixel(4, 10)[0], plain.get_pixel(4, 10)[0]);
+            assert!(feathered > 0);
+            assert!(
+                feathered.abs_diff(expected) <= 1,
+                "{feathered} vs {expected}"
+            );
+            assert_eq!(result.get_pixel(0, 0)[0], 0);
+        }
 
-                let r128 = fill_mask_holes(&input, 128);
-                assert_eq!(r128.get_pixel(0, 1).0[0], 0);
+        #[test]
+        fn soft_interior_is_averaged_over_foreground_only() {
+            let input =
+                GrayImage::from_fn(9, 9, |x, _| if x < 4 { Luma([0]) } else { Luma([128]) });
+            let result = normalized_blur_mask(&input, 1.5);
 
-                let r100 = fill_mask_holes(&input, 100);
-                assert_eq!(r100.get_pixel(0, 1).0[0], 255);
-            }
+            assert_eq!(result.get_pixel(4, 4)[0], 128);
+            assert_eq!(result.get_pixel(8, 4)[0], 128);
+        }
 
-            #[test]
-            fn output_is_always_binary() {
-                // fill_mask_holes should always produce binary output (0 or 255)
-                let mut input = gray_image(5, 5, 200); // non-binary input
-                input.put_pixel(2, 2, Luma([50])); // interior dark pixel
+        #[test]
+        #[should_panic(expected = "sigma must be > 0.0")]
+        fn rejects_zero_sigma() {
+            normalized_blur_mask(&square_mask(), 0.0);
+        }
+    }
 
-                let result = fill_mask_holes(&input, 128);
+    mod solidify_mask {
+        use super::*;
 
-                let is_binary = result.pixels().all(|p| p.0[0] == 0 || p.0[0] == 255);
-                assert!(is_binary);
+        #[test]
+        fn enclosed_soft_region_becomes_opaque() {
+            // 7x7: soft 100 ring at the border, strong 220 body, weak 40 pocket inside the body.
+            let mut input = gray_image(7, 7, 100);
+            for y in 1..6 {
+                for x in 1..6 {
+                    input.put_pixel(x, y, Luma([220]));
+                }
             }
+            input.put_pixel(3, 3, Luma([40]));
+
+            let result = solidify_mask(&input, 128);
+
+            assert_eq!(result.get_pixel(3, 3).0[0], 255);
+            // Soft edges and the strong body keep their values.
+            assert_eq!(result.get_pixel(0, 0).0[0], 100);
+            assert_eq!(result.get_pixel(2, 2).0[0], 220);
         }
 
-        mod prop {
-            use super::*;
-            use proptest::prelude::*;
+        #[test]
+        fn border_connected_region_keeps_values() {
+            let mut input = gray_image(5, 5, 255);
+            input.put_pixel(0, 2, Luma([30]));
+            input.put_pixel(1, 2, Luma([60]));
 
-            proptest! {
-                /// fill_mask_holes: output is always binary and dimensions preserved
-                #[test]
-                fn output_is_binary(
-                    w in 1u32..15,
-                    h in 1u32..15,
-                    fill_value in proptest::num::u8::ANY,
-                    threshold in proptest::num::u8::ANY
-                ) {
-                    let input = GrayImage::from_pixel(w, h, Luma([fill_value]));
-                    let result = fill_mask_holes(&input, threshold);
+            let result = solidify_mask(&input, 128);
 
-                    prop_assert_eq!(result.dimensions(), (w, h));
-                    for px in result.pixels() {
-                        prop_assert!(px.0[0] == 0 || px.0[0] == 255);
-                    }
+            assert_eq!(result, input);
+        }
+
+        #[test]
+        fn matches_fill_holes_on_binary_masks() {
+            let mut input = gray_image(6, 6, 0);
+            for y in 1..5 {
+                for x in 1..5 {
+                    input.put_pixel(x, y, Luma([255]));
                 }
             }
+            input.put_pixel(2, 2, Luma([0]));
+
+            assert_eq!(solidify_mask(&input, 128), fill_mask_holes(&input, 128));
+        }
+
+        #[test]
+        fn empty_image_unchanged() {
+            let input = GrayImage::new(0, 0);
+            assert_eq!(solidify_mask(&input, 128).dimensions(), (0, 0));
         }
     }
 
@@ -1711,6 +3660,31 @@ mod tests {
                 ));
             }
 
+            #[test]
+            fn apply_runs_operations_in_order() {
+                let mask = GrayImage::from_fn(5, 5, |x, y| {
+                    if x == 2 && y == 2 {
+                        Luma([200])
+                    } else {
+                        Luma([0])
+                    }
+                });
+                let pipeline = MaskPipeline::new().threshold_with(100).dilate_with(1.0);
+
+                let result = pipeline.apply(&mask);
+
+                assert_eq!(result, apply_operations(&mask, pipeline.operations()));
+                assert_eq!(result.get_pixel(2, 2).0[0], 255);
+                assert_eq!(result.get_pixel(1, 2).0[0], 255);
+                assert_eq!(result.get_pixel(0, 0).0[0], 0);
+            }
+
+            #[test]
+            fn empty_pipeline_apply_is_identity() {
+                let mask = GrayImage::from_pixel(3, 2, Luma([77]));
+                assert_eq!(MaskPipeline::new().apply(&mask), mask);
+            }
+
             #[test]
             fn partial_pipeline_keeps_inserted_order() {
                 let pipeline = MaskPipeline::new().threshold_with(100).fill_holes_with(100);
@@ -1726,6 +3700,54 @@ mod tests {
         }
     }
 
+    mod mask_ext {
+        use super::*;
+
+        fn ramp() -> GrayImage {
+            GrayImage::from_fn(12, 12, |x, y| Luma([((x + y) * 10) as u8]))
+        }
+
+        #[test]
+        fn methods_match_free_functions() {
+            let mask = ramp();
+            assert_eq!(mask.threshold(100), threshold_mask(&mask, 100));
+            assert_eq!(mask.posterize(4), posterize_mask(&mask, 4));
+            assert_eq!(mask.invert(), invert_mask(&mask));
+            assert_eq!(mask.fill_holes(128), fill_mask_holes(&mask, 128));
+
+            let binary = mask.threshold(100);
+            assert_eq!(binary.dilate(2.0), dilate_euclidean(&binary, 2.0));
+            assert_eq!(
+                binary.erode(2.0),
+                erode_euclidean_with_border_mode(&binary, 2.0, ErosionBorderMode::default())
+            );
+            assert_eq!(
+                binary.erode_with_border_mode(2.0, ErosionBorderMode::OutsideIsUnknown),
+                erode_euclidean_with_border_mode(&binary, 2.0, ErosionBorderMode::OutsideIsUnknown)
+            );
+        }
+
+        #[test]
+        fn chained_methods_match_pipeline() {
+            let mask = ramp();
+            let pipeline = MaskPipeline::new()
+                .blur_with(1.5)
+                .threshold_with(120)
+                .dilate_with(1.0);
+
+            let chained = mask.blur(1.5).threshold(120).dilate(1.0);
+
+            assert_eq!(chained, pipeline.apply(&mask));
+            assert_eq!(chained, mask.apply_operations(pipeline.operations()));
+        }
+
+        #[test]
+        #[should_panic(expected = "radius must be >= 0.0")]
+        fn dilate_rejects_negative_radius() {
+            ramp().dilate(-1.0);
+        }
+    }
+
     mod mask_handle_api {
         use super::*;
         use image::Rgb;
@@ -1733,7 +3755,7 @@ mod tests {
         fn mask_handle() -> MaskHandle {
             MaskHandle {
                 rgb_image: Arc::new(RgbImage::from_pixel(1, 1, Rgb([255, 255, 255]))),
-                mask: GrayImage::from_pixel(1, 1, Luma([255])),
+                mask: Arc::new(GrayImage::from_pixel(1, 1, Luma([255]))),
                 mask_processing_defaults: MaskProcessingDefaults::default(),
                 operations: Vec::new(),
             }
@@ -1742,7 +3764,7 @@ mod tests {
         fn mask_handle_with_images(rgb: RgbImage, mask: GrayImage) -> MaskHandle {
             MaskHandle {
                 rgb_image: Arc::new(rgb),
-                mask,
+                mask: Arc::new(mask),
                 mask_processing_defaults: MaskProcessingDefaults::default(),
                 operations: Vec::new(),
             }
@@ -1776,9 +3798,100 @@ mod tests {
             }
         }
 
+        mod sharing {
+            use super::*;
+
+            fn gray_mask_handle() -> MaskHandle {
+                mask_handle_with_images(
+                    RgbImage::new(4, 3),
+                    GrayImage::from_pixel(4, 3, Luma([128])),
+                )
+            }
+
+            #[test]
+            fn clone_shares_mask_buffer() {
+                let mask = gray_mask_handle();
+                let branch = mask.clone();
+                assert!(Arc::ptr_eq(&mask.mask, &branch.mask));
+            }
+
+            #[test]
+            fn processed_without_operations_shares_mask_buffer() {
+                let mask = gray_mask_handle();
+                let processed = mask.clone().processed().unwrap();
+                assert!(Arc::ptr_eq(&mask.mask, &processed.mask));
+
+                let processed = mask.clone().processed_with(&MaskPipeline::new()).unwrap();
+                assert!(Arc::ptr_eq(&mask.mask, &processed.mask));
+            }
+
+            #[test]
+            fn processed_with_operations_allocates_new_mask() {
+                let mask = gray_mask_handle();
+                let processed = mask.clone().threshold_with(100).processed().unwrap();
+                assert!(!Arc::ptr_eq(&mask.mask, &processed.mask));
+                assert_eq!(processed.as_raw_mask().get_pixel(0, 0)[0], 255);
+                assert_eq!(mask.as_raw_mask().get_pixel(0, 0)[0], 128);
+            }
+        }
+
         mod colorize {
             use super::*;
 
+            #[test]
+            fn validate_accepts_visible_colors() {
+                for color in [
+                    MaskColor::default(),
+                    MaskColor::rgba(0, 0, 0, 1),
+                    MaskColor::default().with_alpha_mode(MaskAlphaMode::Scale(0.5)),
+                    MaskColor::default().with_alpha_mode(MaskAlphaMode::Solid(1)),
+                ] {
+                    assert!(color.validate().is_ok(), "{color:?}");
+                }
+            }
+
+            #[test]
+            fn validate_rejects_invisible_colors() {
+                for (color, reason) in [
+                    (MaskColor::rgba(255, 0, 0, 0), "base alpha is 0"),
+                    (
+                        MaskColor::default().with_alpha_mode(MaskAlphaMode::Scale(0.0)),
+                        "alpha scale 0",
+                    ),
+                    (
+                        MaskColor::default().with_alpha_mode(MaskAlphaMode::Scale(-2.0)),
+                        "alpha scale -2 is clamped",
+                    ),
+                    (
+                        MaskColor::default().with_alpha_mode(MaskAlphaMode::Scale(f32::NAN)),
+                        "must be finite",
+                    ),
+                    (
+                        MaskColor::default().with_alpha_mode(MaskAlphaMode::Solid(0)),
+                        "solid alpha is 0",
+                    ),
+                ] {
+                    match color.validate() {
+                        Err(OutlineError::InvalidMaskColor(message)) => {
+                            assert!(message.contains(reason), "{message}");
+                        }
+                        other => panic!("expected InvalidMaskColor for {color:?}, got {other:?}"),
+                    }
+                }
+            }
+
+            #[test]
+            fn try_colorize_matches_colorize_for_valid_colors() {
+                let mask = gray_image(2, 2, 128);
+                let color = MaskColor::new([0, 180, 255, 255]);
+
+                assert_eq!(
+                    try_colorize_mask(&mask, color).unwrap(),
+                    colorize_mask(&mask, color)
+                );
+                assert!(try_colorize_mask(&mask, [0, 0, 0, 0]).is_err());
+            }
+
             #[test]
             fn default_color_is_white_with_mask_alpha() {
                 let color = MaskColor::default();
@@ -1918,6 +4031,52 @@ mod tests {
             }
         }
 
+        mod posterize_builder {
+            use super::*;
+
+            #[test]
+            fn mask_handle_posterize_records_levels() {
+                let handle = mask_handle().posterize(4);
+                assert!(matches!(
+                    handle.operations.as_slice(),
+                    [MaskOperation::Posterize { levels: 4 }]
+                ));
+            }
+
+            #[test]
+            fn mask_handle_soft_threshold_records_bounds() {
+                let handle = mask_handle().soft_threshold(100, 200);
+                assert!(matches!(
+                    handle.operations.as_slice(),
+                    [MaskOperation::SoftThreshold {
+                        low: 100,
+                        high: 200
+                    }]
+                ));
+            }
+
+            #[test]
+            #[should_panic(expected = "gamma must be > 0.0 and finite")]
+            fn mask_pipeline_gamma_rejects_negative_gamma() {
+                let _ = MaskPipeline::new().gamma(-1.0);
+            }
+
+            #[test]
+            fn mask_handle_invert_records_operation() {
+                let handle = mask_handle().invert();
+                assert!(matches!(
+                    handle.operations.as_slice(),
+                    [MaskOperation::Invert]
+                ));
+            }
+
+            #[test]
+            #[should_panic(expected = "posterize levels must be >= 2")]
+            fn mask_pipeline_posterize_rejects_single_level() {
+                let _ = MaskPipeline::new().posterize(1);
+            }
+        }
+
         mod processing_pipeline {
             use super::*;
 
@@ -1967,7 +4126,7 @@ mod tests {
                 let source = single_pixel_mask_handle().into_image();
                 let handle = MaskHandle {
                     rgb_image: Arc::new(RgbImage::from_pixel(5, 5, Rgb([10, 20, 30]))),
-                    mask: source.clone(),
+                    mask: Arc::new(source.clone()),
                     mask_processing_defaults: MaskProcessingDefaults::default(),
                     operations: Vec::new(),
                 };
@@ -1991,7 +4150,7 @@ mod tests {
                 };
                 let handle = MaskHandle {
                     rgb_image: Arc::new(RgbImage::from_pixel(1, 1, Rgb([255, 255, 255]))),
-                    mask: GrayImage::from_pixel(1, 1, Luma([255])),
+                    mask: Arc::new(GrayImage::from_pixel(1, 1, Luma([255]))),
                     mask_processing_defaults: defaults,
                     operations: Vec::new(),
                 }
@@ -2155,6 +4314,55 @@ mod tests {
                 );
             }
 
+            #[test]
+            fn mask_handle_to_png_bytes_applies_pending_operations() {
+                let bytes = single_pixel_mask_handle()
+                    .dilate_with(1.0)
+                    .to_png_bytes()
+                    .expect("mask should encode");
+
+                let decoded = image::load_from_memory(&bytes)
+                    .expect("encoded mask should load")
+                    .to_luma8();
+
+                assert_eq!(
+                    mask_bounding_box(&decoded, 1),
+                    Some(BoundingBox::new(1, 1, 3, 3))
+                );
+            }
+
+            #[test]
+            fn mask_handle_to_bytes_round_trips() {
+                let handle = single_pixel_mask_handle();
+                let bytes = handle
+                    .to_bytes(ImageFormat::Bmp)
+                    .expect("mask should encode");
+
+                let decoded = image::load_from_memory_with_format(&bytes, ImageFormat::Bmp)
+                    .expect("encoded mask should load")
+                    .to_luma8();
+                assert_eq!(&decoded, handle.as_raw_mask());
+            }
+
+            #[test]
+            fn mask_handle_write_to_applies_pending_operations() {
+                let mut bytes = Vec::new();
+                single_pixel_mask_handle()
+                    .dilate_with(1.0)
+                    .write_to(&mut bytes, ImageFormat::Png)
+                    .expect("mask should encode");
+
+                let decoded = image::load_from_memory(&bytes)
+                    .expect("encoded mask should load")
+                    .to_luma8();
+
+                assert_eq!(decoded.dimensions(), (5, 5));
+                assert_eq!(
+                    mask_bounding_box(&decoded, 1),
+                    Some(BoundingBox::new(1, 1, 3, 3))
+                );
+            }
+
             #[test]
             fn mask_handle_foreground_applies_pending_operations() {
                 let foreground = single_pixel_mask_handle()
@@ -2170,10 +4378,15 @@ mod tests {
             fn mask_handle_colorize_applies_pending_operations() {
                 let colorized = single_pixel_mask_handle()
                     .dilate_with(1.0)
-                    .colorize([0, 180, 255, 255]);
+                    .colorize([0, 180, 255, 255])
+                    .unwrap();
 
                 assert_eq!(colorized.get_pixel(1, 2).0, [0, 180, 255, 255]);
                 assert_eq!(colorized.get_pixel(0, 0).0, [0, 180, 255, 0]);
+                assert!(matches!(
+                    single_pixel_mask_handle().colorize([0, 180, 255, 0]),
+                    Err(OutlineError::InvalidMaskColor(_))
+                ));
             }
 
             #[test]
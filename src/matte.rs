@@ -1,19 +1,25 @@
 use std::borrow::Cow;
+use std::io::Write;
 use std::path::Path;
 use std::sync::Arc;
 
-use image::{GrayImage, RgbImage, RgbaImage};
+use image::imageops::FilterType;
+use image::metadata::Orientation;
+use image::{DynamicImage, GrayImage, ImageBuffer, ImageFormat, Luma, RgbImage, RgbaImage};
+use ndarray::Array2;
 
-use crate::config::{ErosionBorderMode, MaskProcessingDefaults};
+use crate::config::{Connectivity, ErosionBorderMode, MaskProcessingDefaults};
+use crate::encode::{encode_image, encode_png, write_image};
 use crate::foreground::{ForegroundHandle, compose_foreground};
 use crate::geometry::{
     BoundingBox, Padding, crop_bounds_fit_image, crop_gray_image, crop_rgb_image,
     mask_bounding_box, pad_gray_image, pad_rgb_image,
 };
 use crate::mask::{
-    MaskColor, MaskHandle, MaskOperation, MaskPipeline, apply_operations, colorize_mask,
+    MaskColor, MaskHandle, MaskOperation, MaskPipeline, apply_operations, array_to_gray16_image,
+    assert_nonnegative_radius, assert_posterize_levels, invert_mask, try_colorize_mask,
 };
-use crate::{MaskVectorizer, OutlineResult};
+use crate::{MaskVectorizer, OutlineError, OutlineResult};
 
 /// Inference result containing the original RGB image and raw matte prediction.
 ///
@@ -41,6 +47,12 @@ pub struct InferencedMatte {
     rgb_image: Arc<RgbImage>,
     raw_matte: Arc<GrayImage>,
     mask_processing_defaults: MaskProcessingDefaults,
+    /// EXIF orientation applied when decoding the source file.
+    orientation: Orientation,
+    /// Main model output before the final resize to the image size; see `model_matte()`.
+    model_matte: Option<Arc<GrayImage>>,
+    /// The matte before it was quantized into `raw_matte`.
+    raw_matte_f32: Option<Arc<Array2<f32>>>,
 }
 
 impl InferencedMatte {
@@ -53,6 +65,146 @@ impl InferencedMatte {
             rgb_image: Arc::new(rgb_image),
             raw_matte: Arc::new(raw_matte),
             mask_processing_defaults,
+            orientation: Orientation::NoTransforms,
+            model_matte: None,
+            raw_matte_f32: None,
+        }
+    }
+
+    /// Attach the float matte the raw matte was quantized from.
+    pub(crate) fn with_raw_matte_f32(mut self, matte: Array2<f32>) -> Self {
+        self.raw_matte_f32 = Some(Arc::new(matte));
+        self
+    }
+
+    /// Attach the pre-resize matte the raw matte was resized from.
+    pub(crate) fn with_model_matte(mut self, model_matte: GrayImage) -> Self {
+        self.model_matte = Some(Arc::new(model_matte));
+        self
+    }
+
+    /// Record the EXIF orientation that was applied to produce the RGB image.
+    ///
+    /// Files opened through [`Outline`](crate::Outline) record this automatically. Set it on a
+    /// result built with [`from_parts`](Self::from_parts) when the image was decoded with its
+    /// EXIF orientation applied, so stored-orientation outputs still line up with the file.
+    pub fn with_orientation(mut self, orientation: Orientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    /// Replace the parameter values used by no-argument mask processing methods.
+    ///
+    /// Results from [`Outline`](crate::Outline) inherit its defaults; the other constructors
+    /// start from [`MaskProcessingDefaults::default`].
+    pub fn with_mask_processing_defaults(mut self, defaults: MaskProcessingDefaults) -> Self {
+        self.mask_processing_defaults = defaults;
+        self
+    }
+
+    /// Build an inference result from an existing RGB image and matte.
+    ///
+    /// Use this to post-process a matte saved by an earlier run without running the model again.
+    /// Mask processing uses [`MaskProcessingDefaults::default`].
+    ///
+    /// Returns [`OutlineError::AlphaMismatch`] if the image and matte sizes differ.
+    pub fn from_parts(rgb_image: RgbImage, raw_matte: GrayImage) -> OutlineResult<Self> {
+        let expected = rgb_image.dimensions();
+        let found = raw_matte.dimensions();
+        if expected != found {
+            return Err(OutlineError::AlphaMismatch { expected, found });
+        }
+
+        Ok(Self::new(
+            rgb_image,
+            raw_matte,
+            MaskProcessingDefaults::default(),
+        ))
+    }
+
+    /// Build an inference result from an existing RGB image and a matte of any size.
+    ///
+    /// Unlike [`from_parts`](Self::from_parts), a matte whose size differs from the image is
+    /// resized to the image dimensions with `filter` instead of being rejected. Use this for
+    /// mattes produced at a lower resolution or by another tool.
+    ///
+    /// Returns [`OutlineError::AlphaMismatch`] if the sizes differ and either the image or the
+    /// matte is empty, since there is nothing to resize from or to.
+    pub fn from_parts_resized(
+        rgb_image: RgbImage,
+        raw_matte: GrayImage,
+        filter: impl Into<FilterType>,
+    ) -> OutlineResult<Self> {
+        let expected = rgb_image.dimensions();
+        let found = raw_matte.dimensions();
+        let raw_matte = if found == expected {
+            raw_matte
+        } else if expected.0 == 0 || expected.1 == 0 || found.0 == 0 || found.1 == 0 {
+            return Err(OutlineError::AlphaMismatch { expected, found });
+        } else {
+            image::imageops::resize(&raw_matte, expected.0, expected.1, filter.into())
+        };
+
+        Ok(Self::new(
+            rgb_image,
+            raw_matte,
+            MaskProcessingDefaults::default(),
+        ))
+    }
+
+    /// Build a result whose matte is the inverted luminance of `rgb_image`, without a model.
+    ///
+    /// Each matte value is `255 - luma`, so dark ink is opaque and a white background is
+    /// transparent. This suits line art, logos, and scanned documents where the subject is dark
+    /// on light; threshold the matte for a hard cut. Mask processing uses
+    /// [`MaskProcessingDefaults::default`].
+    pub fn from_luma(rgb_image: RgbImage) -> Self {
+        let raw_matte = invert_mask(&image::imageops::grayscale(&rgb_image));
+        Self::new(rgb_image, raw_matte, MaskProcessingDefaults::default())
+    }
+
+    /// Get the main model's matte before its final resize to the image.
+    ///
+    /// For a single model run this is the matte at model resolution. With
+    /// [`with_tiling`](crate::Outline::with_tiling) the tile mattes are each resized to their
+    /// tile and stitched, so this is at the size of the inferred image instead: the input size,
+    /// or the scaled size with [`with_infer_scale`](crate::Outline::with_infer_scale).
+    ///
+    /// Compare it with [`raw_matte`](Self::raw_matte) to tell whether soft or blocky edges come
+    /// from the model or from the resize. Padding added by
+    /// [`with_pad_to_multiple`](crate::Outline::with_pad_to_multiple) is already cropped, and a
+    /// background model's matte is not combined in. Returns `None` for results built from parts.
+    pub fn model_matte(&self) -> Option<&GrayImage> {
+        self.model_matte.as_deref()
+    }
+
+    /// Get the matte at image resolution before it was quantized into
+    /// [`raw_matte`](Self::raw_matte), indexed as `[[y, x]]`.
+    ///
+    /// Use it for numeric work that needs more than 256 levels, such as threshold sweeps. Values
+    /// are in `0.0..=1.0` unless [`MatteActivation::None`](crate::MatteActivation::None) keeps
+    /// the model's raw range; [`array_to_gray_image`](crate::array_to_gray_image) quantizes it
+    /// back to the raw matte. A background model's matte is already combined in. Returns `None`
+    /// unless the [`Outline`](crate::Outline) was built with
+    /// [`with_float_matte(true)`](crate::Outline::with_float_matte), and for results built from
+    /// parts.
+    pub fn raw_matte_f32(&self) -> Option<&Array2<f32>> {
+        self.raw_matte_f32.as_deref()
+    }
+
+    /// Get the matte as a 16-bit grayscale image, mapping `0.0..=1.0` to `0..=65535`.
+    ///
+    /// Built from [`raw_matte_f32`](Self::raw_matte_f32) so gradients keep more than 256 levels,
+    /// for compositing tools that read 16-bit mattes. Without the float matte, as for results
+    /// built from parts, the 8-bit raw matte is widened instead.
+    pub fn raw_matte_u16(&self) -> ImageBuffer<Luma<u16>, Vec<u16>> {
+        match self.raw_matte_f32() {
+            Some(matte) => array_to_gray16_image(matte),
+            None => {
+                ImageBuffer::from_fn(self.raw_matte.width(), self.raw_matte.height(), |x, y| {
+                    Luma([u16::from(self.raw_matte.get_pixel(x, y)[0]) * 257])
+                })
+            }
         }
     }
 
@@ -66,6 +218,70 @@ impl InferencedMatte {
         self.raw_matte.as_ref()
     }
 
+    /// Compute how certain the model is about each matte pixel.
+    ///
+    /// Each value is `255 * |2p - 1|` for the matte alpha `p` in `[0, 1]`, so confidently opaque
+    /// or transparent pixels are 255 and a matte value of 0.5 is 0. Alpha-matting tools can use
+    /// the dark band as the region to refine, without running inference again.
+    ///
+    /// `p` comes from [`raw_matte_f32`](Self::raw_matte_f32) when it was kept, clamped to
+    /// `[0, 1]`, so values near 0.5 are not rounded to the nearest 8-bit level first; otherwise
+    /// from the 8-bit raw matte.
+    pub fn confidence_map(&self) -> GrayImage {
+        if let Some(matte) = self.raw_matte_f32() {
+            let (height, width) = matte.dim();
+            return GrayImage::from_fn(width as u32, height as u32, |x, y| {
+                let p = matte[[y as usize, x as usize]].clamp(0.0, 1.0);
+                Luma([(255.0 * (2.0 * p - 1.0).abs()).round() as u8])
+            });
+        }
+
+        let (width, height) = self.raw_matte.dimensions();
+        let mut confidence = GrayImage::new(width, height);
+        for (src, dst) in self.raw_matte.pixels().zip(confidence.pixels_mut()) {
+            let doubled = 2 * i16::from(src[0]);
+            dst[0] = (doubled - 255).unsigned_abs() as u8;
+        }
+        confidence
+    }
+
+    /// Display dimensions `(width, height)` after EXIF orientation was applied.
+    ///
+    /// These match [`rgb_image`](Self::rgb_image), the matte, and every output derived from them.
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.rgb_image.dimensions()
+    }
+
+    /// Dimensions `(width, height)` as stored in the source file, before EXIF orientation.
+    ///
+    /// Differs from [`dimensions`](Self::dimensions) only when the orientation rotates the image
+    /// by 90 or 270 degrees. In-memory inputs have no orientation, so both are equal.
+    pub fn stored_dimensions(&self) -> (u32, u32) {
+        let (width, height) = self.dimensions();
+        if swaps_dimensions(self.orientation) {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
+
+    /// EXIF orientation applied when the source file was decoded.
+    ///
+    /// [`Orientation::NoTransforms`] for in-memory inputs and files without orientation metadata.
+    pub fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    /// Map an output image from display orientation back to the source file's stored orientation.
+    ///
+    /// Use this for outputs that must line up with the stored pixel grid, for example when a
+    /// downstream system reads the original width and height and ignores EXIF orientation.
+    pub fn to_stored_orientation(&self, image: DynamicImage) -> DynamicImage {
+        let mut image = image;
+        image.apply_orientation(inverse_orientation(self.orientation));
+        image
+    }
+
     /// Begin building a mask processing pipeline from the raw matte.
     pub fn matte(&self) -> MatteHandle {
         MatteHandle {
@@ -77,6 +293,25 @@ impl InferencedMatte {
     }
 }
 
+fn swaps_dimensions(orientation: Orientation) -> bool {
+    matches!(
+        orientation,
+        Orientation::Rotate90
+            | Orientation::Rotate270
+            | Orientation::Rotate90FlipH
+            | Orientation::Rotate270FlipH
+    )
+}
+
+// Rotations by 90 and 270 degrees undo each other; every other orientation is its own inverse.
+pub(crate) fn inverse_orientation(orientation: Orientation) -> Orientation {
+    match orientation {
+        Orientation::Rotate90 => Orientation::Rotate270,
+        Orientation::Rotate270 => Orientation::Rotate90,
+        other => other,
+    }
+}
+
 /// Builder for chaining mask processing operations on the raw matte.
 ///
 /// The raw matte is the soft, grayscale alpha prediction from the model.
@@ -166,6 +401,21 @@ impl MatteHandle {
         Ok(())
     }
 
+    /// Encode the current matte as an 8-bit grayscale PNG in memory.
+    pub fn to_png_bytes(&self) -> OutlineResult<Vec<u8>> {
+        encode_png(&self.resolved_matte())
+    }
+
+    /// Encode the current matte as `format` in memory.
+    pub fn to_bytes(&self, format: ImageFormat) -> OutlineResult<Vec<u8>> {
+        encode_image(&self.resolved_matte(), format)
+    }
+
+    /// Encode the current matte as `format` into `writer`, such as a socket or buffer.
+    pub fn write_to<W: Write>(&self, writer: &mut W, format: ImageFormat) -> OutlineResult<()> {
+        write_image(&self.resolved_matte(), writer, format)
+    }
+
     /// Compute the bounding box of the current matte using a non-zero threshold.
     pub fn bounding_box(&self) -> Option<BoundingBox> {
         self.bounding_box_with(1)
@@ -190,6 +440,38 @@ impl MatteHandle {
         self
     }
 
+    /// Add a coverage-normalized blur using the default sigma.
+    ///
+    /// Unlike [`blur`](MatteHandle::blur), this feathers the edge outward instead of thinning
+    /// the subject; see [`MaskOperation::NormalizedBlur`].
+    pub fn normalized_blur(self) -> Self {
+        let sigma = self.mask_processing_defaults.blur_sigma;
+        self.normalized_blur_with(sigma)
+    }
+
+    /// Add a coverage-normalized blur with a custom sigma.
+    pub fn normalized_blur_with(mut self, sigma: f32) -> Self {
+        self.operations
+            .push(MaskOperation::NormalizedBlur { sigma });
+        self
+    }
+
+    /// Feather the mask edge with a linear alpha ramp `radius` pixels to each side of the outline.
+    ///
+    /// The opaque interior stays exactly 255; see [`MaskOperation::Feather`].
+    ///
+    /// **Note**: Feathering assumes a hard mask. Call [`threshold`](MatteHandle::threshold)
+    /// before `feather` if working with a soft matte.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radius` is negative or NaN.
+    pub fn feather(mut self, radius: f32) -> Self {
+        assert_nonnegative_radius(radius);
+        self.operations.push(MaskOperation::Feather { radius });
+        self
+    }
+
     /// Add a threshold operation using the default mask threshold.
     pub fn threshold(mut self) -> Self {
         let value = self.mask_processing_defaults.mask_threshold;
@@ -203,6 +485,26 @@ impl MatteHandle {
         self
     }
 
+    /// Add a threshold operation whose value is picked per matte with Otsu's method.
+    ///
+    /// Useful when lighting varies between images and no single fixed threshold fits all of
+    /// them. See [`otsu_threshold`](crate::otsu_threshold) to read the picked value.
+    pub fn otsu_threshold(mut self) -> Self {
+        self.operations.push(MaskOperation::OtsuThreshold);
+        self
+    }
+
+    /// Add a soft threshold: values at or below `low` become 0, at or above `high` become 255,
+    /// and values in between ramp linearly.
+    ///
+    /// A middle ground between [`threshold`](MatteHandle::threshold) and the raw matte: the
+    /// result is clean away from the edge but stays feathered across it.
+    pub fn soft_threshold(mut self, low: u8, high: u8) -> Self {
+        self.operations
+            .push(MaskOperation::SoftThreshold { low, high });
+        self
+    }
+
     /// Add a dilation operation using the default radius.
     ///
     /// **Note**: Dilation typically works best on binary masks. Consider calling
@@ -261,6 +563,51 @@ impl MatteHandle {
         self
     }
 
+    /// Add a morphological opening (erode, then dilate) that removes specks narrower than
+    /// `radius`.
+    ///
+    /// **Note**: Opening assumes a binary mask. Call [`threshold`](MatteHandle::threshold)
+    /// before `open` if working with a soft matte.
+    pub fn open(mut self, radius: f32) -> Self {
+        self.operations.push(MaskOperation::Open { radius });
+        self
+    }
+
+    /// Add a morphological closing (dilate, then erode) that fills holes narrower than
+    /// `radius`.
+    ///
+    /// **Note**: Closing assumes a binary mask. Call [`threshold`](MatteHandle::threshold)
+    /// before `close` if working with a soft matte.
+    pub fn close(mut self, radius: f32) -> Self {
+        self.operations.push(MaskOperation::Close { radius });
+        self
+    }
+
+    /// Keep only the largest connected foreground region, using 8-connectivity.
+    ///
+    /// **Note**: Nonzero pixels count as foreground. Call [`threshold`](MatteHandle::threshold)
+    /// before `keep_largest` if working with a soft matte.
+    pub fn keep_largest(self) -> Self {
+        self.keep_largest_with(Connectivity::default())
+    }
+
+    /// Keep only the largest connected foreground region with the given connectivity.
+    pub fn keep_largest_with(mut self, connectivity: Connectivity) -> Self {
+        self.operations
+            .push(MaskOperation::KeepLargestComponent { connectivity });
+        self
+    }
+
+    /// Drop connected foreground regions smaller than `min_area` pixels.
+    ///
+    /// **Note**: Nonzero pixels count as foreground. Call [`threshold`](MatteHandle::threshold)
+    /// before `remove_small_components` if working with a soft matte.
+    pub fn remove_small_components(mut self, min_area: u32) -> Self {
+        self.operations
+            .push(MaskOperation::RemoveSmallComponents { min_area });
+        self
+    }
+
     /// Add a hole-filling operation to the processing pipeline.
     ///
     /// **Note**: Hole-filling typically works best on binary masks. Consider calling
@@ -279,6 +626,54 @@ impl MatteHandle {
         self
     }
 
+    /// Add an operation that makes regions enclosed by strong foreground opaque.
+    ///
+    /// Unlike [`fill_holes`](MatteHandle::fill_holes), this works on a soft matte directly:
+    /// pixels outside the enclosed regions keep their values, so the subject's edges stay soft.
+    pub fn solidify(self) -> Self {
+        let threshold = self.mask_processing_defaults.mask_threshold;
+        self.solidify_with(threshold)
+    }
+
+    /// Add a solidify operation with a custom threshold.
+    pub fn solidify_with(mut self, threshold: u8) -> Self {
+        self.operations.push(MaskOperation::Solidify { threshold });
+        self
+    }
+
+    /// Add an operation that quantizes the matte to `levels` evenly spaced alpha values.
+    ///
+    /// Unlike [`threshold`](MatteHandle::threshold), which always produces a binary mask, this
+    /// keeps a stepped soft alpha, e.g. `posterize(4)` yields 0, 85, 170, and 255.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `levels` is less than 2.
+    pub fn posterize(mut self, levels: u8) -> Self {
+        assert_posterize_levels(levels);
+        self.operations.push(MaskOperation::Posterize { levels });
+        self
+    }
+
+    /// Add an operation that applies a gamma curve, mapping each value `v` to
+    /// `255 * (v / 255)^gamma`.
+    ///
+    /// A gamma above 1 pushes soft values that cluster around mid-gray toward 0, so a following
+    /// [`threshold`](MatteHandle::threshold) separates them more cleanly; see
+    /// [`MaskOperation::Gamma`].
+    pub fn gamma(mut self, gamma: f32) -> Self {
+        self.operations.push(MaskOperation::Gamma { gamma });
+        self
+    }
+
+    /// Add an operation that inverts the matte so each pixel becomes `255 - value`.
+    ///
+    /// Useful to cut out the background instead of the subject; see [`MaskOperation::Invert`].
+    pub fn invert(mut self) -> Self {
+        self.operations.push(MaskOperation::Invert);
+        self
+    }
+
     /// Process the raw matte with the accumulated operations.
     pub fn processed(self) -> OutlineResult<MaskHandle> {
         self.process_with_pipeline(None)
@@ -289,6 +684,32 @@ impl MatteHandle {
         self.process_with_pipeline(Some(pipeline))
     }
 
+    /// Process the raw matte with the accumulated operations and also return the raw matte.
+    ///
+    /// Use this to export both the matte and the processed mask without cloning the handle
+    /// first.
+    pub fn processed_with_raw(self) -> OutlineResult<(MaskHandle, GrayImage)> {
+        self.process_keeping_raw(None)
+    }
+
+    /// Like [`processed_with_raw`](MatteHandle::processed_with_raw), appending a custom pipeline
+    /// after the accumulated operations.
+    pub fn processed_with_pipeline_and_raw(
+        self,
+        pipeline: &MaskPipeline,
+    ) -> OutlineResult<(MaskHandle, GrayImage)> {
+        self.process_keeping_raw(Some(pipeline))
+    }
+
+    fn process_keeping_raw(
+        self,
+        pipeline: Option<&MaskPipeline>,
+    ) -> OutlineResult<(MaskHandle, GrayImage)> {
+        let raw_matte = Arc::clone(&self.raw_matte);
+        let mask = self.process_with_pipeline(pipeline)?;
+        Ok((mask, Arc::unwrap_or_clone(raw_matte)))
+    }
+
     fn process_with_pipeline(
         mut self,
         pipeline: Option<&MaskPipeline>,
@@ -298,9 +719,14 @@ impl MatteHandle {
             ops.extend_from_slice(custom.operations());
         }
 
-        let mask = apply_operations(self.raw_matte.as_ref(), &ops);
+        // Without operations the mask is the raw matte itself, so share it.
+        let mask = if ops.is_empty() {
+            self.raw_matte
+        } else {
+            Arc::new(apply_operations(self.raw_matte.as_ref(), &ops))
+        };
         Ok(MaskHandle::new(
-            Arc::clone(&self.rgb_image),
+            self.rgb_image,
             mask,
             self.mask_processing_defaults,
         ))
@@ -314,9 +740,11 @@ impl MatteHandle {
     }
 
     /// Colorize the current matte into a flat-color RGBA image.
-    pub fn colorize(&self, color: impl Into<MaskColor>) -> RgbaImage {
-        let mask = self.resolved_matte();
-        colorize_mask(&mask, color)
+    ///
+    /// Returns [`OutlineError::InvalidMaskColor`] if [`MaskColor::validate`] rejects the color,
+    /// instead of producing an invisible layer.
+    pub fn colorize(&self, color: impl Into<MaskColor>) -> OutlineResult<RgbaImage> {
+        try_colorize_mask(&self.resolved_matte(), color)
     }
 
     /// Trace the current matte using the specified vectorizer and options.
@@ -385,6 +813,8 @@ impl MatteHandle {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::MaskAlphaMode;
+    use crate::mask::array_to_gray_image;
     use image::{Luma, Rgb};
 
     fn matte_handle() -> MatteHandle {
@@ -435,6 +865,186 @@ mod tests {
         }
     }
 
+    #[test]
+    fn inferenced_matte_from_parts_keeps_images() {
+        let rgb = RgbImage::from_pixel(3, 2, Rgb([1, 2, 3]));
+        let matte = GrayImage::from_pixel(3, 2, Luma([200]));
+
+        let session = InferencedMatte::from_parts(rgb.clone(), matte.clone())
+            .expect("matching sizes should be accepted");
+
+        assert_eq!(session.rgb_image(), &rgb);
+        assert_eq!(session.raw_matte(), &matte);
+        assert_eq!(session.matte().foreground().unwrap().dimensions(), (3, 2));
+    }
+
+    #[test]
+    fn inferenced_matte_from_parts_rejects_size_mismatch() {
+        let rgb = RgbImage::new(3, 2);
+        let matte = GrayImage::new(2, 3);
+
+        let err = InferencedMatte::from_parts(rgb, matte).unwrap_err();
+        assert!(matches!(
+            err,
+            OutlineError::AlphaMismatch {
+                expected: (3, 2),
+                found: (2, 3)
+            }
+        ));
+    }
+
+    #[test]
+    fn inferenced_matte_from_parts_resized_scales_matte_to_image() {
+        let rgb = RgbImage::new(4, 2);
+        let matte = GrayImage::from_fn(2, 1, |x, _| Luma([if x == 0 { 0 } else { 255 }]));
+
+        let session = InferencedMatte::from_parts_resized(rgb, matte, FilterType::Nearest).unwrap();
+
+        assert_eq!(session.raw_matte().dimensions(), (4, 2));
+        assert_eq!(
+            session.raw_matte().as_raw(),
+            &[0, 0, 255, 255, 0, 0, 255, 255]
+        );
+    }
+
+    #[test]
+    fn inferenced_matte_from_parts_resized_rejects_empty_sizes() {
+        let result = InferencedMatte::from_parts_resized(
+            RgbImage::new(4, 2),
+            GrayImage::new(0, 0),
+            FilterType::Nearest,
+        );
+        assert!(matches!(
+            result,
+            Err(OutlineError::AlphaMismatch {
+                expected: (4, 2),
+                found: (0, 0)
+            })
+        ));
+
+        let result = InferencedMatte::from_parts_resized(
+            RgbImage::new(0, 3),
+            GrayImage::new(2, 2),
+            FilterType::Nearest,
+        );
+        assert!(result.is_err());
+
+        let empty = InferencedMatte::from_parts_resized(
+            RgbImage::new(0, 0),
+            GrayImage::new(0, 0),
+            FilterType::Nearest,
+        );
+        assert!(empty.is_ok());
+    }
+
+    #[test]
+    fn inferenced_matte_from_luma_inverts_luminance() {
+        let rgb = RgbImage::from_fn(3, 1, |x, _| match x {
+            0 => Rgb([0, 0, 0]),
+            1 => Rgb([255, 255, 255]),
+            _ => Rgb([100, 100, 100]),
+        });
+
+        let session = InferencedMatte::from_luma(rgb.clone());
+
+        assert_eq!(session.rgb_image(), &rgb);
+        assert_eq!(session.raw_matte().as_raw(), &[255, 0, 155]);
+        assert!(session.model_matte().is_none());
+
+        let defaults = MaskProcessingDefaults {
+            mask_threshold: 200,
+            ..MaskProcessingDefaults::default()
+        };
+        let session = session.with_mask_processing_defaults(defaults);
+        assert_eq!(
+            session
+                .matte()
+                .threshold()
+                .processed()
+                .unwrap()
+                .as_raw_mask()
+                .as_raw(),
+            &[255, 0, 0]
+        );
+    }
+
+    #[test]
+    fn confidence_map_peaks_at_the_extremes() {
+        let rgb = RgbImage::new(5, 1);
+        let matte = GrayImage::from_raw(5, 1, vec![0, 64, 127, 128, 255]).unwrap();
+        let session = InferencedMatte::from_parts(rgb, matte).unwrap();
+
+        assert_eq!(session.confidence_map().as_raw(), &[255, 127, 1, 1, 255]);
+    }
+
+    #[test]
+    fn confidence_map_uses_the_float_matte_when_kept() {
+        let rgb = RgbImage::new(5, 1);
+        let float = Array2::from_shape_vec((1, 5), vec![0.0, 0.25, 0.5, 0.502, 1.5]).unwrap();
+        let session = InferencedMatte::new(rgb, array_to_gray_image(&float), Default::default())
+            .with_raw_matte_f32(float);
+
+        // The 8-bit matte rounds 0.5 and 0.502 to 128, which would read as 1 for both.
+        assert_eq!(session.confidence_map().as_raw(), &[255, 128, 0, 1, 255]);
+    }
+
+    #[test]
+    fn inferenced_matte_from_parts_resized_keeps_matching_matte() {
+        let rgb = RgbImage::new(3, 2);
+        let matte = GrayImage::from_fn(3, 2, |x, y| Luma([(x * 40 + y) as u8]));
+
+        let session =
+            InferencedMatte::from_parts_resized(rgb, matte.clone(), FilterType::Lanczos3).unwrap();
+
+        assert_eq!(session.raw_matte(), &matte);
+    }
+
+    #[test]
+    fn inferenced_matte_without_orientation_has_equal_dimensions() {
+        let session = InferencedMatte::from_parts(RgbImage::new(3, 2), GrayImage::new(3, 2))
+            .expect("matching sizes should be accepted");
+
+        assert_eq!(session.orientation(), Orientation::NoTransforms);
+        assert_eq!(session.dimensions(), (3, 2));
+        assert_eq!(session.stored_dimensions(), (3, 2));
+    }
+
+    #[test]
+    fn inferenced_matte_rotated_orientation_swaps_stored_dimensions() {
+        let session = InferencedMatte::from_parts(RgbImage::new(3, 2), GrayImage::new(3, 2))
+            .expect("matching sizes should be accepted")
+            .with_orientation(Orientation::Rotate90);
+
+        assert_eq!(session.dimensions(), (3, 2));
+        assert_eq!(session.stored_dimensions(), (2, 3));
+    }
+
+    #[test]
+    fn to_stored_orientation_undoes_every_orientation() {
+        let stored =
+            DynamicImage::ImageLuma8(GrayImage::from_fn(3, 2, |x, y| Luma([(y * 3 + x) as u8])));
+        for orientation in [
+            Orientation::NoTransforms,
+            Orientation::Rotate90,
+            Orientation::Rotate180,
+            Orientation::Rotate270,
+            Orientation::FlipHorizontal,
+            Orientation::FlipVertical,
+            Orientation::Rotate90FlipH,
+            Orientation::Rotate270FlipH,
+        ] {
+            let mut display = stored.clone();
+            display.apply_orientation(orientation);
+            let session = InferencedMatte::from_parts(display.to_rgb8(), display.to_luma8())
+                .expect("matching sizes should be accepted")
+                .with_orientation(orientation);
+
+            assert_eq!(session.stored_dimensions(), (3, 2), "{orientation:?}");
+            let restored = session.to_stored_orientation(display);
+            assert_eq!(restored.to_luma8(), stored.to_luma8(), "{orientation:?}");
+        }
+    }
+
     #[test]
     fn matte_handle_dimensions_reports_current_canvas() {
         let cropped = single_pixel_matte_handle()
@@ -446,6 +1056,35 @@ mod tests {
         assert_eq!(padded.dimensions(), (6, 9));
     }
 
+    #[test]
+    fn matte_handle_processed_without_operations_shares_matte() {
+        let handle = single_pixel_matte_handle();
+        let mask = handle.clone().processed().unwrap();
+        assert!(std::ptr::eq(handle.as_raw_matte(), mask.as_raw_mask()));
+
+        let mask = handle.clone().threshold().processed().unwrap();
+        assert!(!std::ptr::eq(handle.as_raw_matte(), mask.as_raw_mask()));
+    }
+
+    #[test]
+    fn matte_handle_processed_with_raw_returns_both_mattes() {
+        let handle = single_pixel_matte_handle();
+        let source = handle.to_raw_matte();
+
+        let (mask, raw) = handle
+            .clone()
+            .dilate_with(1.0)
+            .processed_with_raw()
+            .unwrap();
+        assert_eq!(raw, source);
+        assert_eq!(mask.as_raw_mask().get_pixel(1, 2)[0], 255);
+
+        let pipeline = MaskPipeline::new().dilate_with(1.0);
+        let (piped, raw) = handle.processed_with_pipeline_and_raw(&pipeline).unwrap();
+        assert_eq!(raw, source);
+        assert_eq!(piped.as_raw_mask(), mask.as_raw_mask());
+    }
+
     #[test]
     fn matte_handle_raw_accessors_return_original_matte() {
         let handle = single_pixel_matte_handle().dilate_with(1.0);
@@ -501,6 +1140,54 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn matte_handle_normalized_blur_uses_default_sigma() {
+        let handle = matte_handle().normalized_blur();
+        assert!(matches!(
+            handle.operations.as_slice(),
+            [MaskOperation::NormalizedBlur { sigma }] if (*sigma - 6.0).abs() < f32::EPSILON
+        ));
+    }
+
+    #[test]
+    fn matte_handle_solidify_with_uses_custom_threshold() {
+        let handle = matte_handle().solidify_with(200);
+        assert!(matches!(
+            handle.operations.as_slice(),
+            [MaskOperation::Solidify { threshold: 200 }]
+        ));
+    }
+
+    #[test]
+    fn matte_handle_posterize_records_levels() {
+        let handle = matte_handle().posterize(4);
+        assert!(matches!(
+            handle.operations.as_slice(),
+            [MaskOperation::Posterize { levels: 4 }]
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "radius must be >= 0.0")]
+    fn matte_handle_feather_rejects_negative_radius() {
+        let _ = matte_handle().feather(-1.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "radius must be >= 0.0")]
+    fn matte_handle_feather_rejects_nan_radius() {
+        let _ = matte_handle().feather(f32::NAN);
+    }
+
+    #[test]
+    fn matte_handle_invert_records_operation() {
+        let handle = matte_handle().invert();
+        assert!(matches!(
+            handle.operations.as_slice(),
+            [MaskOperation::Invert]
+        ));
+    }
+
     #[test]
     fn matte_handle_chain_and_pipeline_are_equivalent() {
         let pipeline = MaskPipeline::new()
@@ -686,6 +1373,55 @@ mod tests {
         );
     }
 
+    #[test]
+    fn matte_handle_to_png_bytes_applies_pending_operations() {
+        let bytes = single_pixel_matte_handle()
+            .dilate_with(1.0)
+            .to_png_bytes()
+            .expect("matte should encode");
+
+        let decoded = image::load_from_memory(&bytes)
+            .expect("encoded matte should load")
+            .to_luma8();
+
+        assert_eq!(
+            mask_bounding_box(&decoded, 1),
+            Some(BoundingBox::new(1, 1, 3, 3))
+        );
+    }
+
+    #[test]
+    fn matte_handle_to_bytes_round_trips() {
+        let handle = single_pixel_matte_handle();
+        let bytes = handle
+            .to_bytes(ImageFormat::Tiff)
+            .expect("matte should encode");
+
+        let decoded = image::load_from_memory(&bytes)
+            .expect("encoded matte should load")
+            .to_luma8();
+        assert_eq!(&decoded, handle.as_raw_matte());
+    }
+
+    #[test]
+    fn matte_handle_write_to_applies_pending_operations() {
+        let mut bytes = Vec::new();
+        single_pixel_matte_handle()
+            .dilate_with(1.0)
+            .write_to(&mut bytes, ImageFormat::Png)
+            .expect("matte should encode");
+
+        let decoded = image::load_from_memory(&bytes)
+            .expect("encoded matte should load")
+            .to_luma8();
+
+        assert_eq!(decoded.dimensions(), (5, 5));
+        assert_eq!(
+            mask_bounding_box(&decoded, 1),
+            Some(BoundingBox::new(1, 1, 3, 3))
+        );
+    }
+
     #[test]
     fn matte_handle_foreground_applies_pending_operations() {
         let foreground = single_pixel_matte_handle()
@@ -701,10 +1437,17 @@ mod tests {
     fn matte_handle_colorize_applies_pending_operations() {
         let colorized = single_pixel_matte_handle()
             .dilate_with(1.0)
-            .colorize([0, 180, 255, 255]);
+            .colorize([0, 180, 255, 255])
+            .unwrap();
 
         assert_eq!(colorized.get_pixel(1, 2).0, [0, 180, 255, 255]);
         assert_eq!(colorized.get_pixel(0, 0).0, [0, 180, 255, 0]);
+        assert!(matches!(
+            single_pixel_matte_handle().colorize(
+                MaskColor::new([0, 180, 255, 255]).with_alpha_mode(MaskAlphaMode::Solid(0))
+            ),
+            Err(OutlineError::InvalidMaskColor(_))
+        ));
     }
 
     #[test]
@@ -11,7 +11,8 @@ use sha2::{Digest, Sha256};
 
 use outline::OutlineResult;
 
-const APP_DIR_NAME: &str = "outline-core";
+use crate::cache::default_cache_dir;
+
 const MODEL_FILENAME: &str = "model.onnx";
 const DEFAULT_MODEL_URL: &str =
     "https://github.com/danielgatis/rembg/releases/download/v0.0.0/silueta.onnx";
@@ -60,32 +61,11 @@ impl FetchOptions {
     }
 }
 
-/// Get the default model cache directory path.
-///
-/// Returns `~/.cache/outline-core/` on Linux, `~/Library/Caches/outline-core/` on macOS,
-/// or falls back to current directory if home cannot be determined.
-pub fn default_model_cache_dir() -> PathBuf {
-    let env_override = std::env::var_os("OUTLINE_MODEL_CACHE_DIR").map(PathBuf::from);
-    resolve_cache_dir(env_override, dirs::cache_dir())
-}
-
-fn resolve_cache_dir(env_override: Option<PathBuf>, system_cache_dir: Option<PathBuf>) -> PathBuf {
-    if let Some(path) = env_override
-        && !path.as_os_str().is_empty()
-    {
-        return path;
-    }
-
-    system_cache_dir
-        .map(|path| path.join(APP_DIR_NAME))
-        .unwrap_or_else(|| PathBuf::from("."))
-}
-
 /// Get the default model cache path.
 ///
 /// Returns `~/.cache/outline-core/model.onnx` on Linux, etc.
 pub fn default_model_cache_path() -> PathBuf {
-    default_model_cache_dir().join(MODEL_FILENAME)
+    default_cache_dir().join(MODEL_FILENAME)
 }
 
 fn download_and_verify<R: Read>(
@@ -216,29 +196,6 @@ mod tests {
     use super::*;
     use std::io::Cursor;
 
-    #[test]
-    fn resolve_cache_dir_uses_env_override() {
-        let custom_dir = tempfile::tempdir().expect("failed to create temp dir");
-
-        let resolved = resolve_cache_dir(Some(custom_dir.path().to_path_buf()), None);
-        assert_eq!(resolved, custom_dir.path().to_path_buf());
-    }
-
-    #[test]
-    fn resolve_cache_dir_empty_override_falls_back_to_system_cache() {
-        let system_cache = tempfile::tempdir().expect("failed to create temp dir");
-        let expected = system_cache.path().join(APP_DIR_NAME);
-
-        let resolved = resolve_cache_dir(Some(PathBuf::from("")), Some(system_cache.path().into()));
-        assert_eq!(resolved, expected);
-    }
-
-    #[test]
-    fn resolve_cache_dir_without_any_source_falls_back_to_current_dir() {
-        let resolved = resolve_cache_dir(None, None);
-        assert_eq!(resolved, PathBuf::from("."));
-    }
-
     #[test]
     fn default_model_cache_path_uses_model_filename() {
         assert_eq!(
@@ -0,0 +1,91 @@
+use std::time::{Duration, Instant};
+
+/// A step of the inference pipeline reported to a [`PipelineObserver`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum PipelineStage {
+    /// Initialize the model session (first run only) and decode the input file.
+    Load,
+    /// Resize, normalize, and pad the image into the model input tensor.
+    Preprocess,
+    /// Run the model.
+    Infer,
+    /// Crop padding and resize the model output back to the image size.
+    Postprocess,
+    /// Combine background mattes, run the sanity check, and build the result.
+    Matte,
+}
+
+/// Receives a callback as each [`PipelineStage`] finishes.
+///
+/// Pass an observer to [`Outline::for_image_observed`](crate::Outline::for_image_observed) or
+/// [`Outline::for_rgb_image_observed`](crate::Outline::for_rgb_image_observed) to drive a
+/// progress display or collect timings. Closures taking `(PipelineStage, Duration)` implement
+/// this trait.
+///
+/// ```no_run
+/// use outline::{Outline, PipelineStage};
+///
+/// let outline = Outline::new("model.onnx");
+/// let mut report = |stage: PipelineStage, elapsed: std::time::Duration| {
+///     eprintln!("{stage:?} took {elapsed:?}");
+/// };
+/// let session = outline.for_image_observed("input.jpg", &mut report)?;
+/// # Ok::<_, outline::OutlineError>(())
+/// ```
+pub trait PipelineObserver {
+    /// Called once `stage` has finished, with the time spent in it.
+    ///
+    /// With a background model, the preprocess, infer, and postprocess stages are reported once
+    /// for the background model and then once for the main model. With tiling, the preprocess
+    /// and infer stages are reported once per tile.
+    fn on_stage(&mut self, stage: PipelineStage, elapsed: Duration);
+}
+
+impl<F> PipelineObserver for F
+where
+    F: FnMut(PipelineStage, Duration),
+{
+    fn on_stage(&mut self, stage: PipelineStage, elapsed: Duration) {
+        self(stage, elapsed)
+    }
+}
+
+/// Observer used by the unobserved entry points.
+pub(crate) struct NoopObserver;
+
+impl PipelineObserver for NoopObserver {
+    fn on_stage(&mut self, _stage: PipelineStage, _elapsed: Duration) {}
+}
+
+/// Times consecutive stages and reports each one to an observer.
+pub(crate) struct StageTimer<'a> {
+    observer: &'a mut dyn PipelineObserver,
+    started: Instant,
+}
+
+impl<'a> StageTimer<'a> {
+    pub(crate) fn new(observer: &'a mut dyn PipelineObserver) -> Self {
+        Self {
+            observer,
+            started: Instant::now(),
+        }
+    }
+
+    /// Report `stage` as finished and start timing the next one.
+    pub(crate) fn finish(&mut self, stage: PipelineStage) {
+        let now = Instant::now();
+        self.observer.on_stage(stage, now - self.started);
+        self.started = now;
+    }
+
+    /// Restart timing after nested stages were reported through [`observer`](Self::observer).
+    pub(crate) fn restart(&mut self) {
+        self.started = Instant::now();
+    }
+
+    /// Borrow the observer to report nested stages.
+    pub(crate) fn observer(&mut self) -> &mut dyn PipelineObserver {
+        &mut *self.observer
+    }
+}
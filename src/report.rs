@@ -1,4 +1,18 @@
-use outline::OutlineError;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use outline::{MatteStats, ModelOutputInfo, OutlineError, OutlineResult};
+use serde::Serialize;
+
+use crate::cli::{GlobalOptions, OutputFormatArg};
+use crate::diagnostics::Diagnostic;
+
+pub fn report_diagnostics(diagnostics: &[Diagnostic]) {
+    for diagnostic in diagnostics {
+        eprintln!("{diagnostic}");
+    }
+}
 
 pub fn report_error(err: &OutlineError) {
     match err {
@@ -17,8 +31,188 @@ pub fn report_error(err: &OutlineError) {
                 eprintln!("Or run `outline fetch-model` to download automatically.");
             }
         }
+        OutlineError::DegenerateMatte { .. } => {
+            eprintln!("{err}");
+            eprintln!();
+            eprintln!("The model produced an almost uniform matte. Check that:");
+            eprintln!("  - --model points at a background-removal model");
+            eprintln!("  - --input-size and --model-input-layout match the model, if set");
+        }
+        OutlineError::ModelRun { .. } => {
+            eprintln!("{err}");
+            eprintln!();
+            eprintln!("If the model expects a different input, override the detected one with:");
+            eprintln!("  - --input-size WIDTHxHEIGHT");
+            eprintln!("  - --model-input-layout {{nchw,nhwc}}");
+        }
+        OutlineError::ImageTooLarge { .. } => {
+            eprintln!("{err}");
+            eprintln!();
+            eprintln!("Downscale the input, or raise the limit with --max-pixels (0 disables it).");
+        }
         _ => {
             eprintln!("{err}");
         }
     }
 }
+
+/// Results of a command run, for `--format`.
+///
+/// With `text`, each result is printed as it is recorded, exactly as the commands always have.
+/// With `json`, nothing is printed until [`finish`](Self::finish) writes one JSON object, so
+/// wrappers can parse stdout instead of scraping the text lines.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    #[serde(skip)]
+    format: OutputFormatArg,
+    command: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    input: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    height: Option<u32>,
+    outputs: Vec<ReportOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stats: Option<ReportStats>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    auto_thresholds: Vec<u8>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    items: Vec<ReportItem>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    model_outputs: Vec<ReportModelOutput>,
+}
+
+/// A file written by a command, tagged with what it holds (e.g. `foreground` or `mask`).
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportOutput {
+    pub kind: &'static str,
+    pub path: PathBuf,
+}
+
+/// The outcome for one input of a multi-input command such as `batch`.
+#[derive(Debug, Serialize)]
+pub struct ReportItem {
+    pub input: PathBuf,
+    pub outputs: Vec<ReportOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// One model output tensor listed by `--list-outputs`; `null` dimensions are dynamic.
+#[derive(Debug, Serialize)]
+struct ReportModelOutput {
+    index: usize,
+    name: String,
+    shape: Option<Vec<Option<usize>>>,
+}
+
+#[derive(Debug, Serialize)]
+struct ReportStats {
+    threshold: u8,
+    pixels: u64,
+    #[serde(flatten)]
+    stats: MatteStats,
+}
+
+impl Report {
+    pub fn new(global: &GlobalOptions, command: &'static str) -> Self {
+        Self {
+            format: global.format,
+            command,
+            input: None,
+            width: None,
+            height: None,
+            outputs: Vec::new(),
+            stats: None,
+            auto_thresholds: Vec::new(),
+            items: Vec::new(),
+            model_outputs: Vec::new(),
+        }
+    }
+
+    /// Record the input path and the dimensions of the decoded image.
+    pub fn input(&mut self, path: &Path, (width, height): (u32, u32)) {
+        self.input = Some(path.to_path_buf());
+        self.width = Some(width);
+        self.height = Some(height);
+    }
+
+    /// Record a saved file; the text line reads `<label> saved to <path>`.
+    pub fn saved(&mut self, kind: &'static str, label: impl fmt::Display, path: &Path) {
+        if self.format == OutputFormatArg::Text {
+            println!("{label} saved to {}", path.display());
+        }
+        self.outputs.push(ReportOutput {
+            kind,
+            path: path.to_path_buf(),
+        });
+    }
+
+    /// Record the matte statistics, counting pixels at or above `threshold` as foreground.
+    pub fn stats(&mut self, stats: MatteStats, pixels: u64, threshold: u8) {
+        if self.format == OutputFormatArg::Text {
+            println!(
+                "Coverage: {:.1}% ({} of {pixels} pixels at or above {threshold})",
+                stats.coverage * 100.0,
+                stats.foreground_pixels,
+            );
+            println!(
+                "Matte values: min {}, max {}, mean {:.1}",
+                stats.min, stats.max, stats.mean
+            );
+        }
+        self.stats = Some(ReportStats {
+            threshold,
+            pixels,
+            stats,
+        });
+    }
+
+    /// Record a threshold picked by `--auto-threshold`.
+    pub fn auto_threshold(&mut self, value: u8) {
+        if self.format == OutputFormatArg::Text {
+            println!("Auto threshold (Otsu): {value}");
+        }
+        self.auto_thresholds.push(value);
+    }
+
+    /// Record a model output tensor; the text line reads `<index>: <name> [<shape>]`.
+    pub fn model_output(&mut self, index: usize, output: ModelOutputInfo) {
+        if self.format == OutputFormatArg::Text {
+            println!("{index}: {output}");
+        }
+        self.model_outputs.push(ReportModelOutput {
+            index,
+            name: output.name,
+            shape: output.shape,
+        });
+    }
+
+    /// Record the outcome for one input; text output is left to the command.
+    pub fn item(&mut self, item: ReportItem) {
+        self.items.push(item);
+    }
+
+    /// The outcomes recorded with [`item`](Self::item), in the order they were recorded.
+    pub fn items(&self) -> &[ReportItem] {
+        &self.items
+    }
+
+    pub fn is_text(&self) -> bool {
+        self.format == OutputFormatArg::Text
+    }
+
+    /// Serialize the report as a single-line JSON object.
+    pub fn to_json(&self) -> io::Result<String> {
+        serde_json::to_string(self).map_err(io::Error::other)
+    }
+
+    /// Print the JSON object for `--format json`; text reports were printed as they went.
+    pub fn finish(self) -> OutlineResult<()> {
+        if self.format == OutputFormatArg::Json {
+            println!("{}", self.to_json()?);
+        }
+        Ok(())
+    }
+}
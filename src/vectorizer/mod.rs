@@ -1,5 +1,7 @@
 use image::GrayImage;
 
+#[cfg(any(feature = "vectorizer-simple", feature = "vectorizer-vtracer"))]
+use crate::OutlineError;
 use crate::OutlineResult;
 
 /// A trait representing an algorithm that can turn a mask into a vector representation.
@@ -13,5 +15,19 @@ pub trait MaskVectorizer {
     fn vectorize(&self, mask: &GrayImage, options: &Self::Options) -> OutlineResult<Self::Output>;
 }
 
+/// Check that a stroke width is a finite value above zero.
+#[cfg(any(feature = "vectorizer-simple", feature = "vectorizer-vtracer"))]
+pub(crate) fn validate_stroke_width(width: f64) -> OutlineResult<()> {
+    if width.is_finite() && width > 0.0 {
+        Ok(())
+    } else {
+        Err(OutlineError::Trace(format!(
+            "stroke width must be a finite value > 0.0, got {width}"
+        )))
+    }
+}
+
+#[cfg(feature = "vectorizer-simple")]
+pub mod simple;
 #[cfg(feature = "vectorizer-vtracer")]
 pub mod vtracer;
@@ -0,0 +1,264 @@
+//! A dependency-free tracer that turns mask contours into monochrome SVG paths.
+
+use std::fmt::Write;
+
+use image::GrayImage;
+
+use super::{MaskVectorizer, validate_stroke_width};
+use crate::OutlineResult;
+use crate::config::Connectivity;
+use crate::contour::{contours, simplify_contour};
+use crate::mask::label_components;
+
+/// How [`SimpleSvgVectorizer`] paints the traced outlines.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum SimpleSvgStyle {
+    /// Fill each region in black.
+    Fill,
+    /// Draw only the outline, in black, `width` pixels wide.
+    Stroke { width: f64 },
+}
+
+/// Options for [`SimpleSvgVectorizer`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimpleSvgOptions {
+    /// Whether regions are filled or outlined.
+    pub style: SimpleSvgStyle,
+    /// Mask values at or above this count as foreground.
+    pub threshold: u8,
+    /// Ramer-Douglas-Peucker tolerance in pixels; `0.0` keeps a vertex per boundary pixel.
+    pub simplify: f64,
+    /// Decimal places written for coordinates; trailing zeros are dropped.
+    pub precision: u32,
+}
+
+impl Default for SimpleSvgOptions {
+    fn default() -> Self {
+        Self {
+            style: SimpleSvgStyle::Fill,
+            threshold: 128,
+            simplify: 0.5,
+            precision: 2,
+        }
+    }
+}
+
+/// A [`MaskVectorizer`] that traces the contours of the mask regions into one SVG path.
+///
+/// It follows the boundary pixels found by [`contours`](crate::contours), so the output has no
+/// curves, but it needs none of VTracer's color machinery. Holes are traced as their own
+/// contours and cut out with `fill-rule="evenodd"`. Vertices sit on pixel centers.
+///
+/// Returns [`OutlineError::Trace`](crate::OutlineError::Trace) if a
+/// [`Stroke`](SimpleSvgStyle::Stroke) width is not a finite value above zero.
+///
+/// ```no_run
+/// use outline::{Outline, SimpleSvgOptions, SimpleSvgVectorizer};
+///
+/// let outline = Outline::new("model.onnx");
+/// let session = outline.for_image("input.jpg")?;
+/// let mask = session.matte().processed()?;
+///
+/// let svg = mask.trace(&SimpleSvgVectorizer, &SimpleSvgOptions::default())?;
+/// std::fs::write("outline.svg", svg)?;
+/// # Ok::<_, outline::OutlineError>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SimpleSvgVectorizer;
+
+impl MaskVectorizer for SimpleSvgVectorizer {
+    type Options = SimpleSvgOptions;
+    type Output = String;
+
+    fn vectorize(&self, mask: &GrayImage, options: &Self::Options) -> OutlineResult<Self::Output> {
+        if let SimpleSvgStyle::Stroke { width } = options.style {
+            validate_stroke_width(width)?;
+        }
+        let binary = GrayImage::from_fn(mask.width(), mask.height(), |x, y| {
+            let foreground = mask.get_pixel(x, y)[0] >= options.threshold;
+            image::Luma([if foreground { 255 } else { 0 }])
+        });
+
+        let mut data = String::new();
+        for contour in contours(&binary)
+            .into_iter()
+            .chain(contours(&holes(&binary)))
+        {
+            let mut points: Vec<(f64, f64)> = contour
+                .into_iter()
+                .map(|(x, y)| (f64::from(x) + 0.5, f64::from(y) + 0.5))
+                .collect();
+            if options.simplify > 0.0 && points.len() > 2 {
+                points.push(points[0]);
+                points = simplify_contour(&points, options.simplify);
+                points.pop();
+            }
+            for (index, (x, y)) in points.iter().enumerate() {
+                let command = if index == 0 { 'M' } else { 'L' };
+                let (x, y) = (
+                    format_coord(*x, options.precision),
+                    format_coord(*y, options.precision),
+                );
+                let _ = write!(data, "{command}{x} {y} ");
+            }
+            data.push('Z');
+        }
+
+        let paint = match options.style {
+            SimpleSvgStyle::Fill => r##"fill="#000000" fill-rule="evenodd""##.to_string(),
+            SimpleSvgStyle::Stroke { width } => format!(
+                r##"fill="none" stroke="#000000" stroke-width="{}" stroke-linejoin="round""##,
+                format_coord(width, options.precision)
+            ),
+        };
+        let (width, height) = mask.dimensions();
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+        );
+        if !data.is_empty() {
+            let _ = write!(svg, r#"<path d="{data}" {paint}/>"#);
+        }
+        svg.push_str("</svg>\n");
+        Ok(svg)
+    }
+}
+
+// The background pixels of a binary mask that are enclosed by foreground, as a binary mask.
+//
+// Foreground regions are 8-connected, so the background is split 4-connected; components that
+// do not reach the image border are holes.
+fn holes(binary: &GrayImage) -> GrayImage {
+    let (width, height) = binary.dimensions();
+    let background = GrayImage::from_fn(width, height, |x, y| {
+        image::Luma([255 - binary.get_pixel(x, y)[0]])
+    });
+    let (labels, sizes) = label_components(&background, Connectivity::Four);
+    let mut outside = vec![false; sizes.len() + 1];
+    for (index, &label) in labels.iter().enumerate() {
+        let (x, y) = ((index as u32) % width, (index as u32) / width);
+        if x == 0 || y == 0 || x == width - 1 || y == height - 1 {
+            outside[label] = true;
+        }
+    }
+    GrayImage::from_fn(width, height, |x, y| {
+        let label = labels[(y * width + x) as usize];
+        image::Luma([if label != 0 && !outside[label] {
+            255
+        } else {
+            0
+        }])
+    })
+}
+
+// Format a coordinate with at most `precision` decimals, without trailing zeros.
+fn format_coord(value: f64, precision: u32) -> String {
+    let formatted = format!("{value:.*}", precision as usize);
+    if formatted.contains('.') {
+        formatted
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string()
+    } else {
+        formatted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OutlineError;
+    use image::Luma;
+
+    fn square_mask() -> GrayImage {
+        GrayImage::from_fn(8, 8, |x, y| {
+            let inside = (2..=5).contains(&x) && (2..=5).contains(&y);
+            Luma([if inside { 255 } else { 0 }])
+        })
+    }
+
+    #[test]
+    fn square_mask_becomes_one_filled_path() {
+        let svg = SimpleSvgVectorizer
+            .vectorize(&square_mask(), &SimpleSvgOptions::default())
+            .unwrap();
+
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="8" height="8""#));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches("<path ").count(), 1);
+        assert!(svg.contains(
+            r##"<path d="M2.5 2.5 L5.5 2.5 L5.5 5.5 L2.5 5.5 Z" fill="#000000" fill-rule="evenodd"/>"##
+        ));
+    }
+
+    #[test]
+    fn ring_mask_keeps_its_hole() {
+        let ring = GrayImage::from_fn(8, 8, |x, y| {
+            let outer = (1..=6).contains(&x) && (1..=6).contains(&y);
+            let inner = (3..=4).contains(&x) && (3..=4).contains(&y);
+            Luma([if outer && !inner { 255 } else { 0 }])
+        });
+        let svg = SimpleSvgVectorizer
+            .vectorize(&ring, &SimpleSvgOptions::default())
+            .unwrap();
+
+        assert!(svg.contains(
+            r##"<path d="M1.5 1.5 L6.5 1.5 L6.5 6.5 L1.5 6.5 ZM3.5 3.5 L4.5 3.5 L4.5 4.5 L3.5 4.5 Z""##
+        ));
+        assert!(svg.contains(r#"fill-rule="evenodd""#));
+    }
+
+    #[test]
+    fn background_touching_the_border_is_not_a_hole() {
+        let cup = GrayImage::from_fn(6, 6, |x, y| {
+            let wall = (1..=4).contains(&x) && (1..=4).contains(&y);
+            let opening = (2..=3).contains(&x) && y <= 3;
+            Luma([if wall && !opening { 255 } else { 0 }])
+        });
+        assert!(holes(&cup).pixels().all(|pixel| pixel[0] == 0));
+    }
+
+    #[test]
+    fn invalid_stroke_width_is_rejected() {
+        for width in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let options = SimpleSvgOptions {
+                style: SimpleSvgStyle::Stroke { width },
+                ..SimpleSvgOptions::default()
+            };
+            let err = SimpleSvgVectorizer
+                .vectorize(&square_mask(), &options)
+                .unwrap_err();
+            assert!(matches!(err, OutlineError::Trace(_)), "{width}: {err}");
+        }
+    }
+
+    #[test]
+    fn stroke_style_outlines_without_fill() {
+        let options = SimpleSvgOptions {
+            style: SimpleSvgStyle::Stroke { width: 1.25 },
+            ..SimpleSvgOptions::default()
+        };
+        let svg = SimpleSvgVectorizer
+            .vectorize(&square_mask(), &options)
+            .unwrap();
+
+        assert!(svg.contains(r#"fill="none""#));
+        assert!(svg.contains(r#"stroke-width="1.25""#));
+    }
+
+    #[test]
+    fn empty_mask_has_no_path() {
+        let svg = SimpleSvgVectorizer
+            .vectorize(&GrayImage::new(4, 4), &SimpleSvgOptions::default())
+            .unwrap();
+        assert!(!svg.contains("<path"));
+    }
+
+    #[test]
+    fn coordinates_drop_trailing_zeros() {
+        assert_eq!(format_coord(2.5, 3), "2.5");
+        assert_eq!(format_coord(3.0, 2), "3");
+        assert_eq!(format_coord(1.23456, 2), "1.23");
+        assert_eq!(format_coord(7.0, 0), "7");
+    }
+}
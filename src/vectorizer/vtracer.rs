@@ -1,19 +1,32 @@
-use image::GrayImage;
-use visioncortex::PathSimplifyMode;
-use vtracer::{ColorImage, ColorMode, Config, Hierarchical, SvgFile, convert};
+use image::metadata::Orientation;
+use image::{DynamicImage, GrayImage};
+use std::borrow::Cow;
+use std::fmt::Write as _;
 
-use crate::mask::gray_to_color_image_rgba;
-use crate::{OutlineError, OutlineResult};
+use visioncortex::{CompoundPath, CompoundPathElement, PathSimplifyMode, PointF64};
+use vtracer::{ColorImage, ColorMode, Config, Hierarchical, SvgFile, SvgPath, convert};
 
-use super::MaskVectorizer;
+use crate::mask::{close_mask, gray_to_color_image_rgba, open_mask, threshold_mask};
+use crate::matte::inverse_orientation;
+use crate::{BoundingBox, OutlineError, OutlineResult};
+
+use super::{MaskVectorizer, validate_stroke_width};
 
 /// Configuration for vectorizing masks into SVG paths using VTracer.
 ///
 /// These options map directly to [`vtracer::Config`].
+///
+/// With the `serde` feature, the options serialize with the VTracer enums as lowercase names
+/// (e.g. `"binary"`, `"stacked"`, `"spline"`), and missing fields fall back to their defaults.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct TraceOptions {
+    #[cfg_attr(feature = "serde", serde(with = "serde_names::color_mode"))]
     pub tracer_color_mode: ColorMode,
+    #[cfg_attr(feature = "serde", serde(with = "serde_names::hierarchical"))]
     pub tracer_hierarchical: Hierarchical,
+    #[cfg_attr(feature = "serde", serde(with = "serde_names::path_simplify_mode"))]
     pub tracer_mode: PathSimplifyMode,
     pub tracer_filter_speckle: usize,
     pub tracer_color_precision: i32,
@@ -24,6 +37,50 @@ pub struct TraceOptions {
     pub tracer_splice_threshold: i32,
     pub tracer_path_precision: Option<u32>,
     pub invert_svg: bool,
+    /// Drop traced paths whose [`TracedPath::area`] is below this many square pixels.
+    ///
+    /// Unlike `tracer_filter_speckle`, which VTracer applies to pixel clusters before tracing,
+    /// this is measured on the finished paths. `0.0` keeps every path.
+    pub min_path_area: f64,
+    /// Smooth the mask's outline with this radius in pixels before tracing.
+    ///
+    /// The mask is split at the midpoint, as binary tracing does, then morphologically opened
+    /// and closed, which rounds off staircase steps and one-pixel jaggies that would otherwise
+    /// become jagged SVG paths. `None` traces the mask as is.
+    pub pre_smooth: Option<f32>,
+    /// Whether traced paths are filled or drawn as outlines.
+    pub output_style: TraceOutputStyle,
+}
+
+/// How traced paths are painted in the SVG output.
+///
+/// With the `serde` feature this serializes as `"fill"` or
+/// `{"stroke": {"width": 1.0, "color": "#000000"}}`.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[non_exhaustive]
+pub enum TraceOutputStyle {
+    /// Fill each path with its traced color, as VTracer does.
+    #[default]
+    Fill,
+    /// Draw only the outline of each path, with no fill, for plotters and laser cutters.
+    Stroke {
+        /// Stroke width in pixels.
+        width: f64,
+        /// Any SVG color, e.g. `#000000` or `red`.
+        color: String,
+    },
+}
+
+impl TraceOutputStyle {
+    /// A black outline `width` pixels wide.
+    pub fn stroke(width: f64) -> Self {
+        Self::Stroke {
+            width,
+            color: "#000000".to_string(),
+        }
+    }
 }
 
 impl Default for TraceOptions {
@@ -41,7 +98,126 @@ impl Default for TraceOptions {
             tracer_splice_threshold: 45,
             tracer_path_precision: Some(2),
             invert_svg: false,
+            min_path_area: 0.0,
+            pre_smooth: None,
+            output_style: TraceOutputStyle::Fill,
+        }
+    }
+}
+
+impl TraceOptions {
+    /// Check that the numeric options are within the ranges VTracer can handle.
+    ///
+    /// [`trace`] calls this before handing the options to VTracer, so out-of-range values
+    /// surface as a descriptive [`OutlineError::Trace`] instead of an opaque VTracer error or
+    /// a degenerate SVG.
+    ///
+    /// Accepted ranges:
+    /// - `tracer_color_precision`: `1..=8`
+    /// - `tracer_layer_difference`: `0..=255`
+    /// - `tracer_corner_threshold` and `tracer_splice_threshold`: `0..=180` degrees
+    /// - `tracer_length_threshold`: finite and `>= 0.0`
+    /// - `tracer_max_iterations`: `>= 1`
+    /// - `min_path_area`: finite and `>= 0.0`
+    /// - `pre_smooth`: finite and `>= 0.0` when set
+    /// - `output_style`: a stroke width that is finite and `> 0.0`, and a non-empty color
+    ///   without quotes or angle brackets
+    pub fn validate(&self) -> OutlineResult<()> {
+        check_range("color_precision", self.tracer_color_precision, 1, 8)?;
+        check_range("layer_difference", self.tracer_layer_difference, 0, 255)?;
+        check_range("corner_threshold", self.tracer_corner_threshold, 0, 180)?;
+        check_range("splice_threshold", self.tracer_splice_threshold, 0, 180)?;
+        if !self.tracer_length_threshold.is_finite() || self.tracer_length_threshold < 0.0 {
+            return Err(OutlineError::Trace(format!(
+                "length_threshold must be a finite value >= 0.0, got {}",
+                self.tracer_length_threshold
+            )));
+        }
+        if self.tracer_max_iterations == 0 {
+            return Err(OutlineError::Trace(
+                "max_iterations must be at least 1, got 0".to_string(),
+            ));
         }
+        if !self.min_path_area.is_finite() || self.min_path_area < 0.0 {
+            return Err(OutlineError::Trace(format!(
+                "min_path_area must be a finite value >= 0.0, got {}",
+                self.min_path_area
+            )));
+        }
+        if let Some(radius) = self.pre_smooth
+            && (!radius.is_finite() || radius < 0.0)
+        {
+            return Err(OutlineError::Trace(format!(
+                "pre_smooth must be a finite value >= 0.0, got {radius}"
+            )));
+        }
+        if let TraceOutputStyle::Stroke { width, color } = &self.output_style {
+            validate_stroke_width(*width)?;
+            if color.is_empty() || color.contains(['"', '<', '>']) {
+                return Err(OutlineError::Trace(format!(
+                    "stroke color must be a plain SVG color, got {color:?}"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+// VTracer's enums do not implement serde, so map them to and from lowercase names.
+#[cfg(feature = "serde")]
+mod serde_names {
+    macro_rules! lowercase_names {
+        ($module:ident, $ty:ty, { $($variant:path => $name:literal),+ $(,)? }) => {
+            pub(super) mod $module {
+                use serde::de::Error as _;
+                use serde::{Deserialize, Deserializer, Serializer};
+
+                const NAMES: &[&str] = &[$($name),+];
+
+                pub(in crate::vectorizer) fn serialize<S: Serializer>(
+                    value: &$ty,
+                    serializer: S,
+                ) -> Result<S::Ok, S::Error> {
+                    serializer.serialize_str(match value {
+                        $($variant => $name,)+
+                    })
+                }
+
+                pub(in crate::vectorizer) fn deserialize<'de, D: Deserializer<'de>>(
+                    deserializer: D,
+                ) -> Result<$ty, D::Error> {
+                    let name = String::deserialize(deserializer)?;
+                    match name.as_str() {
+                        $($name => Ok($variant),)+
+                        other => Err(D::Error::unknown_variant(other, NAMES)),
+                    }
+                }
+            }
+        };
+    }
+
+    lowercase_names!(color_mode, vtracer::ColorMode, {
+        vtracer::ColorMode::Color => "color",
+        vtracer::ColorMode::Binary => "binary",
+    });
+    lowercase_names!(hierarchical, vtracer::Hierarchical, {
+        vtracer::Hierarchical::Stacked => "stacked",
+        vtracer::Hierarchical::Cutout => "cutout",
+    });
+    lowercase_names!(path_simplify_mode, visioncortex::PathSimplifyMode, {
+        visioncortex::PathSimplifyMode::None => "none",
+        visioncortex::PathSimplifyMode::Polygon => "polygon",
+        visioncortex::PathSimplifyMode::Spline => "spline",
+    });
+}
+
+fn check_range(name: &str, value: i32, min: i32, max: i32) -> OutlineResult<()> {
+    if (min..=max).contains(&value) {
+        Ok(())
+    } else {
+        Err(OutlineError::Trace(format!(
+            "{name} must be between {min} and {max}, got {value}"
+        )))
     }
 }
 
@@ -75,18 +251,345 @@ impl MaskVectorizer for VtracerSvgVectorizer {
     }
 }
 
+/// A [`MaskVectorizer`] that traces a mask with VTracer and emits one SVG document per path.
+///
+/// Every document shares the full mask's `width`, `height`, and `viewBox`, so the split files
+/// overlay exactly when stacked. Paths are returned in VTracer's drawing order.
+///
+/// ```no_run
+/// use outline::{Outline, TraceOptions, VtracerSplitSvgVectorizer};
+///
+/// let outline = Outline::new("model.onnx");
+/// let session = outline.for_image("input.jpg")?;
+/// let mask = session.matte().threshold().processed()?;
+///
+/// let paths = mask.trace(&VtracerSplitSvgVectorizer, &TraceOptions::default())?;
+/// for (index, svg) in paths.iter().enumerate() {
+///     std::fs::write(format!("path-{index:03}.svg"), svg)?;
+/// }
+/// # Ok::<_, outline::OutlineError>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VtracerSplitSvgVectorizer;
+
+impl MaskVectorizer for VtracerSplitSvgVectorizer {
+    type Options = TraceOptions;
+    type Output = Vec<String>;
+
+    fn vectorize(&self, mask: &GrayImage, options: &Self::Options) -> OutlineResult<Self::Output> {
+        trace_to_svg_paths(mask, options)
+    }
+}
+
+/// Apply [`TraceOptions::pre_smooth`] to the mask, borrowing it unchanged when unset.
+fn pre_smoothed<'a>(
+    mask_image: &'a GrayImage,
+    options: &TraceOptions,
+) -> OutlineResult<Cow<'a, GrayImage>> {
+    options.validate()?;
+    Ok(match options.pre_smooth {
+        Some(radius) => {
+            let binary = threshold_mask(mask_image, 127);
+            Cow::Owned(close_mask(&open_mask(&binary, radius), radius))
+        }
+        None => Cow::Borrowed(mask_image),
+    })
+}
+
 /// The helper function that uses VTracer to trace a grayscale mask to an SVG string.
 pub fn trace_to_svg_string(
     mask_image: &GrayImage,
     options: &TraceOptions,
 ) -> OutlineResult<String> {
-    let color_img = gray_to_color_image_rgba(mask_image, None, options.invert_svg);
+    let mask_image = pre_smoothed(mask_image, options)?;
+    let color_img = gray_to_color_image_rgba(&mask_image, None, options.invert_svg);
+    let svg_file = trace(color_img, options)?;
+    Ok(apply_output_style(
+        svg_file.to_string(),
+        &options.output_style,
+    ))
+}
+
+/// Trace a grayscale mask with VTracer and render each path as a standalone SVG document.
+pub fn trace_to_svg_paths(
+    mask_image: &GrayImage,
+    options: &TraceOptions,
+) -> OutlineResult<Vec<String>> {
+    let mask_image = pre_smoothed(mask_image, options)?;
+    let color_img = gray_to_color_image_rgba(&mask_image, None, options.invert_svg);
+    let svg_file = trace(color_img, options)?;
+    Ok(split_svg_paths(&svg_file)
+        .into_iter()
+        .map(|svg| apply_output_style(svg, &options.output_style))
+        .collect())
+}
+
+/// A single traced path with its measured geometry.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct TracedPath {
+    /// Standalone SVG document for this path, as produced by [`split_svg_paths`].
+    pub svg: String,
+    /// Enclosed area in square pixels.
+    ///
+    /// The largest sub-path is treated as the outer contour and the remaining sub-paths as
+    /// holes, so this is the outer area minus the holes. Spline segments are integrated exactly
+    /// as cubic Bézier curves.
+    pub area: f64,
+    /// Pixel bounds of the path, rounded outward and clamped to the traced image.
+    pub bbox: BoundingBox,
+}
+
+/// Trace a grayscale mask with VTracer and return each path with its area and bounding box.
+///
+/// Paths below [`TraceOptions::min_path_area`] are already removed.
+pub fn trace_paths(
+    mask_image: &GrayImage,
+    options: &TraceOptions,
+) -> OutlineResult<Vec<TracedPath>> {
+    let mask_image = pre_smoothed(mask_image, options)?;
+    let color_img = gray_to_color_image_rgba(&mask_image, None, options.invert_svg);
+    let svg_file = trace(color_img, options)?;
+    Ok(svg_file
+        .paths
+        .iter()
+        .map(|path| TracedPath {
+            svg: apply_output_style(single_path_document(&svg_file, path), &options.output_style),
+            area: path_area(&path.path),
+            bbox: path_bounding_box(&path.path, svg_file.width, svg_file.height),
+        })
+        .collect())
+}
+
+/// Area enclosed by a traced path in square pixels, as reported by [`TracedPath::area`].
+pub(crate) fn path_area(path: &CompoundPath) -> f64 {
+    let mut areas: Vec<f64> = path
+        .paths
+        .iter()
+        .map(|element| subpath_signed_area(element).abs())
+        .collect();
+    areas.sort_by(|a, b| b.total_cmp(a));
+    match areas.split_first() {
+        Some((outer, holes)) => (outer - holes.iter().sum::<f64>()).max(0.0),
+        None => 0.0,
+    }
+}
+
+fn subpath_signed_area(element: &CompoundPathElement) -> f64 {
+    match element {
+        CompoundPathElement::PathI32(path) => polygon_signed_area(
+            path.path
+                .iter()
+                .map(|point| (f64::from(point.x), f64::from(point.y))),
+        ),
+        CompoundPathElement::PathF64(path) => {
+            polygon_signed_area(path.path.iter().map(|point| (point.x, point.y)))
+        }
+        CompoundPathElement::Spline(spline) => {
+            let points = &spline.points;
+            let curves: f64 = points
+                .windows(4)
+                .step_by(3)
+                .map(|curve| cubic_signed_area(curve[0], curve[1], curve[2], curve[3]))
+                .sum();
+            // Close the spline back to its start with a straight edge.
+            let closing = match (points.last(), points.first()) {
+                (Some(last), Some(first)) => (last.x * first.y - first.x * last.y) / 2.0,
+                _ => 0.0,
+            };
+            curves + closing
+        }
+    }
+}
+
+// Shoelace formula over a closed polygon; the last point connects back to the first.
+fn polygon_signed_area(points: impl Iterator<Item = (f64, f64)>) -> f64 {
+    let points: Vec<(f64, f64)> = points.collect();
+    let Some(&last) = points.last() else {
+        return 0.0;
+    };
+    let mut previous = last;
+    let mut twice_area = 0.0;
+    for &(x, y) in &points {
+        twice_area += previous.0 * y - x * previous.1;
+        previous = (x, y);
+    }
+    twice_area / 2.0
+}
+
+// Signed area swept between the origin and a cubic Bézier segment (Green's theorem).
+fn cubic_signed_area(p0: PointF64, p1: PointF64, p2: PointF64, p3: PointF64) -> f64 {
+    let (x0, y0, x1, y1, x2, y2, x3, y3) = (p0.x, p0.y, p1.x, p1.y, p2.x, p2.y, p3.x, p3.y);
+    3.0 * ((y3 - y0) * (x1 + x2) - (x3 - x0) * (y1 + y2) + y1 * (x0 - x2) - x1 * (y0 - y2)
+        + y3 * (x2 + x0 / 3.0)
+        - x3 * (y2 + y0 / 3.0))
+        / 20.0
+}
+
+// Control points bound each Bézier segment, so their extent is a conservative bounding box.
+fn path_bounding_box(path: &CompoundPath, width: usize, height: usize) -> BoundingBox {
+    let points = path.paths.iter().flat_map(|element| match element {
+        CompoundPathElement::PathI32(path) => path
+            .path
+            .iter()
+            .map(|point| (f64::from(point.x), f64::from(point.y)))
+            .collect::<Vec<_>>(),
+        CompoundPathElement::PathF64(path) => {
+            path.path.iter().map(|point| (point.x, point.y)).collect()
+        }
+        CompoundPathElement::Spline(spline) => spline
+            .points
+            .iter()
+            .map(|point| (point.x, point.y))
+            .collect(),
+    });
+
+    let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+    let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for (x, y) in points {
+        min_x = min_x.min(x);
+        min_y = min_y.min(y);
+        max_x = max_x.max(x);
+        max_y = max_y.max(y);
+    }
+    if min_x > max_x {
+        return BoundingBox::new(0, 0, 0, 0);
+    }
+
+    let clamp = |value: f64, limit: usize| value.clamp(0.0, limit as f64) as u32;
+    let (left, top) = (clamp(min_x.floor(), width), clamp(min_y.floor(), height));
+    let (right, bottom) = (clamp(max_x.ceil(), width), clamp(max_y.ceil(), height));
+    BoundingBox::new(left, top, right - left, bottom - top)
+}
+
+/// Render each path of `svg_file` into its own SVG document sharing the original viewBox.
+pub fn split_svg_paths(svg_file: &SvgFile) -> Vec<String> {
+    svg_file
+        .paths
+        .iter()
+        .map(|path| single_path_document(svg_file, path))
+        .collect()
+}
+
+fn single_path_document(svg_file: &SvgFile, path: &SvgPath) -> String {
+    let (width, height) = (svg_file.width, svg_file.height);
+
+    let mut document = String::new();
+    // Writing into a String cannot fail.
+    let _ = writeln!(document, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(
+        document,
+        r#"<svg version="1.1" xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    );
+    write_path_element(&mut document, svg_file, path);
+    document.push_str("</svg>\n");
+    document
+}
+
+fn write_path_element(document: &mut String, svg_file: &SvgFile, path: &SvgPath) {
+    let (data, offset) =
+        path.path
+            .to_svg_string(true, PointF64::default(), svg_file.path_precision);
+    let _ = writeln!(
+        document,
+        r#"<path d="{}" fill="{}" transform="translate({},{})"/>"#,
+        data,
+        path.color.to_hex_string(),
+        offset.x,
+        offset.y
+    );
+}
+
+/// Trace a display-oriented mask in the source file's stored orientation and emit an SVG whose
+/// paths are wrapped in a `transform` that applies the EXIF `orientation`.
+///
+/// The mask is expected in display orientation, as returned by
+/// [`InferencedMatte`](crate::InferencedMatte). The path coordinates refer to the original
+/// file's pixel grid, while the document's `width`, `height`, and `viewBox` match the displayed
+/// image, so it renders exactly like [`trace_to_svg_string`] on the same mask. With
+/// [`Orientation::NoTransforms`] no group is emitted.
+pub fn trace_to_oriented_svg_string(
+    mask_image: &GrayImage,
+    options: &TraceOptions,
+    orientation: Orientation,
+) -> OutlineResult<String> {
+    let mut stored = DynamicImage::ImageLuma8(pre_smoothed(mask_image, options)?.into_owned());
+    stored.apply_orientation(inverse_orientation(orientation));
+    let color_img = gray_to_color_image_rgba(&stored.into_luma8(), None, options.invert_svg);
     let svg_file = trace(color_img, options)?;
-    Ok(svg_file.to_string())
+
+    let (width, height) = (mask_image.width(), mask_image.height());
+    let mut document = String::new();
+    // Writing into a String cannot fail.
+    let _ = writeln!(document, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(
+        document,
+        r#"<svg version="1.1" xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    );
+    let matrix = orientation_matrix(orientation, svg_file.width, svg_file.height);
+    if let Some([a, b, c, d, e, f]) = matrix {
+        let _ = writeln!(
+            document,
+            r#"<g transform="matrix({a} {b} {c} {d} {e} {f})">"#
+        );
+    }
+    for path in &svg_file.paths {
+        write_path_element(&mut document, &svg_file, path);
+    }
+    if matrix.is_some() {
+        document.push_str("</g>\n");
+    }
+    document.push_str("</svg>\n");
+    Ok(apply_output_style(document, &options.output_style))
+}
+
+/// Rewrite the `fill` attribute of every path for `style`; filled documents are returned as is.
+///
+/// Only the `<path>` elements written by VTracer and [`split_svg_paths`] carry a `fill`, so
+/// the replacement cannot touch anything else in the document.
+fn apply_output_style(document: String, style: &TraceOutputStyle) -> String {
+    let TraceOutputStyle::Stroke { width, color } = style else {
+        return document;
+    };
+    let paint =
+        format!(r#"fill="none" stroke="{color}" stroke-width="{width}" stroke-linejoin="round""#);
+    let mut styled = String::with_capacity(document.len());
+    let mut rest = document.as_str();
+    while let Some(start) = rest.find(r#" fill=""#) {
+        let value_start = start + r#" fill=""#.len();
+        let Some(value_len) = rest[value_start..].find('"') else {
+            break;
+        };
+        styled.push_str(&rest[..=start]);
+        styled.push_str(&paint);
+        rest = &rest[value_start + value_len + 1..];
+    }
+    styled.push_str(rest);
+    styled
+}
+
+/// SVG `matrix(a b c d e f)` mapping stored pixel coordinates of a `width` x `height` image to
+/// display coordinates, or `None` when the orientation is the identity.
+fn orientation_matrix(orientation: Orientation, width: usize, height: usize) -> Option<[i64; 6]> {
+    let (w, h) = (width as i64, height as i64);
+    match orientation {
+        Orientation::NoTransforms => None,
+        Orientation::Rotate90 => Some([0, 1, -1, 0, h, 0]),
+        Orientation::Rotate180 => Some([-1, 0, 0, -1, w, h]),
+        Orientation::Rotate270 => Some([0, -1, 1, 0, 0, w]),
+        Orientation::FlipHorizontal => Some([-1, 0, 0, 1, w, 0]),
+        Orientation::FlipVertical => Some([1, 0, 0, -1, 0, h]),
+        Orientation::Rotate90FlipH => Some([0, 1, 1, 0, 0, 0]),
+        Orientation::Rotate270FlipH => Some([0, -1, -1, 0, h, w]),
+    }
 }
 
 /// Trace a ColorImage into an SVG using VTracer with the given options.
+///
+/// Returns [`OutlineError::Trace`] if the options fail [`TraceOptions::validate`].
 pub fn trace(img: ColorImage, options: &TraceOptions) -> OutlineResult<SvgFile> {
+    options.validate()?;
+
     let cfg = Config {
         color_mode: options.tracer_color_mode.clone(),
         hierarchical: options.tracer_hierarchical.clone(),
@@ -101,6 +604,545 @@ pub fn trace(img: ColorImage, options: &TraceOptions) -> OutlineResult<SvgFile>
         path_precision: options.tracer_path_precision,
     };
 
-    let svg_file = convert(img, cfg).map_err(OutlineError::Trace)?;
+    let mut svg_file = convert(img, cfg).map_err(OutlineError::Trace)?;
+    if options.min_path_area > 0.0 {
+        svg_file
+            .paths
+            .retain(|path| path_area(&path.path) >= options.min_path_area);
+    }
     Ok(svg_file)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_rejected(options: TraceOptions, field: &str) {
+        match options.validate() {
+            Err(OutlineError::Trace(message)) => {
+                assert!(message.contains(field), "unexpected message: {message}")
+            }
+            other => panic!("expected {field} to be rejected, got {other:?}"),
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    mod serde {
+        use super::*;
+
+        #[test]
+        fn default_options_round_trip_with_lowercase_enums() {
+            let json = serde_json::to_value(TraceOptions::default()).unwrap();
+            assert_eq!(json["tracer_color_mode"], "binary");
+            assert_eq!(json["tracer_hierarchical"], "stacked");
+            assert_eq!(json["tracer_mode"], "spline");
+
+            let parsed: TraceOptions = serde_json::from_value(json.clone()).unwrap();
+            assert_eq!(serde_json::to_value(parsed).unwrap(), json);
+        }
+
+        #[test]
+        fn output_style_uses_lowercase_variants() {
+            let json = serde_json::to_value(TraceOptions::default()).unwrap();
+            assert_eq!(json["output_style"], "fill");
+
+            let parsed: TraceOptions = serde_json::from_value(serde_json::json!({
+                "output_style": {"stroke": {"width": 2.0, "color": "red"}}
+            }))
+            .unwrap();
+            assert_eq!(
+                parsed.output_style,
+                TraceOutputStyle::Stroke {
+                    width: 2.0,
+                    color: "red".to_string()
+                }
+            );
+        }
+    }
+
+    mod validate {
+        use super::*;
+
+        #[test]
+        fn default_options_are_valid() {
+            TraceOptions::default().validate().unwrap();
+        }
+
+        #[test]
+        fn color_precision_bounds() {
+            for value in [1, 8] {
+                let options = TraceOptions {
+                    tracer_color_precision: value,
+                    ..TraceOptions::default()
+                };
+                options.validate().unwrap();
+            }
+            for value in [0, 9] {
+                let options = TraceOptions {
+                    tracer_color_precision: value,
+                    ..TraceOptions::default()
+                };
+                assert_rejected(options, "color_precision");
+            }
+        }
+
+        #[test]
+        fn layer_difference_bounds() {
+            for value in [0, 255] {
+                let options = TraceOptions {
+                    tracer_layer_difference: value,
+                    ..TraceOptions::default()
+                };
+                options.validate().unwrap();
+            }
+            for value in [-1, 256] {
+                let options = TraceOptions {
+                    tracer_layer_difference: value,
+                    ..TraceOptions::default()
+                };
+                assert_rejected(options, "layer_difference");
+            }
+        }
+
+        #[test]
+        fn corner_threshold_bounds() {
+            for value in [0, 180] {
+                let options = TraceOptions {
+                    tracer_corner_threshold: value,
+                    ..TraceOptions::default()
+                };
+                options.validate().unwrap();
+            }
+            for value in [-1, 181] {
+                let options = TraceOptions {
+                    tracer_corner_threshold: value,
+                    ..TraceOptions::default()
+                };
+                assert_rejected(options, "corner_threshold");
+            }
+        }
+
+        #[test]
+        fn splice_threshold_bounds() {
+            for value in [0, 180] {
+                let options = TraceOptions {
+                    tracer_splice_threshold: value,
+                    ..TraceOptions::default()
+                };
+                options.validate().unwrap();
+            }
+            for value in [-1, 181] {
+                let options = TraceOptions {
+                    tracer_splice_threshold: value,
+                    ..TraceOptions::default()
+                };
+                assert_rejected(options, "splice_threshold");
+            }
+        }
+
+        #[test]
+        fn length_threshold_bounds() {
+            let options = TraceOptions {
+                tracer_length_threshold: 0.0,
+                ..TraceOptions::default()
+            };
+            options.validate().unwrap();
+            for value in [-0.5, f64::NAN, f64::INFINITY] {
+                let options = TraceOptions {
+                    tracer_length_threshold: value,
+                    ..TraceOptions::default()
+                };
+                assert_rejected(options, "length_threshold");
+            }
+        }
+
+        #[test]
+        fn max_iterations_must_be_positive() {
+            let options = TraceOptions {
+                tracer_max_iterations: 1,
+                ..TraceOptions::default()
+            };
+            options.validate().unwrap();
+            let options = TraceOptions {
+                tracer_max_iterations: 0,
+                ..TraceOptions::default()
+            };
+            assert_rejected(options, "max_iterations");
+        }
+
+        #[test]
+        fn pre_smooth_bounds() {
+            let options = TraceOptions {
+                pre_smooth: Some(0.0),
+                ..TraceOptions::default()
+            };
+            options.validate().unwrap();
+            for value in [-1.0, f32::NAN, f32::INFINITY] {
+                let options = TraceOptions {
+                    pre_smooth: Some(value),
+                    ..TraceOptions::default()
+                };
+                assert_rejected(options, "pre_smooth");
+            }
+        }
+
+        #[test]
+        fn stroke_style_bounds() {
+            let options = TraceOptions {
+                output_style: TraceOutputStyle::stroke(0.5),
+                ..TraceOptions::default()
+            };
+            options.validate().unwrap();
+            for width in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+                let options = TraceOptions {
+                    output_style: TraceOutputStyle::stroke(width),
+                    ..TraceOptions::default()
+                };
+                assert_rejected(options, "stroke width");
+            }
+            for color in ["", r#"red" onload="x"#] {
+                let options = TraceOptions {
+                    output_style: TraceOutputStyle::Stroke {
+                        width: 1.0,
+                        color: color.to_string(),
+                    },
+                    ..TraceOptions::default()
+                };
+                assert_rejected(options, "stroke color");
+            }
+        }
+    }
+
+    #[test]
+    fn trace_rejects_invalid_options_before_converting() {
+        let mask = GrayImage::from_pixel(4, 4, image::Luma([255]));
+        let options = TraceOptions {
+            tracer_color_precision: 9,
+            ..TraceOptions::default()
+        };
+        let err = trace_to_svg_string(&mask, &options).unwrap_err();
+        assert!(err.to_string().contains("color_precision"));
+    }
+
+    mod path_metrics {
+        use super::*;
+
+        fn square(x: u32, y: u32, size: u32) -> GrayImage {
+            GrayImage::from_fn(32, 32, |px, py| {
+                let inside = (x..x + size).contains(&px) && (y..y + size).contains(&py);
+                image::Luma([if inside { 255 } else { 0 }])
+            })
+        }
+
+        fn inverted() -> TraceOptions {
+            TraceOptions {
+                invert_svg: true,
+                ..TraceOptions::default()
+            }
+        }
+
+        #[test]
+        fn straight_cubic_matches_shoelace_edge() {
+            let p0 = PointF64::new(1.0, 0.0);
+            let p3 = PointF64::new(0.0, 1.0);
+            let p1 = PointF64::new(2.0 / 3.0, 1.0 / 3.0);
+            let p2 = PointF64::new(1.0 / 3.0, 2.0 / 3.0);
+            assert!((cubic_signed_area(p0, p1, p2, p3) - 0.5).abs() < 1e-9);
+        }
+
+        #[test]
+        fn polygon_area_of_unit_square() {
+            let square = [(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)];
+            assert_eq!(polygon_signed_area(square.into_iter()).abs(), 4.0);
+        }
+
+        #[test]
+        fn polygon_mode_square_area_and_bbox() {
+            let options = TraceOptions {
+                tracer_mode: PathSimplifyMode::Polygon,
+                ..inverted()
+            };
+            let paths = trace_paths(&square(4, 6, 10), &options).unwrap();
+
+            assert_eq!(paths.len(), 1);
+            assert!(
+                (paths[0].area - 100.0).abs() < 1e-6,
+                "area {}",
+                paths[0].area
+            );
+            assert_eq!(paths[0].bbox, BoundingBox::new(4, 6, 10, 10));
+        }
+
+        #[test]
+        fn spline_mode_area_is_close_to_pixel_area() {
+            let paths = trace_paths(&square(4, 6, 12), &inverted()).unwrap();
+
+            assert_eq!(paths.len(), 1);
+            assert!(
+                (100.0..=150.0).contains(&paths[0].area),
+                "area {}",
+                paths[0].area
+            );
+        }
+
+        #[test]
+        fn min_path_area_drops_small_paths() {
+            let mask = GrayImage::from_fn(32, 16, |x, y| {
+                let big = (2..14).contains(&x) && (2..14).contains(&y);
+                let small = (20..25).contains(&x) && (2..7).contains(&y);
+                image::Luma([if big || small { 255 } else { 0 }])
+            });
+
+            let all = trace_paths(&mask, &inverted()).unwrap();
+            assert_eq!(all.len(), 2);
+
+            let options = TraceOptions {
+                min_path_area: 50.0,
+                ..inverted()
+            };
+            let kept = trace_paths(&mask, &options).unwrap();
+            assert_eq!(kept.len(), 1);
+            assert!(kept[0].area >= 50.0);
+            assert_eq!(
+                trace_to_svg_string(&mask, &options)
+                    .unwrap()
+                    .matches("<path")
+                    .count(),
+                1
+            );
+        }
+
+        #[test]
+        fn pre_smooth_removes_specks_and_keeps_the_subject() {
+            let mut mask = square(4, 4, 16);
+            mask.put_pixel(28, 28, image::Luma([255]));
+            let unfiltered = TraceOptions {
+                tracer_filter_speckle: 0,
+                ..inverted()
+            };
+            assert_eq!(trace_paths(&mask, &unfiltered).unwrap().len(), 2);
+
+            let options = TraceOptions {
+                pre_smooth: Some(1.5),
+                ..unfiltered
+            };
+            let paths = trace_paths(&mask, &options).unwrap();
+            assert_eq!(paths.len(), 1);
+            assert!(paths[0].bbox.width >= 14, "bbox {:?}", paths[0].bbox);
+        }
+
+        #[test]
+        fn negative_min_path_area_is_rejected() {
+            let options = TraceOptions {
+                min_path_area: -1.0,
+                ..TraceOptions::default()
+            };
+            assert_rejected(options, "min_path_area");
+        }
+    }
+
+    mod split_paths {
+        use super::*;
+
+        fn two_squares() -> GrayImage {
+            GrayImage::from_fn(32, 16, |x, y| {
+                let in_square =
+                    (2..10).contains(&y) && ((2..10).contains(&x) || (20..28).contains(&x));
+                image::Luma([if in_square { 255 } else { 0 }])
+            })
+        }
+
+        #[test]
+        fn emits_one_document_per_path() {
+            // Binary mode traces the dark region, so invert to get one path per square.
+            let options = TraceOptions {
+                invert_svg: true,
+                ..TraceOptions::default()
+            };
+            let svg = trace_to_svg_string(&two_squares(), &options).unwrap();
+            let documents = trace_to_svg_paths(&two_squares(), &options).unwrap();
+
+            assert_eq!(documents.len(), svg.matches("<path").count());
+            assert_eq!(documents.len(), 2);
+            for document in &documents {
+                assert_eq!(document.matches("<path").count(), 1);
+            }
+        }
+
+        #[test]
+        fn documents_share_the_mask_viewbox() {
+            let documents = trace_to_svg_paths(&two_squares(), &TraceOptions::default()).unwrap();
+            for document in &documents {
+                assert!(document.contains(r#"width="32" height="16" viewBox="0 0 32 16""#));
+                assert!(document.trim_end().ends_with("</svg>"));
+            }
+        }
+
+        #[test]
+        fn vectorizer_matches_helper() {
+            let options = TraceOptions::default();
+            let mask = two_squares();
+            assert_eq!(
+                VtracerSplitSvgVectorizer
+                    .vectorize(&mask, &options)
+                    .unwrap(),
+                trace_to_svg_paths(&mask, &options).unwrap()
+            );
+        }
+    }
+
+    mod output_style {
+        use super::*;
+
+        fn square() -> GrayImage {
+            GrayImage::from_fn(24, 24, |x, y| {
+                let inside = (6..18).contains(&x) && (6..18).contains(&y);
+                image::Luma([if inside { 255 } else { 0 }])
+            })
+        }
+
+        fn stroked(width: f64) -> TraceOptions {
+            TraceOptions {
+                invert_svg: true,
+                output_style: TraceOutputStyle::stroke(width),
+                ..TraceOptions::default()
+            }
+        }
+
+        #[test]
+        fn stroke_replaces_fill_on_every_path() {
+            let filled = TraceOptions {
+                invert_svg: true,
+                ..TraceOptions::default()
+            };
+            let fill_svg = trace_to_svg_string(&square(), &filled).unwrap();
+            let svg = trace_to_svg_string(&square(), &stroked(1.5)).unwrap();
+
+            let paths = svg.matches("<path").count();
+            assert!(paths > 0);
+            assert_eq!(paths, fill_svg.matches("<path").count());
+            assert_eq!(svg.matches(r#"fill="none""#).count(), paths);
+            assert_eq!(svg.matches(r#"stroke-width="1.5""#).count(), paths);
+            assert!(svg.contains(r##"stroke="#000000""##));
+            assert!(!svg.contains(r##"fill="#"##));
+        }
+
+        #[test]
+        fn split_and_oriented_documents_are_stroked() {
+            for document in trace_to_svg_paths(&square(), &stroked(2.0)).unwrap() {
+                assert!(document.contains(r#"fill="none""#));
+                assert!(document.contains(r#"stroke-width="2""#));
+            }
+            let svg = trace_to_oriented_svg_string(&square(), &stroked(2.0), Orientation::Rotate90)
+                .unwrap();
+            assert!(svg.contains(r#"fill="none""#));
+            assert!(svg.contains(r#"stroke-width="2""#));
+        }
+    }
+
+    mod orientation {
+        use super::*;
+        use crate::InferencedMatte;
+        use image::{Luma, RgbImage};
+
+        const ORIENTATIONS: [Orientation; 8] = [
+            Orientation::NoTransforms,
+            Orientation::Rotate90,
+            Orientation::Rotate180,
+            Orientation::Rotate270,
+            Orientation::FlipHorizontal,
+            Orientation::FlipVertical,
+            Orientation::Rotate90FlipH,
+            Orientation::Rotate270FlipH,
+        ];
+
+        #[test]
+        fn matrix_matches_pixel_orientation() {
+            let (width, height) = (5u32, 3u32);
+            for orientation in ORIENTATIONS {
+                let [a, b, c, d, e, f] = orientation_matrix(orientation, 5, 3)
+                    .unwrap_or([1, 0, 0, 1, 0, 0])
+                    .map(|v| v as f64);
+                for (x, y) in [(0, 0), (4, 0), (1, 2), (3, 1)] {
+                    let mut stored = GrayImage::new(width, height);
+                    stored.put_pixel(x, y, Luma([255]));
+                    let mut display = DynamicImage::ImageLuma8(stored);
+                    display.apply_orientation(orientation);
+                    let display = display.into_luma8();
+                    let (dx, dy) = display
+                        .enumerate_pixels()
+                        .find(|(_, _, p)| p[0] == 255)
+                        .map(|(dx, dy, _)| (dx as f64, dy as f64))
+                        .unwrap();
+
+                    let (cx, cy) = (x as f64 + 0.5, y as f64 + 0.5);
+                    let mapped = (a * cx + c * cy + e, b * cx + d * cy + f);
+                    assert_eq!(mapped, (dx + 0.5, dy + 0.5), "{orientation:?} ({x}, {y})");
+                }
+            }
+        }
+
+        fn display_mask() -> GrayImage {
+            // Display-oriented 16x24 mask of a portrait photo with an off-center square.
+            GrayImage::from_fn(16, 24, |x, y| {
+                Luma([if (2..8).contains(&x) && (4..12).contains(&y) {
+                    255
+                } else {
+                    0
+                }])
+            })
+        }
+
+        #[test]
+        fn rotated_document_keeps_display_size_and_wraps_paths() {
+            let mask = display_mask();
+            let svg = trace_to_oriented_svg_string(
+                &mask,
+                &TraceOptions::default(),
+                Orientation::Rotate90,
+            )
+            .unwrap();
+
+            assert!(svg.contains(r#"width="16" height="24" viewBox="0 0 16 24""#));
+            // The stored image is 24x16, so rotating it clockwise translates by its height.
+            assert!(svg.contains(r#"<g transform="matrix(0 1 -1 0 16 0)">"#));
+            assert!(svg.trim_end().ends_with("</g>\n</svg>"));
+        }
+
+        #[test]
+        fn identity_orientation_emits_no_group() {
+            let mask = display_mask();
+            let options = TraceOptions::default();
+            let svg =
+                trace_to_oriented_svg_string(&mask, &options, Orientation::NoTransforms).unwrap();
+
+            assert!(!svg.contains("<g"));
+            assert_eq!(
+                svg.matches("<path").count(),
+                trace_to_svg_string(&mask, &options)
+                    .unwrap()
+                    .matches("<path")
+                    .count()
+            );
+        }
+
+        #[test]
+        fn bring_your_own_matte_uses_recorded_orientation() {
+            let mask = display_mask();
+            let session = InferencedMatte::from_parts(RgbImage::new(16, 24), mask.clone())
+                .unwrap()
+                .with_orientation(Orientation::Rotate270FlipH);
+
+            let svg = trace_to_oriented_svg_string(
+                session.raw_matte(),
+                &TraceOptions::default(),
+                session.orientation(),
+            )
+            .unwrap();
+
+            assert_eq!(session.stored_dimensions(), (24, 16));
+            assert!(svg.contains(r#"width="16" height="24""#));
+            assert!(svg.contains(r#"<g transform="matrix(0 -1 -1 0 16 24)">"#));
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use image::{GrayImage, Rgb, RgbImage};
+
+/// Color scale used by [`matte_to_colormap`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum ColorMap {
+    /// Perceptually uniform dark purple to yellow scale, so small gradients stay visible.
+    #[default]
+    Viridis,
+    /// Plain grayscale, matching the matte itself.
+    Gray,
+}
+
+// Viridis sampled at nine evenly spaced stops; values in between are interpolated linearly.
+const VIRIDIS_STOPS: [[u8; 3]; 9] = [
+    [68, 1, 84],
+    [71, 45, 123],
+    [59, 82, 139],
+    [44, 114, 142],
+    [33, 145, 140],
+    [40, 174, 128],
+    [94, 201, 98],
+    [173, 220, 48],
+    [253, 231, 37],
+];
+
+impl ColorMap {
+    /// Get the color for a matte value.
+    pub fn color(self, value: u8) -> Rgb<u8> {
+        match self {
+            Self::Viridis => Rgb(interpolate_stops(&VIRIDIS_STOPS, value)),
+            Self::Gray => Rgb([value; 3]),
+        }
+    }
+}
+
+/// Render a grayscale matte through a colormap for inspection.
+///
+/// Confidence gradients that are hard to tell apart in gray are easier to read with
+/// [`ColorMap::Viridis`]. The output has the same dimensions as `gray`.
+pub fn matte_to_colormap(gray: &GrayImage, map: ColorMap) -> RgbImage {
+    let lut: [Rgb<u8>; 256] = std::array::from_fn(|value| map.color(value as u8));
+    let (w, h) = gray.dimensions();
+    RgbImage::from_fn(w, h, |x, y| lut[usize::from(gray.get_pixel(x, y)[0])])
+}
+
+fn interpolate_stops(stops: &[[u8; 3]], value: u8) -> [u8; 3] {
+    let segments = stops.len() - 1;
+    let position = f32::from(value) / 255.0 * segments as f32;
+    let index = (position as usize).min(segments - 1);
+    let t = position - index as f32;
+    let (from, to) = (stops[index], stops[index + 1]);
+    std::array::from_fn(|channel| {
+        let (from, to) = (f32::from(from[channel]), f32::from(to[channel]));
+        (from + (to - from) * t).round() as u8
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Luma;
+
+    mod matte_to_colormap {
+        use super::*;
+
+        fn ramp() -> GrayImage {
+            GrayImage::from_fn(256, 2, |x, _| Luma([x as u8]))
+        }
+
+        #[test]
+        fn preserves_dimensions() {
+            let gray = GrayImage::new(7, 3);
+            for map in [ColorMap::Viridis, ColorMap::Gray] {
+                assert_eq!(matte_to_colormap(&gray, map).dimensions(), (7, 3));
+            }
+        }
+
+        #[test]
+        fn viridis_ramp_sweeps_monotonically() {
+            let colored = matte_to_colormap(&ramp(), ColorMap::Viridis);
+
+            assert_eq!(colored.get_pixel(0, 0), &Rgb(VIRIDIS_STOPS[0]));
+            assert_eq!(colored.get_pixel(255, 1), &Rgb(VIRIDIS_STOPS[8]));
+            let luma: Vec<u32> = (0..256)
+                .map(|x| {
+                    let [r, g, b] = colored.get_pixel(x, 0).0;
+                    299 * u32::from(r) + 587 * u32::from(g) + 114 * u32::from(b)
+                })
+                .collect();
+            assert!(luma.windows(2).all(|pair| pair[0] <= pair[1]));
+            assert!(luma[0] < luma[255]);
+        }
+
+        #[test]
+        fn gray_ramp_copies_values() {
+            let colored = matte_to_colormap(&ramp(), ColorMap::Gray);
+            for x in 0..256 {
+                assert_eq!(colored.get_pixel(x, 1), &Rgb([x as u8; 3]));
+            }
+        }
+    }
+}
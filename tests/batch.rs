@@ -0,0 +1,153 @@
+mod support;
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use image::imageops::FilterType;
+use image::{Rgb, RgbImage};
+use outline::batch::{self, BatchOptions};
+use outline::{Outline, OutlineError};
+use tempfile::TempDir;
+
+fn tiny_outline() -> (tempfile::NamedTempFile, Outline) {
+    let model = support::tiny_matte_model_file();
+    let outline = Outline::new(model.path())
+        .with_input_resize_filter(FilterType::Nearest)
+        .with_output_resize_filter(FilterType::Nearest);
+    (model, outline)
+}
+
+fn write_inputs(dir: &TempDir, names: &[&str]) -> Vec<PathBuf> {
+    names
+        .iter()
+        .map(|name| {
+            let path = dir.path().join(name);
+            RgbImage::from_pixel(2, 2, Rgb([10, 20, 30]))
+                .save(&path)
+                .expect("test input should save");
+            path
+        })
+        .collect()
+}
+
+#[test]
+fn run_isolates_errors_and_panics_and_keeps_input_order() {
+    let (_model, outline) = tiny_outline();
+    let dir = TempDir::new().unwrap();
+    let mut inputs = write_inputs(&dir, &["a.png", "b.png", "panic.png", "c.png"]);
+    inputs.insert(1, dir.path().join("missing.png"));
+    let options = BatchOptions::new().with_concurrency(Some(2));
+
+    let items = batch::run(&outline, &inputs, &options, |input, session| {
+        if input.ends_with("panic.png") {
+            panic!("closure failed on purpose");
+        }
+        Ok(session.raw_matte().get_pixel(1, 1).0[0])
+    })
+    .expect("batch should run");
+
+    let inputs_seen: Vec<_> = items.iter().map(|item| item.input.clone()).collect();
+    assert_eq!(inputs_seen, inputs);
+    assert_eq!(items[0].result.as_ref().ok(), Some(&255));
+    assert!(items[1].result.is_err());
+    assert_eq!(items[2].result.as_ref().ok(), Some(&255));
+    assert!(matches!(
+        &items[3].result,
+        Err(OutlineError::Panicked(message)) if message == "closure failed on purpose"
+    ));
+    assert_eq!(items[4].result.as_ref().ok(), Some(&255));
+}
+
+#[test]
+fn run_keeps_input_order_when_later_inputs_finish_first() {
+    let (_model, outline) = tiny_outline();
+    let dir = TempDir::new().unwrap();
+    let inputs = write_inputs(
+        &dir,
+        &["a.png", "b.png", "c.png", "d.png", "e.png", "f.png"],
+    );
+    let options = BatchOptions::new().with_concurrency(Some(3));
+
+    let items = batch::run(&outline, &inputs, &options, |input, _| {
+        let index = inputs.iter().position(|path| path == input).unwrap();
+        // Earlier inputs take longer, so the workers finish them last.
+        thread::sleep(Duration::from_millis(20 * (inputs.len() - index) as u64));
+        Ok(index)
+    })
+    .expect("batch should run");
+
+    let order: Vec<_> = items
+        .iter()
+        .map(|item| *item.result.as_ref().unwrap())
+        .collect();
+    assert_eq!(order, [0, 1, 2, 3, 4, 5]);
+    let inputs_seen: Vec<_> = items.iter().map(|item| item.input.clone()).collect();
+    assert_eq!(inputs_seen, inputs);
+}
+
+#[test]
+fn run_retries_errors_up_to_the_limit() {
+    let (_model, outline) = tiny_outline();
+    let dir = TempDir::new().unwrap();
+    let inputs = write_inputs(&dir, &["flaky.png"]);
+    let calls = AtomicU32::new(0);
+    let options = BatchOptions::new().with_retries(2);
+
+    let items = batch::run(&outline, &inputs, &options, |_, _| {
+        if calls.fetch_add(1, Ordering::Relaxed) == 0 {
+            Err(std::io::Error::other("transient").into())
+        } else {
+            Ok(())
+        }
+    })
+    .expect("batch should run");
+
+    assert_eq!(items[0].attempts, 2);
+    assert!(items[0].result.is_ok());
+
+    let missing = [dir.path().join("missing.png")];
+    let items = batch::run(&outline, &missing, &options, |_, _| Ok(())).unwrap();
+    assert_eq!(items[0].attempts, 3);
+    assert!(items[0].result.is_err());
+}
+
+#[test]
+fn run_times_out_each_input_without_processing_it() {
+    let (_model, outline) = tiny_outline();
+    let dir = TempDir::new().unwrap();
+    let inputs = write_inputs(&dir, &["a.png", "b.png"]);
+    let processed = AtomicU32::new(0);
+    let process = |_: &std::path::Path, _| {
+        processed.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    };
+
+    let options = BatchOptions::new()
+        .with_concurrency(Some(1))
+        .with_timeout(Some(Duration::from_nanos(1)));
+    let items = batch::run(&outline, &inputs, &options, process).expect("batch should run");
+
+    assert!(items.iter().all(|item| matches!(
+        item.result,
+        Err(OutlineError::Timeout { limit }) if limit == Duration::from_nanos(1)
+    )));
+    assert_eq!(processed.load(Ordering::Relaxed), 0);
+
+    let options = options.with_timeout(Some(Duration::from_secs(60)));
+    let items = batch::run(&outline, &inputs, &options, process).expect("batch should run");
+
+    assert!(items.iter().all(|item| item.result.is_ok()));
+    assert_eq!(processed.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn run_rejects_zero_concurrency() {
+    let (_model, outline) = tiny_outline();
+    let options = BatchOptions::new().with_concurrency(Some(0));
+
+    let err = batch::run(&outline, &["a.png"], &options, |_, _| Ok(())).unwrap_err();
+
+    assert!(matches!(err, OutlineError::InvalidThreadCount { .. }));
+}
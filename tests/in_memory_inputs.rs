@@ -4,7 +4,10 @@ use std::io::Cursor;
 
 use image::imageops::FilterType;
 use image::{DynamicImage, GrayImage, ImageFormat, Luma, Rgb, RgbImage, Rgba, RgbaImage};
-use outline::Outline;
+use outline::{
+    ExecutionProvider, InferencedMatte, MatteCombine, Outline, OutlineError, OutputSelector,
+    PreprocessMode, TileOptions, array_to_gray_image,
+};
 use tempfile::NamedTempFile;
 
 fn tiny_outline() -> (NamedTempFile, Outline) {
@@ -92,3 +95,344 @@ fn for_image_bytes_decodes_and_runs_pipeline() {
     assert_eq!(result.rgb_image(), &rgb);
     assert_tiny_matte(result.raw_matte());
 }
+
+#[test]
+fn background_model_matte_is_combined() {
+    let (_model, outline) = tiny_outline();
+    let background = support::tiny_matte_model_file();
+    let outline = outline.with_background_model(background.path());
+
+    let multiplied = outline
+        .for_rgb_image(rgb_input())
+        .expect("dual-model inference should succeed");
+    assert_eq!(multiplied.raw_matte().as_raw(), &[0, 48, 64, 0]);
+
+    let averaged = outline
+        .with_matte_combine(MatteCombine::Average)
+        .for_rgb_image(rgb_input())
+        .expect("dual-model inference should succeed");
+    assert_eq!(averaged.raw_matte().as_raw(), &[128, 128, 128, 128]);
+}
+
+#[test]
+fn remove_background_matches_explicit_pipeline() {
+    let model = support::tiny_matte_model_file();
+    let image = tempfile::Builder::new()
+        .suffix(".png")
+        .tempfile()
+        .expect("temp image should be created");
+    rgb_input()
+        .save_with_format(image.path(), ImageFormat::Png)
+        .expect("PNG encoding should succeed");
+
+    let cutout =
+        outline::remove_background(model.path(), image.path()).expect("one-call cut should work");
+    let expected = Outline::new(model.path())
+        .for_image(image.path())
+        .and_then(|matte| matte.matte().foreground())
+        .expect("explicit pipeline should succeed")
+        .into_image();
+
+    assert_eq!(cutout, expected);
+    assert_eq!(cutout.dimensions(), (2, 2));
+}
+
+#[test]
+fn sanity_check_accepts_varied_matte() {
+    let (_model, outline) = tiny_outline();
+
+    let result = outline
+        .with_sanity_check(true)
+        .for_rgb_image(rgb_input())
+        .expect("a varied matte should pass the sanity check");
+
+    assert_tiny_matte(result.raw_matte());
+}
+
+#[test]
+fn model_matte_keeps_model_resolution() {
+    let (_model, outline) = tiny_outline();
+    let rgb = image::imageops::resize(&rgb_input(), 4, 4, FilterType::Nearest);
+
+    let result = outline
+        .for_rgb_image(rgb)
+        .expect("RGB image inference should succeed");
+
+    assert_eq!(result.raw_matte().dimensions(), (4, 4));
+    assert_tiny_matte(
+        result
+            .model_matte()
+            .expect("inference keeps the model matte"),
+    );
+
+    let from_parts =
+        InferencedMatte::from_parts(rgb_input(), result.model_matte().unwrap().clone())
+            .expect("sizes match");
+    assert!(from_parts.model_matte().is_none());
+}
+
+#[test]
+fn model_outputs_lists_names_and_shapes() {
+    let (_model, outline) = tiny_outline();
+
+    let outputs = outline.model_outputs().expect("tiny model should load");
+
+    assert_eq!(outputs.len(), 1);
+    assert_eq!(outputs[0].name, "matte");
+    assert_eq!(outputs[0].to_string(), "matte [1, 1, 2, 2]");
+}
+
+#[test]
+fn output_selector_picks_the_matte_output() {
+    let (_model, outline) = tiny_outline();
+
+    let by_name = outline
+        .clone()
+        .with_output(OutputSelector::Name("matte".to_string()));
+    assert_tiny_matte(by_name.for_rgb_image(rgb_input()).unwrap().raw_matte());
+
+    let unknown = outline.with_output(OutputSelector::Name("alpha".to_string()));
+    assert!(matches!(
+        unknown.for_rgb_image(rgb_input()),
+        Err(OutlineError::UnknownModelOutput { output, available })
+            if output == "`alpha`" && available == ["matte"]
+    ));
+}
+
+#[test]
+fn cpu_execution_provider_needs_no_fallback() {
+    let (_model, outline) = tiny_outline();
+    let outline = outline.with_execution_provider(ExecutionProvider::Cpu);
+
+    assert_eq!(outline.execution_provider_fallback().unwrap(), None);
+    assert_tiny_matte(outline.for_rgb_image(rgb_input()).unwrap().raw_matte());
+}
+
+#[test]
+fn letterbox_keeps_wide_input_undistorted() {
+    let (_model, outline) = tiny_outline();
+    let rgb = RgbImage::from_pixel(4, 2, Rgb([10, 20, 30]));
+
+    let stretched = outline.for_rgb_image(rgb.clone()).unwrap();
+    let letterboxed = outline
+        .with_preprocess_mode(PreprocessMode::Letterbox { fill: None })
+        .for_rgb_image(rgb)
+        .unwrap();
+
+    // The 2x2 model sees the 4x2 image as its top row only, so the matte must not
+    // stretch the model's second row over the bottom half of the image.
+    assert_eq!(
+        stretched.raw_matte().as_raw(),
+        &[0, 0, 64, 64, 128, 128, 255, 255]
+    );
+    assert_eq!(
+        letterboxed.raw_matte().as_raw(),
+        &[0, 0, 64, 64, 0, 0, 64, 64]
+    );
+    assert_eq!(letterboxed.model_matte().unwrap().dimensions(), (2, 1));
+}
+
+#[test]
+fn tiling_runs_each_tile_at_image_resolution() {
+    let (_model, outline) = tiny_outline();
+    let rgb = RgbImage::from_pixel(4, 2, Rgb([10, 20, 30]));
+
+    let result = outline
+        .with_tiling(TileOptions {
+            tile_size: 2,
+            overlap: 0,
+        })
+        .for_rgb_image(rgb)
+        .unwrap();
+
+    // Each 2x2 tile gets the tiny model's full matte.
+    assert_eq!(
+        result.raw_matte().as_raw(),
+        &[0, 64, 0, 64, 128, 255, 128, 255]
+    );
+    assert_eq!(result.model_matte().unwrap().dimensions(), (4, 2));
+}
+
+#[test]
+fn raw_matte_f32_quantizes_to_raw_matte() {
+    let model = support::tiny_matte_model_file();
+    let outline = Outline::new(model.path()).with_float_matte(true);
+    let rgb = RgbImage::from_fn(7, 5, |x, y| Rgb([(x * 30) as u8, (y * 40) as u8, 7]));
+
+    let result = outline.for_rgb_image(rgb).unwrap();
+    let matte = result
+        .raw_matte_f32()
+        .expect("inference keeps the float matte");
+
+    assert_eq!(matte.dim(), (5, 7));
+    assert!(matte.iter().all(|value| (0.0..=1.0).contains(value)));
+    assert!(matte.iter().any(|value| (value * 255.0).fract() != 0.0));
+    assert_eq!(&array_to_gray_image(matte), result.raw_matte());
+
+    let from_parts =
+        InferencedMatte::from_parts(result.rgb_image().clone(), result.raw_matte().clone())
+            .unwrap();
+    assert!(from_parts.raw_matte_f32().is_none());
+
+    let without = Outline::new(model.path())
+        .for_rgb_image(result.rgb_image().clone())
+        .unwrap();
+    assert!(without.raw_matte_f32().is_none());
+}
+
+#[test]
+fn raw_matte_u16_keeps_float_precision() {
+    let (_model, outline) = tiny_outline();
+    let outline = outline.with_float_matte(true);
+
+    let result = outline.for_rgb_image(rgb_input()).unwrap();
+    let matte = result.raw_matte_u16();
+
+    let values: Vec<u16> = matte.pixels().map(|px| px[0]).collect();
+    assert_eq!(values, vec![0, 16384, 32768, 65535]);
+
+    let from_parts =
+        InferencedMatte::from_parts(result.rgb_image().clone(), result.raw_matte().clone())
+            .unwrap();
+    let widened: Vec<u16> = from_parts
+        .raw_matte_u16()
+        .pixels()
+        .map(|px| px[0])
+        .collect();
+    assert_eq!(widened, vec![0, 64 * 257, 128 * 257, 65535]);
+}
+
+#[test]
+fn infer_scale_keeps_full_resolution_outputs() {
+    let (_model, outline) = tiny_outline();
+    let rgb = RgbImage::from_fn(8, 6, |x, y| Rgb([(x * 30) as u8, (y * 40) as u8, 7]));
+
+    let result = outline
+        .with_infer_scale(0.5)
+        .for_rgb_image(rgb.clone())
+        .expect("scaled inference should succeed");
+
+    assert_eq!(result.rgb_image(), &rgb);
+    assert_eq!(result.raw_matte().dimensions(), (8, 6));
+}
+
+#[test]
+fn max_pixels_rejects_oversized_input() {
+    let (_model, outline) = tiny_outline();
+
+    let err = outline
+        .with_max_pixels(Some(3))
+        .for_rgb_image(rgb_input())
+        .expect_err("a 2x2 input exceeds a 3 pixel limit");
+
+    assert!(matches!(
+        err,
+        OutlineError::ImageTooLarge {
+            pixels: 4,
+            limit: 3
+        }
+    ));
+}
+
+/// Minimal little-endian EXIF block holding only an orientation tag.
+fn exif_orientation(value: u8) -> Vec<u8> {
+    let mut exif = b"II*\0\x08\0\0\0\x01\0\x12\x01\x03\0\x01\0\0\0".to_vec();
+    exif.extend_from_slice(&[value, 0, 0, 0, 0, 0, 0, 0]);
+    exif
+}
+
+#[test]
+fn for_image_bytes_records_exif_orientation() {
+    use image::ImageEncoder;
+    use image::codecs::png::PngEncoder;
+    use image::metadata::Orientation;
+
+    let (_model, outline) = tiny_outline();
+    let stored = RgbImage::from_fn(4, 2, |x, y| Rgb([(x * 40) as u8, (y * 80) as u8, 0]));
+    let mut encoded = Vec::new();
+    let mut encoder = PngEncoder::new(&mut encoded);
+    encoder
+        .set_exif_metadata(exif_orientation(6))
+        .expect("PNG supports EXIF metadata");
+    encoder
+        .write_image(stored.as_raw(), 4, 2, image::ExtendedColorType::Rgb8)
+        .expect("PNG encoding should succeed");
+
+    let result = outline
+        .for_image_bytes(&encoded)
+        .expect("image bytes inference should succeed");
+
+    assert_eq!(result.orientation(), Orientation::Rotate90);
+    assert_eq!(result.dimensions(), (2, 4));
+    assert_eq!(result.stored_dimensions(), (4, 2));
+
+    #[cfg(feature = "vectorizer-vtracer")]
+    {
+        let svg = outline::trace_to_oriented_svg_string(
+            result.raw_matte(),
+            &outline::TraceOptions::default(),
+            result.orientation(),
+        )
+        .expect("tracing should succeed");
+        assert!(svg.contains(r#"width="2" height="4""#));
+        assert!(svg.contains(r#"<g transform="matrix(0 1 -1 0 2 0)">"#));
+    }
+}
+
+#[test]
+fn observer_sees_stages_in_order() {
+    use outline::PipelineStage;
+
+    let (_model, outline) = tiny_outline();
+    let mut stages = Vec::new();
+    let mut record = |stage: PipelineStage, _elapsed: std::time::Duration| stages.push(stage);
+
+    let result = outline
+        .for_rgb_image_observed(rgb_input(), &mut record)
+        .expect("observed inference should succeed");
+
+    assert_tiny_matte(result.raw_matte());
+    assert_eq!(
+        stages,
+        [
+            PipelineStage::Load,
+            PipelineStage::Preprocess,
+            PipelineStage::Infer,
+            PipelineStage::Postprocess,
+            PipelineStage::Matte,
+        ]
+    );
+}
+
+#[test]
+fn observer_sees_background_model_stages_twice() {
+    use outline::PipelineStage;
+
+    let (_model, outline) = tiny_outline();
+    let background = support::tiny_matte_model_file();
+    let outline = outline.with_background_model(background.path());
+    let mut stages = Vec::new();
+    let mut record = |stage: PipelineStage, _elapsed: std::time::Duration| stages.push(stage);
+
+    let image = tempfile::Builder::new()
+        .suffix(".png")
+        .tempfile()
+        .expect("temp file should be created");
+    rgb_input()
+        .save_with_format(image.path(), ImageFormat::Png)
+        .expect("PNG encoding should succeed");
+    outline
+        .for_image_observed(image.path(), &mut record)
+        .expect("observed inference should succeed");
+
+    let model_stages = [
+        PipelineStage::Preprocess,
+        PipelineStage::Infer,
+        PipelineStage::Postprocess,
+    ];
+    assert_eq!(stages.first(), Some(&PipelineStage::Load));
+    assert_eq!(&stages[1..4], &model_stages);
+    assert_eq!(&stages[4..7], &model_stages);
+    assert_eq!(stages.get(7), Some(&PipelineStage::Matte));
+    assert_eq!(stages.len(), 8);
+}